@@ -1,7 +1,14 @@
+use std::collections::{HashMap, VecDeque};
 use std::process::Command;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use anyhow::Result;
 use serde::{Serialize, Deserialize};
 use serde_json;
+use tauri::{AppHandle, Emitter};
+use crate::db::Database;
 use crate::models::Track;
 
 #[derive(Deserialize, Debug)]
@@ -20,6 +27,23 @@ struct JxaTrack {
     rating: i64,
     bpm: i64,
     location: Option<String>,
+    /// Shared track: an Apple Music streaming or iCloud item
+    #[serde(default)]
+    remote: bool,
+    #[serde(default, rename = "playCount")]
+    play_count: i64,
+    #[serde(default, rename = "skipCount")]
+    skip_count: i64,
+    /// Unix timestamp, 0 when never played
+    #[serde(default, rename = "lastPlayed")]
+    last_played: i64,
+    #[serde(default)]
+    loved: bool,
+    /// Unix timestamps; 0 when unavailable, which `insert_track` treats as "keep the stored value"
+    #[serde(default, rename = "modifiedDate")]
+    modified_date: i64,
+    #[serde(default, rename = "dateAdded")]
+    date_added: i64,
 }
 
 pub fn get_changes_since(since_epoch_seconds: i64) -> Result<Vec<Track>> {
@@ -33,8 +57,7 @@ pub fn get_changes_since(since_epoch_seconds: i64) -> Result<Vec<Track>> {
         // actually passing date string is standard.
         // Let's rely on standard applescript date construction from parts to be safe against locale.
 
-        let script = format!(
-            r#"
+        let script = r#"
             use framework "Foundation"
             use scripting additions
             
@@ -55,77 +78,105 @@ pub fn get_changes_since(since_epoch_seconds: i64) -> Result<Vec<Track>> {
                 return newDate
             end getASDateFromTimestamp
 
-            set sinceDate to getASDateFromTimestamp({})
+            on run argv
+                set sinceDate to getASDateFromTimestamp((item 1 of argv) as integer)
+                -- AS dates are local wall-clock time; subtracting this yields Unix seconds
+                set epochDate to getASDateFromTimestamp(0)
             
-            log "Querying changes since: " & (sinceDate as string)
+                log "Querying changes since: " & (sinceDate as string)
 
-            tell application "Music"
-                set recentTracks to (every track whose modification date >= sinceDate)
+                tell application "Music"
+                    set recentTracks to (every track whose modification date >= sinceDate)
                 
-                -- Construct JSON manually to avoid slow object bridges
-                set jsonList to {{}}
+                    -- Construct JSON manually to avoid slow object bridges
+                    set jsonList to {}
                 
-                repeat with t in recentTracks
-                   try
-                       set tId to persistent ID of t
-                       set tName to name of t
-                       set tArtist to artist of t
-                       set tAlbum to album of t
-                       set tComment to comment of t
-                       set tGrouping to grouping of t
-                       set tDuration to duration of t
-                       set tKind to kind of t
-                       set tSize to size of t
-                       set tBitRate to bit rate of t
-                       set tRating to rating of t
-                       set tBpm to bpm of t
-                       
-                       -- Handle Location safely
-                       -- NOTE: `use framework "Foundation"` breaks `POSIX path of` on file refs.
-                       -- We must coerce to alias first, or use NSURL as a fallback.
-                       set tLoc to ""
+                    repeat with t in recentTracks
                        try
-                           set tLoc to POSIX path of (location of t as alias)
-                       on error
+                           set tId to persistent ID of t
+                           set tName to name of t
+                           set tArtist to artist of t
+                           set tAlbum to album of t
+                           set tComment to comment of t
+                           set tGrouping to grouping of t
+                           set tDuration to duration of t
+                           set tKind to kind of t
+                           set tSize to size of t
+                           set tBitRate to bit rate of t
+                           set tRating to rating of t
+                           set tBpm to bpm of t
+                           set tPlays to played count of t
+                           set tSkips to skipped count of t
+                           set tLastPlayed to 0
+                           try
+                               set tLastPlayed to ((played date of t) - epochDate) as integer
+                           end try
+                           set tModified to 0
+                           set tAdded to 0
+                           try
+                               set tModified to ((modification date of t) - epochDate) as integer
+                           end try
                            try
-                               -- Fallback: use NSURL via ObjC bridge
-                               set fileRef to location of t
-                               set fileURL to current application's NSURL's fileURLWithPath:(POSIX path of (fileRef as text))
-                               set tLoc to (fileURL's |path|()) as text
+                               set tAdded to ((date added of t) - epochDate) as integer
+                           end try
+                           set tLoved to false
+                           try
+                               set tLoved to favorited of t
                            on error
-                               set tLoc to ""
+                               try
+                                   set tLoved to loved of t
+                               end try
                            end try
-                       end try
                        
-                       set entry to {{ |id|:tId, |name|:tName, |artist|:tArtist, |album|:tAlbum, |comment|:tComment, |grouping|:tGrouping, |duration|:tDuration, |kind|:tKind, |size|:tSize, |bitRate|:tBitRate, |rating|:tRating, |bpm|:tBpm, |location|:tLoc }}
-                       copy entry to end of jsonList
-                   end try
-                end repeat
-            end tell
+                           -- Handle Location safely
+                           -- NOTE: `use framework "Foundation"` breaks `POSIX path of` on file refs.
+                           -- We must coerce to alias first, or use NSURL as a fallback.
+                           set tLoc to ""
+                           try
+                               set tLoc to POSIX path of (location of t as alias)
+                           on error
+                               try
+                                   -- Fallback: use NSURL via ObjC bridge
+                                   set fileRef to location of t
+                                   set fileURL to current application's NSURL's fileURLWithPath:(POSIX path of (fileRef as text))
+                                   set tLoc to (fileURL's |path|()) as text
+                               on error
+                                   set tLoc to ""
+                               end try
+                           end try
+                       
+                           -- Apple Music/iCloud items are shared tracks (Track Type "Remote" in the XML)
+                           set tRemote to (class of t is shared track)
+
+                           set entry to { |id|:tId, |name|:tName, |artist|:tArtist, |album|:tAlbum, |comment|:tComment, |grouping|:tGrouping, |duration|:tDuration, |kind|:tKind, |size|:tSize, |bitRate|:tBitRate, |rating|:tRating, |bpm|:tBpm, |location|:tLoc, |remote|:tRemote, |playCount|:tPlays, |skipCount|:tSkips, |lastPlayed|:tLastPlayed, |loved|:tLoved, |modifiedDate|:tModified, |dateAdded|:tAdded }
+                           copy entry to end of jsonList
+                       end try
+                    end repeat
+                end tell
             
-            -- JSON Stringify using ObjC bridge
-            set ca to current application
-            set jsonData to ca's NSJSONSerialization's dataWithJSONObject:jsonList options:0 |error|:missing value
-            set jsonString to (ca's NSString's alloc()'s initWithData:jsonData encoding:4) as string
-            return jsonString
-            "#,
-            since_epoch_seconds
-        );
+                -- JSON Stringify using ObjC bridge
+                set ca to current application
+                set jsonData to ca's NSJSONSerialization's dataWithJSONObject:jsonList options:0 |error|:missing value
+                set jsonString to (ca's NSString's alloc()'s initWithData:jsonData encoding:4) as string
+                return jsonString
+            end run
+            "#;
 
         let output = Command::new("osascript")
             .arg("-e")
-            .arg(&script)
+            .arg(script)
+            .arg(since_epoch_seconds.to_string())
             .output()?;
 
         if !output.status.success() {
              let err = String::from_utf8_lossy(&output.stderr);
-             eprintln!("AppleScript Error: {}", err);
+             tracing::error!("AppleScript Error: {}", err);
              return Err(anyhow::anyhow!("AppleScript Get Changes Failed: {}", err));
         }
 
         // Log stderr (AppleScript logs) for debugging
         if !output.stderr.is_empty() {
-            eprintln!("AppleScript Logs: {}", String::from_utf8_lossy(&output.stderr));
+            tracing::debug!("AppleScript Logs: {}", String::from_utf8_lossy(&output.stderr));
         }
 
         let stdout = String::from_utf8_lossy(&output.stdout);
@@ -134,7 +185,10 @@ pub fn get_changes_since(since_epoch_seconds: i64) -> Result<Vec<Track>> {
 
         let tracks: Vec<Track> = as_tracks.into_iter().map(|jt| {
             let path = jt.location.unwrap_or_default();
-            
+            // A shared track without a location is an Apple Music streaming/cloud item; a
+            // missing location on anything else is just a missing file
+            let is_cloud = path.is_empty() && jt.remote;
+
             Track {
                 id: 0, 
                 persistent_id: jt.id,
@@ -148,11 +202,22 @@ pub fn get_changes_since(since_epoch_seconds: i64) -> Result<Vec<Track>> {
                 format: jt.kind,
                 size_bytes: jt.size,
                 bit_rate: jt.bit_rate,
-                modified_date: 0,
+                modified_date: jt.modified_date,
                 rating: jt.rating,
-                date_added: 0,
-                bpm: jt.bpm,
+                date_added: jt.date_added,
+                bpm: jt.bpm as f64,
                 missing: false,
+                play_count: jt.play_count,
+                skip_count: jt.skip_count,
+                last_played: jt.last_played,
+                loved: jt.loved,
+                notes: None,
+                intro_bars: None,
+                outro_bars: None,
+                audio_health: None,
+                color_label: None,
+                offloaded: false,
+                is_cloud,
             }
         }).collect();
 
@@ -170,31 +235,33 @@ pub fn get_changes_since(since_epoch_seconds: i64) -> Result<Vec<Track>> {
 pub fn update_track_rating(persistent_id: &str, rating: u32) -> Result<()> {
     #[cfg(target_os = "macos")]
     {
-        let script = format!(
-            r#"
-            if application "Music" is running then
-                tell application "Music"
-                    try
-                        -- Find track by persistent ID
-                        set myTracks to (every track whose persistent ID is "{}")
-                        if (count of myTracks) > 0 then
-                            set myTrack to item 1 of myTracks
-                            set rating of myTrack to {}
-                        end if
-                    end try
-                end tell
-            end if
-            "#,
-            persistent_id, rating
-        );
+        let script = r#"
+            on run argv
+                if application "Music" is running then
+                    tell application "Music"
+                        try
+                            -- Find track by persistent ID
+                            set myTracks to (every track whose persistent ID is (item 1 of argv))
+                            if (count of myTracks) > 0 then
+                                set myTrack to item 1 of myTracks
+                                set rating of myTrack to ((item 2 of argv) as integer)
+                            end if
+                        end try
+                    end tell
+                end if
+            end run
+        "#;
 
         let output = Command::new("osascript")
             .arg("-e")
-            .arg(&script)
+            .arg(script)
+            .arg(persistent_id)
+            .arg(rating.to_string())
             .output()?;
             
         if !output.status.success() {
-             eprintln!("AppleScript error: {}", String::from_utf8_lossy(&output.stderr));
+             let err = String::from_utf8_lossy(&output.stderr);
+             return Err(anyhow::anyhow!("AppleScript Rating Update Failed: {}", err));
         }
     }
     Ok(())
@@ -206,34 +273,33 @@ pub fn update_track_rating(persistent_id: &str, rating: u32) -> Result<()> {
 pub fn update_track_comment(persistent_id: &str, comment: &str) -> Result<()> {
     #[cfg(target_os = "macos")]
     {
-        // Simple escaping for AppleScript string
-        let escaped_comment = comment.replace('\\', "\\\\").replace('"', "\\\"");
-        
-        let script = format!(
-            r#"
-            if application "Music" is running then
-                tell application "Music"
-                    try
-                        -- Find track by persistent ID
-                        set myTracks to (every track whose persistent ID is "{}")
-                        if (count of myTracks) > 0 then
-                            set myTrack to item 1 of myTracks
-                            set comment of myTrack to "{}"
-                        end if
-                    end try
-                end tell
-            end if
-            "#,
-            persistent_id, escaped_comment
-        );
+        let script = r#"
+            on run argv
+                if application "Music" is running then
+                    tell application "Music"
+                        try
+                            -- Find track by persistent ID
+                            set myTracks to (every track whose persistent ID is (item 1 of argv))
+                            if (count of myTracks) > 0 then
+                                set myTrack to item 1 of myTracks
+                                set comment of myTrack to (item 2 of argv)
+                            end if
+                        end try
+                    end tell
+                end if
+            end run
+        "#;
 
         let output = Command::new("osascript")
             .arg("-e")
-            .arg(&script)
+            .arg(script)
+            .arg(persistent_id)
+            .arg(comment)
             .output()?;
             
         if !output.status.success() {
-             eprintln!("AppleScript error: {}", String::from_utf8_lossy(&output.stderr));
+             let err = String::from_utf8_lossy(&output.stderr);
+             return Err(anyhow::anyhow!("AppleScript Comment Update Failed: {}", err));
         }
     }
     Ok(())
@@ -242,6 +308,16 @@ pub fn update_track_comment(persistent_id: &str, comment: &str) -> Result<()> {
 /// Batch updates comments for multiple tracks using a single JXA (JavaScript for Automation) call.
 /// This acts as a massive performance optimization over calling `osascript` per track.
 pub fn batch_update_track_comments(updates: Vec<(String, String)>) -> Result<()> {
+    batch_update_text_property("comment", updates)
+}
+
+/// Batch updates the grouping of multiple tracks in a single JXA call.
+pub fn batch_update_track_groupings(updates: Vec<(String, String)>) -> Result<()> {
+    batch_update_text_property("grouping", updates)
+}
+
+/// Sets a text property (`comment`, `grouping`) of many tracks through one JXA call.
+fn batch_update_text_property(property: &str, updates: Vec<(String, String)>) -> Result<()> {
     #[cfg(target_os = "macos")]
     {
         if updates.is_empty() {
@@ -252,12 +328,12 @@ pub fn batch_update_track_comments(updates: Vec<(String, String)>) -> Result<()>
         #[derive(Serialize)]
         struct TrackUpdate {
             id: String, // Persistent ID
-            comment: String,
+            value: String,
         }
 
         let payload: Vec<TrackUpdate> = updates
             .into_iter()
-            .map(|(id, comment)| TrackUpdate { id, comment })
+            .map(|(id, value)| TrackUpdate { id, value })
             .collect();
 
         // Serialize data to pass to JXA
@@ -274,6 +350,7 @@ pub fn batch_update_track_comments(updates: Vec<(String, String)>) -> Result<()>
             if (!app.running()) return;
 
             const updates = JSON.parse(argv[0]);
+            const property = argv[1];
             
             updates.forEach(function(item) {
                 try {
@@ -282,7 +359,7 @@ pub fn batch_update_track_comments(updates: Vec<(String, String)>) -> Result<()>
                     
                     if (tracks.length > 0) {
                         // Update the first match
-                        tracks[0].comment = item.comment;
+                        tracks[0][property] = item.value;
                     }
                 } catch (e) {
                     // Swallow errors for individual tracks so the batch continues
@@ -297,6 +374,7 @@ pub fn batch_update_track_comments(updates: Vec<(String, String)>) -> Result<()>
             .arg("-e")
             .arg(script)
             .arg(json_arg) // Pass JSON as argument 0
+            .arg(property)
             .output()?;
 
         if !output.status.success() {
@@ -304,73 +382,192 @@ pub fn batch_update_track_comments(updates: Vec<(String, String)>) -> Result<()>
             return Err(anyhow::anyhow!("JXA Batch Update Failed: {}", err));
         }
     }
-    
+    #[cfg(not(target_os = "macos"))]
+    let _ = (property, updates);
+
     Ok(())
 }
 
 /// Lightweight struct for snapshot-based diffing of fields that Music.app
-/// does NOT include in `modification date` (e.g. rating, BPM).
+/// does NOT include in `modification date` (e.g. rating, BPM, plays).
 #[derive(Debug, Deserialize)]
 pub struct SnapshotEntry {
     pub persistent_id: String,
     pub rating: i64,
     pub bpm: i64,
+    pub play_count: i64,
+    pub skip_count: i64,
+    pub loved: bool,
 }
 
-/// Fetches persistent_id, rating, and BPM for ALL tracks from Music.app
-/// using efficient batch property access (parallel list fetching).
-/// Returns ~20k entries in ~2 seconds for large libraries.
-pub fn get_snapshot_fields() -> Result<Vec<SnapshotEntry>> {
-    #[cfg(target_os = "macos")]
-    {
-        let script = r#"
-            use framework "Foundation"
-            use scripting additions
-
-            tell application "Music"
-                set allIds to persistent ID of every track
-                set allRatings to rating of every track
-                set allBpms to bpm of every track
-            end tell
+/// Libraries up to this size are snapshotted in a single AppleScript call
+#[cfg(target_os = "macos")]
+const SNAPSHOT_SINGLE_SHOT_MAX: usize = 20_000;
+/// Tracks per call when chunking
+#[cfg(target_os = "macos")]
+const SNAPSHOT_CHUNK_SIZE: usize = 10_000;
+/// Chunks fetched at the same time
+#[cfg(target_os = "macos")]
+const SNAPSHOT_PARALLEL_CHUNKS: usize = 4;
+/// A chunk that takes longer than this is killed and the snapshot fails
+#[cfg(target_os = "macos")]
+const SNAPSHOT_CHUNK_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Runs an AppleScript and returns its stdout, killing `osascript` after `timeout`.
+#[cfg(target_os = "macos")]
+fn run_osascript_with_timeout(script: &str, timeout: Duration) -> Result<String> {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let mut child = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain the pipes while waiting, or a large result would block the script
+    let mut stdout = child.stdout.take().ok_or_else(|| anyhow::anyhow!("osascript stdout unavailable"))?;
+    let mut stderr = child.stderr.take().ok_or_else(|| anyhow::anyhow!("osascript stderr unavailable"))?;
+    let stdout_reader = thread::spawn(move || {
+        let mut out = String::new();
+        let _ = stdout.read_to_string(&mut out);
+        out
+    });
+    let stderr_reader = thread::spawn(move || {
+        let mut err = String::new();
+        let _ = stderr.read_to_string(&mut err);
+        err
+    });
+
+    let started = std::time::Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow::anyhow!("AppleScript timed out after {}s", timeout.as_secs()));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
 
-            -- Build a single JSON object with parallel arrays (instant serialization)
-            set ca to current application
-            set payload to {|ids|:allIds, |ratings|:allRatings, |bpms|:allBpms}
-            set jsonData to ca's NSJSONSerialization's dataWithJSONObject:payload options:0 |error|:missing value
-            set jsonString to (ca's NSString's alloc()'s initWithData:jsonData encoding:4) as string
-            return jsonString
-        "#;
+    let out = stdout_reader.join().unwrap_or_default();
+    let err = stderr_reader.join().unwrap_or_default();
+    if !status.success() {
+        return Err(anyhow::anyhow!("{}", err.trim()));
+    }
+    Ok(out)
+}
 
-        let output = Command::new("osascript")
-            .arg("-e")
-            .arg(script)
-            .output()?;
+/// AppleScript returning the snapshot fields of `tracks` (an AppleScript track reference,
+/// e.g. "every track") as JSON parallel arrays.
+#[cfg(target_os = "macos")]
+fn snapshot_script(tracks: &str) -> String {
+    format!(r#"
+        use framework "Foundation"
+        use scripting additions
+
+        tell application "Music"
+            set trackRef to a reference to {tracks}
+            set allIds to persistent ID of trackRef
+            set allRatings to rating of trackRef
+            set allBpms to bpm of trackRef
+            set allPlays to played count of trackRef
+            set allSkips to skipped count of trackRef
+            -- `loved` was renamed `favorited` in macOS 14
+            try
+                set allLoved to favorited of trackRef
+            on error
+                set allLoved to loved of trackRef
+            end try
+        end tell
+
+        -- Build a single JSON object with parallel arrays (instant serialization)
+        set ca to current application
+        set payload to {{|ids|:allIds, |ratings|:allRatings, |bpms|:allBpms, |plays|:allPlays, |skips|:allSkips, |loved|:allLoved}}
+        set jsonData to ca's NSJSONSerialization's dataWithJSONObject:payload options:0 |error|:missing value
+        set jsonString to (ca's NSString's alloc()'s initWithData:jsonData encoding:4) as string
+        return jsonString
+    "#, tracks = tracks)
+}
 
-        if !output.status.success() {
-            let err = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!("AppleScript Snapshot Fetch Failed: {}", err));
-        }
+#[cfg(target_os = "macos")]
+fn parse_snapshot(json: &str) -> Result<Vec<SnapshotEntry>> {
+    #[derive(Deserialize)]
+    struct ParallelArrays {
+        ids: Vec<String>,
+        ratings: Vec<i64>,
+        bpms: Vec<i64>,
+        plays: Vec<i64>,
+        skips: Vec<i64>,
+        loved: Vec<bool>,
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
+    let arrays: ParallelArrays = serde_json::from_str(json)?;
+
+    Ok(arrays.ids.into_iter()
+        .zip(arrays.ratings.into_iter())
+        .zip(arrays.bpms.into_iter())
+        .zip(arrays.plays.into_iter().zip(arrays.skips.into_iter()))
+        .zip(arrays.loved.into_iter())
+        .map(|((((id, rating), bpm), (play_count, skip_count)), loved)| SnapshotEntry {
+            persistent_id: id,
+            rating,
+            bpm,
+            play_count,
+            skip_count,
+            loved,
+        })
+        .collect())
+}
 
-        #[derive(Deserialize)]
-        struct ParallelArrays {
-            ids: Vec<String>,
-            ratings: Vec<i64>,
-            bpms: Vec<i64>,
+/// Fetches persistent_id, rating, BPM, play/skip counts and loved flag for ALL tracks from Music.app
+/// using efficient batch property access (parallel list fetching).
+/// Returns ~20k entries in ~2 seconds; larger libraries are fetched in chunks of 10k tracks,
+/// several at a time, each with its own timeout, since one call times out around 100k tracks.
+pub fn get_snapshot_fields() -> Result<Vec<SnapshotEntry>> {
+    #[cfg(target_os = "macos")]
+    {
+        let count_script = r#"tell application "Music" to count of tracks of library playlist 1"#;
+        let track_count: usize = run_osascript_with_timeout(count_script, SNAPSHOT_CHUNK_TIMEOUT)
+            .map_err(|e| anyhow::anyhow!("AppleScript Track Count Failed: {}", e))?
+            .trim()
+            .parse()?;
+
+        if track_count <= SNAPSHOT_SINGLE_SHOT_MAX {
+            let stdout = run_osascript_with_timeout(&snapshot_script("every track"), SNAPSHOT_CHUNK_TIMEOUT)
+                .map_err(|e| anyhow::anyhow!("AppleScript Snapshot Fetch Failed: {}", e))?;
+            return parse_snapshot(&stdout);
         }
 
-        let arrays: ParallelArrays = serde_json::from_str(&stdout)?;
-
-        let entries: Vec<SnapshotEntry> = arrays.ids.into_iter()
-            .zip(arrays.ratings.into_iter())
-            .zip(arrays.bpms.into_iter())
-            .map(|((id, rating), bpm)| SnapshotEntry {
-                persistent_id: id,
-                rating,
-                bpm,
-            })
+        // AppleScript track indices are 1-based and inclusive
+        let ranges: Vec<(usize, usize)> = (1..=track_count)
+            .step_by(SNAPSHOT_CHUNK_SIZE)
+            .map(|start| (start, (start + SNAPSHOT_CHUNK_SIZE - 1).min(track_count)))
             .collect();
+        tracing::info!("Fetching snapshot of {} tracks in {} chunks", track_count, ranges.len());
+
+        let mut entries = Vec::with_capacity(track_count);
+        for batch in ranges.chunks(SNAPSHOT_PARALLEL_CHUNKS) {
+            let results: Vec<Result<Vec<SnapshotEntry>>> = thread::scope(|scope| {
+                let handles: Vec<_> = batch.iter()
+                    .map(|&(start, end)| scope.spawn(move || {
+                        let tracks = format!("tracks {} thru {} of library playlist 1", start, end);
+                        let stdout = run_osascript_with_timeout(&snapshot_script(&tracks), SNAPSHOT_CHUNK_TIMEOUT)
+                            .map_err(|e| anyhow::anyhow!("AppleScript Snapshot Fetch Failed (tracks {}-{}): {}", start, end, e))?;
+                        parse_snapshot(&stdout)
+                    }))
+                    .collect();
+                handles.into_iter()
+                    .map(|h| h.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Snapshot chunk panicked"))))
+                    .collect()
+            });
+            for result in results {
+                entries.extend(result?);
+            }
+        }
 
         return Ok(entries);
     }
@@ -388,6 +585,8 @@ pub struct PlaylistSnapshotEntry {
     pub parent_persistent_id: Option<String>,
     pub name: String,
     pub is_folder: bool,
+    /// Smart or special (built-in) playlist; Music.app manages its tracks
+    pub is_smart: bool,
     pub track_ids: Vec<String>,
 }
 
@@ -417,6 +616,17 @@ pub fn get_playlist_snapshot() -> Result<Vec<PlaylistSnapshotEntry>> {
                                 set isFldr to true
                             end if
 
+                            -- Smart and special (Purchased, Genius...) playlists are read-only
+                            set isSmart to false
+                            try
+                                set isSmart to smart of p
+                            end try
+                            try
+                                if special kind of p is not none and not isFldr then
+                                    set isSmart to true
+                                end if
+                            end try
+
                             -- Parent persistent ID (if nested)
                             set parentId to missing value
                             try
@@ -434,7 +644,7 @@ pub fn get_playlist_snapshot() -> Result<Vec<PlaylistSnapshotEntry>> {
                                 end try
                             end if
 
-                            set entry to {|id|:pId, |name|:pName, |parent_id|:parentId, |is_folder|:isFldr, |track_ids|:tIds}
+                            set entry to {|id|:pId, |name|:pName, |parent_id|:parentId, |is_folder|:isFldr, |is_smart|:isSmart, |track_ids|:tIds}
                             copy entry to end of resultList
                         end if
                     end try
@@ -466,6 +676,8 @@ pub fn get_playlist_snapshot() -> Result<Vec<PlaylistSnapshotEntry>> {
             name: String,
             parent_id: Option<String>,
             is_folder: bool,
+            #[serde(default)]
+            is_smart: bool,
             track_ids: Vec<String>,
         }
 
@@ -477,6 +689,7 @@ pub fn get_playlist_snapshot() -> Result<Vec<PlaylistSnapshotEntry>> {
                 parent_persistent_id: p.parent_id,
                 name: p.name,
                 is_folder: p.is_folder,
+                is_smart: p.is_smart,
                 track_ids: p.track_ids,
             })
             .collect();
@@ -490,204 +703,874 @@ pub fn get_playlist_snapshot() -> Result<Vec<PlaylistSnapshotEntry>> {
     }
 }
 
-/// Helper to "touch" a file, updating its modification time.
-/// This helps Rekordbox and Finder notice that the file has changed.
-pub fn touch_file(path: &str) -> Result<()> {
-   #[cfg(target_os = "macos")]
-   {
-        Command::new("touch")
-            .arg(path)
-            .output()?;
-   }
-   Ok(())
-}
-
-/// Adds a track to a playlist in Apple Music (iTunes) by their Persistent IDs.
-pub fn add_track_to_playlist(track_pid: &str, playlist_pid: &str) -> Result<()> {
-    #[cfg(target_os = "macos")]
-    {
-        let script = format!(
-            r#"
-            if application "Music" is running then
-                tell application "Music"
-                    try
-                        set theTrack to (first track whose persistent ID is "{}")
-                        set thePlaylist to (first playlist whose persistent ID is "{}")
-                        duplicate theTrack to thePlaylist
-                    on error errMsg
-                         -- ignore errors
-                    end try
-                end tell
-            end if
-            "#,
-            track_pid, playlist_pid
-        );
-
-        Command::new("osascript")
-            .arg("-e")
-            .arg(&script)
-            .output()?;
-    }
-    Ok(())
+/// Cheap per-playlist header: everything but the track list.
+#[derive(Debug, Clone)]
+pub struct PlaylistHeader {
+    pub persistent_id: String,
+    pub parent_persistent_id: Option<String>,
+    pub name: String,
+    pub is_folder: bool,
+    pub is_smart: bool,
+    pub track_count: i64,
+    /// Total duration in seconds
+    pub duration: i64,
+    /// Total size in bytes
+    pub size: i64,
 }
 
-/// Removes a track from a playlist in Apple Music by their Persistent IDs.
-pub fn remove_track_from_playlist(track_pid: &str, playlist_pid: &str) -> Result<()> {
-    #[cfg(target_os = "macos")]
-    {
-        let script = format!(
-            r#"
-            if application "Music" is running then
-                tell application "Music"
-                    try
-                        set thePlaylist to (first playlist whose persistent ID is "{}")
-                        delete (every track of thePlaylist whose persistent ID is "{}")
-                    end try
-                end tell
-            end if
-            "#,
-            playlist_pid, track_pid
-        );
-
-        Command::new("osascript")
-            .arg("-e")
-            .arg(&script)
-            .output()?;
+impl PlaylistHeader {
+    /// Changes whenever the playlist's membership very likely changed. Music.app has no
+    /// modification date for playlists, so count, duration and size stand in for one.
+    /// Reordering is not caught, which the membership diff ignores anyway.
+    pub fn signature(&self) -> String {
+        format!("{}:{}:{}", self.track_count, self.duration, self.size)
     }
-    Ok(())
 }
 
-/// Reorders tracks in an Apple Music playlist by removing all tracks and re-adding them in order.
-/// This is the only reliable way to reorder via AppleScript since Music.app doesn't expose
-/// a direct "move track to position" API.
-pub fn reorder_playlist(playlist_pid: &str, track_pids: &[String]) -> Result<()> {
+/// Fetches every playlist's header without its track list; a fraction of the cost of
+/// `get_playlist_snapshot` on libraries with hundreds of playlists.
+pub fn get_playlist_headers() -> Result<Vec<PlaylistHeader>> {
     #[cfg(target_os = "macos")]
     {
-        // Build a comma-separated list of quoted PIDs for the AppleScript
-        let pid_list: Vec<String> = track_pids.iter().map(|p| format!("\"{}\"" , p)).collect();
-        let pid_array = pid_list.join(", ");
+        let script = r#"
+            use framework "Foundation"
+            use scripting additions
 
-        let script = format!(
-            r##"
-            if application "Music" is running then
-                tell application "Music"
+            tell application "Music"
+                set resultList to {}
+                repeat with p in (every playlist)
                     try
-                        set thePlaylist to (first playlist whose persistent ID is "{}")
-                        set trackPIDs to {{{}}}
-                        
-                        -- Collect references to the tracks before deleting
-                        set trackRefs to {{}}
-                        repeat with pid in trackPIDs
+                        if class of p is not library playlist then
+                            set isFldr to (class of p is folder playlist)
+                            set isSmart to false
                             try
-                                set end of trackRefs to (first track whose persistent ID is pid)
+                                set isSmart to smart of p
                             end try
-                        end repeat
-                        
-                        -- Delete all tracks from the playlist
-                        delete every track of thePlaylist
-                        
-                        -- Re-add in the desired order
-                        repeat with aTrack in trackRefs
                             try
-                                duplicate aTrack to thePlaylist
+                                if special kind of p is not none and not isFldr then
+                                    set isSmart to true
+                                end if
                             end try
-                        end repeat
-                    on error errMsg
-                        -- log but don't fail
+                            set parentId to missing value
+                            try
+                                set parentId to persistent ID of (parent of p)
+                            end try
+                            set tCount to 0
+                            set pDuration to 0
+                            set pSize to 0
+                            if not isFldr then
+                                set tCount to count of tracks of p
+                                try
+                                    set pDuration to duration of p
+                                end try
+                                try
+                                    set pSize to size of p
+                                end try
+                            end if
+                            set entry to {|id|:(persistent ID of p), |name|:(name of p), |parent_id|:parentId, |is_folder|:isFldr, |is_smart|:isSmart, |count|:tCount, |duration|:pDuration, |size|:pSize}
+                            copy entry to end of resultList
+                        end if
                     end try
-                end tell
-            end if
-            "##,
-            playlist_pid, pid_array
-        );
-
-        Command::new("osascript")
-            .arg("-e")
-            .arg(&script)
-            .output()?;
-    }
-    Ok(())
-}
-
-/// Gets the played count for a track in Apple Music by its Persistent ID.
-pub fn get_play_count(track_pid: &str) -> Result<i64> {
-    #[cfg(target_os = "macos")]
-    {
-        let script = format!(
-            r#"
-            tell application "Music"
-                try
-                    set theTrack to (first track whose persistent ID is "{}")
-                    return played count of theTrack
-                on error
-                    return 0
-                end try
+                end repeat
             end tell
-            "#,
-            track_pid
-        );
+
+            set ca to current application
+            set jsonData to ca's NSJSONSerialization's dataWithJSONObject:resultList options:0 |error|:missing value
+            set jsonString to (ca's NSString's alloc()'s initWithData:jsonData encoding:4) as string
+            return jsonString
+        "#;
 
         let output = Command::new("osascript")
             .arg("-e")
-            .arg(&script)
+            .arg(script)
             .output()?;
 
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            return Ok(stdout.parse::<i64>().unwrap_or(0));
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Playlist Header Fetch Failed: {}", err));
         }
-        return Ok(0);
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        #[derive(Deserialize)]
+        struct RawHeader {
+            id: String,
+            name: String,
+            parent_id: Option<String>,
+            is_folder: bool,
+            #[serde(default)]
+            is_smart: bool,
+            count: i64,
+            // Music.app reports these as reals
+            duration: f64,
+            size: f64,
+        }
+
+        let raw: Vec<RawHeader> = serde_json::from_str(&stdout)?;
+
+        return Ok(raw.into_iter()
+            .map(|h| PlaylistHeader {
+                persistent_id: h.id,
+                parent_persistent_id: h.parent_id,
+                name: h.name,
+                is_folder: h.is_folder,
+                is_smart: h.is_smart,
+                track_count: h.count,
+                duration: h.duration.round() as i64,
+                size: h.size.round() as i64,
+            })
+            .collect());
     }
 
     #[cfg(not(target_os = "macos"))]
     {
-        Ok(0)
+        Ok(vec![])
     }
 }
 
-/// Sets the played count for a track in Apple Music by its Persistent ID.
-pub fn set_play_count(track_pid: &str, count: i64) -> Result<()> {
-    #[cfg(target_os = "macos")]
-    {
-        let script = format!(
-            r#"
-            if application "Music" is running then
-                tell application "Music"
-                    try
-                        set theTrack to (first track whose persistent ID is "{}")
-                        set played count of theTrack to {}
-                    end try
-                end tell
-            end if
-            "#,
-            track_pid, count
-        );
-
-        Command::new("osascript")
-            .arg("-e")
-            .arg(&script)
-            .output()?;
+/// Fetches the track persistent IDs of the given playlists only. Playlists that no longer
+/// exist are left out of the result.
+pub fn get_playlist_track_pids(playlist_pids: &[String]) -> Result<HashMap<String, Vec<String>>> {
+    if playlist_pids.is_empty() {
+        return Ok(HashMap::new());
     }
-    Ok(())
-}
 
-/// Fetches all persistent IDs from Music.app efficiently using batch property access.
-/// Returns a HashSet of persistent IDs for fast lookup.
-pub fn get_all_music_app_pids() -> Result<std::collections::HashSet<String>> {
     #[cfg(target_os = "macos")]
     {
         let script = r#"
             use framework "Foundation"
             use scripting additions
 
-            tell application "Music"
-                set allIds to persistent ID of every track
-            end tell
+            on run argv
+                set resultRecord to current application's NSMutableDictionary's dictionary()
+                tell application "Music"
+                    repeat with pid in argv
+                        try
+                            set p to (first playlist whose persistent ID is (pid as text))
+                            set tIds to {}
+                            try
+                                set tIds to persistent ID of every track of p
+                            end try
+                            (resultRecord's setObject:tIds forKey:(pid as text))
+                        end try
+                    end repeat
+                end tell
 
-            set ca to current application
-            set jsonData to ca's NSJSONSerialization's dataWithJSONObject:allIds options:0 |error|:missing value
-            set jsonString to (ca's NSString's alloc()'s initWithData:jsonData encoding:4) as string
-            return jsonString
+                set ca to current application
+                set jsonData to ca's NSJSONSerialization's dataWithJSONObject:resultRecord options:0 |error|:missing value
+                set jsonString to (ca's NSString's alloc()'s initWithData:jsonData encoding:4) as string
+                return jsonString
+            end run
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .args(playlist_pids)
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Playlist Track Fetch Failed: {}", err));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        return Ok(serde_json::from_str(&stdout)?);
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        Ok(HashMap::new())
+    }
+}
+
+/// Helper to "touch" a file, updating its modification time.
+/// This helps Rekordbox and Finder notice that the file has changed.
+pub fn touch_file(path: &str) -> Result<()> {
+   #[cfg(target_os = "macos")]
+   {
+        Command::new("touch")
+            .arg(path)
+            .output()?;
+   }
+   Ok(())
+}
+
+/// Adds a track to a playlist in Apple Music (iTunes) by their Persistent IDs.
+pub fn add_track_to_playlist(track_pid: &str, playlist_pid: &str) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            on run argv
+                if application "Music" is running then
+                    tell application "Music"
+                        try
+                            set theTrack to (first track whose persistent ID is (item 1 of argv))
+                            set thePlaylist to (first playlist whose persistent ID is (item 2 of argv))
+                            duplicate theTrack to thePlaylist
+                        on error errMsg
+                             -- ignore errors
+                        end try
+                    end tell
+                end if
+            end run
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .arg(track_pid)
+            .arg(playlist_pid)
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Add To Playlist Failed: {}", err));
+        }
+    }
+    Ok(())
+}
+
+/// Adds (track persistent ID, playlist persistent ID) pairs through one JXA call. Each
+/// playlist is looked up once; a track already in the playlist is not added twice.
+fn batch_add_to_playlists(adds: Vec<(String, String)>) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        if adds.is_empty() {
+            return Ok(());
+        }
+
+        #[derive(Serialize)]
+        struct PlaylistAdd {
+            track: String,
+            playlist: String,
+        }
+
+        let payload: Vec<PlaylistAdd> = adds
+            .into_iter()
+            .map(|(track, playlist)| PlaylistAdd { track, playlist })
+            .collect();
+        let json_arg = serde_json::to_string(&payload)?;
+
+        let script = r#"
+        function run(argv) {
+            const app = Application('Music');
+            if (!app.running()) return;
+
+            const adds = JSON.parse(argv[0]);
+            const playlists = {};
+            const members = {};
+
+            adds.forEach(function(item) {
+                try {
+                    if (!(item.playlist in playlists)) {
+                        const found = app.playlists.whose({ persistentID: item.playlist });
+                        playlists[item.playlist] = found.length > 0 ? found[0] : null;
+                        members[item.playlist] = playlists[item.playlist]
+                            ? playlists[item.playlist].tracks.persistentID()
+                            : [];
+                    }
+                    const playlist = playlists[item.playlist];
+                    if (!playlist || members[item.playlist].indexOf(item.track) !== -1) return;
+
+                    const tracks = app.tracks.whose({ persistentID: item.track });
+                    if (tracks.length > 0) {
+                        app.duplicate(tracks[0], { to: playlist });
+                        members[item.playlist].push(item.track);
+                    }
+                } catch (e) {
+                    // Swallow errors for individual tracks so the batch continues
+                }
+            });
+        }
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-l")
+            .arg("JavaScript")
+            .arg("-e")
+            .arg(script)
+            .arg(json_arg)
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("JXA Batch Playlist Add Failed: {}", err));
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = adds;
+
+    Ok(())
+}
+
+/// Removes a track from a playlist in Apple Music by their Persistent IDs.
+pub fn remove_track_from_playlist(track_pid: &str, playlist_pid: &str) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            on run argv
+                if application "Music" is running then
+                    tell application "Music"
+                        try
+                            set thePlaylist to (first playlist whose persistent ID is (item 1 of argv))
+                            delete (every track of thePlaylist whose persistent ID is (item 2 of argv))
+                        end try
+                    end tell
+                end if
+            end run
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .arg(playlist_pid)
+            .arg(track_pid)
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Remove From Playlist Failed: {}", err));
+        }
+    }
+    Ok(())
+}
+
+/// Moves the playlist's tracks to its end one by one in `order`, then returns the order
+/// Music.app reports afterwards. `order` must hold every track of the playlist; each move
+/// takes the first not-yet-moved copy of a track, so duplicates work. Keeps each entry's
+/// date added to the playlist. Returns `None` when Music.app isn't running.
+#[cfg(target_os = "macos")]
+fn move_playlist_tracks(playlist_pid: &str, order: &[String]) -> Result<Option<Vec<String>>> {
+    // argv: the playlist, then the tracks in their new order
+    let script = r#"
+        on run argv
+            if application "Music" is not running then return "not running"
+            tell application "Music"
+                set thePlaylist to (first playlist whose persistent ID is (item 1 of argv))
+                repeat with pid in rest of argv
+                    move (first track of thePlaylist whose persistent ID is (pid as text)) to end of thePlaylist
+                end repeat
+                set AppleScript's text item delimiters to linefeed
+                return "order" & linefeed & ((persistent ID of every track of thePlaylist) as text)
+            end tell
+        end run
+    "#;
+
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .arg(playlist_pid)
+        .args(order)
+        .output()?;
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!("AppleScript Move Playlist Tracks Failed: {}", err));
+    }
+    Ok(parse_playlist_order(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Reads the "order" reply of the reorder scripts; `None` means Music.app wasn't running.
+#[cfg(target_os = "macos")]
+fn parse_playlist_order(stdout: &str) -> Option<Vec<String>> {
+    let mut lines = stdout.lines().map(str::trim);
+    if lines.next() != Some("order") {
+        return None;
+    }
+    Some(lines.filter(|l| !l.is_empty()).map(str::to_string).collect())
+}
+
+/// Empties a playlist and refills it with `order`, then returns the order Music.app reports
+/// afterwards. The fallback for when moving tracks fails: every entry's date added to the
+/// playlist is reset. Every track is looked up in the library before anything is deleted,
+/// so a missing track fails the script with the playlist untouched. Returns `None` when
+/// Music.app isn't running.
+#[cfg(target_os = "macos")]
+fn refill_playlist(playlist_pid: &str, order: &[String]) -> Result<Option<Vec<String>>> {
+    // argv: the playlist, then the tracks in their new order
+    let script = r#"
+        on run argv
+            if application "Music" is not running then return "not running"
+            tell application "Music"
+                set thePlaylist to (first playlist whose persistent ID is (item 1 of argv))
+                -- Library references: the playlist's own go stale once its tracks are deleted
+                set trackRefs to {}
+                repeat with pid in rest of argv
+                    set end of trackRefs to (first track of library playlist 1 whose persistent ID is (pid as text))
+                end repeat
+                delete every track of thePlaylist
+                repeat with aTrack in trackRefs
+                    duplicate aTrack to thePlaylist
+                end repeat
+                set AppleScript's text item delimiters to linefeed
+                return "order" & linefeed & ((persistent ID of every track of thePlaylist) as text)
+            end tell
+        end run
+    "#;
+
+    let output = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .arg(playlist_pid)
+        .args(order)
+        .output()?;
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!("AppleScript Reorder Playlist Failed: {}", err));
+    }
+    Ok(parse_playlist_order(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The order a reorder should leave: the wanted tracks that are in the playlist, then the
+/// rest in their current order. Counted per copy, so a track listed twice in the playlist
+/// stays there twice.
+#[cfg(target_os = "macos")]
+fn expected_playlist_order(current: &[String], wanted: &[String]) -> Vec<String> {
+    let mut available: HashMap<&str, usize> = HashMap::new();
+    for pid in current {
+        *available.entry(pid.as_str()).or_default() += 1;
+    }
+
+    let mut placed: HashMap<&str, usize> = HashMap::new();
+    let mut expected = Vec::with_capacity(current.len());
+    for pid in wanted {
+        if let Some(left) = available.get_mut(pid.as_str()).filter(|left| **left > 0) {
+            *left -= 1;
+            *placed.entry(pid.as_str()).or_default() += 1;
+            expected.push(pid.clone());
+        }
+    }
+    for pid in current {
+        match placed.get_mut(pid.as_str()) {
+            Some(n) if *n > 0 => *n -= 1,
+            _ => expected.push(pid.clone()),
+        }
+    }
+    expected
+}
+
+/// Reorders the tracks of an Apple Music playlist by moving them into place, which keeps
+/// their date added to the playlist. If the moves fail or leave a different order, the
+/// playlist is emptied and refilled in order instead, which resets those dates. The current
+/// order is read first and the final one read back; if the refill fails too, the original
+/// is put back and an error returned.
+pub fn reorder_playlist(playlist_pid: &str, track_pids: &[String]) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let Some(original) = get_playlist_track_pids(&[playlist_pid.to_string()])?.remove(playlist_pid) else {
+            return Err(anyhow::anyhow!("Playlist {} not found in Music.app", playlist_pid));
+        };
+        let expected = expected_playlist_order(&original, track_pids);
+        if original == expected {
+            return Ok(());
+        }
+
+        match move_playlist_tracks(playlist_pid, &expected) {
+            Ok(None) => return Ok(()),
+            Ok(Some(order)) if order == expected => return Ok(()),
+            Ok(Some(_)) => tracing::warn!("Moving the tracks of playlist {} left the wrong order; refilling it", playlist_pid),
+            Err(e) => tracing::warn!("Could not move the tracks of playlist {} ({}); refilling it", playlist_pid, e),
+        }
+
+        let failure = match refill_playlist(playlist_pid, &expected) {
+            Ok(None) => return Ok(()),
+            Ok(Some(order)) if order == expected => return Ok(()),
+            Ok(Some(_)) => "final order did not match".to_string(),
+            Err(e) => e.to_string(),
+        };
+
+        tracing::warn!("Reorder of playlist {} failed ({}); restoring the original order", playlist_pid, failure);
+        return match refill_playlist(playlist_pid, &original) {
+            Ok(Some(order)) if order == original => {
+                Err(anyhow::anyhow!("Reorder of playlist {} failed and was rolled back: {}", playlist_pid, failure))
+            }
+            Ok(_) => Err(anyhow::anyhow!(
+                "Reorder of playlist {} failed ({}) and the original order could not be verified",
+                playlist_pid,
+                failure
+            )),
+            Err(e) => Err(anyhow::anyhow!(
+                "Reorder of playlist {} failed ({}) and rolling back failed too: {}",
+                playlist_pid,
+                failure,
+                e
+            )),
+        };
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (playlist_pid, track_pids);
+        Ok(())
+    }
+}
+
+/// Gets the played count for a track in Apple Music by its Persistent ID.
+pub fn get_play_count(track_pid: &str) -> Result<i64> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            on run argv
+                tell application "Music"
+                    try
+                        set theTrack to (first track whose persistent ID is (item 1 of argv))
+                        return played count of theTrack
+                    on error
+                        return 0
+                    end try
+                end tell
+            end run
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .arg(track_pid)
+            .output()?;
+
+        if output.status.success() {
+            let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            return Ok(stdout.parse::<i64>().unwrap_or(0));
+        }
+        return Ok(0);
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        Ok(0)
+    }
+}
+
+/// Sets the played count for a track in Apple Music by its Persistent ID.
+pub fn set_play_count(track_pid: &str, count: i64) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            on run argv
+                if application "Music" is running then
+                    tell application "Music"
+                        try
+                            set theTrack to (first track whose persistent ID is (item 1 of argv))
+                            set played count of theTrack to ((item 2 of argv) as integer)
+                        end try
+                    end tell
+                end if
+            end run
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .arg(track_pid)
+            .arg(count.to_string())
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Set Play Count Failed: {}", err));
+        }
+    }
+    Ok(())
+}
+
+/// Sets the loved (favorited) flag of a Music.app track.
+pub fn set_track_loved(track_pid: &str, loved: bool) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        // `loved` was renamed `favorited` in macOS 14
+        let script = r#"
+            on run argv
+                if application "Music" is running then
+                    tell application "Music"
+                        set theTrack to (first track whose persistent ID is (item 1 of argv))
+                        set newValue to ((item 2 of argv) is "true")
+                        try
+                            set favorited of theTrack to newValue
+                        on error
+                            set loved of theTrack to newValue
+                        end try
+                    end tell
+                end if
+            end run
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .arg(track_pid)
+            .arg(if loved { "true" } else { "false" })
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Set Loved Failed: {}", err));
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (track_pid, loved);
+    Ok(())
+}
+
+/// Points a Music.app track at a new file, e.g. after the library moved to another drive.
+/// Both values are passed as arguments rather than spliced into the script.
+pub fn set_track_location(track_pid: &str, path: &str) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            on run argv
+                if application "Music" is running then
+                    tell application "Music"
+                        set theTrack to (first track whose persistent ID is (item 1 of argv))
+                        set location of theTrack to (POSIX file (item 2 of argv))
+                    end tell
+                end if
+            end run
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .arg(track_pid)
+            .arg(path)
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Set Location Failed: {}", err));
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (track_pid, path);
+    Ok(())
+}
+
+/// Adds an audio file to the Music.app library and returns the new track's Persistent ID.
+/// The path is passed as an argument rather than spliced into the script.
+pub fn add_file_to_library(path: &str) -> Result<String> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            on run argv
+                tell application "Music"
+                    set newTrack to add (POSIX file (item 1 of argv))
+                    return persistent ID of newTrack
+                end tell
+            end run
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .arg(path)
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Add File Failed: {}", err));
+        }
+
+        let pid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if pid.is_empty() {
+            return Err(anyhow::anyhow!("Music.app did not return a persistent ID for {}", path));
+        }
+        return Ok(pid);
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = path;
+        Err(anyhow::anyhow!("Adding files to Music.app is only supported on macOS"))
+    }
+}
+
+/// Deletes tracks from the Music.app library (and so from every playlist) and returns the
+/// persistent IDs it found and deleted. Leaves the audio files where they are.
+pub fn delete_tracks_from_library(track_pids: &[String]) -> Result<Vec<String>> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            on run argv
+                set deletedIds to {}
+                tell application "Music"
+                    repeat with pid in argv
+                        set matches to (every track of library playlist 1 whose persistent ID is (pid as text))
+                        if (count of matches) > 0 then
+                            delete (every track of library playlist 1 whose persistent ID is (pid as text))
+                            set end of deletedIds to (pid as text)
+                        end if
+                    end repeat
+                end tell
+                set AppleScript's text item delimiters to linefeed
+                return deletedIds as text
+            end run
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .args(track_pids)
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Delete Tracks Failed: {}", err));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        return Ok(stdout.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect());
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = track_pids;
+        Err(anyhow::anyhow!("Deleting tracks from Music.app is only supported on macOS"))
+    }
+}
+
+/// Creates a new (empty) user playlist in Music.app and returns its Persistent ID.
+pub fn create_playlist(name: &str) -> Result<String> {
+    if crate::file_library::is_file_only() {
+        return crate::file_library::create_playlist_file(name);
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            on run argv
+                tell application "Music"
+                    set newPlaylist to make new user playlist with properties {name:(item 1 of argv)}
+                    return persistent ID of newPlaylist
+                end tell
+            end run
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .arg(name)
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Create Playlist Failed: {}", err));
+        }
+
+        let pid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if pid.is_empty() {
+            return Err(anyhow::anyhow!("Music.app did not return a persistent ID for playlist {}", name));
+        }
+        return Ok(pid);
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = name;
+        Err(anyhow::anyhow!("Creating playlists in Music.app is only supported on macOS"))
+    }
+}
+
+/// Creates a playlist folder in Music.app, optionally inside another folder,
+/// and returns its Persistent ID.
+pub fn create_playlist_folder(name: &str, parent_pid: Option<&str>) -> Result<String> {
+    if crate::file_library::is_file_only() {
+        return Err(anyhow::anyhow!("Playlist folders are not available in file-only mode"));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            on run argv
+                tell application "Music"
+                    set newFolder to make new folder playlist with properties {name:(item 1 of argv)}
+                    if (count of argv) > 1 then
+                        move newFolder to (first folder playlist whose persistent ID is (item 2 of argv))
+                    end if
+                    return persistent ID of newFolder
+                end tell
+            end run
+        "#;
+
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(script).arg(name);
+        if let Some(parent) = parent_pid {
+            command.arg(parent);
+        }
+        let output = command.output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Create Folder Failed: {}", err));
+        }
+
+        let pid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if pid.is_empty() {
+            return Err(anyhow::anyhow!("Music.app did not return a persistent ID for folder {}", name));
+        }
+        return Ok(pid);
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (name, parent_pid);
+        Err(anyhow::anyhow!("Creating playlist folders in Music.app is only supported on macOS"))
+    }
+}
+
+/// Moves a playlist or folder into `parent_pid`, or to the top level when `None`.
+pub fn move_playlist(playlist_pid: &str, parent_pid: Option<&str>) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            on run argv
+                if application "Music" is running then
+                    tell application "Music"
+                        set thePlaylist to (first playlist whose persistent ID is (item 1 of argv))
+                        if (count of argv) > 1 then
+                            move thePlaylist to (first folder playlist whose persistent ID is (item 2 of argv))
+                        else
+                            move thePlaylist to source 1
+                        end if
+                    end tell
+                end if
+            end run
+        "#;
+
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(script).arg(playlist_pid);
+        if let Some(parent) = parent_pid {
+            command.arg(parent);
+        }
+        let output = command.output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Move Playlist Failed: {}", err));
+        }
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = (playlist_pid, parent_pid);
+    Ok(())
+}
+
+/// Fetches all persistent IDs from Music.app efficiently using batch property access.
+/// Returns a HashSet of persistent IDs for fast lookup.
+pub fn get_all_music_app_pids() -> Result<std::collections::HashSet<String>> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            use framework "Foundation"
+            use scripting additions
+
+            tell application "Music"
+                set allIds to persistent ID of every track
+            end tell
+
+            set ca to current application
+            set jsonData to ca's NSJSONSerialization's dataWithJSONObject:allIds options:0 |error|:missing value
+            set jsonString to (ca's NSString's alloc()'s initWithData:jsonData encoding:4) as string
+            return jsonString
         "#;
 
         let output = Command::new("osascript")
@@ -725,71 +1608,92 @@ pub fn get_tracks_by_persistent_ids(pids: &[String]) -> Result<Vec<Track>> {
 
         // Process in batches of 50 to avoid AppleScript timeout
         for chunk in pids.chunks(50) {
-            let pid_list: Vec<String> = chunk.iter()
-                .map(|pid| format!("\"{}\"" , pid))
-                .collect();
-            let pid_array = pid_list.join(", ");
-
-            let script = format!(
-                r#"
+            let script = r#"
                 use framework "Foundation"
                 use scripting additions
 
-                set pidList to {{{}}}
-                set resultList to {{}}
+                on run argv
+                    set resultList to {}
+                    -- AS dates are local wall-clock time; subtracting this yields Unix seconds
+                    set epochDate to (current date) - ((current application's NSDate's |date|()'s timeIntervalSince1970()) as integer)
 
-                tell application "Music"
-                    repeat with pid in pidList
-                        try
-                            set t to (first track whose persistent ID is pid)
-                            set tId to persistent ID of t
-                            set tName to name of t
-                            set tArtist to artist of t
-                            set tAlbum to album of t
-                            set tComment to comment of t
-                            set tGrouping to grouping of t
-                            set tDuration to duration of t
-                            set tKind to kind of t
-                            set tSize to size of t
-                            set tBitRate to bit rate of t
-                            set tRating to rating of t
-                            set tBpm to bpm of t
-
-                            set tLoc to ""
+                    tell application "Music"
+                        repeat with pid in argv
                             try
-                                set tLoc to POSIX path of (location of t as alias)
-                            on error
+                                set t to (first track whose persistent ID is (pid as text))
+                                set tId to persistent ID of t
+                                set tName to name of t
+                                set tArtist to artist of t
+                                set tAlbum to album of t
+                                set tComment to comment of t
+                                set tGrouping to grouping of t
+                                set tDuration to duration of t
+                                set tKind to kind of t
+                                set tSize to size of t
+                                set tBitRate to bit rate of t
+                                set tRating to rating of t
+                                set tBpm to bpm of t
+                                set tPlays to played count of t
+                                set tSkips to skipped count of t
+                                set tLastPlayed to 0
+                                try
+                                    set tLastPlayed to ((played date of t) - epochDate) as integer
+                                end try
+                                set tModified to 0
+                                set tAdded to 0
+                                try
+                                    set tModified to ((modification date of t) - epochDate) as integer
+                                end try
+                                try
+                                    set tAdded to ((date added of t) - epochDate) as integer
+                                end try
+                                set tLoved to false
                                 try
-                                    set fileRef to location of t
-                                    set fileURL to current application's NSURL's fileURLWithPath:(POSIX path of (fileRef as text))
-                                    set tLoc to (fileURL's |path|()) as text
+                                    set tLoved to favorited of t
                                 on error
-                                    set tLoc to ""
+                                    try
+                                        set tLoved to loved of t
+                                    end try
                                 end try
-                            end try
 
-                            set entry to {{|id|:tId, |name|:tName, |artist|:tArtist, |album|:tAlbum, |comment|:tComment, |grouping|:tGrouping, |duration|:tDuration, |kind|:tKind, |size|:tSize, |bitRate|:tBitRate, |rating|:tRating, |bpm|:tBpm, |location|:tLoc}}
-                            copy entry to end of resultList
-                        end try
-                    end repeat
-                end tell
+                                set tLoc to ""
+                                try
+                                    set tLoc to POSIX path of (location of t as alias)
+                                on error
+                                    try
+                                        set fileRef to location of t
+                                        set fileURL to current application's NSURL's fileURLWithPath:(POSIX path of (fileRef as text))
+                                        set tLoc to (fileURL's |path|()) as text
+                                    on error
+                                        set tLoc to ""
+                                    end try
+                                end try
 
-                set ca to current application
-                set jsonData to ca's NSJSONSerialization's dataWithJSONObject:resultList options:0 |error|:missing value
-                set jsonString to (ca's NSString's alloc()'s initWithData:jsonData encoding:4) as string
-                return jsonString
-                "#,
-                pid_array
-            );
+                                -- Apple Music/iCloud items are shared tracks (Track Type "Remote" in the XML)
+                                set tRemote to (class of t is shared track)
+
+                                set entry to {|id|:tId, |name|:tName, |artist|:tArtist, |album|:tAlbum, |comment|:tComment, |grouping|:tGrouping, |duration|:tDuration, |kind|:tKind, |size|:tSize, |bitRate|:tBitRate, |rating|:tRating, |bpm|:tBpm, |location|:tLoc, |remote|:tRemote, |playCount|:tPlays, |skipCount|:tSkips, |lastPlayed|:tLastPlayed, |loved|:tLoved, |modifiedDate|:tModified, |dateAdded|:tAdded}
+                                copy entry to end of resultList
+                            end try
+                        end repeat
+                    end tell
+
+                    set ca to current application
+                    set jsonData to ca's NSJSONSerialization's dataWithJSONObject:resultList options:0 |error|:missing value
+                    set jsonString to (ca's NSString's alloc()'s initWithData:jsonData encoding:4) as string
+                    return jsonString
+                end run
+            "#;
 
             let output = Command::new("osascript")
                 .arg("-e")
-                .arg(&script)
+                .arg(script)
+                .args(chunk)
                 .output()?;
 
             if !output.status.success() {
                 let err = String::from_utf8_lossy(&output.stderr);
-                eprintln!("AppleScript error fetching tracks by PID: {}", err);
+                tracing::error!("AppleScript error fetching tracks by PID: {}", err);
                 continue; // Skip this batch but keep going
             }
 
@@ -797,13 +1701,14 @@ pub fn get_tracks_by_persistent_ids(pids: &[String]) -> Result<Vec<Track>> {
             let jxa_tracks: Vec<JxaTrack> = match serde_json::from_str(&stdout) {
                 Ok(t) => t,
                 Err(e) => {
-                    eprintln!("JSON parse error for track batch: {}", e);
+                    tracing::error!("JSON parse error for track batch: {}", e);
                     continue;
                 }
             };
 
             for jt in jxa_tracks {
                 let path = jt.location.unwrap_or_default();
+                let is_cloud = path.is_empty() && jt.remote;
                 all_tracks.push(Track {
                     id: 0,
                     persistent_id: jt.id,
@@ -817,11 +1722,22 @@ pub fn get_tracks_by_persistent_ids(pids: &[String]) -> Result<Vec<Track>> {
                     format: jt.kind,
                     size_bytes: jt.size,
                     bit_rate: jt.bit_rate,
-                    modified_date: 0,
+                    modified_date: jt.modified_date,
                     rating: jt.rating,
-                    date_added: 0,
-                    bpm: jt.bpm,
+                    date_added: jt.date_added,
+                    bpm: jt.bpm as f64,
                     missing: false,
+                    play_count: jt.play_count,
+                    skip_count: jt.skip_count,
+                    last_played: jt.last_played,
+                    loved: jt.loved,
+                    notes: None,
+                    intro_bars: None,
+                    outro_bars: None,
+                    audio_health: None,
+                    color_label: None,
+                    offloaded: false,
+                    is_cloud,
                 });
             }
         }
@@ -840,54 +1756,561 @@ pub fn get_tracks_by_persistent_ids(pids: &[String]) -> Result<Vec<Track>> {
 pub fn update_track_info(persistent_id: &str, name: Option<&str>, artist: Option<&str>, album: Option<&str>, bpm: Option<i64>) -> Result<()> {
     #[cfg(target_os = "macos")]
     {
-        let mut set_lines = Vec::new();
-        if let Some(n) = name {
-            let escaped = n.replace('\\', "\\\\").replace('"', "\\\"");
-            set_lines.push(format!("set name of myTrack to \"{}\"", escaped));
-        }
-        if let Some(a) = artist {
-            let escaped = a.replace('\\', "\\\\").replace('"', "\\\"");
-            set_lines.push(format!("set artist of myTrack to \"{}\"", escaped));
-        }
-        if let Some(al) = album {
-            let escaped = al.replace('\\', "\\\\").replace('"', "\\\"");
-            set_lines.push(format!("set album of myTrack to \"{}\"", escaped));
+        // (field, value) pairs, passed after the persistent ID
+        let mut fields: Vec<String> = Vec::new();
+        for (field, value) in [("name", name), ("artist", artist), ("album", album)] {
+            if let Some(value) = value {
+                fields.push(field.to_string());
+                fields.push(value.to_string());
+            }
         }
         if let Some(b) = bpm {
-            set_lines.push(format!("set bpm of myTrack to {}", b));
+            fields.push("bpm".to_string());
+            fields.push(b.to_string());
         }
 
-        if set_lines.is_empty() {
+        if fields.is_empty() {
             return Ok(());
         }
 
-        let set_block = set_lines.join("\n                            ");
+        let script = r#"
+            on run argv
+                if application "Music" is running then
+                    tell application "Music"
+                        try
+                            set myTracks to (every track whose persistent ID is (item 1 of argv))
+                            if (count of myTracks) > 0 then
+                                set myTrack to item 1 of myTracks
+                                repeat with i from 2 to (count of argv) by 2
+                                    set fieldName to item i of argv
+                                    set fieldValue to item (i + 1) of argv
+                                    if fieldName is "name" then
+                                        set name of myTrack to fieldValue
+                                    else if fieldName is "artist" then
+                                        set artist of myTrack to fieldValue
+                                    else if fieldName is "album" then
+                                        set album of myTrack to fieldValue
+                                    else if fieldName is "bpm" then
+                                        set bpm of myTrack to (fieldValue as integer)
+                                    end if
+                                end repeat
+                            end if
+                        end try
+                    end tell
+                end if
+            end run
+        "#;
+
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .arg(persistent_id)
+            .args(&fields)
+            .output()?;
+
+        if !output.status.success() {
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Track Info Update Failed: {}", err));
+        }
+    }
+    Ok(())
+}
 
-        let script = format!(
-            r#"
+/// Persistent ID of the track Music.app is playing, or `None` when it is stopped,
+/// paused or not running. Never launches it.
+pub fn get_playing_track_pid() -> Result<Option<String>> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
             if application "Music" is running then
                 tell application "Music"
                     try
-                        set myTracks to (every track whose persistent ID is "{}")
-                        if (count of myTracks) > 0 then
-                            set myTrack to item 1 of myTracks
-                            {}
+                        if player state is playing then
+                            return persistent ID of current track
                         end if
                     end try
                 end tell
             end if
-            "#,
-            persistent_id, set_block
-        );
+            return ""
+            "#;
 
         let output = Command::new("osascript")
             .arg("-e")
-            .arg(&script)
+            .arg(script)
             .output()?;
 
         if !output.status.success() {
-            eprintln!("AppleScript error (update_track_info): {}", String::from_utf8_lossy(&output.stderr));
+            let err = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!("AppleScript Now Playing Failed: {}", err));
+        }
+        let pid = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        return Ok(Some(pid).filter(|p| !p.is_empty()));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        Ok(None)
+    }
+}
+
+/// Returns true if Music.app is currently running. Never launches it.
+pub fn is_music_running() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        return Command::new("osascript")
+            .arg("-e")
+            .arg(r#"application "Music" is running"#)
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim() == "true")
+            .unwrap_or(false);
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        crate::itunes_com::is_running()
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        false
+    }
+}
+
+// ─── Worker Queue ────────────────────────────────────────────────
+
+/// Attempts for a single update before it is parked as failed and `apple-update-failed` is emitted.
+const MAX_ATTEMPTS: u32 = 5;
+/// First retry delay; doubles on each failed attempt.
+const BASE_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// How often the worker re-checks whether Music.app (iTunes on Windows) has been (re)opened.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+const MUSIC_CLOSED_POLL: Duration = Duration::from_secs(10);
+
+/// A single write destined for Music.app.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppleUpdate {
+    Comment { persistent_id: String, comment: String },
+    /// (persistent_id, comment) pairs applied through one JXA call.
+    Comments { updates: Vec<(String, String)> },
+    /// (persistent_id, grouping) pairs applied through one JXA call.
+    Groupings { updates: Vec<(String, String)> },
+    Rating { persistent_id: String, rating: u32 },
+    TrackInfo {
+        persistent_id: String,
+        name: Option<String>,
+        artist: Option<String>,
+        album: Option<String>,
+        bpm: Option<i64>,
+    },
+    AddToPlaylist { track_pid: String, playlist_pid: String },
+    /// (track_pid, playlist_pid) pairs added through one JXA call.
+    PlaylistAdds { adds: Vec<(String, String)> },
+    RemoveFromPlaylist { track_pid: String, playlist_pid: String },
+    ReorderPlaylist { playlist_pid: String, track_pids: Vec<String> },
+    PlayCount { persistent_id: String, count: i64 },
+    Location { persistent_id: String, path: String },
+    Loved { persistent_id: String, loved: bool },
+    /// `parent_pid` of `None` moves the playlist to the top level
+    MovePlaylist { playlist_pid: String, parent_pid: Option<String> },
+}
+
+impl AppleUpdate {
+    /// One update adding `track_pids` to a playlist in order, applied in a single call.
+    pub fn playlist_adds<'a>(track_pids: impl IntoIterator<Item = &'a str>, playlist_pid: &str) -> Self {
+        AppleUpdate::PlaylistAdds {
+            adds: track_pids.into_iter().map(|pid| (pid.to_string(), playlist_pid.to_string())).collect(),
+        }
+    }
+
+    fn apply(&self) -> Result<()> {
+        // With the tags kept in their own file frame, Music.app only gets the user comment
+        if let Some(update) = self.with_music_app_comments() {
+            return update.apply_update();
+        }
+        self.apply_update()
+    }
+
+    /// A copy with the comments as Music.app should show them, when they differ.
+    fn with_music_app_comments(&self) -> Option<AppleUpdate> {
+        if crate::metadata::tag_storage() == crate::metadata::TagStorage::Comment {
+            return None;
+        }
+        match self {
+            AppleUpdate::Comment { persistent_id, comment } => Some(AppleUpdate::Comment {
+                persistent_id: persistent_id.clone(),
+                comment: crate::metadata::music_app_comment(comment),
+            }),
+            AppleUpdate::Comments { updates } => Some(AppleUpdate::Comments {
+                updates: updates.iter()
+                    .map(|(pid, comment)| (pid.clone(), crate::metadata::music_app_comment(comment)))
+                    .collect(),
+            }),
+            _ => None,
+        }
+    }
+
+    fn apply_update(&self) -> Result<()> {
+        #[cfg(all(target_os = "macos", feature = "scripting-bridge"))]
+        if let Some(result) = crate::scripting_bridge::apply(self) {
+            return result;
+        }
+
+        #[cfg(target_os = "windows")]
+        if let Some(result) = crate::itunes_com::apply(self) {
+            return result;
+        }
+
+        match self {
+            AppleUpdate::Comment { persistent_id, comment } => update_track_comment(persistent_id, comment),
+            AppleUpdate::Comments { updates } => batch_update_track_comments(updates.clone()),
+            AppleUpdate::Groupings { updates } => batch_update_track_groupings(updates.clone()),
+            AppleUpdate::Rating { persistent_id, rating } => update_track_rating(persistent_id, *rating),
+            AppleUpdate::TrackInfo { persistent_id, name, artist, album, bpm } => update_track_info(
+                persistent_id,
+                name.as_deref(),
+                artist.as_deref(),
+                album.as_deref(),
+                *bpm,
+            ),
+            AppleUpdate::AddToPlaylist { track_pid, playlist_pid } => add_track_to_playlist(track_pid, playlist_pid),
+            AppleUpdate::PlaylistAdds { adds } => batch_add_to_playlists(adds.clone()),
+            AppleUpdate::RemoveFromPlaylist { track_pid, playlist_pid } => remove_track_from_playlist(track_pid, playlist_pid),
+            AppleUpdate::ReorderPlaylist { playlist_pid, track_pids } => reorder_playlist(playlist_pid, track_pids),
+            AppleUpdate::PlayCount { persistent_id, count } => set_play_count(persistent_id, *count),
+            AppleUpdate::Location { persistent_id, path } => set_track_location(persistent_id, path),
+            AppleUpdate::Loved { persistent_id, loved } => set_track_loved(persistent_id, *loved),
+            AppleUpdate::MovePlaylist { playlist_pid, parent_pid } => move_playlist(playlist_pid, parent_pid.as_deref()),
+        }
+    }
+
+    /// Short human-readable description for logs and the pending-updates view.
+    pub fn describe(&self) -> String {
+        match self {
+            AppleUpdate::Comment { persistent_id, .. } => format!("Update comment of {}", persistent_id),
+            AppleUpdate::Comments { updates } => format!("Update comments of {} tracks", updates.len()),
+            AppleUpdate::Groupings { updates } => format!("Update groupings of {} tracks", updates.len()),
+            AppleUpdate::Rating { persistent_id, rating } => format!("Set rating of {} to {}", persistent_id, rating),
+            AppleUpdate::TrackInfo { persistent_id, .. } => format!("Update track info of {}", persistent_id),
+            AppleUpdate::AddToPlaylist { track_pid, playlist_pid } => format!("Add {} to playlist {}", track_pid, playlist_pid),
+            AppleUpdate::PlaylistAdds { adds } => format!("Add {} playlist entries", adds.len()),
+            AppleUpdate::RemoveFromPlaylist { track_pid, playlist_pid } => format!("Remove {} from playlist {}", track_pid, playlist_pid),
+            AppleUpdate::ReorderPlaylist { playlist_pid, track_pids } => format!("Reorder playlist {} ({} tracks)", playlist_pid, track_pids.len()),
+            AppleUpdate::PlayCount { persistent_id, count } => format!("Set play count of {} to {}", persistent_id, count),
+            AppleUpdate::Location { persistent_id, path } => format!("Relocate {} to {}", persistent_id, path),
+            AppleUpdate::Loved { persistent_id, loved } => {
+                format!("{} {}", if *loved { "Love" } else { "Unlove" }, persistent_id)
+            }
+            AppleUpdate::MovePlaylist { playlist_pid, parent_pid } => match parent_pid {
+                Some(parent) => format!("Move playlist {} into folder {}", playlist_pid, parent),
+                None => format!("Move playlist {} to the top level", playlist_pid),
+            },
+        }
+    }
+}
+
+/// An update waiting in (or being retried by) the worker queue.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingUpdate {
+    pub id: u64,
+    pub description: String,
+    pub update: AppleUpdate,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub queued_at: i64,
+    /// Gave up after `MAX_ATTEMPTS`; kept (and journaled) until retried or discarded
+    pub failed: bool,
+    /// Row in the `pending_sync` journal, if the update was persisted.
+    #[serde(skip)]
+    journal_id: Option<i64>,
+    /// Not tried again before this; set by the backoff after a failure
+    #[serde(skip)]
+    retry_at: Option<Instant>,
+}
+
+impl PendingUpdate {
+    fn is_due(&self, now: Instant) -> bool {
+        !self.failed && self.retry_at.is_none_or(|at| at <= now)
+    }
+}
+
+struct QueueInner {
+    pending: Mutex<VecDeque<PendingUpdate>>,
+    ready: Condvar,
+    next_id: AtomicU64,
+    /// Dedicated connection for the offline journal, so updates survive Music.app
+    /// being closed and TagDeck being restarted before they are applied.
+    journal: Option<Mutex<Database>>,
+}
+
+impl QueueInner {
+    fn with_journal<T>(&self, f: impl FnOnce(&Database) -> Result<T>) -> Option<T> {
+        let journal = self.journal.as_ref()?;
+        let db = journal.lock().unwrap_or_else(|e| e.into_inner());
+        match f(&db) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                tracing::error!("Journal error: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Serializes every Music.app write through one worker thread.
+/// Updates are applied in order; when Music.app is closed the queue waits for it.
+/// A failed update moves behind the others and is retried with exponential backoff;
+/// after `MAX_ATTEMPTS` it is parked as failed, never dropped without the user's say.
+pub struct SyncQueue {
+    inner: Arc<QueueInner>,
+}
+
+impl SyncQueue {
+    /// Creates the queue, restores any updates journaled by a previous session,
+    /// and spawns the worker thread.
+    pub fn start(app: AppHandle, journal: Option<Database>) -> Self {
+        let inner = Arc::new(QueueInner {
+            pending: Mutex::new(VecDeque::new()),
+            ready: Condvar::new(),
+            next_id: AtomicU64::new(1),
+            journal: journal.map(Mutex::new),
+        });
+
+        let restored = inner.with_journal(|db| db.get_pending_sync()).unwrap_or_default();
+        if !restored.is_empty() {
+            let mut pending = inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+            for (journal_id, payload, attempts, last_error, queued_at) in restored {
+                match serde_json::from_str::<AppleUpdate>(&payload) {
+                    Ok(update) => pending.push_back(PendingUpdate {
+                        id: inner.next_id.fetch_add(1, Ordering::SeqCst),
+                        description: update.describe(),
+                        update,
+                        attempts,
+                        last_error,
+                        queued_at,
+                        failed: attempts >= MAX_ATTEMPTS,
+                        journal_id: Some(journal_id),
+                        retry_at: None,
+                    }),
+                    Err(e) => {
+                        tracing::warn!("Discarding unreadable journal entry {}: {}", journal_id, e);
+                        inner.with_journal(|db| db.delete_pending_sync(journal_id));
+                    }
+                }
+            }
+            tracing::debug!("Restored {} pending Music.app update(s) from journal", pending.len());
+        }
+
+        let worker_inner = inner.clone();
+        thread::spawn(move || run_worker(worker_inner, app));
+
+        Self { inner }
+    }
+
+    /// Queues an update and returns its ID.
+    pub fn enqueue(&self, update: AppleUpdate) -> u64 {
+        let id = self.inner.next_id.fetch_add(1, Ordering::SeqCst);
+        let queued_at = chrono::Utc::now().timestamp();
+
+        let journal_id = serde_json::to_string(&update)
+            .ok()
+            .and_then(|payload| self.inner.with_journal(|db| db.insert_pending_sync(&payload, queued_at)));
+
+        let entry = PendingUpdate {
+            id,
+            description: update.describe(),
+            update,
+            attempts: 0,
+            last_error: None,
+            queued_at,
+            failed: false,
+            journal_id,
+            retry_at: None,
+        };
+
+        let mut pending = self.inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.push_back(entry);
+        self.inner.ready.notify_one();
+        id
+    }
+
+    /// Whether a comment write for this track is still waiting to reach Music.app.
+    pub fn has_pending_comment(&self, persistent_id: &str) -> bool {
+        let pending = self.inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.iter().any(|p| match &p.update {
+            AppleUpdate::Comment { persistent_id: pid, .. } => pid == persistent_id,
+            AppleUpdate::Comments { updates } => updates.iter().any(|(pid, _)| pid == persistent_id),
+            _ => false,
+        })
+    }
+
+    /// Drops the queued comment writes for this track, e.g. once the user settled a conflict
+    /// in favour of Music.app's comment. Batches lose just this track; the rest still go out.
+    pub fn drop_pending_comment(&self, persistent_id: &str) {
+        let mut pending = self.inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let mut kept = VecDeque::with_capacity(pending.len());
+        for entry in pending.drain(..) {
+            let remaining = match &entry.update {
+                AppleUpdate::Comment { persistent_id: pid, .. } if pid == persistent_id => None,
+                AppleUpdate::Comments { updates } if updates.iter().any(|(pid, _)| pid == persistent_id) => {
+                    Some(updates.iter().filter(|(pid, _)| pid != persistent_id).cloned().collect::<Vec<_>>())
+                }
+                _ => {
+                    kept.push_back(entry);
+                    continue;
+                }
+            };
+            if let Some(jid) = entry.journal_id {
+                self.inner.with_journal(|db| db.delete_pending_sync(jid));
+            }
+            if let Some(updates) = remaining.filter(|u| !u.is_empty()) {
+                // A new id and journal row, so a worker already applying the old batch
+                // doesn't take this one off the queue with it
+                let update = AppleUpdate::Comments { updates };
+                let journal_id = serde_json::to_string(&update).ok()
+                    .and_then(|payload| self.inner.with_journal(|db| db.insert_pending_sync(&payload, entry.queued_at)));
+                kept.push_back(PendingUpdate {
+                    id: self.inner.next_id.fetch_add(1, Ordering::SeqCst),
+                    description: update.describe(),
+                    update,
+                    journal_id,
+                    ..entry
+                });
+            }
+        }
+        *pending = kept;
+    }
+
+    /// Snapshot of everything not yet applied, in execution order, failed updates included.
+    pub fn pending(&self) -> Vec<PendingUpdate> {
+        let pending = self.inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending.iter().cloned().collect()
+    }
+
+    /// Gives a failed update a fresh set of attempts. Returns whether it was found.
+    pub fn retry(&self, id: u64) -> bool {
+        let mut pending = self.inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(entry) = pending.iter_mut().find(|p| p.id == id) else { return false };
+        entry.failed = false;
+        entry.attempts = 0;
+        entry.retry_at = None;
+        if let Some(jid) = entry.journal_id {
+            let err = entry.last_error.clone().unwrap_or_default();
+            self.inner.with_journal(|db| db.update_pending_sync(jid, 0, &err));
+        }
+        self.inner.ready.notify_one();
+        true
+    }
+
+    /// Removes an update from the queue and the journal. Returns whether it was found.
+    pub fn discard(&self, id: u64) -> bool {
+        let mut pending = self.inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(pos) = pending.iter().position(|p| p.id == id) else { return false };
+        if let Some(entry) = pending.remove(pos) {
+            tracing::info!("Discarded Music.app update: {}", entry.description);
+            if let Some(jid) = entry.journal_id {
+                self.inner.with_journal(|db| db.delete_pending_sync(jid));
+            }
+        }
+        true
+    }
+}
+
+fn backoff_for(attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    BASE_BACKOFF.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+fn run_worker(inner: Arc<QueueInner>, app: AppHandle) {
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    let mut waiting_for_music = false;
+
+    loop {
+        // Block until an update is due; it stays queued until it succeeds or is parked as failed.
+        let job = {
+            let mut pending = inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+            loop {
+                let now = Instant::now();
+                if let Some(job) = pending.iter().find(|p| p.is_due(now)) {
+                    break job.clone();
+                }
+                let next_retry = pending.iter().filter(|p| !p.failed).filter_map(|p| p.retry_at).min();
+                pending = match next_retry {
+                    Some(at) => inner.ready.wait_timeout(pending, at.saturating_duration_since(now))
+                        .map(|(guard, _)| guard)
+                        .unwrap_or_else(|e| e.into_inner().0),
+                    None => inner.ready.wait(pending).unwrap_or_else(|e| e.into_inner()),
+                };
+            }
+        };
+
+        let file_only = crate::file_library::is_file_only();
+
+        #[cfg(any(target_os = "macos", target_os = "windows"))]
+        if !file_only {
+            if !is_music_running() {
+                if !waiting_for_music {
+                    waiting_for_music = true;
+                    tracing::info!("Music.app is not running; holding pending updates until it opens");
+                }
+                thread::sleep(MUSIC_CLOSED_POLL);
+                continue;
+            }
+            if waiting_for_music {
+                waiting_for_music = false;
+                let count = inner.pending.lock().map(|p| p.len()).unwrap_or(0);
+                tracing::info!("Music.app is available; flushing {} pending update(s)", count);
+            }
+        }
+
+        let result = if file_only {
+            // Playlists live in M3U files and there is no Music.app to update
+            match &inner.journal {
+                Some(journal) => {
+                    let db = journal.lock().unwrap_or_else(|e| e.into_inner());
+                    crate::file_library::apply_file_update(&db, &job.update)
+                }
+                None => Ok(()),
+            }
+        } else {
+            job.update.apply()
+        };
+
+        let mut pending = inner.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(pos) = pending.iter().position(|p| p.id == job.id) else { continue };
+
+        match result {
+            Ok(()) => {
+                pending.remove(pos);
+                drop(pending);
+                if let Some(jid) = job.journal_id {
+                    inner.with_journal(|db| db.delete_pending_sync(jid));
+                }
+            }
+            Err(e) => {
+                let attempts = job.attempts + 1;
+                let failed = attempts >= MAX_ATTEMPTS;
+                let delay = backoff_for(attempts);
+                // Behind the others, so one bad update doesn't hold up the rest
+                let Some(mut entry) = pending.remove(pos) else { continue };
+                entry.attempts = attempts;
+                entry.last_error = Some(e.to_string());
+                entry.failed = failed;
+                entry.retry_at = Some(Instant::now() + delay);
+                pending.push_back(entry.clone());
+                drop(pending);
+                if let Some(jid) = job.journal_id {
+                    let err = e.to_string();
+                    inner.with_journal(|db| db.update_pending_sync(jid, attempts, &err));
+                }
+                if failed {
+                    tracing::error!("Music.app update failed {} times, parked until retried ({}): {}", attempts, job.description, e);
+                    let _ = app.emit("apple-update-failed", &entry);
+                } else {
+                    tracing::warn!("Music.app update failed ({}), retrying in {}s: {}", job.description, delay.as_secs(), e);
+                }
+            }
         }
     }
-    Ok(())
 }
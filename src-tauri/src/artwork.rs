@@ -0,0 +1,185 @@
+//! Cover art lookup for tracks without embedded artwork, using the iTunes Search API.
+//! Lookup only returns candidates; nothing is written until the caller picks one
+//! and calls `apply_artwork`.
+
+use crate::commands::AppState;
+use crate::models::Track;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const ITUNES_SEARCH_URL: &str = "https://itunes.apple.com/search";
+
+/// Tracks looked up per call when no `limit` is given; the Search API allows ~20 requests a minute.
+const DEFAULT_LOOKUP_LIMIT: usize = 20;
+
+/// Pause between Search API requests to stay under its rate limit.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(3);
+
+const CANDIDATES_PER_TRACK: usize = 5;
+
+/// Size requested from Apple's image CDN; results only list the 100px URL.
+const ARTWORK_SIZE: &str = "600x600bb";
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ArtworkCandidate {
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    pub artwork_url: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct ArtworkLookup {
+    pub track_id: i64,
+    pub candidates: Vec<ArtworkCandidate>,
+    /// Set when the lookup for this track failed
+    pub error: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ArtworkLookupProgress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+#[derive(serde::Deserialize)]
+struct SearchResponse {
+    results: Vec<SearchResult>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResult {
+    #[serde(default)]
+    artist_name: String,
+    #[serde(default)]
+    track_name: String,
+    #[serde(default)]
+    collection_name: String,
+    artwork_url100: Option<String>,
+}
+
+/// Client for every online lookup (artwork, release metadata, AcoustID).
+pub(crate) fn http_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        // MusicBrainz and Discogs reject requests without an identifying User-Agent
+        .user_agent(concat!("TagDeck/", env!("CARGO_PKG_VERSION"), " ( https://github.com/factor8/TagDeck )"))
+        .timeout(Duration::from_secs(20))
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+async fn search_artwork(client: &reqwest::Client, track: &Track) -> Result<Vec<ArtworkCandidate>, String> {
+    let term = format!(
+        "{} {}",
+        track.artist.as_deref().unwrap_or(""),
+        track.title.as_deref().unwrap_or("")
+    );
+    let response: SearchResponse = client.get(ITUNES_SEARCH_URL)
+        .query(&[
+            ("term", term.trim()),
+            ("media", "music"),
+            ("entity", "song"),
+            ("limit", &CANDIDATES_PER_TRACK.to_string()),
+        ])
+        .send().await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("iTunes search failed: {}", e))?
+        .json().await
+        .map_err(|e| format!("Unexpected iTunes search response: {}", e))?;
+
+    let mut candidates: Vec<ArtworkCandidate> = Vec::new();
+    for result in response.results {
+        let Some(url) = result.artwork_url100 else { continue };
+        let artwork_url = url.replace("100x100bb", ARTWORK_SIZE);
+        // Singles and album versions often share one cover
+        if candidates.iter().any(|c| c.artwork_url == artwork_url) {
+            continue;
+        }
+        candidates.push(ArtworkCandidate {
+            artist: result.artist_name,
+            title: result.track_name,
+            album: result.collection_name,
+            artwork_url,
+        });
+    }
+    Ok(candidates)
+}
+
+/// Looks up cover candidates for tracks without embedded artwork: `ids`, or the first
+/// `limit` (default 20) such tracks in the library. Emits `artwork-lookup-progress`.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn fetch_missing_artwork(
+    app: AppHandle,
+    ids: Option<Vec<i64>>,
+    limit: Option<usize>,
+) -> Result<Vec<ArtworkLookup>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LOOKUP_LIMIT);
+    let handle = app.clone();
+    let tracks = tauri::async_runtime::spawn_blocking(move || -> Result<Vec<Track>, String> {
+        let state = handle.state::<AppState>();
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let candidates: Vec<Track> = match ids {
+            Some(ids) => ids.iter().filter_map(|id| db.get_track(*id).ok().flatten()).collect(),
+            None => db.get_all_tracks().map_err(|e| e.to_string())?,
+        };
+        drop(db);
+        Ok(candidates.into_iter()
+            .filter(|t| !t.missing && t.title.is_some())
+            .filter(|t| matches!(crate::metadata::has_artwork(&t.file_path), Ok(false)))
+            .take(limit)
+            .collect())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let client = http_client()?;
+    let total = tracks.len();
+    let mut lookups = Vec::with_capacity(total);
+    for (i, track) in tracks.iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(REQUEST_INTERVAL).await;
+        }
+        let lookup = match search_artwork(&client, track).await {
+            Ok(candidates) => ArtworkLookup { track_id: track.id, candidates, error: None },
+            Err(e) => ArtworkLookup { track_id: track.id, candidates: Vec::new(), error: Some(e) },
+        };
+        lookups.push(lookup);
+        let _ = app.emit("artwork-lookup-progress", ArtworkLookupProgress { processed: i + 1, total });
+    }
+
+    let found = lookups.iter().filter(|l| !l.candidates.is_empty()).count();
+    tracing::info!("Artwork lookup: candidates found for {} of {} tracks", found, total);
+    Ok(lookups)
+}
+
+/// Downloads the confirmed `artwork_url` and embeds it as the track's front cover.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn apply_artwork(app: AppHandle, track_id: i64, artwork_url: String) -> Result<(), String> {
+    let file_path = {
+        let state = app.state::<AppState>();
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        db.get_track(track_id).map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Track {} not found", track_id))?
+            .file_path
+    };
+
+    let image = http_client()?
+        .get(&artwork_url)
+        .send().await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Failed to download artwork: {}", e))?
+        .bytes().await
+        .map_err(|e| format!("Failed to download artwork: {}", e))?;
+
+    let path = file_path.clone();
+    tauri::async_runtime::spawn_blocking(move || crate::metadata::write_artwork(&path, &image))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    tracing::info!("Embedded artwork in {}", file_path);
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Decoding audio files to raw samples for the analysis passes. Built with the
+//! `audio-analysis` feature.
+
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder, DecoderOptions};
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Result of decoding a whole file with `check_stream`.
+#[derive(Debug, Clone, Copy)]
+pub struct StreamCheck {
+    /// Length of the audio that actually decoded
+    pub decoded_secs: f64,
+    /// Packets that failed to decode and were skipped
+    pub bad_packets: usize,
+}
+
+/// The file's format reader, a decoder for its default track, that track's id and sample rate.
+fn open(path: &str) -> anyhow::Result<(Box<dyn FormatReader>, Box<dyn Decoder>, u32, u32)> {
+    let file = std::fs::File::open(path)?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = Path::new(path).extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())?;
+    let format = probed.format;
+    let track = format.default_track().ok_or_else(|| anyhow::anyhow!("No audio track"))?;
+    let track_id = track.id;
+    let source_rate = track.codec_params.sample_rate.ok_or_else(|| anyhow::anyhow!("Unknown sample rate"))?;
+    let decoder = symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+    Ok((format, decoder, track_id, source_rate))
+}
+
+/// Decodes the file's default track, downmixed to mono and resampled to `sample_rate`.
+pub fn decode_mono(path: &str, sample_rate: u32) -> anyhow::Result<Vec<f32>> {
+    let mut samples = Vec::new();
+    stream_mono(path, sample_rate, |chunk| samples.extend_from_slice(chunk))?;
+    Ok(samples)
+}
+
+/// Like `decode_mono`, but hands the samples to `f` a packet at a time instead of holding
+/// the whole track in memory.
+pub fn stream_mono(path: &str, sample_rate: u32, mut f: impl FnMut(&[f32])) -> anyhow::Result<()> {
+    let (mut format, mut decoder, track_id, source_rate) = open(path)?;
+    let mut resampler = Resampler::new(source_rate, sample_rate);
+    let mut mono = Vec::new();
+    let mut resampled = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            // End of stream
+            Err(DecodeError::IoError(_)) => break,
+            Err(e) => return Err(e.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt frame; skip it like players do
+            Err(DecodeError::DecodeError(_)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        mono.clear();
+        mono.extend(buffer.samples().chunks(channels).map(|frame| frame.iter().sum::<f32>() / channels as f32));
+        resampled.clear();
+        resampler.push(&mono, &mut resampled);
+        f(&resampled);
+    }
+    Ok(())
+}
+
+/// Decodes the whole default track without keeping the samples, counting what decoded and
+/// what did not. Used to spot corrupt or truncated files.
+pub fn check_stream(path: &str) -> anyhow::Result<StreamCheck> {
+    let (mut format, mut decoder, track_id, source_rate) = open(path)?;
+    let mut frames = 0u64;
+    let mut bad_packets = 0;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            // End of stream
+            Err(DecodeError::IoError(_)) => break,
+            Err(e) => return Err(e.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(decoded) => frames += decoded.frames() as u64,
+            Err(DecodeError::DecodeError(_)) => bad_packets += 1,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(StreamCheck { decoded_secs: frames as f64 / source_rate as f64, bad_packets })
+}
+
+/// Linear-interpolation resampling fed one chunk at a time; plenty for analysis that looks
+/// at 16 kHz audio or less.
+struct Resampler {
+    /// Input samples per output sample
+    step: f64,
+    /// Position of the next output sample, counted from the last sample of the previous chunk
+    pos: f64,
+    /// Last sample of the previous chunk, to interpolate across the boundary
+    last: Option<f32>,
+}
+
+impl Resampler {
+    fn new(from: u32, to: u32) -> Self {
+        Self { step: from as f64 / to as f64, pos: 0.0, last: None }
+    }
+
+    fn push(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        if self.step == 1.0 {
+            out.extend_from_slice(input);
+            return;
+        }
+        if input.is_empty() {
+            return;
+        }
+        // The previous chunk's last sample sits at position 0, ahead of this chunk
+        let carried = self.last.is_some() as usize;
+        let at = |i: usize| if i < carried { self.last.unwrap_or_default() } else { input[i - carried] };
+        let len = input.len() + carried;
+        while self.pos + 1.0 < len as f64 {
+            let idx = self.pos as usize;
+            let frac = (self.pos - idx as f64) as f32;
+            out.push(at(idx) + (at(idx + 1) - at(idx)) * frac);
+            self.pos += self.step;
+        }
+        self.pos -= (len - 1) as f64;
+        self.last = input.last().copied();
+    }
+}
@@ -0,0 +1,247 @@
+//! Opt-in JSON API on localhost so Keyboard Maestro macros, Stream Deck plugins and
+//! scripts can drive TagDeck during a set. Every request needs the
+//! `Authorization: Bearer <token>` header shown in settings, which also keeps web pages
+//! open in a browser from calling it.
+//!
+//! Endpoints:
+//! - `GET /search?q=<text>&limit=<n>`: tracks whose title, artist, album or comment match
+//! - `POST /tracks/<id>/tags` with `{"tag": "peak"}`: add a tag
+//! - `DELETE /tracks/<id>/tags/<tag>`: remove a tag
+//! - `POST /now-playing` with `{"track_id"|"persistent_id"|"file_path": ...}`: report the
+//!   playing track; emits `now-playing` and adds it to the running session
+
+use crate::commands::AppState;
+use crate::models::Track;
+use crate::tokens::{random_token, tokens_match};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Manager, State};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+const AUTOMATION_KEY: &str = "automation_api";
+
+const DEFAULT_PORT: u16 = 7431;
+
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// Request bodies are small JSON objects; anything larger is refused.
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutomationSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Issued the first time the API is enabled; empty until then
+    pub token: String,
+}
+
+impl Default for AutomationSettings {
+    fn default() -> Self {
+        Self { enabled: false, port: DEFAULT_PORT, token: String::new() }
+    }
+}
+
+/// Holds the running server. Replacing or clearing it unblocks the old server's thread.
+#[derive(Default)]
+pub struct AutomationState {
+    server: Mutex<Option<Arc<Server>>>,
+}
+
+#[derive(Deserialize)]
+struct TagBody {
+    tag: String,
+}
+
+#[derive(Deserialize)]
+struct NowPlayingBody {
+    track_id: Option<i64>,
+    persistent_id: Option<String>,
+    file_path: Option<String>,
+}
+
+fn load_settings(state: &AppState) -> AutomationSettings {
+    state.db.read().ok()
+        .and_then(|db| db.get_setting(AUTOMATION_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(state: &AppState, settings: &AutomationSettings) -> Result<(), String> {
+    let json = serde_json::to_string(settings).map_err(|e| e.to_string())?;
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_setting(AUTOMATION_KEY, &json).map_err(|e| e.to_string())
+}
+
+/// (Re)starts the server, or stops it when disabled in settings.
+pub fn start_automation_server(app: AppHandle) {
+    let settings = load_settings(&app.state::<AppState>());
+    let automation = app.state::<AutomationState>();
+
+    if let Ok(mut slot) = automation.server.lock() {
+        if let Some(old) = slot.take() {
+            old.unblock();
+        }
+    }
+    if !settings.enabled {
+        return;
+    }
+    if settings.token.is_empty() {
+        tracing::error!("Automation API is enabled but has no token; not starting");
+        return;
+    }
+
+    // Loopback only: the API is for tools on this Mac
+    let server = match Server::http(("127.0.0.1", settings.port)) {
+        Ok(server) => Arc::new(server),
+        Err(e) => {
+            tracing::error!("Automation API could not listen on port {}: {}", settings.port, e);
+            return;
+        }
+    };
+    if let Ok(mut slot) = automation.server.lock() {
+        *slot = Some(server.clone());
+    }
+
+    tracing::info!("Automation API listening on http://127.0.0.1:{}", settings.port);
+
+    std::thread::spawn(move || {
+        let expected = format!("Bearer {}", settings.token);
+        // Ends when the server is unblocked
+        for request in server.incoming_requests() {
+            handle_request(&app, request, &expected);
+        }
+    });
+}
+
+fn json_response<T: Serialize>(status: u16, body: &T) -> Response<std::io::Cursor<Vec<u8>>> {
+    let json = serde_json::to_string(body).unwrap_or_else(|_| "null".to_string());
+    let content_type = Header::from_bytes("Content-Type", "application/json").expect("valid header");
+    Response::from_string(json).with_status_code(status).with_header(content_type)
+}
+
+fn error_response(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    json_response(status, &serde_json::json!({ "error": message }))
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(request: &mut Request) -> Result<T, String> {
+    let mut body = String::new();
+    request.as_reader().take(MAX_BODY_BYTES).read_to_string(&mut body).map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| format!("Invalid JSON body: {}", e))
+}
+
+fn handle_request(app: &AppHandle, mut request: Request, expected_auth: &str) {
+    let authorized = request.headers().iter()
+        .any(|h| h.field.equiv("Authorization") && tokens_match(h.value.as_str(), expected_auth));
+    let response = if !authorized {
+        error_response(401, "Missing or wrong API token")
+    } else {
+        match route(app, &mut request) {
+            Ok(response) => response,
+            Err((status, message)) => error_response(status, &message),
+        }
+    };
+    let _ = request.respond(response);
+}
+
+type RouteResult = Result<Response<std::io::Cursor<Vec<u8>>>, (u16, String)>;
+
+fn route(app: &AppHandle, request: &mut Request) -> RouteResult {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((url.as_str(), ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let state = app.state::<AppState>();
+    let bad_request = |e: String| (400, e);
+
+    let method = request.method().clone();
+    match (method, segments.as_slice()) {
+        (Method::Get, ["search"]) => {
+            let params: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes()).into_owned().collect();
+            let param = |name: &str| params.iter().find(|(k, _)| k == name).map(|(_, v)| v.clone());
+            let text = param("q").unwrap_or_default();
+            let limit = param("limit").and_then(|l| l.parse().ok()).unwrap_or(DEFAULT_SEARCH_LIMIT);
+            Ok(json_response(200, &search(&state, &text, limit).map_err(|e| (500, e))?))
+        }
+        (Method::Post, ["tracks", id, "tags"]) => {
+            let id: i64 = id.parse().map_err(|_| (400, format!("Invalid track id: {}", id)))?;
+            let body: TagBody = read_json(request).map_err(bad_request)?;
+            tauri::async_runtime::block_on(crate::commands::add_tag_to_tracks(app, &state, &[id], &body.tag))
+                .map_err(|e| (500, e))?;
+            Ok(json_response(200, &serde_json::json!({ "ok": true })))
+        }
+        (Method::Delete, ["tracks", id, "tags", tag]) => {
+            let id: i64 = id.parse().map_err(|_| (400, format!("Invalid track id: {}", id)))?;
+            let tag = urlencoding::decode(tag).map_err(|e| (400, e.to_string()))?.into_owned();
+            tauri::async_runtime::block_on(crate::commands::remove_tag_from_tracks(app, &state, &[id], &tag))
+                .map_err(|e| (500, e))?;
+            Ok(json_response(200, &serde_json::json!({ "ok": true })))
+        }
+        (Method::Post, ["now-playing"]) => {
+            let body: NowPlayingBody = read_json(request).map_err(bad_request)?;
+            let track = resolve_track(&state, &body).map_err(|e| (404, e))?;
+            crate::now_playing::report_now_playing(app, track.clone(), "automation");
+            Ok(json_response(200, &track))
+        }
+        (method, _) => Err((404, format!("No endpoint for {} /{}", method, segments.join("/")))),
+    }
+}
+
+fn search(state: &AppState, text: &str, limit: usize) -> Result<Vec<Track>, String> {
+    let needle = text.trim().to_lowercase();
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let tracks = db.get_all_tracks().map_err(|e| e.to_string())?;
+    Ok(tracks.into_iter()
+        .filter(|t| {
+            needle.is_empty()
+                || [&t.title, &t.artist, &t.album, &t.comment_raw].iter()
+                    .any(|field| field.as_deref().is_some_and(|v| v.to_lowercase().contains(&needle)))
+        })
+        .take(limit)
+        .collect())
+}
+
+fn resolve_track(state: &AppState, body: &NowPlayingBody) -> Result<Track, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let id = match (body.track_id, &body.persistent_id, &body.file_path) {
+        (Some(id), _, _) => Some(id),
+        (None, Some(pid), _) => db.get_track_id_by_persistent_id(pid).map_err(|e| e.to_string())?,
+        (None, None, Some(path)) => db.get_track_by_path(path).map_err(|e| e.to_string())?.map(|t| t.id),
+        (None, None, None) => return Err("Give track_id, persistent_id or file_path".to_string()),
+    };
+    id.and_then(|id| db.get_track(id).ok().flatten())
+        .ok_or_else(|| "Track not found".to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_automation_settings(state: State<'_, AppState>) -> Result<AutomationSettings, String> {
+    Ok(load_settings(&state))
+}
+
+/// Enables/disables the API. `port` changes the listening port; `regenerate_token`
+/// issues a new token, invalidating the old one.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn set_automation_settings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+    port: Option<u16>,
+    regenerate_token: Option<bool>,
+) -> Result<AutomationSettings, String> {
+    let mut settings = load_settings(&state);
+    settings.enabled = enabled;
+    if let Some(port) = port {
+        settings.port = port;
+    }
+    if regenerate_token.unwrap_or(false) || (enabled && settings.token.is_empty()) {
+        settings.token = random_token()?;
+    }
+    save_settings(&state, &settings)?;
+
+    start_automation_server(app.clone());
+
+    tracing::info!("Automation API {}", if enabled { "enabled" } else { "disabled" });
+    Ok(settings)
+}
@@ -0,0 +1,222 @@
+//! Headless TagDeck for scripts and cron jobs. Works on the same database as the app;
+//! comment changes bound for Music.app are journaled and delivered the next time
+//! TagDeck runs.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tagdeck_lib::apple_music::AppleUpdate;
+use tagdeck_lib::commands::{
+    canonical_tag, commit_comment_batch, load_file_ratings_from, load_preserve_mtime_from,
+    load_tag_normalization_from, reread_track_file, restore_comment_files, write_comment_files,
+    MissingScan,
+};
+use tagdeck_lib::db::Database;
+use tagdeck_lib::models::Track;
+use tagdeck_lib::path_rules::PathResolver;
+
+/// Matches the app's `identifier` in tauri.conf.json, which names its data directory.
+const APP_IDENTIFIER: &str = "com.factor8.tagdeck";
+
+const USAGE: &str = "Usage: tagdeck-cli [--db <path>] <command>
+
+Commands:
+  tag add <tag> (--playlist <name> | --track <id>...)
+  tag remove <tag> (--playlist <name> | --track <id>...)
+  export rekordbox <out.xml>
+  rescan";
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    match run(&mut args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &mut Vec<String>) -> Result<(), String> {
+    let db_path = match take_option(args, "--db")? {
+        Some(path) => PathBuf::from(path),
+        None => default_db_path()?,
+    };
+    if !db_path.exists() {
+        return Err(format!("No TagDeck database at {} (open the app once, or pass --db)", db_path.display()));
+    }
+    let db = Database::new(&db_path).map_err(|e| e.to_string())?;
+    load_tag_normalization_from(&db);
+    load_preserve_mtime_from(&db);
+    load_file_ratings_from(&db);
+
+    let words: Vec<&str> = args.iter().map(String::as_str).collect();
+    match words.as_slice() {
+        ["tag", "add", tag, rest @ ..] => tag_tracks(&db, tag, rest, true),
+        ["tag", "remove", tag, rest @ ..] => tag_tracks(&db, tag, rest, false),
+        ["export", "rekordbox", out] => export_rekordbox(&db, out),
+        ["rescan"] => rescan(&db),
+        _ => Err(format!("Unrecognized command\n\n{}", USAGE)),
+    }
+}
+
+fn default_db_path() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_dir().ok_or("Could not determine the app data directory")?;
+    Ok(data_dir.join(APP_IDENTIFIER).join("tagdeck.db"))
+}
+
+/// Removes `--name value` from `args`, returning the value.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    let Some(idx) = args.iter().position(|a| a == name) else { return Ok(None) };
+    if idx + 1 >= args.len() {
+        return Err(format!("{} needs a value", name));
+    }
+    let value = args.remove(idx + 1);
+    args.remove(idx);
+    Ok(Some(value))
+}
+
+/// Resolves `--playlist <name>` or one or more `--track <id>` into tracks.
+fn select_tracks(db: &Database, selectors: &[&str]) -> Result<Vec<Track>, String> {
+    let mut ids: Vec<i64> = Vec::new();
+    let mut rest = selectors.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or_else(|| format!("{} needs a value", flag))?;
+        match *flag {
+            "--playlist" => {
+                let playlist = db.get_playlists().map_err(|e| e.to_string())?
+                    .into_iter()
+                    .find(|p| !p.is_folder && p.name.eq_ignore_ascii_case(value))
+                    .ok_or_else(|| format!("Playlist \"{}\" not found", value))?;
+                ids.extend(db.get_playlist_track_ids(playlist.id).map_err(|e| e.to_string())?);
+            }
+            "--track" => ids.push(value.parse().map_err(|_| format!("Invalid track id: {}", value))?),
+            other => return Err(format!("Unknown option: {}", other)),
+        }
+    }
+    if ids.is_empty() {
+        return Err("Select tracks with --playlist <name> or --track <id>".to_string());
+    }
+
+    let mut tracks = Vec::with_capacity(ids.len());
+    for id in ids {
+        if tracks.iter().any(|t: &Track| t.id == id) {
+            continue;
+        }
+        let track = db.get_track(id).map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Track {} not found", id))?;
+        tracks.push(track);
+    }
+    Ok(tracks)
+}
+
+fn tag_tracks(db: &Database, tag: &str, selectors: &[&str], add: bool) -> Result<(), String> {
+    let tag = if add { canonical_tag(db, tag) } else { tag.trim().to_string() };
+    if tag.is_empty() {
+        return Err("Tag is empty".to_string());
+    }
+    let tracks = select_tracks(db, selectors)?;
+
+    let changes: Vec<(Track, String)> = tracks.into_iter()
+        .filter_map(|track| {
+            let comment = track.comment_raw.clone().unwrap_or_default();
+            let updated = if add {
+                tagdeck_lib::comment::add_tags(&comment, &[tag.clone()])
+            } else {
+                tagdeck_lib::comment::remove_tags(&comment, &[tag.clone()])
+            };
+            updated.map(|c| (track, c))
+        })
+        .collect();
+    let wanted = changes.len();
+
+    // Same file writes and single DB transaction as the app's batch tag commands
+    let written = write_comment_files(changes, |_| {});
+    let committed = commit_comment_batch(db, &written).map_err(|e| restore_comment_files(&written, e))?;
+    if !committed.music_updates.is_empty() {
+        journal_for_music(db, &AppleUpdate::Comments { updates: committed.music_updates })?;
+    }
+
+    let changed = committed.undo.len();
+    println!("{} \"{}\" {} {} track(s)", if add { "Added" } else { "Removed" }, tag, if add { "to" } else { "from" }, changed);
+    if changed < wanted {
+        return Err(format!("{} file(s) could not be written", wanted - changed));
+    }
+    Ok(())
+}
+
+/// The app's sync queue replays its journal on startup.
+fn journal_for_music(db: &Database, update: &AppleUpdate) -> Result<(), String> {
+    let payload = serde_json::to_string(update).map_err(|e| e.to_string())?;
+    db.insert_pending_sync(&payload, chrono::Utc::now().timestamp()).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Whole library plus every regular playlist, referencing the tracks where they are.
+fn export_rekordbox(db: &Database, out: &str) -> Result<(), String> {
+    let tracks = db.get_all_tracks().map_err(|e| e.to_string())?;
+    let collection: Vec<(Track, String)> = tracks.into_iter()
+        .filter(|t| !t.missing)
+        .map(|t| {
+            let path = t.file_path.clone();
+            (t, path)
+        })
+        .collect();
+
+    let mut cues = HashMap::new();
+    for (track, _) in &collection {
+        cues.insert(track.id, db.get_cue_points(track.id).map_err(|e| e.to_string())?);
+    }
+
+    let exported: HashSet<i64> = collection.iter().map(|(t, _)| t.id).collect();
+    let mut playlists = Vec::new();
+    for playlist in db.get_playlists().map_err(|e| e.to_string())? {
+        if playlist.is_folder {
+            continue;
+        }
+        let ids: Vec<i64> = db.get_playlist_track_ids(playlist.id).map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|id| exported.contains(id))
+            .collect();
+        playlists.push((playlist.name, ids));
+    }
+
+    let xml = tagdeck_lib::rekordbox::render_xml(&collection, &cues, &playlists);
+    std::fs::write(out, xml).map_err(|e| format!("Failed to write {}: {}", out, e))?;
+    println!("Exported {} tracks and {} playlists to {}", collection.len(), playlists.len(), out);
+    Ok(())
+}
+
+/// Re-reads the library from disk: the app's missing file scan first, then the tags of
+/// every local file (comment, grouping, BPM and, with file ratings on, the rating) are read
+/// back into the database, the way the app refreshes a track after an outside edit.
+fn rescan(db: &Database) -> Result<(), String> {
+    let tracks = db.get_all_tracks().map_err(|e| e.to_string())?;
+    let scan = MissingScan::run(&tracks, &PathResolver::load(db), |_, _| {});
+    let result = scan.apply(db, tracks.len()).map_err(|e| e.to_string())?;
+    println!(
+        "Checked {} tracks: {} missing, {} relocated, {} recovered, {} offloaded",
+        result.checked, result.missing, result.relocated, result.recovered, result.offloaded
+    );
+
+    // After the scan, so relocated tracks are read from their new path
+    let tracks = db.get_all_tracks().map_err(|e| e.to_string())?;
+    let local: Vec<&Track> = tracks.iter().filter(|t| !t.missing && !t.is_cloud).collect();
+    let mut failed = 0;
+    for track in &local {
+        match reread_track_file(db, track.id) {
+            Ok(Some(update)) => journal_for_music(db, &update)?,
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("Skipping {}: {}", track.file_path, e);
+                failed += 1;
+            }
+        }
+    }
+    println!("Re-read tags of {} of {} tracks", local.len() - failed, local.len());
+    Ok(())
+}
@@ -0,0 +1,162 @@
+//! Granular change notifications. `db.rs` reports every row it changes; a flusher thread
+//! coalesces the reports and emits typed events, so the frontend can patch rows in place
+//! instead of refetching the whole library after each edit or sync:
+//!
+//! - `track-updated` `{ id, fields }`: the tracks columns that changed; empty `fields`
+//!   means the whole row (sync upserts, restores)
+//! - `track-removed` `{ id }`
+//! - `playlist-updated` `{ id }`: membership, order or the playlist itself
+//! - `tag-created` `{ name }`
+//! - `library-changed`: too many tracks changed at once to be worth patching; refetch
+//!
+//! Reports are only collected once `start` ran, so the CLI and tests pay nothing. A report
+//! made inside a transaction that rolls back still goes out; the frontend then reloads a
+//! row that didn't change.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// How often pending reports are emitted
+const FLUSH_INTERVAL: Duration = Duration::from_millis(150);
+/// More changed tracks than this in one flush become a single `library-changed`
+const MAX_TRACK_EVENTS: usize = 500;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PENDING: Mutex<Pending> = Mutex::new(Pending::new());
+
+#[derive(Default)]
+struct Pending {
+    /// Track id -> changed columns; an empty set means the whole row
+    tracks: BTreeMap<i64, BTreeSet<String>>,
+    removed_tracks: BTreeSet<i64>,
+    playlists: BTreeSet<i64>,
+    tags: BTreeSet<String>,
+}
+
+impl Pending {
+    const fn new() -> Self {
+        Pending {
+            tracks: BTreeMap::new(),
+            removed_tracks: BTreeSet::new(),
+            playlists: BTreeSet::new(),
+            tags: BTreeSet::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tracks.is_empty() && self.removed_tracks.is_empty() && self.playlists.is_empty() && self.tags.is_empty()
+    }
+}
+
+/// Payload of `track-updated`.
+#[derive(Clone, Serialize)]
+pub struct TrackUpdated {
+    pub id: i64,
+    /// Changed tracks columns; empty when the whole row should be reloaded
+    pub fields: Vec<String>,
+}
+
+/// Payload of `track-removed` and `playlist-updated`.
+#[derive(Clone, Serialize)]
+pub struct IdPayload {
+    pub id: i64,
+}
+
+/// Payload of `tag-created`.
+#[derive(Clone, Serialize)]
+pub struct TagCreated {
+    pub name: String,
+}
+
+/// Whether reports are being collected, for callers that need a lookup to make one.
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn with_pending(f: impl FnOnce(&mut Pending)) {
+    if !enabled() {
+        return;
+    }
+    f(&mut PENDING.lock().unwrap_or_else(|e| e.into_inner()));
+}
+
+/// `field` of track `id` changed.
+pub fn track_changed(id: i64, field: &str) {
+    with_pending(|p| match p.tracks.get_mut(&id) {
+        // A whole-row report already covers every field
+        Some(fields) if fields.is_empty() => {}
+        Some(fields) => {
+            fields.insert(field.to_string());
+        }
+        None => {
+            p.tracks.insert(id, BTreeSet::from([field.to_string()]));
+        }
+    });
+}
+
+/// Track `id` was written as a whole (inserted, refreshed by the sync, restored).
+pub fn track_replaced(id: i64) {
+    with_pending(|p| {
+        p.removed_tracks.remove(&id);
+        p.tracks.insert(id, BTreeSet::new());
+    });
+}
+
+pub fn track_removed(id: i64) {
+    with_pending(|p| {
+        p.tracks.remove(&id);
+        p.removed_tracks.insert(id);
+    });
+}
+
+pub fn playlist_changed(id: i64) {
+    with_pending(|p| {
+        p.playlists.insert(id);
+    });
+}
+
+pub fn tag_created(name: &str) {
+    with_pending(|p| {
+        p.tags.insert(name.to_string());
+    });
+}
+
+fn flush(app: &AppHandle) {
+    let pending = {
+        let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.is_empty() {
+            return;
+        }
+        std::mem::take(&mut *pending)
+    };
+
+    if pending.tracks.len() + pending.removed_tracks.len() > MAX_TRACK_EVENTS {
+        let _ = app.emit("library-changed", ());
+    } else {
+        for (id, fields) in pending.tracks {
+            let _ = app.emit("track-updated", TrackUpdated { id, fields: fields.into_iter().collect() });
+        }
+        for id in pending.removed_tracks {
+            let _ = app.emit("track-removed", IdPayload { id });
+        }
+    }
+    for id in pending.playlists {
+        let _ = app.emit("playlist-updated", IdPayload { id });
+    }
+    for name in pending.tags {
+        let _ = app.emit("tag-created", TagCreated { name });
+    }
+}
+
+/// Starts collecting reports and emitting them every `FLUSH_INTERVAL`.
+pub fn start(app: AppHandle) {
+    ENABLED.store(true, Ordering::Relaxed);
+    std::thread::spawn(move || loop {
+        std::thread::sleep(FLUSH_INTERVAL);
+        flush(&app);
+    });
+}
@@ -0,0 +1,184 @@
+//! Cloud-offloaded files. iCloud Drive and Dropbox online-only folders leave a placeholder
+//! where an audio file was, with the data still in the cloud; reading one blocks on a
+//! download, or fails offline. Tag writes check first and refuse with a typed error, or,
+//! with `materialize_cloud_files` on, download the file before writing.
+
+use crate::commands::AppState;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::State;
+
+const MATERIALIZE_KEY: &str = "materialize_cloud_files";
+/// How long `materialize_file` waits for a download
+const MATERIALIZE_TIMEOUT: Duration = Duration::from_secs(120);
+const MATERIALIZE_POLL: Duration = Duration::from_millis(500);
+/// `SF_DATALESS` from <sys/stat.h>: the file's data is held by a File Provider
+#[cfg(target_os = "macos")]
+const SF_DATALESS: u32 = 0x4000_0000;
+
+/// When set, tag writes download placeholders instead of refusing them. Off by default:
+/// a batch over an offloaded folder would otherwise pull the whole folder down.
+static MATERIALIZE_BEFORE_WRITE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_materialize_before_write(enabled: bool) {
+    MATERIALIZE_BEFORE_WRITE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn materialize_before_write() -> bool {
+    MATERIALIZE_BEFORE_WRITE.load(Ordering::Relaxed)
+}
+
+/// The stub older iCloud Drive versions leave for an evicted file: `.name.icloud` next to it.
+fn icloud_stub(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_string_lossy().to_string();
+    Some(path.with_file_name(format!(".{}.icloud", name)))
+}
+
+#[cfg(target_os = "macos")]
+fn is_dataless(meta: &std::fs::Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    meta.st_flags() & SF_DATALESS != 0
+}
+
+#[cfg(not(target_os = "macos"))]
+fn is_dataless(_meta: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// Whether `path` is a cloud placeholder: a dataless file (iCloud Drive, Dropbox on File
+/// Provider) or a missing file with an iCloud stub next to it. Only stats, so it never
+/// triggers a download itself.
+pub fn is_placeholder<P: AsRef<Path>>(path: P) -> bool {
+    let path = path.as_ref();
+    match std::fs::metadata(path) {
+        Ok(meta) => is_dataless(&meta),
+        Err(_) => icloud_stub(path).is_some_and(|stub| stub.exists()),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn request_icloud_download(path: &Path) -> Result<(), String> {
+    let output = std::process::Command::new("brctl")
+        .arg("download")
+        .arg(path)
+        .output()
+        .map_err(|e| format!("Failed to run brctl: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn request_icloud_download(path: &Path) -> Result<(), String> {
+    Err(format!("Cannot download {:?}: iCloud Drive is only supported on macOS", path))
+}
+
+/// Downloads a placeholder and waits until the file is on disk. Reading a dataless file
+/// makes its File Provider fetch it; an iCloud stub is fetched with `brctl download`.
+pub fn materialize_file<P: AsRef<Path>>(path: P) -> Result<(), String> {
+    use std::io::Read;
+
+    let path = path.as_ref();
+    if !is_placeholder(path) {
+        return Ok(());
+    }
+    if path.exists() {
+        let mut byte = [0u8; 1];
+        std::fs::File::open(path)
+            .and_then(|mut file| file.read(&mut byte))
+            .map_err(|e| format!("Failed to download {:?}: {}", path, e))?;
+    } else {
+        request_icloud_download(path)?;
+    }
+
+    let start = Instant::now();
+    while is_placeholder(path) {
+        if start.elapsed() > MATERIALIZE_TIMEOUT {
+            return Err(format!("Timed out downloading {:?}", path));
+        }
+        std::thread::sleep(MATERIALIZE_POLL);
+    }
+    Ok(())
+}
+
+/// Checked before tag writes: files on disk pass; a placeholder is downloaded when
+/// `materialize_cloud_files` is on and refused otherwise.
+pub fn ensure_local(path: &Path) -> Result<(), String> {
+    if !is_placeholder(path) {
+        return Ok(());
+    }
+    if materialize_before_write() {
+        return materialize_file(path);
+    }
+    Err(format!("{:?} is a cloud placeholder; download it before writing tags", path))
+}
+
+/// Applies the stored "download placeholders before tag writes" preference.
+pub fn load_materialize_before_write(state: &AppState) {
+    let enabled = state.db.read().ok()
+        .and_then(|db| db.get_setting(MATERIALIZE_KEY).ok().flatten())
+        .as_deref() == Some("true");
+    set_materialize_before_write(enabled);
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn get_materialize_cloud_files() -> bool {
+    materialize_before_write()
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_materialize_cloud_files(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_setting(MATERIALIZE_KEY, if enabled { "true" } else { "false" })
+        .map_err(|e| e.to_string())?;
+    set_materialize_before_write(enabled);
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MaterializeResult {
+    pub materialized: usize,
+    /// Tracks whose file could not be downloaded, with the error
+    pub failed: Vec<(i64, String)>,
+}
+
+/// Downloads the placeholder files of `ids` and clears their offloaded flag. Tracks whose
+/// file is already on disk count as materialized.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn materialize_files(ids: Vec<i64>, state: State<'_, AppState>) -> Result<MaterializeResult, String> {
+    use rayon::prelude::*;
+
+    let tracks: Vec<(i64, String)> = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        ids.iter()
+            .filter_map(|id| db.get_track(*id).ok().flatten())
+            .map(|t| (t.id, t.file_path))
+            .collect()
+    };
+
+    let (done, failed): (Vec<_>, Vec<_>) = tauri::async_runtime::spawn_blocking(move || {
+        tracks.into_par_iter()
+            .map(|(id, path)| materialize_file(&path).map(|()| id).map_err(|e| (id, e)))
+            .partition(|r| r.is_ok())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let done: Vec<i64> = done.into_iter().filter_map(Result::ok).collect();
+    let failed: Vec<(i64, String)> = failed.into_iter().filter_map(Result::err).collect();
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        for id in &done {
+            db.set_track_offloaded(*id, false).map_err(|e| e.to_string())?;
+        }
+    }
+
+    tracing::info!("Materialized {} cloud file(s), {} failed", done.len(), failed.len());
+    Ok(MaterializeResult { materialized: done.len(), failed })
+}
@@ -1,50 +1,106 @@
-use crate::db::Database;
+use crate::db::{Database, DbPool};
 use crate::library_parser::parse_library;
-use crate::system_library::fetch_system_library;
-use crate::metadata::{write_metadata as write_tags_to_file, get_artwork, write_track_info};
+use crate::path_rules::{PathResolver, load_resolver};
+use crate::system_library::{stream_system_library, FetchProgress};
+use crate::metadata::{write_track_comment, get_artwork, write_track_info};
 use crate::apple_music::{
-    update_track_comment, batch_update_track_comments, update_track_rating, touch_file, add_track_to_playlist, get_changes_since, get_snapshot_fields, get_playlist_snapshot,
-    remove_track_from_playlist as apple_remove_from_playlist, get_play_count, set_play_count, update_track_info as apple_update_track_info,
-    get_all_music_app_pids, get_tracks_by_persistent_ids
+    touch_file, get_changes_since, get_snapshot_fields, get_playlist_snapshot, get_play_count,
+    get_all_music_app_pids, get_tracks_by_persistent_ids, get_playlist_headers, get_playlist_track_pids,
+    AppleUpdate, PendingUpdate, PlaylistSnapshotEntry, SyncQueue
 };
 use crate::models::{Track, Playlist};
-use crate::undo::{UndoStack, Action, TrackState, TrackRef};
+use crate::undo::{UndoStack, Action, TrackState, TrackRef, RatingState, LovedState, PlayCountState, UndoActionInfo};
+use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::{State, Manager};
+use tauri::{State, Manager, Emitter};
 
 pub struct AppState {
-    pub db: Mutex<Database>,
+    pub db: DbPool,
     pub undo_stack: Mutex<UndoStack>,
     pub is_syncing: AtomicBool,
+    /// Tags taken by `copy_tag_block`, for `paste_tag_block`
+    pub tag_clipboard: Mutex<Vec<String>>,
+    pub apple_queue: SyncQueue,
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn undo(state: State<'_, AppState>) -> Result<Option<String>, String> {
     let mut undo_stack = state.undo_stack.lock().map_err(|_| "Failed to lock undo stack")?;
     let db = state.db.lock().map_err(|_| "Failed to lock DB")?;
-    undo_stack.undo(&db).map_err(|e| e.to_string())
+    undo_stack.undo(&db, &state.apple_queue).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn redo(state: State<'_, AppState>) -> Result<Option<String>, String> {
     let mut undo_stack = state.undo_stack.lock().map_err(|_| "Failed to lock undo stack")?;
     let db = state.db.lock().map_err(|_| "Failed to lock DB")?;
-    undo_stack.redo(&db).map_err(|e| e.to_string())
+    undo_stack.redo(&db, &state.apple_queue).map_err(|e| e.to_string())
 }
 
+/// Returns the undo history, most recent first.
 #[tauri::command]
-pub async fn import_library(app: tauri::AppHandle, xml_path: String, state: State<'_, AppState>) -> Result<usize, String> {
-    println!("Importing library from: {}", xml_path);
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn list_undo_actions(state: State<'_, AppState>) -> Result<Vec<UndoActionInfo>, String> {
+    let undo_stack = state.undo_stack.lock().map_err(|_| "Failed to lock undo stack")?;
+    Ok(undo_stack.list())
+}
+
+/// Undoes all actions up to and including `index` from `list_undo_actions`.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn undo_to(state: State<'_, AppState>, index: usize) -> Result<Vec<String>, String> {
+    let mut undo_stack = state.undo_stack.lock().map_err(|_| "Failed to lock undo stack")?;
+    let db = state.db.lock().map_err(|_| "Failed to lock DB")?;
+    undo_stack.undo_to(index, &db, &state.apple_queue).map_err(|e| e.to_string())
+}
+
+/// Returns Music.app writes that are queued or being retried.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_pending_apple_updates(state: State<'_, AppState>) -> Result<Vec<PendingUpdate>, String> {
+    Ok(state.apple_queue.pending())
+}
+
+/// Gives a Music.app write that was parked as failed a fresh set of attempts.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn retry_apple_update(id: u64, state: State<'_, AppState>) -> Result<(), String> {
+    if !state.apple_queue.retry(id) {
+        return Err(format!("Update {} is not queued", id));
+    }
+    Ok(())
+}
+
+/// Drops a queued Music.app write, e.g. one that keeps failing.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn discard_apple_update(id: u64, state: State<'_, AppState>) -> Result<(), String> {
+    if !state.apple_queue.discard(id) {
+        return Err(format!("Update {} is not queued", id));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn import_library(xml_path: String, state: State<'_, AppState>) -> Result<usize, String> {
+    tracing::debug!("Importing library from: {}", xml_path);
 
     // 1. Parse XML
-    let tracks = parse_library(&xml_path).map_err(|e| {
-        let msg = format!("XML Parse Error: {}", e);
-        app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+    let (mut tracks, playlists) = parse_library(&xml_path).map_err(|e| {
+        tracing::error!("XML Parse Error: {}", e);
         e.to_string()
     })?;
     let count = tracks.len();
-    println!("Found {} tracks", count);
+    tracing::debug!("Found {} tracks and {} playlists", count, playlists.len());
+
+    let rewritten = load_resolver(&state).apply_to_tracks(&mut tracks);
+    if rewritten > 0 {
+        tracing::info!("Path rules rewrote {} track locations", rewritten);
+    }
 
     // 2. Insert into DB
     let db = state
@@ -52,67 +108,79 @@ pub async fn import_library(app: tauri::AppHandle, xml_path: String, state: Stat
         .lock()
         .map_err(|_| "Failed to lock DB".to_string())?;
 
-    for track in tracks {
+    for mut track in tracks {
+        crate::tag_storage::restore_tag_block(&db, &mut track);
         if let Err(e) = db.insert_track(&track) {
             let msg = format!("DB Error (XML Import): {}", e);
-             app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+             tracing::error!("{}", msg);
              return Err(e.to_string());
         }
     }
 
+    // Playlists after tracks, so their members resolve
+    if let Err(e) = db.insert_playlists(&playlists) {
+        tracing::error!("DB Error (XML Import playlists): {}", e);
+        return Err(e.to_string());
+    }
+
     // Sync tags
     if let Err(e) = db.sync_tags() {
-        let msg = format!("Tag Sync Error: {}", e);
-        app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+        tracing::error!("Tag Sync Error: {}", e);
     }
 
     Ok(count)
 }
 
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SchemaVersion {
+    pub version: u32,
+}
+
+/// The IPC schema version (see `models::SCHEMA_VERSION`), so a frontend bundle can tell
+/// which fields this backend sends.
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn get_schema_version() -> SchemaVersion {
+    SchemaVersion { version: crate::models::SCHEMA_VERSION }
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn get_tracks(state: State<'_, AppState>) -> Result<Vec<Track>, String> {
     let db = state
         .db
-        .lock()
+        .read()
         .map_err(|_| "Failed to lock DB".to_string())?;
     let tracks = db.get_all_tracks().map_err(|e| e.to_string())?;
     
     Ok(tracks)
 }
 
+/// The current rows of `ids`, for patching the track list after `track-updated` events.
+/// Ids that no longer exist are left out.
 #[tauri::command]
-pub async fn get_global_tags(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_tracks_by_ids(ids: Vec<i64>, state: State<'_, AppState>) -> Result<Vec<Track>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    Ok(ids.iter().filter_map(|id| db.get_track(*id).ok().flatten()).collect())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_global_tags(state: State<'_, AppState>) -> Result<Vec<crate::models::TagSummary>, String> {
+    // The tags table is refreshed on every comment write, so autocomplete never scans the library
     let db = state
         .db
-        .lock()
+        .read()
         .map_err(|_| "Failed to lock DB".to_string())?;
 
-    let tracks = db.get_all_tracks().map_err(|e| e.to_string())?;
-    let mut unique_tags = std::collections::HashSet::new();
-
-    for track in tracks {
-        if let Some(raw) = track.comment_raw {
-            // Logic must match TagEditor.tsx: Split on " && "
-            if let Some(idx) = raw.find(" && ") {
-                let tag_part = &raw[idx + 4..];
-                for tag in tag_part.split(';') {
-                    let trimmed = tag.trim();
-                    if !trimmed.is_empty() {
-                        unique_tags.insert(trimmed.to_string());
-                    }
-                }
-            }
-        }
-    }
-
-    let mut sorted_tags: Vec<String> = unique_tags.into_iter().collect();
-    sorted_tags.sort_by(|a, b| a.to_lowercase().cmp(&b.to_lowercase()));
-    Ok(sorted_tags)
+    db.get_tag_summaries().map_err(|e| e.to_string())
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub fn show_in_finder(path: String) -> Result<(), String> {
-    println!("Revealing file at: {}", path);
+    tracing::debug!("Revealing file at: {}", path);
     #[cfg(target_os = "macos")]
     {
         std::process::Command::new("open")
@@ -139,147 +207,18 @@ pub fn show_in_finder(path: String) -> Result<(), String> {
     Ok(())
 }
 
-#[tauri::command]
-pub async fn analyze_with_mixed_in_key(app: tauri::AppHandle, track_ids: Vec<i64>, file_paths: Vec<String>, state: State<'_, AppState>) -> Result<(), String> {
-    let file_count = file_paths.len();
-    
-    #[cfg(target_os = "macos")]
-    {
-        let mik_path = "/Applications/Mixed In Key 8.app";
-        if !std::path::Path::new(mik_path).exists() {
-            return Err("Mixed In Key 8 not found. Please install from https://mixedinkey.com/".to_string());
-        }
-
-        // Validate files exist and capture their current modification times
-        let mut file_mod_times: Vec<(String, std::time::SystemTime)> = Vec::new();
-        for path in &file_paths {
-            let path_obj = std::path::Path::new(path);
-            if !path_obj.exists() {
-                return Err(format!("File not found: {}", path));
-            }
-            
-            // Get current modification time
-            let metadata = std::fs::metadata(path_obj)
-                .map_err(|e| format!("Failed to read file metadata: {}", e))?;
-            let mod_time = metadata.modified()
-                .map_err(|e| format!("Failed to get modification time: {}", e))?;
-            
-            file_mod_times.push((path.clone(), mod_time));
-        }
-
-        // Build AppleScript to automate Mixed In Key 8
-        let paths_arg = file_paths.iter()
-            .map(|p| format!("POSIX file \"{}\"", p.replace("\"", "\\\"")))
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        let script = format!(r#"
-tell application "Mixed In Key 8"
-    activate
-    delay 1
-    
-    -- Add files to queue
-    set fileList to {{{}}}
-    repeat with aFile in fileList
-        try
-            open aFile
-        end try
-    end repeat
-    
-    -- Give MiK8 time to start processing
-    delay 3
-end tell
-"#, paths_arg);
-
-        let launch_msg = format!("Launching Mixed In Key 8 with {} file(s)", file_count);
-        app.state::<crate::logging::LogState>().add_log("INFO", &launch_msg, &app);
-        
-        // Launch MiK8 in the background
-        std::process::Command::new("osascript")
-            .arg("-e")
-            .arg(&script)
-            .spawn()
-            .map_err(|e| format!("Failed to launch Mixed In Key with AppleScript: {}", e))?;
-        
-        // Wait for all files to be modified (indicating MiK8 has processed them)
-        let max_wait_seconds = 10 + (file_count * 15); // 10s base + 15s per file
-        let poll_interval = std::time::Duration::from_secs(2);
-        let start_time = std::time::Instant::now();
-        
-        let mut files_processed = 0;
-        
-        loop {
-            // Check if we've exceeded the timeout
-            if start_time.elapsed().as_secs() > max_wait_seconds as u64 {
-                let timeout_msg = format!("Mixed In Key timeout: {} of {} files processed", files_processed, file_count);
-                app.state::<crate::logging::LogState>().add_log("ERROR", &timeout_msg, &app);
-                break;
-            }
-            
-            // Check each file's modification time
-            let mut all_processed = true;
-            files_processed = 0;
-            
-            for (path, original_time) in &file_mod_times {
-                if let Ok(metadata) = std::fs::metadata(path) {
-                    if let Ok(current_time) = metadata.modified() {
-                        if current_time > *original_time {
-                            files_processed += 1;
-                        } else {
-                            all_processed = false;
-                        }
-                    }
-                }
-            }
-            
-            if all_processed {
-                break;
-            }
-            
-            std::thread::sleep(poll_interval);
-        }
-        
-        // Give MiK8 a moment to finish writing
-        std::thread::sleep(std::time::Duration::from_secs(1));
-        
-        // Try to quit MiK8
-        let quit_script = r#"
-tell application "Mixed In Key 8"
-    quit
-end tell
-"#;
-        let _ = std::process::Command::new("osascript")
-            .arg("-e")
-            .arg(quit_script)
-            .output();
-        
-        // After MiK8 finishes, refresh the metadata for all processed tracks
-        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
-        
-        let mut success_count = 0;
-        for track_id in &track_ids {
-            if let Err(e) = refresh_track_metadata_from_file(&db, *track_id) {
-                let error_msg = format!("Failed to refresh track {}: {}", track_id, e);
-                app.state::<crate::logging::LogState>().add_log("ERROR", &error_msg, &app);
-            } else {
-                success_count += 1;
-            }
-        }
-        
-        let completion_msg = format!("Mixed In Key analysis complete: {} of {} tracks updated", success_count, file_count);
-        app.state::<crate::logging::LogState>().add_log("INFO", &completion_msg, &app);
-    }
-    
-    #[cfg(not(target_os = "macos"))]
-    {
-        return Err("Mixed In Key integration is only supported on macOS".to_string());
+/// Helper function to refresh a track's metadata from its file
+pub(crate) fn refresh_track_metadata_from_file(db: &Database, queue: &SyncQueue, track_id: i64) -> Result<(), String> {
+    if let Some(update) = reread_track_file(db, track_id)? {
+        queue.enqueue(update);
     }
-    
     Ok(())
 }
 
-/// Helper function to refresh a track's metadata from its file
-fn refresh_track_metadata_from_file(db: &Database, track_id: i64) -> Result<(), String> {
+/// Core of `refresh_track_metadata_from_file`, shared with the CLI's `rescan`: reads the
+/// comment, grouping, BPM and (with file ratings on) the rating back from the file into the
+/// DB. Returns the rating update Music.app needs, if any.
+pub fn reread_track_file(db: &Database, track_id: i64) -> Result<Option<AppleUpdate>, String> {
     use crate::metadata::read_metadata;
     use lofty::read_from_path;
     use lofty::prelude::*;
@@ -300,12 +239,26 @@ fn refresh_track_metadata_from_file(db: &Database, track_id: i64) -> Result<(),
     
     let tag = tagged_file.primary_tag().or_else(|| tagged_file.first_tag());
     
-    // Read BPM from the standard BPM tag field
+    // Read BPM, preferring the precise field over the whole-number TBPM/tmpo
     let bpm = tag
-        .and_then(|t| t.get_string(&ItemKey::Bpm))
-        .and_then(|s| s.trim().parse::<i64>().ok())
-        .unwrap_or(0);
+        .and_then(|t| t.get_string(&ItemKey::Bpm).or_else(|| t.get_string(&ItemKey::IntegerBpm)))
+        .and_then(|s| s.trim().parse::<f64>().ok())
+        .unwrap_or(0.0);
     
+    // A rating stored in the file (POPM / rate / RATING) wins when file ratings are on
+    let mut rating_update = None;
+    if crate::metadata::file_ratings() {
+        if let Ok(Some(rating)) = crate::metadata::read_rating(&track.file_path) {
+            if rating as i64 != track.rating {
+                db.update_track_rating(track.id, rating)
+                    .map_err(|e| format!("Failed to update rating in database: {}", e))?;
+                if !track.persistent_id.is_empty() {
+                    rating_update = Some(AppleUpdate::Rating { persistent_id: track.persistent_id.clone(), rating });
+                }
+            }
+        }
+    }
+
     // Update the database with the new metadata
     let mut updated_track = track;
     updated_track.comment_raw = Some(comment);
@@ -315,15 +268,18 @@ fn refresh_track_metadata_from_file(db: &Database, track_id: i64) -> Result<(),
     db.update_track(&updated_track)
         .map_err(|e| format!("Failed to update track in database: {}", e))?;
     
-    Ok(())
+    Ok(rating_update)
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn write_tags(
     id: i64,
     new_tags: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), crate::metadata::TagWriteError> {
+    let new_tags = crate::comment::normalize_comment(&new_tags);
+
     // 1. Get file path from DB
     let db = state
         .db
@@ -332,7 +288,7 @@ pub async fn write_tags(
     
     // Using get_track now that it exists
     let mut track = db.get_track(id).map_err(|e| e.to_string())?
-        .ok_or("Track not found")?;
+        .ok_or_else(|| "Track not found".to_string())?;
 
     // Prepare Undo
     let old_comment = track.comment_raw.clone().unwrap_or_default();
@@ -346,18 +302,21 @@ pub async fn write_tags(
         }]
     };
 
-    // 2. Write to File
-    write_tags_to_file(&track.file_path, &new_tags).map_err(|e| e.to_string())?;
+    // 2. Write to File (verified; the DB is only updated once it landed)
+    write_track_comment(&track, &new_tags)?;
 
     // 2a. Touch file (for Rekordbox/Finder to notice change)
-    if let Err(e) = touch_file(&track.file_path) {
-        println!("Warning: Failed to touch file: {}", e);
+    if !track.is_cloud {
+        if let Err(e) = touch_file(&track.file_path) {
+            tracing::warn!("Failed to touch file: {}", e);
+        }
     }
     
     // 2b. Update in Music.app (via AppleScript) - Direct Metadata Update
-    if let Err(e) = update_track_comment(&track.persistent_id, &new_tags) {
-         println!("Warning: Failed to update track in Music: {}", e);
-    }
+    state.apple_queue.enqueue(AppleUpdate::Comment {
+        persistent_id: track.persistent_id.clone(),
+        comment: new_tags.clone(),
+    });
 
     // 3. Update DB
     track.comment_raw = Some(new_tags);
@@ -372,218 +331,398 @@ pub async fn write_tags(
     Ok(())
 }
 
-#[tauri::command]
-pub async fn batch_add_tag(ids: Vec<i64>, tag: String, state: State<'_, AppState>) -> Result<(), String> {
-    let raw_tag = tag.trim();
-    if raw_tag.is_empty() {
-        return Ok(());
-    }
+/// Payload of `batch-tag-progress`, emitted while a batch tag change writes files.
+#[derive(Clone, serde::Serialize)]
+pub struct BatchTagProgress {
+    pub processed: usize,
+    pub total: usize,
+}
 
-    let db_mutex = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
-    
-    // Collect tracks to avoid holding lock too long if we needed to, but here we need lock for update anyway
-    // Or we iterate one by one. For safety/simplicity let's get all tracks first.
-    let mut tracks_to_update = Vec::new();
+/// Files written between `batch-tag-progress` events
+const BATCH_TAG_PROGRESS_INTERVAL: usize = 50;
 
-    for id in &ids {
-        if let Ok(Some(track)) = db_mutex.get_track(*id) {
-             tracks_to_update.push(track);
-        }
+/// Applies new comments to a batch of tracks: files are written in parallel, then the DB is
+/// updated in one transaction, Music.app gets one batch and the undo stack one step.
+/// Tracks whose file can't be written are left untouched. Returns the number updated.
+pub(crate) async fn apply_comment_batch(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    changes: Vec<(Track, String)>,
+) -> Result<usize, String> {
+    if changes.is_empty() {
+        return Ok(0);
     }
-    // Drop lock to perform file IO
-    drop(db_mutex); 
+    let handle = app.clone();
+    let undo_track_states = tauri::async_runtime::spawn_blocking(move || write_comment_batch(&handle, changes))
+        .await
+        .map_err(|e| e.to_string())??;
 
-    let mut apple_music_updates = Vec::new();
-    let mut undo_track_states = Vec::new();
+    let updated = undo_track_states.len();
+    if !undo_track_states.is_empty() {
+        if let Ok(mut stack) = state.undo_stack.lock() {
+            stack.push(Action::UpdateTrackComments { tracks: undo_track_states });
+        }
+    }
+    Ok(updated)
+}
 
-    for mut track in tracks_to_update {
-        let current_comment = track.comment_raw.clone().unwrap_or_default();
-        let old_comment_val = current_comment.clone(); // Capture for undo
+/// Blocking part of `apply_comment_batch`, for callers already off the async runtime. Does
+/// everything but the undo step and returns the undo states of the tracks that changed,
+/// so a caller running several batches can push them as one step.
+pub(crate) fn write_comment_batch(
+    app: &tauri::AppHandle,
+    changes: Vec<(Track, String)>,
+) -> Result<Vec<TrackState>, String> {
+    if changes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let state = app.state::<AppState>();
+    let total = changes.len();
+    let written = write_comment_files(changes, |done| {
+        if done % BATCH_TAG_PROGRESS_INTERVAL == 0 || done == total {
+            let _ = app.emit("batch-tag-progress", BatchTagProgress { processed: done, total });
+        }
+    });
 
-        let (user_comment, tag_block) = if let Some(idx) = current_comment.find(" && ") {
-            (&current_comment[..idx], &current_comment[idx + 4..])
-        } else {
-            (current_comment.as_str(), "")
-        };
+    let committed = match state.db.lock() {
+        Ok(db) => commit_comment_batch(&db, &written),
+        Err(_) => Err("Failed to lock DB".to_string()),
+    };
+    let committed = committed.map_err(|e| restore_comment_files(&written, e))?;
 
-        // Check if exists
-        let mut tags: Vec<String> = tag_block.split(';')
-            .map(|t| t.trim().to_string())
-            .filter(|t| !t.is_empty())
-            .collect();
-
-        // Case insensitive check
-        if !tags.iter().any(|t| t.to_lowercase() == raw_tag.to_lowercase()) {
-            tags.push(raw_tag.to_string());
-            
-            // Reconstruct
-            let new_tag_block = tags.join("; ");
-            let new_full_comment = if !new_tag_block.is_empty() {
-                if user_comment.is_empty() {
-                     format!(" && {}", new_tag_block)
-                } else {
-                     format!("{} && {}", user_comment, new_tag_block)
-                }
-            } else {
-                user_comment.to_string()
-            };
+    // Flush Batch Update
+    if !committed.music_updates.is_empty() {
+        state.apple_queue.enqueue(AppleUpdate::Comments { updates: committed.music_updates });
+    }
 
-            // Prepare Undo State
-            undo_track_states.push(TrackState {
-                id: track.id,
-                persistent_id: track.persistent_id.clone(),
-                file_path: track.file_path.clone(),
-                old_comment: old_comment_val,
-                new_comment: new_full_comment.clone(),
-            });
+    tracing::info!("Updated comments of {} of {} tracks", committed.undo.len(), total);
+    Ok(committed.undo)
+}
 
-            // WRITE
-            // 1. File
-             if let Err(e) = write_tags_to_file(&track.file_path, &new_full_comment) {
-                 println!("Failed to write file {}: {}", track.id, e);
-                 continue; 
-             }
+/// Tracks a comment batch changed, from `commit_comment_batch`.
+pub struct CommittedComments {
+    pub undo: Vec<TrackState>,
+    /// (persistent id, comment) for each track Music.app knows
+    pub music_updates: Vec<(String, String)>,
+}
 
-            // 2. DB (re-lock)
-            track.comment_raw = Some(new_full_comment.clone());
-            {
-                if let Ok(db) = state.db.lock() {
-                    let _ = db.update_track(&track);
+/// First half of the comment pipeline, shared with the CLI: writes the new comments to the
+/// files in parallel and returns the changes that landed. Files that can't be written are
+/// logged and left out. `progress` gets the number of files processed so far.
+pub fn write_comment_files(changes: Vec<(Track, String)>, progress: impl Fn(usize) + Sync) -> Vec<(Track, String)> {
+    use rayon::prelude::*;
+    use std::sync::atomic::AtomicUsize;
+
+    let processed = AtomicUsize::new(0);
+    changes.into_par_iter()
+        .filter_map(|(track, comment)| {
+            let result = write_track_comment(&track, &comment);
+            progress(processed.fetch_add(1, Ordering::Relaxed) + 1);
+            match result {
+                Ok(()) => Some((track, comment)),
+                Err(e) => {
+                    tracing::warn!("Failed to write file {}: {}", track.id, e);
+                    None
                 }
             }
+        })
+        .collect()
+}
 
-            // 3. Queue Music.app Update
-             if !track.persistent_id.is_empty() {
-                 apple_music_updates.push((track.persistent_id.clone(), new_full_comment));
-             } else {
-                 let _ = touch_file(&track.file_path);
-             }
+/// Second half of the comment pipeline: stores the written comments in one transaction.
+/// The caller takes the writer lock only for this, after the file writes, and hands the
+/// batch to `restore_comment_files` when it fails.
+pub fn commit_comment_batch(db: &Database, written: &[(Track, String)]) -> Result<CommittedComments, String> {
+    let mut apple_music_updates = Vec::new();
+    let mut undo_track_states = Vec::new();
+    let mut updated_tracks = Vec::with_capacity(written.len());
+
+    for (track, new_comment) in written {
+        let mut track = track.clone();
+        let new_comment = new_comment.clone();
+        undo_track_states.push(TrackState {
+            id: track.id,
+            persistent_id: track.persistent_id.clone(),
+            file_path: track.file_path.clone(),
+            old_comment: track.comment_raw.clone().unwrap_or_default(),
+            new_comment: new_comment.clone(),
+        });
+        if !track.persistent_id.is_empty() {
+            apple_music_updates.push((track.persistent_id.clone(), new_comment.clone()));
+        } else {
+            let _ = touch_file(&track.file_path);
         }
+        track.comment_raw = Some(new_comment);
+        updated_tracks.push(track);
     }
 
-    // Flush Batch Update
-    if !apple_music_updates.is_empty() {
-        if let Err(e) = batch_update_track_comments(apple_music_updates) {
-            println!("Batch update to Music app failed: {}", e);
-        }
+    db.update_tracks(&updated_tracks).map_err(|e| format!("DB Error (update tracks): {}", e))?;
+    Ok(CommittedComments { undo: undo_track_states, music_updates: apple_music_updates })
+}
+
+/// Puts the old comments back into files whose new comment never reached the DB, so files
+/// and DB agree again. Returns `error` with the outcome of the rollback added.
+pub fn restore_comment_files(written: &[(Track, String)], error: String) -> String {
+    use rayon::prelude::*;
+
+    let failed: Vec<String> = written.par_iter()
+        .filter_map(|(track, _)| {
+            let old_comment = track.comment_raw.clone().unwrap_or_default();
+            write_track_comment(track, &old_comment).err().map(|e| {
+                tracing::error!("Failed to restore the comment of {}: {}", track.file_path, e);
+                track.file_path.clone()
+            })
+        })
+        .collect();
+    if failed.is_empty() {
+        format!("{}; the files were restored", error)
+    } else {
+        format!("{}; {} of {} file(s) could not be restored: {}", error, failed.len(), written.len(), failed.join(", "))
     }
+}
 
-    // Push Undo Action
-    if !undo_track_states.is_empty() {
-        if let Ok(mut stack) = state.undo_stack.lock() {
-            stack.push(Action::UpdateTrackComments { tracks: undo_track_states });
+/// Adds `tag` to the tracks right away as one undoable batch, for callers that act on a
+/// handful of tracks and want the result before they return.
+pub(crate) async fn add_tag_to_tracks(app: &tauri::AppHandle, state: &AppState, ids: &[i64], tag: &str) -> Result<(), String> {
+    let mut changes = Vec::new();
+    {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let tag = canonical_tag(&db, tag);
+        if tag.is_empty() {
+            return Ok(());
+        }
+
+        let new_tags = [tag];
+        for id in ids {
+            let Ok(Some(track)) = db.get_track(*id) else { continue };
+            let current_comment = track.comment_raw.clone().unwrap_or_default();
+            if let Some(new_comment) = crate::comment::add_tags(&current_comment, &new_tags) {
+                changes.push((track, new_comment));
+            }
         }
     }
 
-    Ok(())
+    apply_comment_batch(app, state, changes).await.map(|_| ())
 }
 
-#[tauri::command]
-pub async fn batch_remove_tag(ids: Vec<i64>, tag: String, state: State<'_, AppState>) -> Result<(), String> {
+/// Removes `tag` from the tracks right away; the counterpart of `add_tag_to_tracks`.
+pub(crate) async fn remove_tag_from_tracks(app: &tauri::AppHandle, state: &AppState, ids: &[i64], tag: &str) -> Result<(), String> {
     let raw_tag = tag.trim();
     if raw_tag.is_empty() {
         return Ok(());
     }
-    
-    // Lock briefly to get tracks
-    let mut tracks_to_update = Vec::new();
+
+    let mut changes = Vec::new();
     {
-        let db_mutex = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
-        for id in &ids {
-            if let Ok(Some(track)) = db_mutex.get_track(*id) {
-                tracks_to_update.push(track);
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let old_tags = [raw_tag.to_string()];
+        for id in ids {
+            let Ok(Some(track)) = db.get_track(*id) else { continue };
+            let current_comment = track.comment_raw.clone().unwrap_or_default();
+            if let Some(new_comment) = crate::comment::remove_tags(&current_comment, &old_tags) {
+                changes.push((track, new_comment));
             }
         }
-    } // Drop lock
+    }
 
-    let mut apple_music_updates = Vec::new();
-    let mut undo_track_states = Vec::new();
+    apply_comment_batch(app, state, changes).await.map(|_| ())
+}
 
-    for mut track in tracks_to_update {
-        // Parse Comments
-        let current_comment = track.comment_raw.clone().unwrap_or_default();
-        let old_comment_val = current_comment.clone();
+/// Queues a job adding `tag` to the tracks. Returns the job id, or `None` for an empty tag.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn batch_add_tag(
+    ids: Vec<i64>,
+    tag: String,
+    state: State<'_, AppState>,
+    jobs: State<'_, crate::jobs::JobQueue>,
+) -> Result<Option<u64>, String> {
+    let tag = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        canonical_tag(&db, &tag)
+    };
+    if tag.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(jobs.submit(crate::jobs::JobKind::BatchAddTag { ids, tag })))
+}
 
-        let (user_comment, tag_block) = if let Some(idx) = current_comment.find(" && ") {
-            (&current_comment[..idx], &current_comment[idx + 4..])
-        } else {
-            (current_comment.as_str(), "")
-        };
+/// Queues a job removing `tag` from the tracks. Returns the job id, or `None` for an empty tag.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn batch_remove_tag(ids: Vec<i64>, tag: String, jobs: State<'_, crate::jobs::JobQueue>) -> Result<Option<u64>, String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(jobs.submit(crate::jobs::JobKind::BatchRemoveTag { ids, tag: tag.to_string() })))
+}
 
-        // Filter OUT the tag
-        let mut tags: Vec<String> = tag_block.split(';')
-            .map(|t| t.trim().to_string())
-            .filter(|t| !t.is_empty())
-            .collect();
-        
-        let initial_len = tags.len();
-        tags.retain(|t| t.to_lowercase() != raw_tag.to_lowercase());
-        
-        // If changed
-        if tags.len() != initial_len {
-            // Reconstruct
-            let new_tag_block = tags.join("; ");
-            let new_full_comment = if !new_tag_block.is_empty() {
-                if user_comment.is_empty() {
-                     format!(" && {}", new_tag_block)
-                } else {
-                     format!("{} && {}", user_comment, new_tag_block)
+/// How copied tags combine with a target track's own tags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagCopyMode {
+    /// The target ends up with exactly the copied tags
+    Replace,
+    /// The copied tags are added to the ones the target already has
+    Merge,
+}
+
+/// Applies a tag set to tracks as one undoable batch. The targets' user comments are kept.
+/// Returns the number of tracks that changed.
+pub(crate) async fn apply_tags_to_tracks(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    tags: &[String],
+    target_ids: &[i64],
+    mode: TagCopyMode,
+) -> Result<usize, String> {
+    let mut changes = Vec::new();
+    {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        for id in target_ids {
+            let Ok(Some(track)) = db.get_track(*id) else { continue };
+            let current_comment = track.comment_raw.clone().unwrap_or_default();
+            let new_comment = match mode {
+                TagCopyMode::Merge => crate::comment::add_tags(&current_comment, tags),
+                TagCopyMode::Replace => {
+                    let (user_comment, _) = crate::comment::split_comment(&current_comment);
+                    Some(crate::comment::build_comment(user_comment, tags))
+                        .filter(|c| *c != current_comment)
                 }
-            } else {
-                user_comment.to_string()
             };
+            if let Some(new_comment) = new_comment {
+                changes.push((track, new_comment));
+            }
+        }
+    }
 
-            // Prepare Undo State
-            undo_track_states.push(TrackState {
-                id: track.id,
-                persistent_id: track.persistent_id.clone(),
-                file_path: track.file_path.clone(),
-                old_comment: old_comment_val,
-                new_comment: new_full_comment.clone(),
-            });
+    apply_comment_batch(app, state, changes).await
+}
 
-            // WRITE
-            if let Err(e) = write_tags_to_file(&track.file_path, &new_full_comment) {
-                println!("Failed to write file {}: {}", track.id, e);
-                continue; 
-            }
+/// Gives the target tracks the source track's tags, replacing or merging with their own.
+/// One undo step; returns the number of tracks that changed. A source without tags is
+/// refused in Replace mode, since it would wipe every target's tags.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn copy_tags(
+    source_id: i64,
+    target_ids: Vec<i64>,
+    mode: TagCopyMode,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let source = state.db.read().map_err(|_| "Failed to lock DB".to_string())?
+        .get_track(source_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Track {} not found", source_id))?;
+    let (_, tags) = crate::comment::split_comment(source.comment_raw.as_deref().unwrap_or_default());
+    if tags.is_empty() {
+        return match mode {
+            TagCopyMode::Merge => Ok(0),
+            TagCopyMode::Replace => Err("Source track has no tags".to_string()),
+        };
+    }
+    let targets: Vec<i64> = target_ids.into_iter().filter(|id| *id != source_id).collect();
 
-            // DB
-            track.comment_raw = Some(new_full_comment.clone());
-            {
-                if let Ok(db) = state.db.lock() {
-                    let _ = db.update_track(&track);
-                }
-            }
+    apply_tags_to_tracks(&app, &state, &tags, &targets, mode).await
+}
 
-            // Music.app Queue
-             if !track.persistent_id.is_empty() {
-                 apple_music_updates.push((track.persistent_id.clone(), new_full_comment));
-             } else {
-                 let _ = touch_file(&track.file_path);
-             }
-        }
+/// Copies a track's tags to the tag clipboard and returns them.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn copy_tag_block(track_id: i64, state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let track = state.db.read().map_err(|_| "Failed to lock DB".to_string())?
+        .get_track(track_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Track {} not found", track_id))?;
+    let (_, tags) = crate::comment::split_comment(track.comment_raw.as_deref().unwrap_or_default());
+    *state.tag_clipboard.lock().map_err(|_| "Failed to lock tag clipboard".to_string())? = tags.clone();
+    Ok(tags)
+}
+
+/// Applies the tag clipboard to tracks, replacing or merging with their tags. One undo
+/// step; returns the number of tracks that changed. Replacing with an empty clipboard is
+/// refused rather than clearing every track's tags.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn paste_tag_block(
+    ids: Vec<i64>,
+    mode: TagCopyMode,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let tags = state.tag_clipboard.lock().map_err(|_| "Failed to lock tag clipboard".to_string())?.clone();
+    if tags.is_empty() {
+        return match mode {
+            TagCopyMode::Merge => Ok(0),
+            TagCopyMode::Replace => Err("Tag clipboard is empty".to_string()),
+        };
     }
+    apply_tags_to_tracks(&app, &state, &tags, &ids, mode).await
+}
+
+/// Puts back the comment (and grouping) tracks had before TagDeck first changed them,
+/// as saved in the original-metadata vault. One undo step; returns the number restored.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn restore_original_comment(ids: Vec<i64>, app: tauri::AppHandle, state: State<'_, AppState>) -> Result<usize, String> {
+    use rayon::prelude::*;
 
-    // Flush Batch
-    if !apple_music_updates.is_empty() {
-        if let Err(e) = batch_update_track_comments(apple_music_updates) {
-             println!("Batch update to Music app failed: {}", e);
+    let mut to_restore = Vec::new();
+    {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        for id in &ids {
+            if let (Ok(Some(track)), Ok(Some(original))) = (db.get_track(*id), db.get_original_metadata(*id)) {
+                to_restore.push((track, original));
+            }
         }
     }
 
-    // Push Undo Action
-    if !undo_track_states.is_empty() {
-        if let Ok(mut stack) = state.undo_stack.lock() {
-            stack.push(Action::UpdateTrackComments { tracks: undo_track_states });
-        }
+    // Groupings first, so a track whose comment write fails still gets its grouping saved
+    let tracks: Vec<(Track, String, bool)> = tauri::async_runtime::spawn_blocking(move || {
+        to_restore.into_par_iter()
+            .map(|(mut track, (original_comment, original_grouping))| {
+                let grouping = original_grouping.unwrap_or_default();
+                let mut grouping_changed = false;
+                if track.grouping_raw.as_deref().unwrap_or("") != grouping {
+                    match crate::metadata::write_grouping(&track.file_path, &grouping) {
+                        Ok(()) => {
+                            track.grouping_raw = Some(grouping);
+                            grouping_changed = true;
+                        }
+                        Err(e) => tracing::warn!("Failed to restore grouping of {}: {}", track.file_path, e),
+                    }
+                }
+                (track, original_comment.unwrap_or_default(), grouping_changed)
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let regrouped: Vec<Track> = tracks.iter().filter(|(_, _, changed)| *changed).map(|(t, _, _)| t.clone()).collect();
+    if !regrouped.is_empty() {
+        state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+            .update_tracks(&regrouped)
+            .map_err(|e| format!("DB Error (update tracks): {}", e))?;
     }
 
-    Ok(())
+    let changes: Vec<(Track, String)> = tracks.into_iter()
+        .filter(|(track, comment, _)| track.comment_raw.as_deref().unwrap_or("") != comment)
+        .map(|(track, comment, _)| (track, comment))
+        .collect();
+    let restored = apply_comment_batch(&app, &state, changes).await?;
+
+    tracing::info!("Restored original comments on {} of {} tracks", restored, ids.len());
+    Ok(restored)
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn import_from_music_app(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<usize, String> {
+    if crate::file_library::is_file_only() {
+        return Err("Music.app import is off in file-only mode; rescan the library folder instead".to_string());
+    }
+
     // Acquire sync lock
     if state.is_syncing.swap(true, Ordering::SeqCst) {
         return Err("Sync already in progress".to_string());
@@ -598,60 +737,254 @@ pub async fn import_from_music_app(app: tauri::AppHandle, state: State<'_, AppSt
     }
     let _guard = SyncGuard(&state.is_syncing);
 
-    println!("Importing from Music.app...");
+    tracing::debug!("Importing from Music.app...");
+
+    // 1. Stream from Sidecar, inserting each batch in its own transaction as it arrives
+    let resolver = load_resolver(&state);
+    let started = std::time::Instant::now();
+    let mut count = 0;
+    let mut rewritten = 0;
+
+    let streamed = stream_system_library(&app, |mut batch, progress| {
+        rewritten += resolver.apply_to_tracks(&mut batch);
+        let db = state.db.lock().map_err(|_| anyhow::anyhow!("Failed to lock DB"))?;
+        for track in batch.iter_mut() {
+            crate::tag_storage::restore_tag_block(&db, track);
+        }
+        db.insert_tracks(&batch).map_err(|e| anyhow::anyhow!("DB Error (insert tracks): {}", e))?;
+        drop(db);
+        count += batch.len();
+        let _ = app.emit("import-progress", ImportProgress::new(count, progress, started.elapsed()));
+        Ok(())
+    }).await;
 
-    // 1. Fetch from Sidecar
-    let (tracks, playlists) = match fetch_system_library(&app).await {
-        Ok(res) => res,
+    let playlists = match streamed {
+        Ok(playlists) => playlists,
         Err(e) => {
-            let msg = format!("Sidecar Error: {}", e);
-            app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+            let msg = format!("Import from Music.app failed after {} tracks: {}", count, e);
+            tracing::error!("{}", msg);
             return Err(msg);
         }
     };
-    let count = tracks.len();
-    println!("Found {} tracks and {} playlists from Music.app", count, playlists.len());
+    tracing::debug!("Imported {} tracks and {} playlists from Music.app", count, playlists.len());
 
-    // 2. Insert into DB
+    if rewritten > 0 {
+        tracing::info!("Path rules rewrote {} track locations", rewritten);
+    }
+
+    // 2. Playlists last, so every member track is already in the DB
     let db = state
         .db
         .lock()
         .map_err(|_| "Failed to lock DB".to_string())?;
 
-    for track in tracks {
-        if let Err(e) = db.insert_track(&track) {
-            let msg = format!("DB Error (insert track): {}", e);
-            app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
-            return Err(msg);
-        }
-    }
-    
-    for playlist in playlists {
-        if let Err(e) = db.insert_playlist(&playlist) {
-             let msg = format!("DB Error (insert playlist): {}", e);
-             app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
-             return Err(msg);
-        }
+    if let Err(e) = db.insert_playlists(&playlists) {
+        let msg = format!("DB Error (insert playlist): {}", e);
+        tracing::error!("{}", msg);
+        return Err(msg);
     }
 
+    tracing::info!("Imported {} tracks from Music.app in {:.1}s", count, started.elapsed().as_secs_f64());
     Ok(count)
 }
 
-#[derive(serde::Serialize)]
-pub struct SyncResult {
-    pub tracks_updated: usize,
-    pub tracks_added: usize,
-    pub tracks_deleted: usize,
-    pub playlists_updated: usize,
+/// Payload of `import-progress`, emitted after each batch of a Music.app import.
+#[derive(Clone, serde::Serialize)]
+pub struct ImportProgress {
+    pub imported: usize,
+    /// Upper bound from the sidecar; 0 if unknown
+    pub total: usize,
+    pub elapsed_secs: f64,
+    /// Remaining time at the current rate, once it can be estimated
+    pub eta_secs: Option<f64>,
 }
 
-#[tauri::command]
-pub async fn sync_recent_changes(app: tauri::AppHandle, state: State<'_, AppState>, since_timestamp: i64) -> Result<SyncResult, String> {
-    
-    // Check if full sync is running, but don't error out hard—just skip
-    if state.is_syncing.load(Ordering::SeqCst) {
-        println!("Sync skipped: Full sync in progress");
-        return Ok(SyncResult { tracks_updated: 0, tracks_added: 0, tracks_deleted: 0, playlists_updated: 0 });
+impl ImportProgress {
+    fn new(imported: usize, progress: &FetchProgress, elapsed: std::time::Duration) -> Self {
+        let elapsed_secs = elapsed.as_secs_f64();
+        let eta_secs = (imported > 0 && progress.total > imported)
+            .then(|| elapsed_secs / imported as f64 * (progress.total - imported) as f64);
+        Self { imported, total: progress.total, elapsed_secs, eta_secs }
+    }
+}
+
+/// Payload of `sync-progress`, emitted as `sync_recent_changes` works through its phases.
+#[derive(Clone, serde::Serialize)]
+pub struct SyncProgress {
+    /// "library", "metadata", "snapshot", "playlists", then "done"
+    pub phase: &'static str,
+    pub done: usize,
+    /// 0 while the phase is still fetching from Music.app
+    pub total: usize,
+    pub message: String,
+}
+
+/// A Music.app playlist for the Phase 3 diff. `track_ids` is only authoritative when
+/// `members_fetched`; otherwise the header says membership is unchanged and the DB's stands.
+struct SyncPlaylist {
+    entry: PlaylistSnapshotEntry,
+    members_fetched: bool,
+}
+
+/// Every Music.app playlist for the Phase 3 diff, plus the header signatures to record once
+/// it is applied. Track lists are only fetched for playlists whose header changed since the
+/// last sync or that the DB doesn't have yet. `full` fetches them all.
+fn fetch_playlists_for_sync(
+    db_pids: &HashSet<String>,
+    known_headers: &HashMap<String, String>,
+    full: bool,
+) -> anyhow::Result<(Vec<SyncPlaylist>, HashMap<String, String>)> {
+    let all_fetched = |entries: Vec<PlaylistSnapshotEntry>| {
+        entries.into_iter().map(|entry| SyncPlaylist { entry, members_fetched: true }).collect()
+    };
+    let headers = match get_playlist_headers() {
+        Ok(headers) => headers,
+        Err(e) => {
+            tracing::warn!("Playlist header fetch failed, fetching every playlist: {}", e);
+            return Ok((all_fetched(get_playlist_snapshot()?), HashMap::new()));
+        }
+    };
+    let mut signatures: HashMap<String, String> = headers.iter()
+        .map(|h| (h.persistent_id.clone(), h.signature()))
+        .collect();
+    if full {
+        return Ok((all_fetched(get_playlist_snapshot()?), signatures));
+    }
+
+    let changed: Vec<String> = headers.iter()
+        .filter(|h| !h.is_folder)
+        .filter(|h| !db_pids.contains(&h.persistent_id)
+            || known_headers.get(&h.persistent_id) != signatures.get(&h.persistent_id))
+        .map(|h| h.persistent_id.clone())
+        .collect();
+    tracing::info!("{} of {} playlists changed since the last sync", changed.len(), headers.len());
+
+    let mut fetched = get_playlist_track_pids(&changed)?;
+    // Not fetched after all: check again next time
+    for pid in changed.iter().filter(|pid| !fetched.contains_key(*pid)) {
+        signatures.remove(pid);
+    }
+
+    let playlists = headers.into_iter()
+        .map(|h| {
+            let track_ids = fetched.remove(&h.persistent_id);
+            SyncPlaylist {
+                members_fetched: track_ids.is_some(),
+                entry: PlaylistSnapshotEntry {
+                    persistent_id: h.persistent_id,
+                    parent_persistent_id: h.parent_persistent_id,
+                    name: h.name,
+                    is_folder: h.is_folder,
+                    is_smart: h.is_smart,
+                    track_ids: track_ids.unwrap_or_default(),
+                },
+            }
+        })
+        .collect();
+    Ok((playlists, signatures))
+}
+
+/// (old, new) persistent ID pairs for tracks about to be deleted whose file shows up again
+/// under a new ID: Music.app re-imported the file. Each old ID is matched at most once.
+fn find_reimports(db: &Database, deleted_pids: &[String], new_tracks: &[Track]) -> Vec<(String, String)> {
+    let mut by_path: HashMap<String, String> = deleted_pids.iter()
+        .filter_map(|pid| {
+            let id = db.get_track_id_by_persistent_id(pid).ok().flatten()?;
+            let path = db.get_track_path(id).ok()?;
+            (!path.is_empty()).then(|| (path, pid.clone()))
+        })
+        .collect();
+    new_tracks.iter()
+        .filter(|t| !t.file_path.is_empty())
+        .filter_map(|t| by_path.remove(&t.file_path).map(|old| (old, t.persistent_id.clone())))
+        .collect()
+}
+
+/// Moves a track's row, and everything attached to it, from `old_pid` to `new_pid`. For
+/// re-imports the sync misses, e.g. a file replaced at a different path.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn remap_persistent_id(old_pid: String, new_pid: String, state: State<'_, AppState>) -> Result<i64, String> {
+    if old_pid == new_pid {
+        return Err("Old and new persistent IDs are the same".to_string());
+    }
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    let id = db.remap_persistent_id(&old_pid, &new_pid).map_err(|e| e.to_string())?;
+    tracing::info!("Remapped track {} from {} to {}", id, old_pid, new_pid);
+    Ok(id)
+}
+
+/// Items between `sync-progress` events inside a phase
+const SYNC_PROGRESS_INTERVAL: usize = 100;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SyncResult {
+    pub tracks_updated: usize,
+    pub tracks_added: usize,
+    pub tracks_deleted: usize,
+    pub playlists_updated: usize,
+}
+
+/// Settings key: Unix time of the last completed recent-changes sync
+pub(crate) const LAST_SYNC_KEY: &str = "last_sync_time";
+/// Look-back added to the last sync time, since `modification date` is only second-accurate
+/// and a change can land while a sync is running
+const SYNC_OVERLAP_SECS: i64 = 3600;
+/// Window used when there is no usable last sync time
+const FIRST_SYNC_WINDOW_SECS: i64 = 86400;
+
+/// Where a recent-changes sync should start: the last completed sync minus the overlap.
+/// Without a recorded sync, or with one in the future (clock changed), the last day.
+pub(crate) fn sync_window_start(state: &AppState) -> i64 {
+    let now = chrono::Utc::now().timestamp();
+    let last = state.db.read().ok()
+        .and_then(|db| db.get_setting(LAST_SYNC_KEY).ok().flatten())
+        .and_then(|s| s.parse::<i64>().ok())
+        .filter(|t| *t <= now);
+    match last {
+        Some(last) => (last - SYNC_OVERLAP_SECS).max(0),
+        None => now - FIRST_SYNC_WINDOW_SECS,
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn sync_recent_changes(app: tauri::AppHandle, state: State<'_, AppState>, since_timestamp: i64) -> Result<SyncResult, String> {
+    sync_recent(&app, &state, since_timestamp, SyncScope::Full)
+}
+
+/// Syncs everything changed in Music.app since the last completed sync. The window is kept
+/// in the settings table, so it doesn't drift while the machine sleeps.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn sync_now(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<SyncResult, String> {
+    sync_recent(&app, &state, sync_window_start(&state), SyncScope::Full)
+}
+
+/// Which phases a recent-changes sync runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SyncScope {
+    /// Every phase
+    Full,
+    /// Imports, deletions, date-based metadata and playlists, without the library-wide
+    /// rating/BPM/plays snapshot. For when the library database itself did not change.
+    Recent,
+}
+
+/// Pulls everything changed in Music.app since `since_timestamp` into the DB. Shared by
+/// the `sync_recent_changes` command and the library watcher.
+pub(crate) fn sync_recent(app: &tauri::AppHandle, state: &AppState, since_timestamp: i64, scope: SyncScope) -> Result<SyncResult, String> {
+    let started_at = chrono::Utc::now().timestamp();
+
+    // File-only libraries are kept current by their own watcher
+    if crate::file_library::is_file_only() {
+        return Ok(SyncResult { tracks_updated: 0, tracks_added: 0, tracks_deleted: 0, playlists_updated: 0 });
+    }
+
+    // Check if full sync is running, but don't error out hard—just skip
+    if state.is_syncing.load(Ordering::SeqCst) {
+        tracing::debug!("Sync skipped: Full sync in progress");
+        return Ok(SyncResult { tracks_updated: 0, tracks_added: 0, tracks_deleted: 0, playlists_updated: 0 });
     }
     // We do NOT set the lock for real-time sync (unless we want to block full sync?)
     // Actually, we should probably lock it too to prevent concurrent real-time syncs?
@@ -671,9 +1004,11 @@ pub async fn sync_recent_changes(app: tauri::AppHandle, state: State<'_, AppStat
     }
     let _guard = SyncGuard(&state.is_syncing);
 
-    let start_msg = format!("Syncing recent changes from Music.app since timestamp: {}", since_timestamp);
-    println!("{}", start_msg);
-    app.state::<crate::logging::LogState>().add_log("INFO", &start_msg, &app);
+    tracing::info!("Syncing recent changes from Music.app since timestamp: {}", since_timestamp);
+
+    let progress = |phase: &'static str, done: usize, total: usize, message: String| {
+        let _ = app.emit("sync-progress", SyncProgress { phase, done, total, message });
+    };
 
     let mut total_updated = 0;
     let mut tracks_added = 0;
@@ -681,42 +1016,61 @@ pub async fn sync_recent_changes(app: tauri::AppHandle, state: State<'_, AppStat
 
     // --- Phase 0: Detect newly imported and deleted tracks ---
     // Compare the set of persistent IDs in Music.app vs our DB to find additions and deletions.
-    let phase0_msg = "Phase 0: Checking for imported/deleted tracks...";
-    println!("{}", phase0_msg);
-    app.state::<crate::logging::LogState>().add_log("INFO", phase0_msg, &app);
+    tracing::info!("Phase 0: Checking for imported/deleted tracks...");
+    progress("library", 0, 0, "Checking for imported and deleted tracks".to_string());
 
     match get_all_music_app_pids() {
         Ok(music_pids) => {
             let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
             let db_pids = db.get_all_track_pids().map_err(|e| e.to_string())?;
+            // Tracks deleted in TagDeck only; forget the ones Music.app no longer has either
+            let skipped_pids = db.get_deleted_track_pids().map_err(|e| e.to_string())?;
+            let gone: Vec<String> = skipped_pids.iter()
+                .filter(|pid| !music_pids.contains(*pid))
+                .cloned()
+                .collect();
+            if let Err(e) = db.forget_deleted_tracks(&gone) {
+                tracing::warn!("Failed to prune deleted track list: {}", e);
+            }
             drop(db); // Release lock before potentially slow AppleScript calls
 
             // Detect NEW tracks (in Music.app but not in our DB)
             let new_pids: Vec<String> = music_pids.iter()
-                .filter(|pid| !db_pids.contains(*pid))
+                .filter(|pid| !db_pids.contains(*pid) && !skipped_pids.contains(*pid))
                 .cloned()
                 .collect();
 
             // Detect DELETED tracks (in our DB but not in Music.app)
-            let deleted_pids: Vec<String> = db_pids.iter()
+            let mut deleted_pids: Vec<String> = db_pids.iter()
                 .filter(|pid| !music_pids.contains(*pid))
                 .cloned()
                 .collect();
 
             // Handle newly imported tracks
             if !new_pids.is_empty() {
-                let import_msg = format!("Found {} new track(s) in Music.app. Importing...", new_pids.len());
-                println!("{}", import_msg);
-                app.state::<crate::logging::LogState>().add_log("INFO", &import_msg, &app);
+                tracing::info!("Found {} new track(s) in Music.app. Importing...", new_pids.len());
+                progress("library", 0, new_pids.len(), format!("Importing {} new tracks", new_pids.len()));
 
                 match get_tracks_by_persistent_ids(&new_pids) {
-                    Ok(new_tracks) => {
-                        let count = new_tracks.len();
+                    Ok(mut new_tracks) => {
                         let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
-                        for track in &new_tracks {
+                        // A re-imported file (same path, new persistent ID) keeps its row
+                        for (old_pid, new_pid) in find_reimports(&db, &deleted_pids, &new_tracks) {
+                            match db.remap_persistent_id(&old_pid, &new_pid) {
+                                Ok(_) => {
+                                    tracing::info!("Re-imported track {} is now {}", old_pid, new_pid);
+                                    deleted_pids.retain(|pid| pid != &old_pid);
+                                    new_tracks.retain(|t| t.persistent_id != new_pid);
+                                    total_updated += 1;
+                                }
+                                Err(e) => tracing::error!("Failed to remap {} to {}: {}", old_pid, new_pid, e),
+                            }
+                        }
+                        let count = new_tracks.len();
+                        for track in new_tracks.iter_mut() {
+                            crate::tag_storage::restore_tag_block(&db, track);
                             if let Err(e) = db.insert_track(track) {
-                                let msg = format!("DB Error importing new track {}: {}", track.persistent_id, e);
-                                app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+                                tracing::error!("DB Error importing new track {}: {}", track.persistent_id, e);
                             }
                         }
                         // Log some details
@@ -724,44 +1078,35 @@ pub async fn sync_recent_changes(app: tauri::AppHandle, state: State<'_, AppStat
                             if i < 10 {
                                 let title = track.title.as_deref().unwrap_or("Unknown");
                                 let artist = track.artist.as_deref().unwrap_or("Unknown");
-                                let detail = format!("Imported: {} - {}", artist, title);
-                                println!("{}", detail);
-                                app.state::<crate::logging::LogState>().add_log("INFO", &detail, &app);
+                                tracing::info!("Imported: {} - {}", artist, title);
                             }
                         }
                         if count > 10 {
-                            let more = format!("...and {} more imported tracks", count - 10);
-                            app.state::<crate::logging::LogState>().add_log("INFO", &more, &app);
+                            tracing::info!("...and {} more imported tracks", count - 10);
                         }
                         drop(db);
                         tracks_added += count;
                         total_updated += count;
                     }
                     Err(e) => {
-                        let msg = format!("Failed to fetch new track data from Music.app: {}", e);
-                        app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+                        tracing::error!("Failed to fetch new track data from Music.app: {}", e);
                     }
                 }
             }
 
             // Handle deleted tracks
             if !deleted_pids.is_empty() {
-                let delete_msg = format!("Found {} track(s) removed from Music.app. Removing from DB...", deleted_pids.len());
-                println!("{}", delete_msg);
-                app.state::<crate::logging::LogState>().add_log("INFO", &delete_msg, &app);
+                tracing::info!("Found {} track(s) removed from Music.app. Removing from DB...", deleted_pids.len());
 
                 let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
                 match db.remove_tracks_by_persistent_ids(&deleted_pids) {
                     Ok(count) => {
-                        let msg = format!("Removed {} deleted track(s) from DB", count);
-                        println!("{}", msg);
-                        app.state::<crate::logging::LogState>().add_log("INFO", &msg, &app);
+                        tracing::info!("Removed {} deleted track(s) from DB", count);
                         tracks_deleted += count;
                         total_updated += count;
                     }
                     Err(e) => {
-                        let msg = format!("DB Error removing deleted tracks: {}", e);
-                        app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+                        tracing::error!("DB Error removing deleted tracks: {}", e);
                     }
                 }
                 drop(db);
@@ -769,121 +1114,254 @@ pub async fn sync_recent_changes(app: tauri::AppHandle, state: State<'_, AppStat
 
             if new_pids.is_empty() && deleted_pids.is_empty() {
                 let msg = "Phase 0: No imported or deleted tracks detected.";
-                println!("{}", msg);
-                app.state::<crate::logging::LogState>().add_log("INFO", msg, &app);
+                tracing::info!("{}", msg);
             }
         }
         Err(e) => {
-            let msg = format!("Phase 0 failed (non-fatal): {}", e);
-            eprintln!("{}", msg);
-            app.state::<crate::logging::LogState>().add_log("WARN", &msg, &app);
+            tracing::warn!("Phase 0 failed (non-fatal): {}", e);
         }
     }
 
     // --- Phase 1: Date-based query for metadata changes (title, artist, album, comment, grouping) ---
     // `modification date` in Music.app covers these fields.
-    let tracks = get_changes_since(since_timestamp).map_err(|e| {
+    progress("metadata", 0, 0, "Fetching changed tracks".to_string());
+    let mut tracks = get_changes_since(since_timestamp).map_err(|e| {
         let msg = format!("Failed to fetch date-based changes: {}", e);
-        app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+        tracing::error!("{}", msg);
+        progress("done", 0, 0, msg.clone());
         msg
     })?;
+    // An edit in Music.app must not bring back a track deleted in TagDeck
+    let skipped_pids = state.db.read().map_err(|_| "Failed to lock DB".to_string())?
+        .get_deleted_track_pids().map_err(|e| e.to_string())?;
+    tracks.retain(|t| !skipped_pids.contains(&t.persistent_id));
 
     let meta_count = tracks.len();
-    println!("Found {} metadata-changed tracks via modification date", meta_count);
-    app.state::<crate::logging::LogState>().add_log("INFO", &format!("Found {} metadata-changed tracks via modification date", meta_count), &app);
+    tracing::info!("Found {} metadata-changed tracks via modification date", meta_count);
+    progress("metadata", 0, meta_count, format!("{} tracks changed", meta_count));
 
     if meta_count > 0 {
         let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
         for t in &tracks {
             let title = t.title.as_deref().unwrap_or("Unknown Title");
             let artist = t.artist.as_deref().unwrap_or("Unknown Artist");
-            println!("Syncing metadata: {} - {}", artist, title);
             if total_updated < 10 {
-                app.state::<crate::logging::LogState>().add_log("INFO", &format!("Syncing metadata: {} - {}", artist, title), &app);
+                tracing::info!("Syncing metadata: {} - {}", artist, title);
+            } else {
+                tracing::debug!("Syncing metadata: {} - {}", artist, title);
             }
         }
-        for track in tracks {
+        let mut conflicted = 0;
+        for (i, mut track) in tracks.into_iter().enumerate() {
+            crate::tag_storage::restore_tag_block(&db, &mut track);
+            // Both sides edited the comment: flag it and keep TagDeck's until the user decides
+            match crate::conflicts::check_incoming(&db, &state.apple_queue, &mut track, since_timestamp) {
+                Ok(true) => conflicted += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!("Conflict check failed for {}: {}", track.persistent_id, e);
+                }
+            }
             if let Err(e) = db.insert_track(&track) {
-                let msg = format!("DB Error (update track {}): {}", track.persistent_id, e);
-                app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+                tracing::error!("DB Error (update track {}): {}", track.persistent_id, e);
+            }
+            if (i + 1) % SYNC_PROGRESS_INTERVAL == 0 || i + 1 == meta_count {
+                progress("metadata", i + 1, meta_count, format!("Updated {} of {} tracks", i + 1, meta_count));
             }
         }
+        if conflicted > 0 {
+            tracing::warn!("{} track(s) have conflicting comments in Music.app; kept TagDeck's until resolved", conflicted);
+        }
         total_updated += meta_count;
         drop(db);
     }
 
-    // --- Phase 2: Snapshot diff for rating & BPM ---
-    // Music.app does NOT update `modification date` when rating or BPM changes.
-    // We fetch a lightweight snapshot of (persistent_id, rating, bpm) for all tracks
-    // and diff against our DB to detect changes.
-    let snapshot_msg = "Fetching rating/BPM snapshot from Music.app for diff...";
-    println!("{}", snapshot_msg);
-    app.state::<crate::logging::LogState>().add_log("INFO", snapshot_msg, &app);
+    // --- Phase 2: Snapshot diff for rating, BPM & plays ---
+    // Music.app does NOT update `modification date` when rating, BPM or play count changes.
+    // We fetch a lightweight snapshot of (persistent_id, rating, bpm, plays, skips) for all
+    // tracks and diff against our DB to detect changes.
+    let snapshot = (scope == SyncScope::Full).then(|| {
+        tracing::info!("Fetching rating/BPM snapshot from Music.app for diff...");
+        progress("snapshot", 0, 0, "Fetching ratings, BPM and play counts".to_string());
+        get_snapshot_fields()
+    });
 
-    match get_snapshot_fields() {
-        Ok(snapshot) => {
-            let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
-            let db_snapshot = db.get_rating_bpm_snapshot().map_err(|e| e.to_string())?;
+    match snapshot {
+        None => tracing::debug!("Snapshot diff skipped: library database unchanged"),
+        Some(Ok(mut snapshot)) => {
+            // Both sides in persistent ID order: DB rows are streamed and merged against the
+            // Music.app list instead of being loaded into maps
+            snapshot.sort_unstable_by(|a, b| a.persistent_id.cmp(&b.persistent_id));
+            let total = snapshot.len();
+
+            let mut played_pids: Vec<String> = Vec::new();
+            let mut loved_changes: Vec<(i64, &str, bool)> = Vec::new();
+            let mut rating_bpm_changes: Vec<(&str, (i64, i64), (i64, i64))> = Vec::new();
+            {
+                let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+                let mut music = snapshot.iter().enumerate().peekable();
+                db.for_each_track_sync_state(|row| {
+                    // Music.app tracks missing from the DB are skipped (not imported yet)
+                    while music.next_if(|(_, entry)| entry.persistent_id < row.persistent_id).is_some() {}
+                    let Some((i, entry)) = music.next_if(|(_, entry)| entry.persistent_id == row.persistent_id) else {
+                        return Ok(());
+                    };
+                    if i % (SYNC_PROGRESS_INTERVAL * 10) == 0 {
+                        progress("snapshot", i, total, format!("Compared {} of {} tracks", i, total));
+                    }
+                    if row.play_count != entry.play_count || row.skip_count != entry.skip_count {
+                        played_pids.push(entry.persistent_id.clone());
+                    }
+                    if row.loved != entry.loved {
+                        loved_changes.push((row.id, &entry.persistent_id, entry.loved));
+                    }
+                    if row.rating != entry.rating || row.bpm != entry.bpm {
+                        rating_bpm_changes.push((&entry.persistent_id, (row.rating, row.bpm), (entry.rating, entry.bpm)));
+                    }
+                    Ok(())
+                }).map_err(|e| e.to_string())?;
+            }
 
             let mut diff_count = 0;
-            for entry in &snapshot {
-                if let Some(&(db_rating, db_bpm)) = db_snapshot.get(&entry.persistent_id) {
-                    if db_rating != entry.rating || db_bpm != entry.bpm {
-                        if let Err(e) = db.update_rating_bpm(&entry.persistent_id, entry.rating, entry.bpm) {
-                            let msg = format!("DB Error (snapshot update {}): {}", entry.persistent_id, e);
-                            app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
-                        } else {
-                            diff_count += 1;
-                            if diff_count <= 10 {
-                                let detail = format!("Snapshot diff: {} — rating {} → {}, bpm {} → {}",
-                                    entry.persistent_id, db_rating, entry.rating, db_bpm, entry.bpm);
-                                println!("{}", detail);
-                                app.state::<crate::logging::LogState>().add_log("INFO", &detail, &app);
-                            }
-                        }
+            let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+            for (id, pid, loved) in loved_changes {
+                match db.update_track_loved(id, loved) {
+                    Ok(()) => diff_count += 1,
+                    Err(e) => {
+                        tracing::error!("DB Error (loved update {}): {}", pid, e);
+                    }
+                }
+            }
+            for (pid, (db_rating, db_bpm), (rating, bpm)) in rating_bpm_changes {
+                if let Err(e) = db.update_rating_bpm(pid, rating, bpm) {
+                    tracing::error!("DB Error (snapshot update {}): {}", pid, e);
+                } else {
+                    diff_count += 1;
+                    if diff_count <= 10 {
+                        tracing::info!("Snapshot diff: {} — rating {} → {}, bpm {} → {}",
+                            pid, db_rating, rating, db_bpm, bpm);
                     }
                 }
-                // If persistent_id not in our DB, skip (track not imported yet)
             }
 
-            let snap_msg = format!("Snapshot diff found {} rating/BPM changes", diff_count);
-            println!("{}", snap_msg);
-            app.state::<crate::logging::LogState>().add_log("INFO", &snap_msg, &app);
+            tracing::info!("Snapshot diff found {} rating/BPM/loved changes", diff_count);
+            progress("snapshot", total, total, format!("{} rating/BPM/loved changes", diff_count));
             total_updated += diff_count;
+            drop(db);
+
+            // The snapshot cannot carry dates, so played tracks are re-fetched for their last-played date
+            if !played_pids.is_empty() {
+                let played = get_tracks_by_persistent_ids(&played_pids).unwrap_or_else(|e| {
+                    tracing::warn!("Play count refresh failed (non-fatal): {}", e);
+                    Vec::new()
+                });
+                let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+                for t in &played {
+                    if let Err(e) = db.update_play_stats(&t.persistent_id, t.play_count, t.skip_count, t.last_played) {
+                        tracing::error!("DB Error (play stats {}): {}", t.persistent_id, e);
+                    }
+                }
+                tracing::info!("Updated play counts for {} tracks", played.len());
+            }
         }
-        Err(e) => {
-            let msg = format!("Snapshot diff failed (non-fatal): {}", e);
-            eprintln!("{}", msg);
-            app.state::<crate::logging::LogState>().add_log("WARN", &msg, &app);
+        Some(Err(e)) => {
+            tracing::warn!("Snapshot diff failed (non-fatal): {}", e);
         }
     }
 
     // --- Phase 3: Playlist snapshot diff ---
     // Detect added, removed, renamed, reordered playlists and membership changes.
     let mut playlist_changes = 0;
-    let playlist_msg = "Fetching playlist snapshot from Music.app for diff...";
-    println!("{}", playlist_msg);
-    app.state::<crate::logging::LogState>().add_log("INFO", playlist_msg, &app);
+    tracing::info!("Fetching playlist snapshot from Music.app for diff...");
+    progress("playlists", 0, 0, "Fetching playlists".to_string());
+
+    let (db_pids, known_headers) = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        (
+            db.get_playlist_persistent_ids().map_err(|e| e.to_string())?,
+            db.get_playlist_sync_headers().map_err(|e| e.to_string())?,
+        )
+    };
+    // Newly imported tracks may belong to playlists whose header did not change
+    let full_playlist_fetch = tracks_added > 0 || known_headers.is_empty();
 
-    match get_playlist_snapshot() {
-        Ok(music_playlists) => {
+    match fetch_playlists_for_sync(&db_pids, &known_headers, full_playlist_fetch) {
+        Ok((music_playlists, mut signatures)) => {
             let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
-            let db_snapshot = db.get_playlist_snapshot().map_err(|e| e.to_string())?;
-            
+
             // Fetch all known track PIDs to filter the music_playlist tracks
             // This prevents false positive diffs when a playlist contains tracks not in TagDeck's DB.
             let all_track_pids = db.get_all_track_pids().map_err(|e| e.to_string())?;
 
-            // Build a set of persistent IDs from Music.app for deletion detection
-            let music_pids: std::collections::HashSet<String> = music_playlists.iter()
-                .map(|p| p.persistent_id.clone())
-                .collect();
+            // Filter the track IDs from Music.app to only represent tracks we know about locally.
+            // Otherwise, a single missing track causes infinite sync loops.
+            // Also deduplicate: Music.app can have the same track multiple times in a playlist,
+            // but our DB schema uses PRIMARY KEY (playlist_id, track_id) which prevents duplicates.
+            // Without dedup, the diff sees more tracks from Music.app than the DB can store,
+            // causing infinite phantom syncs.
+            let filter_track_ids = |track_ids: &[String]| -> Vec<String> {
+                let mut seen = HashSet::new();
+                track_ids.iter()
+                    .filter(|tid| all_track_pids.contains(*tid))
+                    .filter(|tid| seen.insert(tid.as_str()))
+                    .cloned()
+                    .collect()
+            };
+            let to_playlist = |mp: &PlaylistSnapshotEntry, track_ids: Vec<String>| Playlist {
+                id: 0,
+                persistent_id: mp.persistent_id.clone(),
+                parent_persistent_id: mp.parent_persistent_id.clone(),
+                name: mp.name.clone(),
+                is_folder: mp.is_folder,
+                is_smart: mp.is_smart,
+                track_ids: Some(track_ids),
+            };
 
-            // Detect deleted playlists (in DB but not in Music.app)
-            let deleted_pids: Vec<String> = db_snapshot.keys()
-                .filter(|pid| !music_pids.contains(*pid))
-                .cloned()
+            let by_pid: HashMap<&str, &SyncPlaylist> = music_playlists.iter()
+                .map(|p| (p.entry.persistent_id.as_str(), p))
                 .collect();
+            let playlist_total = music_playlists.len();
+
+            // Stream the DB side one playlist at a time; only the changed ones are kept
+            let mut deleted_pids: Vec<String> = Vec::new();
+            let mut upserts: Vec<Playlist> = Vec::new();
+            let mut compared = 0;
+            db.for_each_playlist_snapshot(|pid, row| {
+                let Some(mp) = by_pid.get(pid.as_str()) else {
+                    // In DB but not in Music.app
+                    deleted_pids.push(pid);
+                    return Ok(());
+                };
+                compared += 1;
+                if compared % SYNC_PROGRESS_INTERVAL == 0 {
+                    progress("playlists", compared, playlist_total, format!("Compared {} of {} playlists", compared, playlist_total));
+                }
+                let fetched = mp.members_fetched.then(|| filter_track_ids(&mp.entry.track_ids));
+                // Compare track membership using sorted lists to avoid false
+                // positives caused by Music.app returning tracks in a
+                // non-deterministic order (current UI sort, etc.).
+                let membership_changed = fetched.as_ref().is_some_and(|ids| {
+                    let mut sorted_fetched = ids.clone();
+                    sorted_fetched.sort();
+                    let mut sorted_db = row.track_ids.clone();
+                    sorted_db.sort();
+                    sorted_fetched != sorted_db
+                });
+                let entry = &mp.entry;
+                if membership_changed
+                    || row.name != entry.name
+                    || row.is_folder != entry.is_folder
+                    || row.is_smart != entry.is_smart
+                    || row.parent_persistent_id != entry.parent_persistent_id
+                {
+                    upserts.push(to_playlist(entry, fetched.unwrap_or(row.track_ids)));
+                }
+                Ok(())
+            }).map_err(|e| e.to_string())?;
+            // New playlists, in Music.app order
+            for mp in music_playlists.iter().filter(|p| !db_pids.contains(&p.entry.persistent_id)) {
+                upserts.push(to_playlist(&mp.entry, filter_track_ids(&mp.entry.track_ids)));
+            }
 
             if !deleted_pids.is_empty() {
                 match db.remove_playlists_by_persistent_ids(&deleted_pids) {
@@ -894,106 +1372,71 @@ pub async fn sync_recent_changes(app: tauri::AppHandle, state: State<'_, AppStat
                         } else {
                             format!("Removed {} deleted playlists", count)
                         };
-                        println!("{}", msg);
-                        app.state::<crate::logging::LogState>().add_log("INFO", &msg, &app);
+                        tracing::info!("{}", msg);
                         playlist_changes += count;
                     },
                     Err(e) => {
-                        let msg = format!("DB Error removing deleted playlists: {}", e);
-                        app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+                        tracing::error!("DB Error removing deleted playlists: {}", e);
                     }
                 }
             }
 
-            // Detect added or changed playlists
-            for mp in &music_playlists {
-                // Filter the track IDs from Music.app to only represent tracks we know about locally.
-                // Otherwise, a single missing track causes infinite sync loops.
-                // Also deduplicate: Music.app can have the same track multiple times in a playlist,
-                // but our DB schema uses PRIMARY KEY (playlist_id, track_id) which prevents duplicates.
-                // Without dedup, the diff sees more tracks from Music.app than the DB can store,
-                // causing infinite phantom syncs.
-                let mut seen = std::collections::HashSet::new();
-                let filtered_track_ids: Vec<String> = mp.track_ids.iter()
-                    .filter(|tid| all_track_pids.contains(*tid))
-                    .filter(|tid| seen.insert((*tid).clone()))
-                    .cloned()
-                    .collect();
-
-                let needs_upsert = match db_snapshot.get(&mp.persistent_id) {
-                    None => true, // New playlist
-                    Some((db_name, db_is_folder, db_parent_pid, db_track_ids)) => {
-                        // Compare track membership using sorted lists to avoid false
-                        // positives caused by Music.app returning tracks in a
-                        // non-deterministic order (current UI sort, etc.).
-                        let mut sorted_filtered = filtered_track_ids.clone();
-                        sorted_filtered.sort();
-                        let mut sorted_db = db_track_ids.clone();
-                        sorted_db.sort();
-
-                        // Check if any field changed
-                        db_name != &mp.name
-                            || db_is_folder != &mp.is_folder
-                            || db_parent_pid != &mp.parent_persistent_id
-                            || sorted_db != sorted_filtered
-                    }
-                };
-                
-                if needs_upsert {
-                    // Use the filtered track IDs for the DB update too, 
-                    // although DB insert logic does this via JOIN anyway.
-                    // Doing it here ensures the diff logic matches the insert output.
-                    let playlist = Playlist {
-                        id: 0,
-                        persistent_id: mp.persistent_id.clone(),
-                        parent_persistent_id: mp.parent_persistent_id.clone(),
-                        name: mp.name.clone(),
-                        is_folder: mp.is_folder,
-                        track_ids: Some(filtered_track_ids),
-                    };
-                    if let Err(e) = db.insert_playlist(&playlist) {
-                        let msg = format!("DB Error upserting playlist {}: {}", mp.name, e);
-                        app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
-                    } else {
-                        playlist_changes += 1;
-                        if playlist_changes <= 10 {
-                            let detail = format!("Playlist synced: \"{}\"", mp.name);
-                            println!("{}", detail);
-                            app.state::<crate::logging::LogState>().add_log("INFO", &detail, &app);
-                        }
+            for playlist in &upserts {
+                if let Err(e) = db.insert_playlist(playlist) {
+                    tracing::error!("DB Error upserting playlist {}: {}", playlist.name, e);
+                    signatures.remove(&playlist.persistent_id);
+                } else {
+                    playlist_changes += 1;
+                    if playlist_changes <= 10 {
+                        tracing::info!("Playlist synced: \"{}\"", playlist.name);
                     }
                 }
             }
 
-            let pl_msg = format!("Playlist diff found {} changes", playlist_changes);
-            println!("{}", pl_msg);
-            app.state::<crate::logging::LogState>().add_log("INFO", &pl_msg, &app);
+            tracing::info!("Playlist diff found {} changes", playlist_changes);
+            if let Err(e) = db.set_playlist_sync_headers(&signatures) {
+                tracing::warn!("Failed to record playlist headers: {}", e);
+            }
+            progress("playlists", playlist_total, playlist_total, format!("{} playlist changes", playlist_changes));
         }
         Err(e) => {
-            let msg = format!("Playlist snapshot diff failed (non-fatal): {}", e);
-            eprintln!("{}", msg);
-            app.state::<crate::logging::LogState>().add_log("WARN", &msg, &app);
+            tracing::warn!("Playlist snapshot diff failed (non-fatal): {}", e);
         }
     }
 
-    let complete_msg = format!("Sync complete. {} tracks updated, {} added, {} deleted, {} playlist events.", 
+    let summary = format!("Sync complete. {} tracks updated, {} added, {} deleted, {} playlist events.",
         total_updated - tracks_added - tracks_deleted, tracks_added, tracks_deleted, playlist_changes);
-    println!("{}", complete_msg);
-    app.state::<crate::logging::LogState>().add_log("INFO", &complete_msg, &app);
+    tracing::info!("{}", summary);
+    progress("done", total_updated, total_updated, summary);
+
+    if let Err(e) = state.db.lock().map_err(|_| "Failed to lock DB".to_string())
+        .and_then(|db| db.set_setting(LAST_SYNC_KEY, &started_at.to_string()).map_err(|e| e.to_string()))
+    {
+        tracing::warn!("Failed to record sync time: {}", e);
+    }
 
     // Sum all changes so frontend triggers refresh if ANY change occurred (metadata, rating, or playlist)
     Ok(SyncResult { tracks_updated: total_updated, tracks_added, tracks_deleted, playlists_updated: playlist_changes })
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn get_playlists(state: State<'_, AppState>) -> Result<Vec<crate::models::Playlist>, String> {
-    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
     db.get_playlists().map_err(|e| e.to_string())
 }
 
+/// Smart and built-in playlists are read-only: Music.app decides their tracks.
+pub(crate) fn ensure_editable_playlist(db: &Database, playlist_id: i64) -> Result<(), String> {
+    if db.is_playlist_smart(playlist_id).unwrap_or(false) {
+        return Err("Smart playlists are managed by Music.app and cannot be edited".to_string());
+    }
+    Ok(())
+}
+
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn add_to_playlist(
-    app: tauri::AppHandle,
     track_ids: Vec<i64>,
     playlist_id: i64,
     state: State<'_, AppState>,
@@ -1003,6 +1446,7 @@ pub async fn add_to_playlist(
         let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
         let pid = db.get_playlist_persistent_id(playlist_id)
             .map_err(|e| format!("Failed to get playlist: {}", e))?;
+        ensure_editable_playlist(&db, playlist_id)?;
 
         let mut data = Vec::new();
         for tid in &track_ids {
@@ -1015,12 +1459,12 @@ pub async fn add_to_playlist(
 
     let valid_track_ids: Vec<i64> = track_data.iter().map(|(t, _)| *t).collect();
     
-    // 2. Apple Music Sync
-    for (_, pid) in &track_data {
-        if let Err(e) = add_track_to_playlist(pid, &playlist_pid) {
-             let msg = format!("Failed to add track {} to playlist: {}", pid, e);
-             app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
-        }
+    // 2. Apple Music Sync (one JXA call for the whole selection)
+    if !track_data.is_empty() {
+        state.apple_queue.enqueue(AppleUpdate::playlist_adds(
+            track_data.iter().map(|(_, pid)| pid.as_str()),
+            &playlist_pid,
+        ));
     }
 
     // 3. Local DB Sync
@@ -1028,8 +1472,7 @@ pub async fn add_to_playlist(
         let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
         for tid in &valid_track_ids {
             if let Err(e) = db.add_track_to_playlist_db(playlist_id, *tid) {
-                 let msg = format!("Failed to update local playlist: {}", e);
-                 app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+                 tracing::error!("Failed to update local playlist: {}", e);
             }
         }
     }
@@ -1054,8 +1497,8 @@ pub async fn add_to_playlist(
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn remove_from_playlist(
-    app: tauri::AppHandle,
     track_ids: Vec<i64>,
     playlist_id: i64,
     state: State<'_, AppState>,
@@ -1064,6 +1507,7 @@ pub async fn remove_from_playlist(
         let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
         let pid = db.get_playlist_persistent_id(playlist_id)
             .map_err(|e| format!("Failed to get playlist: {}", e))?;
+        ensure_editable_playlist(&db, playlist_id)?;
         let mut data = Vec::new();
         for tid in &track_ids {
             if let Ok(tpid) = db.get_track_persistent_id(*tid) {
@@ -1073,12 +1517,18 @@ pub async fn remove_from_playlist(
         (pid, data)
     };
 
+    // Capture the current order for undo before anything changes
+    let previous_order = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        playlist_track_refs(&db, playlist_id)?
+    };
+
     // Remove from Apple Music
     for (_, tpid) in &track_data {
-        if let Err(e) = apple_remove_from_playlist(tpid, &playlist_pid) {
-            let msg = format!("Failed to remove track from playlist in Music.app: {}", e);
-            app.state::<crate::logging::LogState>().add_log("WARN", &msg, &app);
-        }
+        state.apple_queue.enqueue(AppleUpdate::RemoveFromPlaylist {
+            track_pid: tpid.clone(),
+            playlist_pid: playlist_pid.clone(),
+        });
     }
 
     // Remove from local DB
@@ -1090,12 +1540,32 @@ pub async fn remove_from_playlist(
         tids.len()
     };
 
+    // Push Undo Action
+    if !track_data.is_empty() {
+        if let Ok(mut stack) = state.undo_stack.lock() {
+            stack.push(Action::RemoveFromPlaylist {
+                playlist_id,
+                playlist_persistent_id: playlist_pid,
+                tracks: track_data.into_iter().map(|(id, persistent_id)| TrackRef { id, persistent_id }).collect(),
+                previous_order,
+            });
+        }
+    }
+
     Ok(removed)
 }
 
+/// Current track order of a playlist as (id, persistent ID) refs.
+pub(crate) fn playlist_track_refs(db: &Database, playlist_id: i64) -> Result<Vec<TrackRef>, String> {
+    let ids = db.get_playlist_track_ids(playlist_id).map_err(|e| e.to_string())?;
+    Ok(ids.into_iter()
+        .filter_map(|id| db.get_track_persistent_id(id).ok().map(|persistent_id| TrackRef { id, persistent_id }))
+        .collect())
+}
+
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn reorder_playlist_tracks(
-    app: tauri::AppHandle,
     playlist_id: i64,
     ordered_track_ids: Vec<i64>,
     state: State<'_, AppState>,
@@ -1105,6 +1575,7 @@ pub async fn reorder_playlist_tracks(
         let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
         let ppid = db.get_playlist_persistent_id(playlist_id)
             .map_err(|e| format!("Failed to get playlist: {}", e))?;
+        ensure_editable_playlist(&db, playlist_id)?;
         let mut pids = Vec::new();
         for tid in &ordered_track_ids {
             if let Ok(tpid) = db.get_track_persistent_id(*tid) {
@@ -1114,177 +1585,861 @@ pub async fn reorder_playlist_tracks(
         (ppid, pids)
     };
 
-    // 2. Update local DB
-    {
+    // 2. Update local DB (capturing the old order for undo)
+    let (old_order, new_order) = {
         let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        let old_order = playlist_track_refs(&db, playlist_id)?;
         db.reorder_playlist_tracks(playlist_id, &ordered_track_ids)
             .map_err(|e| e.to_string())?;
-    }
+        (old_order, playlist_track_refs(&db, playlist_id)?)
+    };
 
-    // 3. Sync to Apple Music (in background — don't block the UI)
-    let app_handle = app.clone();
-    tauri::async_runtime::spawn(async move {
-        if let Err(e) = crate::apple_music::reorder_playlist(&playlist_pid, &track_pids) {
-            let msg = format!("Failed to reorder playlist in Music.app: {}", e);
-            eprintln!("{}", msg);
-            app_handle.state::<crate::logging::LogState>().add_log("WARN", &msg, &app_handle);
-        }
-    });
+    // 3. Sync to Apple Music (in background via the worker queue — don't block the UI)
+    state.apple_queue.enqueue(AppleUpdate::ReorderPlaylist { playlist_pid: playlist_pid.clone(), track_pids });
+
+    // 4. Push Undo Action
+    if let Ok(mut stack) = state.undo_stack.lock() {
+        stack.push(Action::ReorderPlaylist {
+            playlist_id,
+            playlist_persistent_id: playlist_pid,
+            old_order,
+            new_order,
+        });
+    }
 
     Ok(())
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn update_rating(
-    app: tauri::AppHandle,
     track_id: i64,
     rating: u32,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    
+
     let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
 
     // 1. Get Persistent ID
-    let persistent_id = db.get_track_persistent_id(track_id).map_err(|e| e.to_string())?;
+    let track = db.get_track(track_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Track {} not found", track_id))?;
 
     // 2. Update Music.app
-    if let Err(e) = update_track_rating(&persistent_id, rating) {
-        let msg = format!("Failed to update Apple Music rating: {}", e);
-        app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
-        return Err(msg);
-    }
+    state.apple_queue.enqueue(AppleUpdate::Rating { persistent_id: track.persistent_id, rating });
 
     // 3. Update Local DB
     db.update_track_rating(track_id, rating).map_err(|e| e.to_string())?;
 
+    // 4. Mirror into the file, if enabled
+    if let Err(e) = crate::metadata::mirror_rating(&track.file_path, rating) {
+        tracing::warn!("Failed to write rating to file: {}", e);
+    }
+
     Ok(())
 }
 
+/// Sets the Music.app loved/favorite flag of a track. Undoable.
 #[tauri::command]
-pub async fn get_playlist_track_ids(state: State<'_, AppState>, playlist_id: i64) -> Result<Vec<i64>, String> {
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_loved(
+    track_id: i64,
+    loved: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
     let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
-    db.get_playlist_track_ids(playlist_id).map_err(|e| e.to_string())
+    let track = db.get_track(track_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Track {} not found", track_id))?;
+    if track.loved == loved {
+        return Ok(());
+    }
+
+    db.update_track_loved(track_id, loved).map_err(|e| e.to_string())?;
+    if !track.persistent_id.is_empty() {
+        state.apple_queue.enqueue(AppleUpdate::Loved { persistent_id: track.persistent_id.clone(), loved });
+    }
+    drop(db);
+
+    if let Ok(mut stack) = state.undo_stack.lock() {
+        stack.push(Action::UpdateLoved {
+            tracks: vec![LovedState {
+                id: track_id,
+                persistent_id: track.persistent_id,
+                old_loved: track.loved,
+                new_loved: loved,
+            }],
+        });
+    }
+    Ok(())
 }
 
+/// Sets the color label of `ids`, or clears it with `None`, as one undo step. Color labels
+/// stay in TagDeck (and its Rekordbox export); files and Music.app are not touched.
 #[tauri::command]
-pub async fn mark_track_missing(id: i64, missing: bool, state: State<'_, AppState>) -> Result<(), String> {
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_color_label(
+    ids: Vec<i64>,
+    color: Option<crate::models::TrackColor>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
     let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
-
-    if missing {
-         if let Ok(path) = db.get_track_path(id) {
-             println!("Debug: Marking track {} missing. Path: '{}'", id, path);
-             // Check if it exists
-             match std::fs::metadata(&path) {
-                 Ok(_) => println!("  - File actually EXISTS!"),
-                 Err(_) => {
-                     println!("  - File NOT FOUND at path.");
-                     
-                     // Try heuristic fix for typical "iTunes vs iTunes/Music" nesting issue
-                     // Expanded to handle iTunes Music, iTunes Media variations
-                     if path.contains("/iTunes/") {
-                         let candidates = [
-                             "/iTunes/Music/",
-                             "/iTunes/iTunes Music/",
-                             "/iTunes/iTunes Media/Music/",
-                             "/iTunes/iTunes Media/",
-                         ];
-
-                         for candidate in candidates {
-                             let fixed_path = path.replace("/iTunes/", candidate);
-                             if fixed_path != path && std::path::Path::new(&fixed_path).exists() {
-                                 println!("  - FOUND at corrected path: '{}'", fixed_path);
-                                 println!("  - Auto-correcting database entry...");
-                                 if let Err(e) = db.update_track_path(id, &fixed_path) {
-                                     println!("  - Failed to update DB: {}", e);
-                                 } else {
-                                     println!("  - DB Updated. Next playback should work.");
-                                     return Ok(()); // Do NOT mark missing
-                                 }
-                             }
-                         }
-                     }
-                 }
-             }
-         }
+    let changes: Vec<crate::undo::ColorState> = ids.iter()
+        .filter_map(|id| db.get_track(*id).ok().flatten())
+        .filter(|t| t.color_label != color)
+        .map(|t| crate::undo::ColorState { id: t.id, old_color: t.color_label, new_color: color })
+        .collect();
+    if changes.is_empty() {
+        return Ok(0);
     }
 
-    db.set_track_missing(id, missing).map_err(|e| e.to_string())
+    let colors: Vec<(i64, Option<crate::models::TrackColor>)> = changes.iter().map(|c| (c.id, color)).collect();
+    db.update_track_colors(&colors).map_err(|e| e.to_string())?;
+    drop(db);
+
+    let updated = changes.len();
+    if let Ok(mut stack) = state.undo_stack.lock() {
+        stack.push(Action::UpdateColors { tracks: changes });
+    }
+    Ok(updated)
 }
 
 #[tauri::command]
-pub async fn debug_db_path(_state: State<'_, AppState>) -> Result<String, String> {
-    Ok("Debug path info not exposed directly but DB is open".to_string())
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_track_notes(track_id: i64, state: State<'_, AppState>) -> Result<Option<String>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let track = db.get_track(track_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Track {} not found", track_id))?;
+    Ok(track.notes)
 }
 
+/// Sets free-form notes (cue ideas, mix notes, "drop at 1:32") on a track. Notes live only
+/// in TagDeck's DB and are never written to the file or Music.app. Empty clears them.
 #[tauri::command]
-pub async fn get_track_artwork(id: i64, state: State<'_, AppState>) -> Result<Option<Vec<u8>>, String> {
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_track_notes(track_id: i64, notes: Option<String>, state: State<'_, AppState>) -> Result<(), String> {
     let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
-    let path = db.get_track_path(id).map_err(|e| e.to_string())?;
-    drop(db); // Release lock before doing IO
-    
-    get_artwork(&path).map_err(|e| e.to_string())
+    db.update_track_notes(track_id, notes.as_deref()).map_err(|e| e.to_string())
 }
 
-// Tag Group Commands
-
+/// Restores a track's comment/tags and rating to their values at `timestamp` (Unix seconds),
+/// reconstructed from the `track_history` audit log. Goes through the normal write path
+/// (file + DB + Music.app) and is pushed as a single undo step.
 #[tauri::command]
-pub async fn get_tag_groups(state: State<'_, AppState>) -> Result<Vec<crate::models::TagGroup>, String> {
-    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
-        .get_tag_groups().map_err(|e| e.to_string())
-}
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn revert_track_to(
+    track_id: i64,
+    timestamp: i64,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, String> {
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
 
-#[tauri::command]
-pub async fn create_tag_group(name: String, state: State<'_, AppState>) -> Result<crate::models::TagGroup, String> {
-    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
-        .create_tag_group(&name).map_err(|e| e.to_string())
-}
+    let track = db.get_track(track_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Track {} not found", track_id))?;
+    let comment_then = db.get_field_value_at(track_id, "comment_raw", timestamp).map_err(|e| e.to_string())?;
+    let rating_then = db.get_field_value_at(track_id, "rating", timestamp).map_err(|e| e.to_string())?;
+
+    let mut actions = Vec::new();
+
+    // 1. Comment / tags
+    if let Some(target) = comment_then {
+        let target = target.unwrap_or_default();
+        let current = track.comment_raw.clone().unwrap_or_default();
+        if target != current {
+            write_track_comment(&track, &target).map_err(|e| e.to_string())?;
+            db.update_track_metadata(track.id, &target).map_err(|e| e.to_string())?;
+            if !track.persistent_id.is_empty() {
+                state.apple_queue.enqueue(AppleUpdate::Comment {
+                    persistent_id: track.persistent_id.clone(),
+                    comment: target.clone(),
+                });
+            } else {
+                let _ = touch_file(&track.file_path);
+            }
+            actions.push(Action::UpdateTrackComments {
+                tracks: vec![TrackState {
+                    id: track.id,
+                    persistent_id: track.persistent_id.clone(),
+                    file_path: track.file_path.clone(),
+                    old_comment: current,
+                    new_comment: target,
+                }],
+            });
+        }
+    }
 
-#[tauri::command]
-pub async fn update_tag_group(id: i64, name: String, state: State<'_, AppState>) -> Result<(), String> {
-    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
-        .update_tag_group(id, &name).map_err(|e| e.to_string())
+    // 2. Rating
+    if let Some(target) = rating_then {
+        let target: u32 = target.and_then(|v| v.parse().ok()).unwrap_or(0);
+        let current = track.rating.max(0) as u32;
+        if target != current {
+            db.update_track_rating(track.id, target).map_err(|e| e.to_string())?;
+            if let Err(e) = crate::metadata::mirror_rating(&track.file_path, target) {
+                tracing::warn!("Failed to write rating to file: {}", e);
+            }
+            if !track.persistent_id.is_empty() {
+                state.apple_queue.enqueue(AppleUpdate::Rating {
+                    persistent_id: track.persistent_id.clone(),
+                    rating: target,
+                });
+            }
+            actions.push(Action::UpdateRatings {
+                tracks: vec![RatingState {
+                    id: track.id,
+                    persistent_id: track.persistent_id.clone(),
+                    old_rating: current,
+                    new_rating: target,
+                }],
+            });
+        }
+    }
+
+    if actions.is_empty() {
+        return Ok(None);
+    }
+
+    drop(db);
+
+    if let Ok(mut stack) = state.undo_stack.lock() {
+        stack.push(Action::Composite {
+            description: "Revert Track".to_string(),
+            actions,
+        });
+    }
+
+    let msg = format!("Reverted track {} to its state at {}", track_id, timestamp);
+    tracing::info!("{}", msg);
+    Ok(Some(msg))
 }
 
-#[tauri::command]
-pub async fn delete_tag_group(id: i64, state: State<'_, AppState>) -> Result<(), String> {
-    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
-        .delete_tag_group(id).map_err(|e| e.to_string())
+#[derive(serde::Serialize)]
+pub struct RecentChange {
+    pub changed_at: i64,
+    /// Columns edited through TagDeck (e.g. "comment_raw", "rating"); empty when the
+    /// change is only known from the file's modification date
+    pub fields: Vec<String>,
+    pub track: Track,
 }
 
+/// The `limit` most recently tagged or edited tracks, newest first, so a tagging
+/// session can pick up where it left off.
 #[tauri::command]
-pub async fn set_tag_group(tag_id: i64, group_id: Option<i64>, state: State<'_, AppState>) -> Result<(), String> {
-    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
-        .set_tag_group(tag_id, group_id).map_err(|e| e.to_string())
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_recent_changes(limit: Option<usize>, state: State<'_, AppState>) -> Result<Vec<RecentChange>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let changes = db.get_recent_changes(limit.unwrap_or(50)).map_err(|e| e.to_string())?;
+    Ok(changes.into_iter()
+        .filter_map(|(id, changed_at, fields)| {
+            db.get_track(id).ok().flatten().map(|track| RecentChange { changed_at, fields, track })
+        })
+        .collect())
 }
 
 #[tauri::command]
-pub async fn reorder_tag_groups(ordered_ids: Vec<i64>, state: State<'_, AppState>) -> Result<(), String> {
-    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
-        .reorder_tag_groups(ordered_ids).map_err(|e| e.to_string())
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_playlist_track_ids(state: State<'_, AppState>, playlist_id: i64) -> Result<Vec<i64>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    db.get_playlist_track_ids(playlist_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub async fn get_all_tags(state: State<'_, AppState>) -> Result<Vec<crate::models::Tag>, String> {
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn mark_track_missing(id: i64, missing: bool, state: State<'_, AppState>) -> Result<(), String> {
     let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
-    db.sync_tags().map_err(|e| e.to_string())?;
-    db.get_all_tags().map_err(|e| e.to_string())
-}
 
-#[tauri::command]
-pub async fn delete_tag(tag_id: i64, state: State<'_, AppState>) -> Result<(), String> {
-    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+    if missing {
+         if let Ok(path) = db.get_track_path(id) {
+             tracing::debug!("Marking track {} missing. Path: '{}'", id, path);
+             // Check if it exists
+             match std::fs::metadata(&path) {
+                 Ok(_) => tracing::debug!("  - File actually EXISTS!"),
+                 Err(_) => {
+                     tracing::debug!("  - File NOT FOUND at path.");
+                     
+                     // Try the user's path rules, then the "iTunes vs iTunes/Music" nesting variants
+                     if let Some(fixed_path) = PathResolver::load(&db).resolve(&path) {
+                         tracing::debug!("  - FOUND at corrected path: '{}'", fixed_path);
+                         tracing::debug!("  - Auto-correcting database entry...");
+                         if let Err(e) = db.update_track_path(id, &fixed_path) {
+                             tracing::debug!("  - Failed to update DB: {}", e);
+                         } else {
+                             tracing::debug!("  - DB Updated. Next playback should work.");
+                             return Ok(()); // Do NOT mark missing
+                         }
+                     }
+                 }
+             }
+         }
+    }
+
+    db.set_track_missing(id, missing).map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MissingScanResult {
+    pub checked: usize,
+    /// Tracks whose file could not be found, even via the iTunes path variants
+    pub missing: usize,
+    /// Tracks found through a path rule or iTunes variant and corrected in the DB
+    pub relocated: usize,
+    /// Tracks previously flagged missing whose file is back
+    pub recovered: usize,
+    /// Tracks whose file is a cloud placeholder; flagged offloaded, not missing
+    pub offloaded: usize,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct MissingScanProgress {
+    checked: usize,
+    total: usize,
+}
+
+/// Outcome of checking track files on disk, before it is stored.
+pub struct MissingScan {
+    /// (id, corrected path) for tracks found through a path rule or iTunes variant
+    pub fixes: Vec<(i64, String)>,
+    pub missing: Vec<i64>,
+    /// Tracks flagged missing whose file is back
+    pub recovered: Vec<i64>,
+    /// Tracks whose file is a cloud placeholder
+    pub offloaded: Vec<i64>,
+    /// Tracks flagged offloaded whose file has been downloaded
+    pub downloaded: Vec<i64>,
+}
+
+impl MissingScan {
+    /// Checks each track's file, trying the path rules for the ones that are gone.
+    /// `progress` is called with (checked, total).
+    pub fn run(tracks: &[Track], resolver: &PathResolver, mut progress: impl FnMut(usize, usize)) -> Self {
+        let total = tracks.len();
+        let mut scan = MissingScan {
+            fixes: Vec::new(),
+            missing: Vec::new(),
+            recovered: Vec::new(),
+            offloaded: Vec::new(),
+            downloaded: Vec::new(),
+        };
+
+        for (i, track) in tracks.iter().enumerate() {
+            if track.is_cloud {
+                // Apple Music cloud tracks have no file to look for
+            } else if crate::cloud_files::is_placeholder(&track.file_path) {
+                // Checked before `exists`: an evicted iCloud file has only a stub next to its path
+                if track.missing {
+                    scan.recovered.push(track.id);
+                }
+                scan.offloaded.push(track.id);
+            } else if std::path::Path::new(&track.file_path).exists() {
+                if track.missing {
+                    scan.recovered.push(track.id);
+                }
+                if track.offloaded {
+                    scan.downloaded.push(track.id);
+                }
+            } else if let Some(fixed) = resolver.resolve(&track.file_path) {
+                scan.fixes.push((track.id, fixed));
+            } else {
+                scan.missing.push(track.id);
+            }
+
+            if (i + 1) % 250 == 0 || i + 1 == total {
+                progress(i + 1, total);
+            }
+        }
+        scan
+    }
+
+    /// Stores the corrected paths and the missing and offloaded flags.
+    pub fn apply(&self, db: &Database, checked: usize) -> anyhow::Result<MissingScanResult> {
+        for (id, path) in &self.fixes {
+            db.update_track_path(*id, path)?;
+            db.set_track_missing(*id, false)?;
+            db.set_track_offloaded(*id, false)?;
+        }
+        for id in &self.missing {
+            db.set_track_missing(*id, true)?;
+            db.set_track_offloaded(*id, false)?;
+        }
+        for id in &self.recovered {
+            db.set_track_missing(*id, false)?;
+        }
+        for id in &self.offloaded {
+            db.set_track_offloaded(*id, true)?;
+        }
+        for id in &self.downloaded {
+            db.set_track_offloaded(*id, false)?;
+        }
+        Ok(MissingScanResult {
+            checked,
+            missing: self.missing.len(),
+            relocated: self.fixes.len(),
+            recovered: self.recovered.len(),
+            offloaded: self.offloaded.len(),
+        })
+    }
+}
+
+/// Checks every track's file and stores the result, emitting `missing-scan-progress`.
+/// Blocking; shared by `scan_missing_files` and the nightly maintenance.
+pub(crate) fn run_missing_scan(app: &tauri::AppHandle) -> Result<MissingScanResult, String> {
+    let state = app.state::<AppState>();
+    let (tracks, resolver) = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        (db.get_all_tracks().map_err(|e| e.to_string())?, PathResolver::load(&db))
+    };
+
+    let scan = MissingScan::run(&tracks, &resolver, |checked, total| {
+        let _ = app.emit("missing-scan-progress", MissingScanProgress { checked, total });
+    });
+
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    scan.apply(&db, tracks.len()).map_err(|e| e.to_string())
+}
+
+/// Checks every track's file on a blocking thread, auto-correcting paths through the
+/// path rules the way `mark_track_missing` does and updating the missing flags in one pass.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn scan_missing_files(app: tauri::AppHandle) -> Result<MissingScanResult, String> {
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || run_missing_scan(&handle))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    tracing::info!(
+        "Missing file scan: {} checked, {} missing, {} relocated, {} recovered, {} offloaded",
+        result.checked, result.missing, result.relocated, result.recovered, result.offloaded
+    );
+    Ok(result)
+}
+
+/// Points a single track at a new file and tells Music.app about the new location.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn relocate_track(id: i64, new_path: String, state: State<'_, AppState>) -> Result<(), String> {
+    if !std::path::Path::new(&new_path).exists() {
+        return Err(format!("File not found: {}", new_path));
+    }
+
+    let persistent_id = {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        let track = db.get_track(id).map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Track {} not found", id))?;
+        db.update_track_path(id, &new_path).map_err(|e| e.to_string())?;
+        db.set_track_missing(id, false).map_err(|e| e.to_string())?;
+        track.persistent_id
+    };
+
+    if !persistent_id.is_empty() {
+        state.apple_queue.enqueue(AppleUpdate::Location { persistent_id, path: new_path.clone() });
+    }
+
+    tracing::info!("Relocated track {} to {}", id, new_path);
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct RelocateFolderResult {
+    pub relocated: usize,
+    /// Rewritten paths under the new prefix that do not exist; those tracks were left alone
+    pub not_found: Vec<String>,
+}
+
+/// Rewrites every track path under `old_prefix` to `new_prefix` (e.g. after moving the
+/// library to a new drive). Only tracks whose file exists at the new location are changed.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn relocate_folder(
+    old_prefix: String,
+    new_prefix: String,
+    state: State<'_, AppState>,
+) -> Result<RelocateFolderResult, String> {
+    let old_prefix = old_prefix.trim_end_matches('/').to_string();
+    let new_prefix = new_prefix.trim_end_matches('/').to_string();
+    if old_prefix.is_empty() || new_prefix.is_empty() {
+        return Err("Both folder prefixes are required".to_string());
+    }
+
+    let tracks = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        db.get_all_tracks().map_err(|e| e.to_string())?
+    };
+
+    let mut moves: Vec<(i64, String, String)> = Vec::new();
+    let mut not_found = Vec::new();
+    for track in &tracks {
+        // Match whole path components so /Music does not also catch /Music 2
+        let rest = match track.file_path.strip_prefix(&old_prefix) {
+            Some(rest) if rest.starts_with('/') => rest,
+            _ => continue,
+        };
+        let new_path = format!("{}{}", new_prefix, rest);
+        if std::path::Path::new(&new_path).exists() {
+            moves.push((track.id, track.persistent_id.clone(), new_path));
+        } else {
+            not_found.push(new_path);
+        }
+    }
+
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        for (id, _, path) in &moves {
+            db.update_track_path(*id, path).map_err(|e| e.to_string())?;
+            db.set_track_missing(*id, false).map_err(|e| e.to_string())?;
+        }
+    }
+
+    for (_, persistent_id, path) in &moves {
+        if !persistent_id.is_empty() {
+            state.apple_queue.enqueue(AppleUpdate::Location {
+                persistent_id: persistent_id.clone(),
+                path: path.clone(),
+            });
+        }
+    }
+
+    tracing::info!(
+        "Relocated {} tracks from {} to {} ({} not found at new location)",
+        moves.len(), old_prefix, new_prefix, not_found.len()
+    );
+
+    Ok(RelocateFolderResult {
+        relocated: moves.len(),
+        not_found,
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn debug_db_path(_state: State<'_, AppState>) -> Result<String, String> {
+    Ok("Debug path info not exposed directly but DB is open".to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_track_artwork(id: i64, state: State<'_, AppState>) -> Result<Option<Vec<u8>>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let path = db.get_track_path(id).map_err(|e| e.to_string())?;
+    drop(db); // Release lock before doing IO
+    
+    get_artwork(&path).map_err(|e| e.to_string())
+}
+
+// Tag Group Commands
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_tag_groups(state: State<'_, AppState>) -> Result<Vec<crate::models::TagGroup>, String> {
+    state.db.read().map_err(|_| "Failed to lock DB".to_string())?
+        .get_tag_groups().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn create_tag_group(name: String, state: State<'_, AppState>) -> Result<crate::models::TagGroup, String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .create_tag_group(&name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn update_tag_group(id: i64, name: String, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .update_tag_group(id, &name).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn delete_tag_group(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .delete_tag_group(id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_tag_group(tag_id: i64, group_id: Option<i64>, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_tag_group(tag_id, group_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn reorder_tag_groups(ordered_ids: Vec<i64>, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .reorder_tag_groups(ordered_ids).map_err(|e| e.to_string())
+}
+
+// Tag Normalization Commands
+
+const TAG_NORMALIZATION_KEY: &str = "tag_normalization";
+
+/// Normalizes a new tag and, when the case policy preserves spelling, reuses the
+/// spelling the library already has so "Chill" and "chill" stay one tag.
+pub fn canonical_tag(db: &Database, tag: &str) -> String {
+    let policy = crate::comment::normalization();
+    let tag = policy.apply(tag);
+    if policy.case == crate::comment::TagCase::Preserve {
+        if let Ok(Some(existing)) = db.canonical_tag_name(&tag) {
+            return existing;
+        }
+    }
+    tag
+}
+
+/// Applies the stored tag normalization policy.
+pub fn load_tag_normalization(state: &AppState) {
+    if let Ok(db) = state.db.read() {
+        load_tag_normalization_from(&db);
+    }
+}
+
+pub fn load_tag_normalization_from(db: &Database) {
+    let stored = db.get_setting(TAG_NORMALIZATION_KEY).ok().flatten()
+        .and_then(|json| serde_json::from_str::<crate::comment::TagNormalization>(&json).ok());
+    if let Some(policy) = stored {
+        crate::comment::set_normalization(policy);
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_tag_normalization() -> Result<crate::comment::TagNormalization, String> {
+    Ok(crate::comment::normalization())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_tag_normalization(policy: crate::comment::TagNormalization, state: State<'_, AppState>) -> Result<(), String> {
+    let json = serde_json::to_string(&policy).map_err(|e| e.to_string())?;
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_setting(TAG_NORMALIZATION_KEY, &json).map_err(|e| e.to_string())?;
+    crate::comment::set_normalization(policy);
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct NormalizeTagsResult {
+    pub tracks_updated: usize,
+    /// Tag table entries folded into their normalized spelling
+    pub tags_merged: usize,
+}
+
+/// Rewrites every comment so tags follow the normalization policy, merging variants
+/// that differ only by case or whitespace. With the "preserve" case policy the most
+/// used spelling wins. All comment changes form one undo step.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn normalize_existing_tags(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<NormalizeTagsResult, String> {
+    let policy = crate::comment::normalization();
+    let (tracks, tag_rows) = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        (
+            db.get_all_tracks().map_err(|e| e.to_string())?,
+            db.get_all_tags().map_err(|e| e.to_string())?,
+        )
+    };
+
+    // Pick one spelling per case-insensitive normalized name
+    let mut spellings: std::collections::HashMap<String, std::collections::HashMap<String, usize>> = std::collections::HashMap::new();
+    for track in &tracks {
+        let (_, tags) = crate::comment::split_comment(track.comment_raw.as_deref().unwrap_or(""));
+        for tag in tags {
+            let normalized = policy.apply(&tag);
+            *spellings.entry(normalized.to_lowercase()).or_default().entry(normalized).or_insert(0) += 1;
+        }
+    }
+    let canonical: std::collections::HashMap<String, String> = spellings.into_iter()
+        .map(|(key, variants)| {
+            let best = variants.into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+                .map(|(name, _)| name)
+                .unwrap_or_default();
+            (key, best)
+        })
+        .collect();
+    let canonical_of = |tag: &str| {
+        let normalized = policy.apply(tag);
+        canonical.get(&normalized.to_lowercase()).cloned().unwrap_or(normalized)
+    };
+
+    let mut changes = Vec::new();
+    for track in tracks {
+        let old_comment = track.comment_raw.clone().unwrap_or_default();
+        let (user_comment, tags) = crate::comment::split_comment(&old_comment);
+        if tags.is_empty() {
+            continue;
+        }
+        let mut new_tags: Vec<String> = Vec::new();
+        for tag in &tags {
+            let tag = canonical_of(tag);
+            if !tag.is_empty() && !new_tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+                new_tags.push(tag);
+            }
+        }
+        if new_tags == tags {
+            continue;
+        }
+        let new_comment = crate::comment::build_comment(user_comment, &new_tags);
+        changes.push((track, new_comment));
+    }
+    let tracks_updated = apply_comment_batch(&app, &state, changes).await?;
+
+    // Fold tag table variants (keeps group assignments)
+    let mut tags_merged = 0;
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        for row in &tag_rows {
+            let target = canonical_of(&row.name);
+            if !target.is_empty() && target != row.name {
+                db.merge_tag_into(&row.name, &target).map_err(|e| e.to_string())?;
+                tags_merged += 1;
+            }
+        }
+    }
+
+    tracing::info!("Normalized tags: {} tracks updated, {} tag variants merged", tracks_updated, tags_merged);
+    Ok(NormalizeTagsResult { tracks_updated, tags_merged })
+}
+
+// Tag Set Commands
+
+/// Trims and de-duplicates (case-insensitively) the member tags of a tag set.
+fn clean_tag_set(tags: Vec<String>) -> Result<Vec<String>, String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = crate::comment::normalize_tag(&tag);
+        let tag = tag.as_str();
+        if tag.is_empty() {
+            continue;
+        }
+        if tag.contains(';') || tag.contains("&&") {
+            return Err(format!("Invalid tag name: {}", tag));
+        }
+        if !cleaned.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            cleaned.push(tag.to_string());
+        }
+    }
+    Ok(cleaned)
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_tag_sets(state: State<'_, AppState>) -> Result<Vec<crate::models::TagSet>, String> {
+    state.db.read().map_err(|_| "Failed to lock DB".to_string())?
+        .get_tag_sets().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn create_tag_set(name: String, tags: Vec<String>, state: State<'_, AppState>) -> Result<crate::models::TagSet, String> {
+    let tags = clean_tag_set(tags)?;
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .create_tag_set(name.trim(), &tags).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn update_tag_set(id: i64, name: String, tags: Vec<String>, state: State<'_, AppState>) -> Result<(), String> {
+    let tags = clean_tag_set(tags)?;
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .update_tag_set(id, name.trim(), &tags).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn delete_tag_set(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .delete_tag_set(id).map_err(|e| e.to_string())
+}
+
+/// Adds every tag in a tag set to the given tracks as a single undo step.
+/// Returns the number of tracks whose comment changed.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn apply_tag_set(ids: Vec<i64>, set_id: i64, app: tauri::AppHandle, state: State<'_, AppState>) -> Result<usize, String> {
+    let tag_set = state.db.read().map_err(|_| "Failed to lock DB".to_string())?
+        .get_tag_set(set_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Tag set {} not found", set_id))?;
+
+    let updated = apply_tags_to_tracks(&app, &state, &tag_set.tags, &ids, TagCopyMode::Merge).await?;
+    tracing::info!("Applied tag set '{}' to {} of {} tracks", tag_set.name, updated, ids.len());
+    Ok(updated)
+}
+
+// Tag Shortcut Commands
+
+/// Lower-cases a shortcut key and checks it is one of "1"-"9" or "a"-"z".
+fn clean_shortcut_key(key: &str) -> Result<String, String> {
+    let key = key.trim().to_lowercase();
+    match key.as_bytes() {
+        [b'1'..=b'9'] | [b'a'..=b'z'] => Ok(key),
+        _ => Err(format!("Invalid shortcut key: {} (use 1-9 or a-z)", key)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_tag_shortcuts(state: State<'_, AppState>) -> Result<Vec<crate::models::TagShortcut>, String> {
+    state.db.read().map_err(|_| "Failed to lock DB".to_string())?
+        .get_tag_shortcuts().map_err(|e| e.to_string())
+}
+
+/// Binds a key to a tag; a key already in use is re-bound.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_tag_shortcut(key: String, tag_id: i64, state: State<'_, AppState>) -> Result<crate::models::TagShortcut, String> {
+    let key = clean_shortcut_key(&key)?;
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    if !db.set_tag_shortcut(&key, tag_id).map_err(|e| e.to_string())? {
+        return Err(format!("Tag {} not found", tag_id));
+    }
+    db.get_tag_shortcut(&key).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Shortcut {} not found", key))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn delete_tag_shortcut(key: String, state: State<'_, AppState>) -> Result<(), String> {
+    let key = clean_shortcut_key(&key)?;
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .delete_tag_shortcut(&key).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_all_tags(state: State<'_, AppState>) -> Result<Vec<crate::models::Tag>, String> {
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    db.sync_tags().map_err(|e| e.to_string())?;
+    db.get_all_tags().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn delete_tag(tag_id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
         .delete_tag(tag_id).map_err(|e| e.to_string())
 }
 
+/// Deletes (or archives, when `archive` is true) every tag no track uses anymore.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn prune_unused_tags(archive: bool, state: State<'_, AppState>) -> Result<usize, String> {
+    let count = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .prune_unused_tags(archive).map_err(|e| e.to_string())?;
+
+    tracing::info!("{} {} unused tag(s)", if archive { "Archived" } else { "Deleted" }, count);
+    Ok(count)
+}
+
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn update_track_info(
     app: tauri::AppHandle,
     track_id: i64,
     title: Option<String>,
     artist: Option<String>,
     album: Option<String>,
-    bpm: Option<i64>,
+    bpm: Option<f64>,
     comment: Option<String>,
+    key: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
@@ -1326,75 +2481,78 @@ pub async fn update_track_info(
         new_artist: artist.clone(),
         old_album: if album.is_some() { track.album.clone() } else { None },
         new_album: album.clone(),
-        old_bpm: if bpm.is_some() { Some(track.bpm as i64) } else { None },
+        old_bpm: if bpm.is_some() { Some(track.bpm) } else { None },
         new_bpm: bpm,
         old_comment_raw: if new_comment_raw.is_some() { track.comment_raw.clone() } else { None },
         new_comment_raw: new_comment_raw.clone(),
     };
 
-    // 4. Update local DB
-    db.update_track_info(
-        track_id,
-        title.as_deref(),
-        artist.as_deref(),
-        album.as_deref(),
-        bpm,
-        new_comment_raw.as_deref(),
-    ).map_err(|e| e.to_string())?;
-
     drop(db); // Release lock before IO
 
-    // 5. Write to file metadata (title/artist/album/bpm)
-    if title.is_some() || artist.is_some() || album.is_some() || bpm.is_some() {
+    // 4. Write the comment to the file first: if it doesn't land, nothing is recorded
+    if let Some(ref new_cr) = new_comment_raw {
+        write_track_comment(&track, new_cr)
+            .map_err(|e| format!("Failed to write comment to file: {}", e))?;
+    }
+
+    // 4b. Update local DB
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .update_track_info(
+            track_id,
+            title.as_deref(),
+            artist.as_deref(),
+            album.as_deref(),
+            bpm,
+            new_comment_raw.as_deref(),
+        ).map_err(|e| e.to_string())?;
+
+    // Musical key: explicit, or the Camelot key Mixed In Key put at the start of the comment
+    let key = key.or_else(|| {
+        let comment = new_comment_raw.as_deref()?;
+        let (user_comment, _) = crate::comment::split_comment(comment);
+        Some(crate::import_export::camelot_key(user_comment)).filter(|k| !k.is_empty())
+    });
+
+    // 5. Write to file metadata (title/artist/album/bpm/key); cloud tracks have no file
+    let has_changes = title.is_some() || artist.is_some() || album.is_some() || bpm.is_some() || key.is_some();
+    if has_changes && !track.is_cloud {
         if let Err(e) = write_track_info(
             &track.file_path,
             title.as_deref(),
             artist.as_deref(),
             album.as_deref(),
             bpm,
+            key.as_deref(),
         ) {
-            let msg = format!("Warning: Failed to write track info to file: {}", e);
-            app.state::<crate::logging::LogState>().add_log("WARN", &msg, &app);
-            eprintln!("{}", msg);
-        }
-    }
-
-    // 5b. Write comment to file if changed
-    if let Some(ref new_cr) = new_comment_raw {
-        if let Err(e) = write_tags_to_file(&track.file_path, new_cr) {
-            let msg = format!("Warning: Failed to write comment to file: {}", e);
-            app.state::<crate::logging::LogState>().add_log("WARN", &msg, &app);
-            eprintln!("{}", msg);
+            tracing::warn!("Failed to write track info to file: {}", e);
         }
     }
 
     // 6. Touch file so Finder/Rekordbox notices
-    if let Err(e) = touch_file(&track.file_path) {
-        eprintln!("Warning: Failed to touch file: {}", e);
+    if !track.is_cloud {
+        if let Err(e) = touch_file(&track.file_path) {
+            tracing::warn!("Failed to touch file: {}", e);
+        }
     }
 
     // 7. Update Apple Music
     if title.is_some() || artist.is_some() || album.is_some() || bpm.is_some() {
-        if let Err(e) = apple_update_track_info(
-            &track.persistent_id,
-            title.as_deref(),
-            artist.as_deref(),
-            album.as_deref(),
-            bpm,
-        ) {
-            let msg = format!("Warning: Failed to update Apple Music: {}", e);
-            app.state::<crate::logging::LogState>().add_log("WARN", &msg, &app);
-            eprintln!("{}", msg);
-        }
+        state.apple_queue.enqueue(AppleUpdate::TrackInfo {
+            persistent_id: track.persistent_id.clone(),
+            name: title.clone(),
+            artist: artist.clone(),
+            album: album.clone(),
+            // Music.app only stores whole BPMs
+            bpm: bpm.map(|b| b.round() as i64),
+        });
     }
 
     // 7b. Update comment in Apple Music if changed
     if let Some(ref new_cr) = new_comment_raw {
-        if let Err(e) = update_track_comment(&track.persistent_id, new_cr) {
-            let msg = format!("Warning: Failed to update Apple Music comment: {}", e);
-            app.state::<crate::logging::LogState>().add_log("WARN", &msg, &app);
-            eprintln!("{}", msg);
-        }
+        state.apple_queue.enqueue(AppleUpdate::Comment {
+            persistent_id: track.persistent_id.clone(),
+            comment: new_cr.clone(),
+        });
     }
 
     // 8. Push Undo
@@ -1405,23 +2563,126 @@ pub async fn update_track_info(
     Ok(())
 }
 
+/// Correction for a BPM detected at the wrong tempo multiple.
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BpmFactor {
+    /// Detected at half tempo (87 for a 174 DnB track)
+    Double,
+    /// Detected at double tempo
+    Half,
+    /// Detected as the half-time feel of a triplet tempo (e.g. 106.67 for 160 footwork)
+    ThreeHalves,
+}
+
+impl BpmFactor {
+    fn apply(self, bpm: f64) -> f64 {
+        let fixed = match self {
+            BpmFactor::Double => bpm * 2.0,
+            BpmFactor::Half => bpm / 2.0,
+            BpmFactor::ThreeHalves => bpm * 1.5,
+        };
+        (fixed * 100.0).round() / 100.0
+    }
+}
+
+/// Multiplies the BPM of tracks by `factor` in the files, the DB and Music.app, as one
+/// undo step. Files are written in parallel and the DB in one transaction. Tracks without
+/// a BPM are skipped; returns the number changed.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn fix_bpm(ids: Vec<i64>, factor: BpmFactor, state: State<'_, AppState>) -> Result<usize, String> {
+    use rayon::prelude::*;
+
+    let tracks: Vec<Track> = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        ids.iter()
+            .filter_map(|id| db.get_track(*id).ok().flatten())
+            .filter(|t| t.bpm > 0.0)
+            .collect()
+    };
+
+    let written: Vec<(Track, f64)> = tauri::async_runtime::spawn_blocking(move || {
+        tracks.into_par_iter()
+            .filter_map(|track| {
+                let new_bpm = factor.apply(track.bpm);
+                match write_track_info(&track.file_path, None, None, None, Some(new_bpm), None) {
+                    Ok(()) => Some((track, new_bpm)),
+                    Err(e) => {
+                        tracing::warn!("Failed to write BPM to {}: {}", track.file_path, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let bpms: Vec<(i64, f64)> = written.iter().map(|(track, bpm)| (track.id, *bpm)).collect();
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .update_track_bpms(&bpms).map_err(|e| e.to_string())?;
+
+    let mut actions = Vec::new();
+    for (track, new_bpm) in written {
+        if !track.persistent_id.is_empty() {
+            state.apple_queue.enqueue(AppleUpdate::TrackInfo {
+                persistent_id: track.persistent_id.clone(),
+                name: None,
+                artist: None,
+                album: None,
+                // Music.app only stores whole BPMs
+                bpm: Some(new_bpm.round() as i64),
+            });
+        }
+        actions.push(Action::UpdateTrackInfo {
+            track: crate::undo::TrackInfoState {
+                id: track.id,
+                persistent_id: track.persistent_id,
+                file_path: track.file_path,
+                old_title: None,
+                new_title: None,
+                old_artist: None,
+                new_artist: None,
+                old_album: None,
+                new_album: None,
+                old_bpm: Some(track.bpm),
+                new_bpm: Some(new_bpm),
+                old_comment_raw: None,
+                new_comment_raw: None,
+            },
+        });
+    }
+
+    let fixed = actions.len();
+    if fixed > 0 {
+        if let Ok(mut stack) = state.undo_stack.lock() {
+            stack.push(Action::Composite { description: format!("Fix BPM ({} tracks)", fixed), actions });
+        }
+    }
+    tracing::info!("Fixed the BPM of {} of {} tracks ({:?})", fixed, ids.len(), factor);
+    Ok(fixed)
+}
+
 #[derive(serde::Serialize)]
 pub struct PlaylistInfo {
     pub id: i64,
     pub persistent_id: String,
     pub name: String,
+    pub is_smart: bool,
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn get_playlists_for_track(track_id: i64, state: State<'_, AppState>) -> Result<Vec<PlaylistInfo>, String> {
-    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
     let rows = db.get_playlists_for_track(track_id).map_err(|e| e.to_string())?;
-    Ok(rows.into_iter().map(|(id, persistent_id, name)| PlaylistInfo { id, persistent_id, name }).collect())
+    Ok(rows.into_iter().map(|(id, persistent_id, name, is_smart)| PlaylistInfo { id, persistent_id, name, is_smart }).collect())
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub async fn copy_playlist_memberships(
-    app: tauri::AppHandle,
     target_track_id: i64,
     source_track_id: i64,
     playlist_ids: Vec<i64>,
@@ -1436,6 +2697,9 @@ pub async fn copy_playlist_memberships(
         
         let mut pdata = Vec::new();
         for pid in &playlist_ids {
+            if ensure_editable_playlist(&db, *pid).is_err() {
+                continue;
+            }
             if let Ok(ppid) = db.get_playlist_persistent_id(*pid) {
                 pdata.push((*pid, ppid));
             }
@@ -1445,50 +2709,27 @@ pub async fn copy_playlist_memberships(
 
     let mut added_count = 0;
 
-    // 1. Add target track to each selected playlist (Apple Music + DB)
-    for (db_id, ppid) in &playlist_data {
-        // Apple Music
-        if let Err(e) = add_track_to_playlist(&target_pid, ppid) {
-            let msg = format!("Failed to add track to playlist in Music.app: {}", e);
-            app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
-        }
-
+    // 1. Add target track to each selected playlist (Apple Music in one call + DB)
+    if !playlist_data.is_empty() {
+        state.apple_queue.enqueue(AppleUpdate::PlaylistAdds {
+            adds: playlist_data.iter().map(|(_, ppid)| (target_pid.clone(), ppid.clone())).collect(),
+        });
+    }
+    for (db_id, _) in &playlist_data {
         // Local DB
         {
             let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
             if let Err(e) = db.add_track_to_playlist_db(*db_id, target_track_id) {
-                let msg = format!("Failed to add track to playlist in DB: {}", e);
-                app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+                tracing::error!("Failed to add track to playlist in DB: {}", e);
             }
         }
         added_count += 1;
     }
 
-    // 2. Combine play counts if requested
+    // 2. Combine play counts if requested (undoable); the source keeps its own count
     if combine_play_counts {
-        match get_play_count(&source_pid) {
-            Ok(source_count) => {
-                match get_play_count(&target_pid) {
-                    Ok(target_count) => {
-                        let combined = source_count + target_count;
-                        if let Err(e) = set_play_count(&target_pid, combined) {
-                            let msg = format!("Failed to set combined play count: {}", e);
-                            app.state::<crate::logging::LogState>().add_log("WARN", &msg, &app);
-                        } else {
-                            let msg = format!("Combined play counts: {} + {} = {}", source_count, target_count, combined);
-                            app.state::<crate::logging::LogState>().add_log("INFO", &msg, &app);
-                        }
-                    }
-                    Err(e) => {
-                        let msg = format!("Failed to get target play count: {}", e);
-                        app.state::<crate::logging::LogState>().add_log("WARN", &msg, &app);
-                    }
-                }
-            }
-            Err(e) => {
-                let msg = format!("Failed to get source play count: {}", e);
-                app.state::<crate::logging::LogState>().add_log("WARN", &msg, &app);
-            }
+        if let Err(e) = transfer_play_count_between(&state, source_track_id, target_track_id, PlayCountMode::Sum, false) {
+            tracing::warn!("Failed to combine play counts: {}", e);
         }
     }
 
@@ -1496,17 +2737,16 @@ pub async fn copy_playlist_memberships(
     if remove_source {
         for (db_id, ppid) in &playlist_data {
             // Apple Music
-            if let Err(e) = apple_remove_from_playlist(&source_pid, ppid) {
-                let msg = format!("Failed to remove source from playlist in Music.app: {}", e);
-                app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
-            }
+            state.apple_queue.enqueue(AppleUpdate::RemoveFromPlaylist {
+                track_pid: source_pid.clone(),
+                playlist_pid: ppid.clone(),
+            });
 
             // Local DB
             {
                 let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
                 if let Err(e) = db.remove_track_from_playlist(*db_id, source_track_id) {
-                    let msg = format!("Failed to remove source from playlist in DB: {}", e);
-                    app.state::<crate::logging::LogState>().add_log("ERROR", &msg, &app);
+                    tracing::error!("Failed to remove source from playlist in DB: {}", e);
                 }
             }
         }
@@ -1514,3 +2754,176 @@ pub async fn copy_playlist_memberships(
 
     Ok(format!("Added to {} playlist{}", added_count, if added_count != 1 { "s" } else { "" }))
 }
+
+/// How `transfer_play_count` combines the source's plays with the target's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlayCountMode {
+    /// The target gets both counts added up
+    Sum,
+    /// The target gets the source's count
+    Replace,
+    /// The target keeps the higher of the two
+    Max,
+}
+
+impl PlayCountMode {
+    fn combine(self, source: i64, target: i64) -> i64 {
+        match self {
+            PlayCountMode::Sum => source + target,
+            PlayCountMode::Replace => source,
+            PlayCountMode::Max => source.max(target),
+        }
+    }
+}
+
+/// A track's play count: Music.app's when it is running (it may be ahead of the last
+/// sync), TagDeck's otherwise.
+fn current_play_count(track: &Track) -> i64 {
+    if !track.persistent_id.is_empty() && crate::apple_music::is_music_running() {
+        if let Ok(count) = get_play_count(&track.persistent_id) {
+            return count;
+        }
+    }
+    track.play_count
+}
+
+/// Combines the plays of `source_id` into `target_id` per `mode`. With `move_plays` a Sum or
+/// Replace also resets the source to 0, so the plays aren't counted twice; otherwise the
+/// source keeps its count. Pushes one undo step holding both original counts and returns the
+/// target's new count.
+fn transfer_play_count_between(
+    state: &AppState,
+    source_id: i64,
+    target_id: i64,
+    mode: PlayCountMode,
+    move_plays: bool,
+) -> Result<i64, String> {
+    if source_id == target_id {
+        return Err("Source and target are the same track".to_string());
+    }
+    let (source, target) = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let source = db.get_track(source_id).map_err(|e| e.to_string())?.ok_or("Source track not found")?;
+        let target = db.get_track(target_id).map_err(|e| e.to_string())?.ok_or("Target track not found")?;
+        (source, target)
+    };
+
+    let source_count = current_play_count(&source);
+    let target_count = current_play_count(&target);
+    let reset_source = move_plays && mode != PlayCountMode::Max;
+    let source_state = PlayCountState {
+        id: source.id,
+        persistent_id: source.persistent_id,
+        old_count: source_count,
+        new_count: if reset_source { 0 } else { source_count },
+    };
+    let target_state = PlayCountState {
+        id: target.id,
+        persistent_id: target.persistent_id,
+        old_count: target_count,
+        new_count: mode.combine(source_count, target_count),
+    };
+
+    let changed: Vec<&PlayCountState> = [&source_state, &target_state].into_iter()
+        .filter(|track| reset_source || track.id == target_state.id)
+        .collect();
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        for track in &changed {
+            db.update_track_play_count(track.id, track.new_count).map_err(|e| e.to_string())?;
+        }
+    }
+    for track in changed {
+        if !track.persistent_id.is_empty() {
+            state.apple_queue.enqueue(AppleUpdate::PlayCount {
+                persistent_id: track.persistent_id.clone(),
+                count: track.new_count,
+            });
+        }
+    }
+
+    tracing::info!(
+        "Transferred play count {} -> {} ({:?}): {} + {} => {}",
+        source_id, target_id, mode, source_count, target_count, target_state.new_count
+    );
+    let new_count = target_state.new_count;
+    if let Ok(mut stack) = state.undo_stack.lock() {
+        stack.push(Action::TransferPlayCount { source: source_state, target: target_state });
+    }
+    Ok(new_count)
+}
+
+/// Combines the plays of `source_id` into `target_id` (summed, replaced or the higher of the
+/// two) as one undo step. With `move_plays`, a sum or replace also resets the source to 0.
+/// Returns the target's new count.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn transfer_play_count(
+    source_id: i64,
+    target_id: i64,
+    mode: PlayCountMode,
+    move_plays: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<i64, String> {
+    transfer_play_count_between(&state, source_id, target_id, mode, move_plays.unwrap_or(false))
+}
+
+const PRESERVE_MTIME_KEY: &str = "preserve_mtime";
+const FILE_RATINGS_KEY: &str = "file_ratings";
+
+/// Applies the stored "preserve modification time on tag writes" preference.
+pub fn load_preserve_mtime(state: &AppState) {
+    if let Ok(db) = state.db.read() {
+        load_preserve_mtime_from(&db);
+    }
+}
+
+pub fn load_preserve_mtime_from(db: &Database) {
+    let enabled = db.get_setting(PRESERVE_MTIME_KEY).ok().flatten().as_deref() == Some("true");
+    crate::metadata::set_preserve_mtime(enabled);
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_preserve_mtime() -> Result<bool, String> {
+    Ok(crate::metadata::preserve_mtime())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_preserve_mtime(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_setting(PRESERVE_MTIME_KEY, if enabled { "true" } else { "false" })
+        .map_err(|e| e.to_string())?;
+    crate::metadata::set_preserve_mtime(enabled);
+    Ok(())
+}
+
+/// Applies the stored "write ratings into files" preference.
+pub fn load_file_ratings(state: &AppState) {
+    if let Ok(db) = state.db.read() {
+        load_file_ratings_from(&db);
+    }
+}
+
+pub fn load_file_ratings_from(db: &Database) {
+    let enabled = db.get_setting(FILE_RATINGS_KEY).ok().flatten().as_deref() == Some("true");
+    crate::metadata::set_file_ratings(enabled);
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_file_ratings() -> Result<bool, String> {
+    Ok(crate::metadata::file_ratings())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_file_ratings(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_setting(FILE_RATINGS_KEY, if enabled { "true" } else { "false" })
+        .map_err(|e| e.to_string())?;
+    crate::metadata::set_file_ratings(enabled);
+    Ok(())
+}
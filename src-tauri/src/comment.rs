@@ -0,0 +1,147 @@
+//! Helpers for the comment format TagDeck stores tags in: `user comment && tag1; tag2`.
+
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use unicode_normalization::UnicodeNormalization;
+
+const TAG_DELIMITER: &str = " && ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagCase {
+    /// Keep the spelling as typed (or as already used in the library)
+    Preserve,
+    Lower,
+    /// First letter of every word upper-cased
+    Title,
+}
+
+/// How tag names are cleaned up when tags are created. Surrounding whitespace is always trimmed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagNormalization {
+    /// Turn runs of whitespace inside a tag into a single space
+    pub collapse_whitespace: bool,
+    pub case: TagCase,
+    /// Unicode NFC, so "é" typed on different keyboards compares equal
+    pub nfc: bool,
+}
+
+impl TagNormalization {
+    pub const DEFAULT: TagNormalization = TagNormalization {
+        collapse_whitespace: true,
+        case: TagCase::Preserve,
+        nfc: true,
+    };
+
+    pub fn apply(&self, tag: &str) -> String {
+        let mut out: String = if self.nfc { tag.trim().nfc().collect() } else { tag.trim().to_string() };
+        if self.collapse_whitespace {
+            out = out.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        match self.case {
+            TagCase::Preserve => out,
+            TagCase::Lower => out.to_lowercase(),
+            TagCase::Title => out.split(' ')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+                        None => String::new(),
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(" "),
+        }
+    }
+}
+
+impl Default for TagNormalization {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+static NORMALIZATION: RwLock<TagNormalization> = RwLock::new(TagNormalization::DEFAULT);
+
+pub fn normalization() -> TagNormalization {
+    NORMALIZATION.read().map(|p| p.clone()).unwrap_or_default()
+}
+
+pub fn set_normalization(policy: TagNormalization) {
+    if let Ok(mut current) = NORMALIZATION.write() {
+        *current = policy;
+    }
+}
+
+/// Applies the current normalization policy to one tag name.
+pub fn normalize_tag(tag: &str) -> String {
+    normalization().apply(tag)
+}
+
+/// Normalizes every tag in a raw comment and drops case-insensitive duplicates.
+/// The user comment part is left exactly as written.
+pub fn normalize_comment(comment: &str) -> String {
+    let (user_comment, tags) = split_comment(comment);
+    if tags.is_empty() {
+        return comment.to_string();
+    }
+    let policy = normalization();
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = policy.apply(&tag);
+        if !tag.is_empty() && !normalized.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+            normalized.push(tag);
+        }
+    }
+    build_comment(user_comment, &normalized)
+}
+
+/// Splits a raw comment into the user comment and its tag list.
+pub fn split_comment(comment: &str) -> (&str, Vec<String>) {
+    match comment.find(TAG_DELIMITER) {
+        Some(idx) => (
+            &comment[..idx],
+            comment[idx + TAG_DELIMITER.len()..].split(';')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+        ),
+        None => (comment, Vec::new()),
+    }
+}
+
+/// Reassembles a raw comment from its parts.
+pub fn build_comment(user_comment: &str, tags: &[String]) -> String {
+    if tags.is_empty() {
+        user_comment.to_string()
+    } else {
+        format!("{}{}{}", user_comment, TAG_DELIMITER, tags.join("; "))
+    }
+}
+
+/// Adds tags that are not already present (case-insensitive).
+/// Returns `None` when the comment would not change.
+pub fn add_tags(comment: &str, new_tags: &[String]) -> Option<String> {
+    let (user_comment, mut tags) = split_comment(comment);
+    let mut changed = false;
+
+    for tag in new_tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+            changed = true;
+        }
+    }
+
+    if changed { Some(build_comment(user_comment, &tags)) } else { None }
+}
+
+/// Removes tags (case-insensitive). Returns `None` when none of them were present.
+pub fn remove_tags(comment: &str, old_tags: &[String]) -> Option<String> {
+    let (user_comment, mut tags) = split_comment(comment);
+    let before = tags.len();
+    tags.retain(|t| !old_tags.iter().any(|o| o.trim().eq_ignore_ascii_case(t)));
+
+    if tags.len() != before { Some(build_comment(user_comment, &tags)) } else { None }
+}
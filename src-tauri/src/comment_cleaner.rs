@@ -0,0 +1,140 @@
+//! The user-comment side of the comment field (left of the tag block): what is in there
+//! across the library, and a batch cleaner for the junk stores and promo pools leave
+//! behind. The tag block is never touched.
+
+use crate::commands::AppState;
+use crate::models::Track;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{AppHandle, State};
+
+/// Cleaned when the caller gives no patterns of its own (matched case-insensitively).
+const DEFAULT_JUNK_PATTERNS: &[&str] = &[
+    r"(https?://|www\.)\S+",
+    r"\b(purchased|bought|downloaded)\s+(at|from|on)\s+\S+(\.\S+)?",
+    r"\b(visit|get more at|more at)\s+\S+\.(com|net|org|io)\S*",
+    r"\b(for\s+)?promo(tional)?\s+(use\s+)?only\b",
+    r"\bpromo\s+copy\b",
+    r"\b(beatport|traxsource|junodownload|bandcamp)(\.com)?\b",
+];
+
+/// Comments listed by `get_user_comment_summary` when no `limit` is given.
+const DEFAULT_SUMMARY_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserCommentCount {
+    pub comment: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentCleanupChange {
+    pub track_id: i64,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentCleanupResult {
+    pub changes: Vec<CommentCleanupChange>,
+    /// Tracks actually rewritten; 0 for a dry run
+    pub updated: usize,
+}
+
+fn compile_patterns(patterns: Option<Vec<String>>) -> Result<Vec<Regex>, String> {
+    let patterns = patterns.unwrap_or_else(|| DEFAULT_JUNK_PATTERNS.iter().map(|p| p.to_string()).collect());
+    patterns.iter()
+        .map(|p| RegexBuilder::new(p).case_insensitive(true).build()
+            .map_err(|e| format!("Invalid pattern '{}': {}", p, e)))
+        .collect()
+}
+
+/// Strips `patterns` from a user comment, then tidies the whitespace and separators the
+/// removal leaves at either end.
+fn clean_user_comment(user_comment: &str, patterns: &[Regex]) -> String {
+    let mut cleaned = user_comment.to_string();
+    for pattern in patterns {
+        cleaned = pattern.replace_all(&cleaned, "").into_owned();
+    }
+    let cleaned = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    cleaned.trim_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '|' | '/' | ',' | ':' | '•'))
+        .to_string()
+}
+
+fn load_tracks(state: &AppState, ids: Option<&[i64]>) -> Result<Vec<Track>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    match ids {
+        Some(ids) => Ok(ids.iter().filter_map(|id| db.get_track(*id).ok().flatten()).collect()),
+        None => db.get_all_tracks().map_err(|e| e.to_string()),
+    }
+}
+
+/// Distinct user comments (whitespace-trimmed) across the library with the number of tracks
+/// carrying each, most common first.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_user_comment_summary(limit: Option<usize>, state: State<'_, AppState>) -> Result<Vec<UserCommentCount>, String> {
+    let tracks = load_tracks(&state, None)?;
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for track in &tracks {
+        let (user_comment, _) = crate::comment::split_comment(track.comment_raw.as_deref().unwrap_or_default());
+        let user_comment = user_comment.trim();
+        if !user_comment.is_empty() {
+            *counts.entry(user_comment.to_string()).or_default() += 1;
+        }
+    }
+
+    let mut summary: Vec<UserCommentCount> = counts.into_iter()
+        .map(|(comment, count)| UserCommentCount { comment, count })
+        .collect();
+    summary.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.comment.cmp(&b.comment)));
+    summary.truncate(limit.unwrap_or(DEFAULT_SUMMARY_LIMIT));
+    Ok(summary)
+}
+
+/// Removes junk from the user comments of `ids` (or the whole library): `patterns` are
+/// case-insensitive regular expressions, defaulting to URLs, store and promo text. The
+/// tag block stays as it is. Unless `dry_run` is `false`, only the planned changes are
+/// returned; with it they are written as one undo step.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn clean_user_comments(
+    ids: Option<Vec<i64>>,
+    patterns: Option<Vec<String>>,
+    dry_run: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<CommentCleanupResult, String> {
+    let patterns = compile_patterns(patterns)?;
+    let dry_run = dry_run.unwrap_or(true);
+    let tracks = load_tracks(&state, ids.as_deref())?;
+
+    let mut changes = Vec::new();
+    let mut batch = Vec::new();
+    for track in tracks {
+        let comment = track.comment_raw.clone().unwrap_or_default();
+        let (user_comment, tags) = crate::comment::split_comment(&comment);
+        let cleaned = clean_user_comment(user_comment, &patterns);
+        if cleaned == user_comment.trim() {
+            continue;
+        }
+        let new_comment = crate::comment::build_comment(&cleaned, &tags);
+        if new_comment == comment {
+            continue;
+        }
+        changes.push(CommentCleanupChange {
+            track_id: track.id,
+            before: user_comment.to_string(),
+            after: cleaned,
+        });
+        batch.push((track, new_comment));
+    }
+
+    if dry_run {
+        return Ok(CommentCleanupResult { changes, updated: 0 });
+    }
+    let updated = crate::commands::apply_comment_batch(&app, &state, batch).await?;
+    tracing::info!("Cleaned the user comment of {} of {} tracks", updated, changes.len());
+    Ok(CommentCleanupResult { changes, updated })
+}
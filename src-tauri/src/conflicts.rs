@@ -0,0 +1,135 @@
+//! Comment conflicts: Music.app reports a different comment for a track whose comment
+//! TagDeck changed since the last sync (or has not delivered to Music.app yet). Instead of
+//! letting either side silently win, the track is flagged until the user picks one.
+
+use crate::apple_music::{AppleUpdate, SyncQueue};
+use crate::commands::AppState;
+use crate::db::Database;
+use crate::metadata::write_track_comment;
+use crate::models::Track;
+use crate::undo::{Action, TrackState};
+use tauri::{AppHandle, State};
+
+#[derive(serde::Serialize)]
+pub struct CommentConflict {
+    /// What TagDeck last wrote (kept in the DB and file until resolved)
+    pub local_comment: String,
+    pub music_comment: String,
+    pub detected_at: i64,
+    pub track: Track,
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictSide {
+    /// Keep TagDeck's comment and push it to Music.app
+    Local,
+    /// Take Music.app's comment into the DB and file
+    Music,
+}
+
+/// Checks a track fetched during sync against the DB. On a conflict the conflict is
+/// recorded and `incoming` is given TagDeck's comment, so storing it keeps the local side.
+/// A track with an unresolved conflict keeps its local comment until the user settles it.
+/// Returns whether the track is conflicted.
+pub(crate) fn check_incoming(db: &Database, queue: &SyncQueue, incoming: &mut Track, since: i64) -> anyhow::Result<bool> {
+    let id = match db.get_track_id_by_persistent_id(&incoming.persistent_id)? {
+        Some(id) => id,
+        None => return Ok(false),
+    };
+    let local = match db.get_track(id)? {
+        Some(t) => t.comment_raw.unwrap_or_default(),
+        None => return Ok(false),
+    };
+    if db.has_comment_conflict(id)? {
+        incoming.comment_raw = Some(local);
+        return Ok(true);
+    }
+    let music = incoming.comment_raw.clone().unwrap_or_default();
+    if local == music {
+        return Ok(false);
+    }
+    if !queue.has_pending_comment(&incoming.persistent_id) && !db.comment_edited_since(id, since)? {
+        // Only Music.app changed: a normal edit made there
+        return Ok(false);
+    }
+
+    db.set_comment_conflict(id, &local, &music)?;
+    incoming.comment_raw = Some(local);
+    Ok(true)
+}
+
+/// Tracks whose comment differs between TagDeck and Music.app, newest first.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_conflicts(state: State<'_, AppState>) -> Result<Vec<CommentConflict>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let conflicts = db.get_comment_conflicts().map_err(|e| e.to_string())?;
+    Ok(conflicts.into_iter()
+        .filter_map(|(id, local_comment, music_comment, detected_at)| {
+            db.get_track(id).ok().flatten().map(|track| CommentConflict { local_comment, music_comment, detected_at, track })
+        })
+        .collect())
+}
+
+/// Settles a conflict by keeping one side's comment everywhere. Taking Music.app's
+/// comment is undoable like any other comment edit, and drops any local comment write
+/// still queued for Music.app.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn resolve_conflict(
+    app: AppHandle,
+    track_id: i64,
+    keep: ConflictSide,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let (local_comment, music_comment, track) = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let (_, local_comment, music_comment, _) = db.get_comment_conflicts().map_err(|e| e.to_string())?
+            .into_iter()
+            .find(|(id, ..)| *id == track_id)
+            .ok_or_else(|| format!("Track {} has no conflict", track_id))?;
+        let track = db.get_track(track_id).map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Track {} not found", track_id))?;
+        (local_comment, music_comment, track)
+    };
+    // The file is written before the writer lock is taken
+    if let ConflictSide::Music = keep {
+        write_track_comment(&track, &music_comment).map_err(|e| e.to_string())?;
+        state.apple_queue.drop_pending_comment(&track.persistent_id);
+    }
+
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    match keep {
+        ConflictSide::Local => {
+            if !track.persistent_id.is_empty() {
+                state.apple_queue.enqueue(AppleUpdate::Comment {
+                    persistent_id: track.persistent_id.clone(),
+                    comment: local_comment,
+                });
+            }
+        }
+        ConflictSide::Music => {
+            db.update_track_metadata(track_id, &music_comment).map_err(|e| e.to_string())?;
+            if let Ok(mut stack) = state.undo_stack.lock() {
+                stack.push(Action::UpdateTrackComments {
+                    tracks: vec![TrackState {
+                        id: track_id,
+                        persistent_id: track.persistent_id.clone(),
+                        file_path: track.file_path.clone(),
+                        old_comment: track.comment_raw.clone().unwrap_or_default(),
+                        new_comment: music_comment,
+                    }],
+                });
+            }
+        }
+    }
+    db.delete_comment_conflict(track_id).map_err(|e| e.to_string())?;
+    drop(db);
+
+    tracing::info!("Resolved comment conflict on track {} keeping the {} comment", track_id, match keep {
+        ConflictSide::Local => "TagDeck",
+        ConflictSide::Music => "Music.app",
+    });
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! Panic hook that leaves a crash report in the log directory: the panic message and
+//! location, the thread, a backtrace and the last log lines. Background threads (watchers,
+//! the sync worker) would otherwise die without a trace.
+
+use crate::logging::{LogEntry, LogState};
+use chrono::Local;
+use serde::Serialize;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// Log lines included in a report
+const CRASH_LOG_LINES: usize = 200;
+/// Older crash files are deleted beyond this
+const MAX_CRASH_FILES: usize = 10;
+const CRASH_FILE_PREFIX: &str = "crash-";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CrashReport {
+    pub path: String,
+    /// Unix timestamp of the crash file
+    pub created_at: i64,
+    pub content: String,
+}
+
+fn format_entry(entry: &LogEntry) -> String {
+    format!("[{}] [{}] [{}] {}", entry.timestamp, entry.level, entry.target, entry.message)
+}
+
+/// The newest log lines, from the in-memory buffer or, if it is busy (the panic may have
+/// happened while it was locked), from the end of the current log file.
+fn recent_log_lines(state: &LogState, dir: &Path) -> Vec<String> {
+    if let Ok(logs) = state.logs.try_lock() {
+        let start = logs.len().saturating_sub(CRASH_LOG_LINES);
+        return logs[start..].iter().map(format_entry).collect();
+    }
+    let content = fs::read_to_string(dir.join("tagdeck.log")).unwrap_or_default();
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.len().saturating_sub(CRASH_LOG_LINES);
+    lines[start..].iter().map(|l| l.to_string()).collect()
+}
+
+fn crash_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|entries| entries.flatten()
+            .map(|e| e.path())
+            .filter(|p| p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(CRASH_FILE_PREFIX) && n.ends_with(".log")))
+            .collect())
+        .unwrap_or_default();
+    // Names carry the timestamp, so this is oldest first
+    files.sort();
+    files
+}
+
+fn write_report(app: &AppHandle, info: &std::panic::PanicHookInfo<'_>) -> Option<PathBuf> {
+    let state = app.try_state::<LogState>()?;
+    let dir = state.get_log_dir()?;
+
+    let message = info.payload().downcast_ref::<&str>().map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "<non-string panic payload>".to_string());
+    let location = info.location()
+        .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column()))
+        .unwrap_or_else(|| "<unknown>".to_string());
+    let thread = std::thread::current();
+
+    let report = format!(
+        "TagDeck {} crashed at {}\n\nThread: {}\nPanic: {}\nLocation: {}\n\nBacktrace:\n{}\n\nLast {} log lines:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        Local::now().format("%Y-%m-%d %H:%M:%S %Z"),
+        thread.name().unwrap_or("<unnamed>"),
+        message,
+        location,
+        Backtrace::force_capture(),
+        CRASH_LOG_LINES,
+        recent_log_lines(&state, &dir).join("\n"),
+    );
+
+    let path = dir.join(format!("{}{}.log", CRASH_FILE_PREFIX, Local::now().format("%Y%m%d-%H%M%S%.3f")));
+    fs::write(&path, report).ok()?;
+
+    let files = crash_files(&dir);
+    for old in files.iter().take(files.len().saturating_sub(MAX_CRASH_FILES)) {
+        let _ = fs::remove_file(old);
+    }
+    Some(path)
+}
+
+/// Installs the crash report hook in front of the default one (which still prints to stderr).
+/// Call once the log directory is set up.
+pub fn install_panic_hook(app: &AppHandle) {
+    let app = app.clone();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        match write_report(&app, info) {
+            Some(path) => tracing::error!("Panic: {} (crash report: {})", info, path.display()),
+            None => tracing::error!("Panic: {} (no crash report written)", info),
+        }
+        default_hook(info);
+    }));
+}
+
+/// The most recent crash report, if any.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn get_last_crash_report(state: tauri::State<'_, LogState>) -> Option<CrashReport> {
+    let dir = state.get_log_dir()?;
+    let path = crash_files(&dir).pop()?;
+    let created_at = fs::metadata(&path).ok()
+        .and_then(|m| m.modified().ok())
+        .map(|t| chrono::DateTime::<chrono::Utc>::from(t).timestamp())
+        .unwrap_or_default();
+    let content = fs::read_to_string(&path).ok()?;
+    Some(CrashReport { path: path.to_string_lossy().to_string(), created_at, content })
+}
@@ -0,0 +1,71 @@
+//! Cue points (memory cues, hot cues and loops) kept in TagDeck's DB, so prep data
+//! lives next to the tags and travels with Rekordbox exports.
+
+use crate::commands::AppState;
+use crate::models::{CuePoint, CueType};
+use tauri::State;
+
+/// Rejects positions outside the track and loops without a valid end.
+fn validate(cue: &CuePoint, duration_secs: f64) -> Result<(), String> {
+    if cue.position_secs < 0.0 || (duration_secs > 0.0 && cue.position_secs > duration_secs) {
+        return Err(format!("Cue position {:.3}s is outside the track", cue.position_secs));
+    }
+    if cue.cue_type == CueType::Loop && !cue.end_secs.is_some_and(|end| end > cue.position_secs) {
+        return Err("A loop needs an end after its start".to_string());
+    }
+    if let Some(color) = &cue.color {
+        let valid = color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+        if !valid {
+            return Err(format!("Invalid cue color '{}', expected #RRGGBB", color));
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_cue_points(track_id: i64, state: State<'_, AppState>) -> Result<Vec<CuePoint>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    db.get_cue_points(track_id).map_err(|e| e.to_string())
+}
+
+/// Adds a cue point to `cue.track_id`; `cue.id` is ignored. Returns the stored cue.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn add_cue_point(mut cue: CuePoint, state: State<'_, AppState>) -> Result<CuePoint, String> {
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    let track = db.get_track(cue.track_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Track {} not found", cue.track_id))?;
+    if cue.cue_type != CueType::Loop {
+        cue.end_secs = None;
+    }
+    validate(&cue, track.duration_secs)?;
+    cue.id = db.insert_cue_point(&cue).map_err(|e| e.to_string())?;
+    Ok(cue)
+}
+
+/// Updates position, type, end, label and color of an existing cue point.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn update_cue_point(mut cue: CuePoint, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    let existing = db.get_cue_point(cue.id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Cue point {} not found", cue.id))?;
+    // Cue points cannot move between tracks
+    cue.track_id = existing.track_id;
+    if cue.cue_type != CueType::Loop {
+        cue.end_secs = None;
+    }
+    let duration = db.get_track(cue.track_id).map_err(|e| e.to_string())?
+        .map(|t| t.duration_secs)
+        .unwrap_or(0.0);
+    validate(&cue, duration)?;
+    db.update_cue_point(&cue).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn delete_cue_point(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    db.delete_cue_point(id).map_err(|e| e.to_string())
+}
@@ -1,8 +1,18 @@
 use anyhow::Result;
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OpenFlags};
+use serde::Serialize;
 use std::path::Path;
+use std::sync::{LockResult, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use crate::change_events;
 use crate::models::{Track};
 
+/// Number of read-only connections kept open alongside the single writer.
+const READER_COUNT: usize = 3;
+/// How long a connection waits on a locked database before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
 const DB_SCHEMA: &str = r#"
     CREATE TABLE IF NOT EXISTS tracks (
         id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -20,8 +30,20 @@ const DB_SCHEMA: &str = r#"
         modified_date INTEGER,
         rating INTEGER,
         date_added INTEGER,
-        bpm INTEGER,
-        missing BOOLEAN DEFAULT 0
+        bpm REAL,
+        missing BOOLEAN DEFAULT 0,
+        play_count INTEGER DEFAULT 0,
+        skip_count INTEGER DEFAULT 0,
+        last_played INTEGER DEFAULT 0,
+        loved BOOLEAN DEFAULT 0,
+        notes TEXT,
+        intro_bars INTEGER,
+        outro_bars INTEGER,
+        structure_analyzed_at INTEGER,
+        audio_health TEXT,
+        color_label TEXT,
+        offloaded BOOLEAN DEFAULT 0,
+        is_cloud BOOLEAN DEFAULT 0
     );
 
     CREATE TABLE IF NOT EXISTS playlists (
@@ -29,7 +51,8 @@ const DB_SCHEMA: &str = r#"
         persistent_id TEXT UNIQUE,
         parent_persistent_id TEXT,
         name TEXT,
-        is_folder BOOLEAN DEFAULT 0
+        is_folder BOOLEAN DEFAULT 0,
+        is_smart BOOLEAN DEFAULT 0
     );
 
     CREATE TABLE IF NOT EXISTS playlist_tracks (
@@ -47,14 +70,248 @@ const DB_SCHEMA: &str = r#"
         position INTEGER DEFAULT 0
     );
 
+    CREATE TABLE IF NOT EXISTS tag_sets (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT UNIQUE,
+        tags TEXT
+    );
+
     CREATE TABLE IF NOT EXISTS tags (
         id INTEGER PRIMARY KEY AUTOINCREMENT,
         name TEXT UNIQUE COLLATE NOCASE,
         usage_count INTEGER DEFAULT 0,
         group_id INTEGER REFERENCES tag_groups(id) ON DELETE SET NULL
     );
+
+    CREATE TABLE IF NOT EXISTS pending_sync (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        payload TEXT NOT NULL,
+        attempts INTEGER DEFAULT 0,
+        last_error TEXT,
+        queued_at INTEGER
+    );
+
+    CREATE TABLE IF NOT EXISTS settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS jobs (
+        id INTEGER PRIMARY KEY,
+        status TEXT NOT NULL,
+        data TEXT NOT NULL,
+        updated_at INTEGER
+    );
+
+    CREATE TABLE IF NOT EXISTS track_history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        track_id INTEGER NOT NULL,
+        field TEXT NOT NULL,
+        old_value TEXT,
+        new_value TEXT,
+        changed_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS original_metadata (
+        track_id INTEGER PRIMARY KEY,
+        comment TEXT,
+        grouping TEXT,
+        saved_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS comment_conflicts (
+        track_id INTEGER PRIMARY KEY,
+        local_comment TEXT,
+        music_comment TEXT,
+        detected_at INTEGER NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS cue_points (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        track_id INTEGER NOT NULL,
+        position_secs REAL NOT NULL,
+        cue_type TEXT NOT NULL DEFAULT 'cue',
+        end_secs REAL,
+        label TEXT NOT NULL DEFAULT '',
+        color TEXT
+    );
+
+    CREATE TABLE IF NOT EXISTS sessions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER
+    );
+
+    CREATE TABLE IF NOT EXISTS session_tracks (
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        track_id INTEGER NOT NULL,
+        played_at INTEGER NOT NULL,
+        PRIMARY KEY (session_id, position)
+    );
+
+    CREATE TABLE IF NOT EXISTS listening_history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        track_id INTEGER NOT NULL,
+        played_at INTEGER NOT NULL,
+        source TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_listening_history_played_at ON listening_history(played_at);
+
+    CREATE TABLE IF NOT EXISTS library_snapshots (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        label TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+
+    -- Music.app playlist headers seen by the last sync, to skip unchanged track lists
+    CREATE TABLE IF NOT EXISTS playlist_sync_headers (
+        persistent_id TEXT PRIMARY KEY,
+        signature TEXT NOT NULL
+    );
+
+    -- Keyboard key ("1"-"9", "a"-"z") -> tag, shared by the UI, CLI, MIDI and HTTP surfaces
+    CREATE TABLE IF NOT EXISTS tag_shortcuts (
+        key TEXT PRIMARY KEY,
+        tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE
+    );
+
+    -- Tracks deleted in TagDeck but still in Music.app, so the sync doesn't re-import them
+    CREATE TABLE IF NOT EXISTS deleted_tracks (
+        persistent_id TEXT PRIMARY KEY,
+        deleted_at INTEGER NOT NULL
+    );
+"#;
+
+/// Indices for the hot lookups. Applied after the column migrations in `Database::new`,
+/// since older databases only gain some of these columns there. `tracks.persistent_id`
+/// and `playlists.persistent_id` need none: their UNIQUE constraints are indexed already.
+const DB_INDICES: &str = r#"
+    CREATE INDEX IF NOT EXISTS idx_tracks_missing ON tracks(missing);
+    CREATE INDEX IF NOT EXISTS idx_tracks_file_path ON tracks(file_path);
+    CREATE INDEX IF NOT EXISTS idx_tracks_date_added ON tracks(date_added);
+    CREATE INDEX IF NOT EXISTS idx_tracks_last_played ON tracks(last_played);
+
+    -- The (playlist_id, track_id) primary key can't serve "which playlists hold this track"
+    CREATE INDEX IF NOT EXISTS idx_playlist_tracks_track ON playlist_tracks(track_id, playlist_id);
+    -- Covers ordered playlist reads without touching the table
+    CREATE INDEX IF NOT EXISTS idx_playlist_tracks_order ON playlist_tracks(playlist_id, position, track_id);
+
+    CREATE INDEX IF NOT EXISTS idx_tags_group ON tags(group_id);
+    CREATE INDEX IF NOT EXISTS idx_track_history_track ON track_history(track_id, field, changed_at);
+    CREATE INDEX IF NOT EXISTS idx_cue_points_track ON cue_points(track_id, position_secs);
+    CREATE INDEX IF NOT EXISTS idx_listening_history_track ON listening_history(track_id, played_at);
 "#;
 
+/// Columns read into a `Track`, in the order `Database::row_to_track` expects.
+const TRACK_COLUMNS: &str = "id, persistent_id, file_path, artist, title, album,
+    comment_raw, grouping_raw, duration_secs, format, size_bytes, bit_rate, modified_date,
+    rating, date_added, bpm, missing, play_count, skip_count, last_played, loved, notes,
+    intro_bars, outro_bars, audio_health, color_label, offloaded, is_cloud";
+
+/// Camelot key number and letter at the start of `t.comment_raw` (see `import_export::camelot_key`).
+/// SQLite's CAST takes the leading integer; the letter is the character after it.
+pub(crate) const KEY_NUMBER_SQL: &str = "CAST(ltrim(t.comment_raw) AS INTEGER)";
+pub(crate) const KEY_LETTER_SQL: &str =
+    "upper(substr(ltrim(t.comment_raw), length(CAST(CAST(ltrim(t.comment_raw) AS INTEGER) AS TEXT)) + 1, 1))";
+
+/// Comment has no tags after the ` && ` delimiter.
+const UNTAGGED_SQL: &str = "(t.comment_raw IS NULL OR instr(t.comment_raw, ' && ') = 0
+    OR trim(replace(substr(t.comment_raw, instr(t.comment_raw, ' && ') + 4), ';', '')) = '')";
+
+/// A small connection pool: one writer plus a handful of read-only connections.
+/// WAL mode lets the readers see a consistent snapshot while a long batch write
+/// (sync, batch tagging) holds the writer, so `get_tracks` never queues behind it.
+pub struct DbPool {
+    writer: Mutex<Database>,
+    readers: Vec<Mutex<Database>>,
+    next_reader: AtomicUsize,
+}
+
+impl DbPool {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        // The writer runs schema setup and switches the file to WAL before any reader opens it.
+        let writer = Database::new(path)?;
+
+        let mut readers = Vec::with_capacity(READER_COUNT);
+        for _ in 0..READER_COUNT {
+            readers.push(Mutex::new(Database::open_reader(path)?));
+        }
+
+        Ok(Self {
+            writer: Mutex::new(writer),
+            readers,
+            next_reader: AtomicUsize::new(0),
+        })
+    }
+
+    /// Locks the writer connection. Anything that mutates the DB must go through here.
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, Database>> {
+        self.writer.lock()
+    }
+
+    /// Checks out a read-only connection. Prefers an idle reader and otherwise
+    /// waits on the next one in round-robin order.
+    pub fn read(&self) -> LockResult<MutexGuard<'_, Database>> {
+        for reader in &self.readers {
+            if let Ok(guard) = reader.try_lock() {
+                return Ok(guard);
+            }
+        }
+        let idx = self.next_reader.fetch_add(1, Ordering::Relaxed) % self.readers.len();
+        self.readers[idx].lock()
+    }
+}
+
+/// A track's fields that Music.app changes without touching `modification date`.
+pub struct TrackSyncState {
+    pub id: i64,
+    pub persistent_id: String,
+    pub rating: i64,
+    pub bpm: i64,
+    pub play_count: i64,
+    pub skip_count: i64,
+    pub loved: bool,
+}
+
+/// A track removed by `Database::delete_tracks`, with its playlist memberships as
+/// (playlist id, position), so `restore_deleted_tracks` can put it back as it was.
+#[derive(Debug, Clone)]
+pub struct DeletedTrack {
+    pub track: Track,
+    pub playlists: Vec<(i64, i64)>,
+}
+
+/// Tables with a `track_id` column, whose rows go with (or move with) their track.
+const TRACK_LINKED_TABLES: [&str; 7] = [
+    "playlist_tracks", "original_metadata", "comment_conflicts", "cue_points",
+    "track_history", "session_tracks", "listening_history",
+];
+
+/// What `Database::cleanup_orphans` removed, or would remove on a dry run.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanCleanup {
+    /// playlist_tracks rows whose track or playlist no longer exists
+    pub playlist_rows: usize,
+    /// Names of the unused, ungrouped tags
+    pub tags: Vec<String>,
+    /// File paths of the tracks that had no persistent ID
+    pub tracks: Vec<String>,
+}
+
+/// A playlist as stored locally, for diffing against Music.app.
+pub struct PlaylistSnapshotRow {
+    pub name: String,
+    pub is_folder: bool,
+    pub is_smart: bool,
+    pub parent_persistent_id: Option<String>,
+    /// Track persistent IDs in playlist order
+    pub track_ids: Vec<String>,
+}
+
 pub struct Database {
     conn: Connection,
 }
@@ -62,6 +319,12 @@ pub struct Database {
 impl Database {
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
         let conn = Connection::open(path)?;
+
+        // WAL allows concurrent readers while a write transaction is open.
+        // `journal_mode` returns the resulting mode as a row, so it must be queried rather than executed.
+        let _mode: String = conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+
         conn.execute_batch(DB_SCHEMA)?;
         
         // Explicitly ensure tag_groups exists because execute_batch might not create it if it stops early (though it shouldn't)
@@ -79,14 +342,47 @@ impl Database {
         let _ = conn.execute("ALTER TABLE tracks ADD COLUMN bpm INTEGER DEFAULT 0", []);
         let _ = conn.execute("ALTER TABLE playlists ADD COLUMN is_folder BOOLEAN DEFAULT 0", []);
         let _ = conn.execute("ALTER TABLE playlists ADD COLUMN parent_persistent_id TEXT", []);
+        let _ = conn.execute("ALTER TABLE playlists ADD COLUMN is_smart BOOLEAN DEFAULT 0", []);
         let _ = conn.execute("ALTER TABLE tracks ADD COLUMN missing BOOLEAN DEFAULT 0", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN play_count INTEGER DEFAULT 0", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN skip_count INTEGER DEFAULT 0", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN last_played INTEGER DEFAULT 0", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN loved BOOLEAN DEFAULT 0", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN notes TEXT", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN intro_bars INTEGER", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN outro_bars INTEGER", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN structure_analyzed_at INTEGER", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN audio_health TEXT", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN color_label TEXT", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN offloaded BOOLEAN DEFAULT 0", []);
+        let _ = conn.execute("ALTER TABLE tracks ADD COLUMN is_cloud BOOLEAN DEFAULT 0", []);
         
         // Add columns to existing tags table
         let _ = conn.execute("ALTER TABLE tags ADD COLUMN group_id INTEGER REFERENCES tag_groups(id) ON DELETE SET NULL", []);
-        
+        let _ = conn.execute("ALTER TABLE tags ADD COLUMN archived BOOLEAN DEFAULT 0", []);
+
+        conn.execute_batch(DB_INDICES)?;
+
+        Ok(Self { conn })
+    }
+
+    /// Opens a read-only connection to an existing database. Schema setup and
+    /// migrations are left to the writer opened via `Database::new`.
+    fn open_reader<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
         Ok(Self { conn })
     }
 
+    /// Confirms the database file accepts writes by taking (and releasing) a write lock.
+    pub fn check_writable(&self) -> Result<()> {
+        self.conn.execute_batch("BEGIN IMMEDIATE; ROLLBACK;")?;
+        Ok(())
+    }
+
     /// Returns a HashSet of all track persistent_ids in the DB.
     pub fn get_all_track_pids(&self) -> Result<std::collections::HashSet<String>> {
         let mut stmt = self.conn.prepare("SELECT persistent_id FROM tracks")?;
@@ -98,41 +394,74 @@ impl Database {
         Ok(set)
     }
 
-    /// Returns a HashMap of persistent_id -> (rating, bpm) for all tracks in the DB.
-    /// Used for efficient snapshot-based diffing against Music.app.
-    pub fn get_rating_bpm_snapshot(&self) -> Result<std::collections::HashMap<String, (i64, i64)>> {
-        let mut stmt = self.conn.prepare("SELECT persistent_id, rating, bpm FROM tracks")?;
-        let rows = stmt.query_map([], |row| {
-            Ok((
-                row.get::<_, String>(0)?,
-                row.get::<_, i64>(1)?,
-                row.get::<_, i64>(2)?,
-            ))
-        })?;
-        let mut map = std::collections::HashMap::new();
-        for row in rows {
-            let (pid, rating, bpm) = row?;
-            map.insert(pid, (rating, bpm));
+    /// Streams every track's Music.app-synced fields in persistent ID order, for merging
+    /// against the Music.app snapshot without loading the library into maps. BPM is
+    /// rounded the same way it is when sent to Music.app.
+    pub fn for_each_track_sync_state(&self, mut f: impl FnMut(TrackSyncState) -> Result<()>) -> Result<()> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, persistent_id, COALESCE(rating, 0), CAST(ROUND(COALESCE(bpm, 0)) AS INTEGER),
+                    COALESCE(play_count, 0), COALESCE(skip_count, 0), COALESCE(loved, 0)
+             FROM tracks WHERE persistent_id IS NOT NULL ORDER BY persistent_id"
+        )?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            f(TrackSyncState {
+                id: row.get(0)?,
+                persistent_id: row.get(1)?,
+                rating: row.get(2)?,
+                bpm: row.get(3)?,
+                play_count: row.get(4)?,
+                skip_count: row.get(5)?,
+                loved: row.get(6)?,
+            })?;
         }
-        Ok(map)
+        Ok(())
     }
 
     /// Updates only the rating and BPM for a track identified by persistent_id.
+    /// `bpm` comes from Music.app (whole numbers); a fractional BPM that rounds to it is kept.
     pub fn update_rating_bpm(&self, persistent_id: &str, rating: i64, bpm: i64) -> Result<()> {
+        if let Some(id) = self.get_track_id_by_persistent_id(persistent_id)? {
+            self.record_change(id, "rating", Some(rating.to_string()))?;
+            change_events::track_changed(id, "bpm");
+        }
         self.conn.execute(
-            "UPDATE tracks SET rating = ?1, bpm = ?2 WHERE persistent_id = ?3",
+            "UPDATE tracks SET rating = ?1,
+                bpm = CASE WHEN ROUND(COALESCE(bpm, 0)) = ?2 THEN bpm ELSE ?2 END
+             WHERE persistent_id = ?3",
             params![rating, bpm, persistent_id],
         )?;
         Ok(())
     }
 
+    /// Updates play statistics only. Plays are not edits, so nothing is recorded in history.
+    pub fn update_play_stats(&self, persistent_id: &str, play_count: i64, skip_count: i64, last_played: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE tracks SET play_count = ?1, skip_count = ?2,
+                last_played = CASE WHEN ?3 = 0 THEN last_played ELSE ?3 END
+             WHERE persistent_id = ?4",
+            params![play_count, skip_count, last_played, persistent_id],
+        )?;
+        if change_events::enabled() {
+            if let Some(id) = self.get_track_id_by_persistent_id(persistent_id)? {
+                for field in ["play_count", "skip_count", "last_played"] {
+                    change_events::track_changed(id, field);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Inserts or refreshes a track. `loved` is only taken on insert: not every library
+    /// source exposes it, so the sync snapshot diff keeps it current instead.
     pub fn insert_track(&self, track: &crate::models::Track) -> Result<()> {
         self.conn.execute(
             "INSERT INTO tracks (
                 persistent_id, file_path, artist, title, album, 
                 comment_raw, grouping_raw, duration_secs, format, 
-                size_bytes, bit_rate, modified_date, rating, date_added, bpm
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
+                size_bytes, bit_rate, modified_date, rating, date_added, bpm,
+                play_count, skip_count, last_played, loved, is_cloud
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)
             ON CONFLICT(persistent_id) DO UPDATE SET
                 file_path=CASE WHEN excluded.file_path = '' THEN tracks.file_path ELSE excluded.file_path END,
                 artist=excluded.artist,
@@ -147,7 +476,11 @@ impl Database {
                 modified_date=CASE WHEN excluded.modified_date = 0 THEN tracks.modified_date ELSE excluded.modified_date END,
                 rating=excluded.rating,
                 date_added=CASE WHEN excluded.date_added = 0 THEN tracks.date_added ELSE excluded.date_added END,
-                bpm=excluded.bpm
+                bpm=CASE WHEN ROUND(COALESCE(tracks.bpm, 0)) = ROUND(excluded.bpm) THEN tracks.bpm ELSE excluded.bpm END,
+                play_count=excluded.play_count,
+                skip_count=excluded.skip_count,
+                last_played=CASE WHEN excluded.last_played = 0 THEN tracks.last_played ELSE excluded.last_played END,
+                is_cloud=CASE WHEN excluded.file_path = '' AND tracks.file_path != '' THEN tracks.is_cloud ELSE excluded.is_cloud END
             ",
             params![
                 track.persistent_id,
@@ -164,42 +497,89 @@ impl Database {
                 track.modified_date,
                 track.rating,
                 track.date_added,
-                track.bpm
+                track.bpm,
+                track.play_count,
+                track.skip_count,
+                track.last_played,
+                track.loved,
+                track.is_cloud
             ],
         )?;
+        if change_events::enabled() {
+            if let Some(id) = self.get_track_id_by_persistent_id(&track.persistent_id)? {
+                change_events::track_replaced(id);
+            }
+        }
         Ok(())
     }
 
+    /// Maps a row selected with `TRACK_COLUMNS`.
+    fn row_to_track(row: &rusqlite::Row) -> rusqlite::Result<Track> {
+        Ok(Track {
+            id: row.get(0)?,
+            persistent_id: row.get(1)?,
+            file_path: row.get(2)?,
+            artist: row.get(3)?,
+            title: row.get(4)?,
+            album: row.get(5)?,
+            comment_raw: row.get(6)?,
+            grouping_raw: row.get(7)?,
+            duration_secs: row.get(8)?,
+            format: row.get(9)?,
+            size_bytes: row.get(10)?,
+            bit_rate: row.get(11)?,
+            modified_date: row.get(12)?,
+            rating: row.get(13)?,
+            date_added: row.get(14)?,
+            bpm: row.get(15)?,
+            missing: row.get(16).unwrap_or(false),
+            play_count: row.get::<_, Option<i64>>(17)?.unwrap_or(0),
+            skip_count: row.get::<_, Option<i64>>(18)?.unwrap_or(0),
+            last_played: row.get::<_, Option<i64>>(19)?.unwrap_or(0),
+            loved: row.get::<_, Option<bool>>(20)?.unwrap_or(false),
+            notes: row.get(21)?,
+            intro_bars: row.get(22)?,
+            outro_bars: row.get(23)?,
+            audio_health: row.get(24)?,
+            color_label: row.get::<_, Option<String>>(25)?.as_deref().and_then(crate::models::TrackColor::parse),
+            offloaded: row.get::<_, Option<bool>>(26)?.unwrap_or(false),
+            is_cloud: row.get::<_, Option<bool>>(27)?.unwrap_or(false),
+        })
+    }
+
     pub fn get_track(&self, id: i64) -> Result<Option<Track>> {
-        let mut stmt = self.conn.prepare("SELECT * FROM tracks WHERE id = ?1")?;
+        let mut stmt = self.conn.prepare(&format!("SELECT {} FROM tracks WHERE id = ?1", TRACK_COLUMNS))?;
         let mut rows = stmt.query(params![id])?;
 
         if let Some(row) = rows.next()? {
-            Ok(Some(Track {
-                id: row.get(0)?,
-                persistent_id: row.get(1)?,
-                file_path: row.get(2)?,
-                artist: row.get(3)?,
-                title: row.get(4)?,
-                album: row.get(5)?,
-                comment_raw: row.get(6)?,
-                grouping_raw: row.get(7)?,
-                duration_secs: row.get(8)?,
-                format: row.get(9)?,
-                size_bytes: row.get(10)?,
-                bit_rate: row.get(11)?,
-                modified_date: row.get(12)?,
-                rating: row.get(13)?,
-                date_added: row.get(14)?,
-                bpm: row.get(15)?,
-                missing: row.get(16).unwrap_or(false),
-            }))
+            Ok(Some(Self::row_to_track(row)?))
         } else {
             Ok(None)
         }
     }
 
+    /// Tracks matching a trusted SQL `condition` over the tracks table (aliased `t`).
+    fn get_tracks_where<P: rusqlite::Params>(&self, condition: &str, params: P) -> Result<Vec<Track>> {
+        let mut stmt = self.conn.prepare(&format!("SELECT {} FROM tracks t WHERE {}", TRACK_COLUMNS, condition))?;
+        let tracks = stmt.query_map(params, Self::row_to_track)?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(tracks)
+    }
+
+    pub fn get_track_by_path(&self, file_path: &str) -> Result<Option<Track>> {
+        let mut stmt = self.conn.prepare("SELECT id FROM tracks WHERE file_path = ?1")?;
+        let mut rows = stmt.query(params![file_path])?;
+
+        match rows.next()? {
+            Some(row) => self.get_track(row.get(0)?),
+            None => Ok(None),
+        }
+    }
+
     pub fn update_track(&self, track: &Track) -> Result<()> {
+        self.preserve_original(track.id, track.comment_raw.as_deref().unwrap_or(""))?;
+        self.adjust_tag_counts(track.id, track.comment_raw.as_deref().unwrap_or(""))?;
+        self.record_change(track.id, "comment_raw", track.comment_raw.clone())?;
         self.conn.execute(
             "UPDATE tracks SET
                 comment_raw = ?1,
@@ -220,43 +600,53 @@ impl Database {
                  track.id
              ]
         )?;
+        change_events::track_changed(track.id, "grouping_raw");
+        change_events::track_changed(track.id, "modified_date");
         Ok(())
     }
 
-    /// Returns a snapshot of all playlists in the DB for diffing.
-    /// Maps persistent_id → (name, is_folder, parent_persistent_id, vec of track persistent_ids)
-    pub fn get_playlist_snapshot(&self) -> Result<std::collections::HashMap<String, (String, bool, Option<String>, Vec<String>)>> {
-        use std::collections::HashMap;
-
-        let mut map: HashMap<String, (String, bool, Option<String>, Vec<String>)> = HashMap::new();
-
+    /// Streams each playlist with its track persistent IDs (in playlist order) from a
+    /// single query, one playlist at a time.
+    pub fn for_each_playlist_snapshot(&self, mut f: impl FnMut(String, PlaylistSnapshotRow) -> Result<()>) -> Result<()> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, persistent_id, parent_persistent_id, name, is_folder FROM playlists"
+            "SELECT p.id, p.persistent_id, p.parent_persistent_id, p.name, p.is_folder, COALESCE(p.is_smart, 0), t.persistent_id
+             FROM playlists p
+             LEFT JOIN playlist_tracks pt ON pt.playlist_id = p.id
+             LEFT JOIN tracks t ON t.id = pt.track_id
+             ORDER BY p.id, pt.position ASC"
         )?;
-        let rows = stmt.query_map([], |row| {
+        let mut rows = stmt.query([])?;
+        let mut current: Option<(i64, String, PlaylistSnapshotRow)> = None;
+        while let Some(row) = rows.next()? {
             let id: i64 = row.get(0)?;
-            let pid: String = row.get(1)?;
-            let parent_pid: Option<String> = row.get(2)?;
-            let name: String = row.get(3)?;
-            let is_folder: bool = row.get(4)?;
-            Ok((id, pid, parent_pid, name, is_folder))
-        })?.collect::<Result<Vec<_>, rusqlite::Error>>()?;
-
-        for (db_id, pid, parent_pid, name, is_folder) in &rows {
-            // Get track persistent IDs for this playlist
-            let mut track_stmt = self.conn.prepare(
-                "SELECT t.persistent_id FROM playlist_tracks pt 
-                 JOIN tracks t ON t.id = pt.track_id 
-                 WHERE pt.playlist_id = ?1 
-                 ORDER BY pt.position ASC"
-            )?;
-            let track_pids = track_stmt.query_map(params![db_id], |row| row.get::<_, String>(0))?
-                .collect::<Result<Vec<_>, rusqlite::Error>>()?;
-
-            map.insert(pid.clone(), (name.clone(), *is_folder, parent_pid.clone(), track_pids));
+            if current.as_ref().map(|(current_id, _, _)| *current_id) != Some(id) {
+                if let Some((_, pid, playlist)) = current.take() {
+                    f(pid, playlist)?;
+                }
+                current = Some((id, row.get(1)?, PlaylistSnapshotRow {
+                    name: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                    is_folder: row.get(4)?,
+                    is_smart: row.get(5)?,
+                    parent_persistent_id: row.get(2)?,
+                    track_ids: Vec::new(),
+                }));
+            }
+            if let (Some((_, _, playlist)), Some(track_pid)) = (current.as_mut(), row.get::<_, Option<String>>(6)?) {
+                playlist.track_ids.push(track_pid);
+            }
         }
+        if let Some((_, pid, playlist)) = current {
+            f(pid, playlist)?;
+        }
+        Ok(())
+    }
 
-        Ok(map)
+    /// Persistent IDs of every playlist.
+    pub fn get_playlist_persistent_ids(&self) -> Result<std::collections::HashSet<String>> {
+        let mut stmt = self.conn.prepare("SELECT persistent_id FROM playlists")?;
+        let pids = stmt.query_map([], |row| row.get(0))?
+            .collect::<Result<std::collections::HashSet<String>, _>>()?;
+        Ok(pids)
     }
 
     /// Removes playlists from the DB that are no longer present in Music.app.
@@ -281,6 +671,7 @@ impl Database {
                     "DELETE FROM playlist_tracks WHERE playlist_id = ?1",
                     params![id],
                 )?;
+                change_events::playlist_changed(id);
             }
 
             self.conn.execute(
@@ -292,7 +683,7 @@ impl Database {
     }
 
     pub fn get_playlists(&self) -> Result<Vec<crate::models::Playlist>> {
-        let mut stmt = self.conn.prepare("SELECT id, persistent_id, parent_persistent_id, name, is_folder FROM playlists WHERE name != 'Music' ORDER BY is_folder DESC, name ASC")?;
+        let mut stmt = self.conn.prepare("SELECT id, persistent_id, parent_persistent_id, name, is_folder, COALESCE(is_smart, 0) FROM playlists WHERE name != 'Music' ORDER BY is_folder DESC, name ASC")?;
         let playlists = stmt.query_map([], |row| {
             Ok(crate::models::Playlist {
                 id: row.get(0)?,
@@ -301,6 +692,7 @@ impl Database {
                 name: row.get(3)?,
                 is_folder: row.get(4)?,
                 track_ids: None, // Not loaded by default
+                is_smart: row.get(5)?,
             })
         })?.collect::<Result<Vec<_>, rusqlite::Error>>()?;
         Ok(playlists)
@@ -315,6 +707,22 @@ impl Database {
         Ok(ids)
     }
 
+    /// Track IDs of a playlist ordered by `order_by`, a trusted SQL ORDER BY list over
+    /// `t` (tracks); ties keep their current playlist position.
+    pub fn get_playlist_track_ids_ordered(&self, playlist_id: i64, order_by: &str) -> Result<Vec<i64>> {
+        let sql = format!(
+            "SELECT pt.track_id FROM playlist_tracks pt
+             JOIN tracks t ON t.id = pt.track_id
+             WHERE pt.playlist_id = ?1
+             ORDER BY {}, pt.position ASC",
+            order_by
+        );
+        let mut stmt = self.conn.prepare(&sql)?;
+        let ids = stmt.query_map(params![playlist_id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, rusqlite::Error>>()?;
+        Ok(ids)
+    }
+
     pub fn add_track_to_playlist_db(&self, playlist_id: i64, track_id: i64) -> Result<()> {
         // Get max position
         let max_pos: Option<i64> = self.conn.query_row(
@@ -330,6 +738,37 @@ impl Database {
             "INSERT OR IGNORE INTO playlist_tracks (playlist_id, track_id, position) VALUES (?1, ?2, ?3)",
             params![playlist_id, track_id, new_pos]
         )?;
+        change_events::playlist_changed(playlist_id);
+        Ok(())
+    }
+
+    /// Inserts or refreshes a batch of tracks in one transaction; nothing is written if any fails.
+    pub fn insert_tracks(&self, tracks: &[Track]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for track in tracks {
+            self.insert_track(track)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Saves the comments (and groupings) of a batch of tracks in one transaction.
+    pub fn update_tracks(&self, tracks: &[Track]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for track in tracks {
+            self.update_track(track)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Inserts or refreshes a set of playlists (with their track lists) in one transaction.
+    pub fn insert_playlists(&self, playlists: &[crate::models::Playlist]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for playlist in playlists {
+            self.insert_playlist(playlist)?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
@@ -340,9 +779,10 @@ impl Database {
         // For simplicity:
         
         self.conn.execute(
-            "INSERT INTO playlists (persistent_id, parent_persistent_id, name, is_folder) VALUES (?1, ?2, ?3, ?4)
-             ON CONFLICT(persistent_id) DO UPDATE SET name=excluded.name, is_folder=excluded.is_folder, parent_persistent_id=excluded.parent_persistent_id",
-            params![playlist.persistent_id, playlist.parent_persistent_id, playlist.name, playlist.is_folder],
+            "INSERT INTO playlists (persistent_id, parent_persistent_id, name, is_folder, is_smart) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(persistent_id) DO UPDATE SET name=excluded.name, is_folder=excluded.is_folder,
+                parent_persistent_id=excluded.parent_persistent_id, is_smart=excluded.is_smart",
+            params![playlist.persistent_id, playlist.parent_persistent_id, playlist.name, playlist.is_folder, playlist.is_smart],
         )?;
 
         let playlist_db_id: i64 = self.conn.query_row(
@@ -368,7 +808,8 @@ impl Database {
                 let _ = stmt.execute(params![playlist_db_id, pid, index as i64]);
             }
         }
-        
+        change_events::playlist_changed(playlist_db_id);
+
         Ok(())
     }
 
@@ -381,6 +822,33 @@ impl Database {
         Ok(pid)
     }
 
+    pub fn get_track_id_by_persistent_id(&self, persistent_id: &str) -> Result<Option<i64>> {
+        let mut stmt = self.conn.prepare("SELECT id FROM tracks WHERE persistent_id = ?1")?;
+        let mut rows = stmt.query(params![persistent_id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_playlist_parent(&self, id: i64, parent_persistent_id: Option<&str>) -> Result<()> {
+        self.conn.execute(
+            "UPDATE playlists SET parent_persistent_id = ?1 WHERE id = ?2",
+            params![parent_persistent_id, id],
+        )?;
+        change_events::playlist_changed(id);
+        Ok(())
+    }
+
+    pub fn is_playlist_smart(&self, id: i64) -> Result<bool> {
+        let smart: bool = self.conn.query_row(
+            "SELECT COALESCE(is_smart, 0) FROM playlists WHERE id = ?1",
+            params![id],
+            |row| row.get(0)
+        )?;
+        Ok(smart)
+    }
+
     pub fn get_playlist_persistent_id(&self, id: i64) -> Result<String> {
         let pid: String = self.conn.query_row(
             "SELECT persistent_id FROM playlists WHERE id = ?1",
@@ -390,7 +858,19 @@ impl Database {
         Ok(pid)
     }
 
+    pub fn get_playlist_id_by_persistent_id(&self, persistent_id: &str) -> Result<Option<i64>> {
+        let mut stmt = self.conn.prepare("SELECT id FROM playlists WHERE persistent_id = ?1")?;
+        let mut rows = stmt.query(params![persistent_id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
     pub fn update_track_metadata(&self, id: i64, comment: &str) -> Result<()> {
+        self.preserve_original(id, comment)?;
+        self.adjust_tag_counts(id, comment)?;
+        self.record_change(id, "comment_raw", Some(comment.to_string()))?;
         self.conn.execute(
             "UPDATE tracks SET comment_raw = ?1 WHERE id = ?2",
             params![comment, id],
@@ -398,7 +878,46 @@ impl Database {
         Ok(())
     }
 
+    /// Sets the grouping of a batch of tracks in one transaction. The first change vaults
+    /// the track's original comment and grouping, as comment edits do.
+    pub fn update_track_groupings(&self, updates: &[(i64, String)]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (id, grouping) in updates {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO original_metadata (track_id, comment, grouping, saved_at)
+                 SELECT id, comment_raw, grouping_raw, ?2 FROM tracks
+                 WHERE id = ?1 AND COALESCE(grouping_raw, '') != ?3",
+                params![id, chrono::Utc::now().timestamp(), grouping],
+            )?;
+            self.record_change(*id, "grouping_raw", Some(grouping.clone()))?;
+            self.conn.execute("UPDATE tracks SET grouping_raw = ?1 WHERE id = ?2", params![grouping, id])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Empty notes are stored as NULL.
+    pub fn update_track_notes(&self, id: i64, notes: Option<&str>) -> Result<()> {
+        let notes = notes.map(str::trim).filter(|n| !n.is_empty());
+        self.record_change(id, "notes", notes.map(str::to_string))?;
+        self.conn.execute(
+            "UPDATE tracks SET notes = ?1 WHERE id = ?2",
+            params![notes, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_track_loved(&self, id: i64, loved: bool) -> Result<()> {
+        self.record_change(id, "loved", Some(loved.to_string()))?;
+        self.conn.execute(
+            "UPDATE tracks SET loved = ?1 WHERE id = ?2",
+            params![loved, id],
+        )?;
+        Ok(())
+    }
+
     pub fn update_track_rating(&self, id: i64, rating: u32) -> Result<()> {
+        self.record_change(id, "rating", Some(rating.to_string()))?;
         self.conn.execute(
             "UPDATE tracks SET rating = ?1 WHERE id = ?2",
             params![rating, id],
@@ -406,6 +925,17 @@ impl Database {
         Ok(())
     }
 
+    /// Sets a track's play count by hand (a transfer between duplicates), unlike
+    /// `update_play_stats` which mirrors plays from Music.app.
+    pub fn update_track_play_count(&self, id: i64, play_count: i64) -> Result<()> {
+        self.record_change(id, "play_count", Some(play_count.to_string()))?;
+        self.conn.execute(
+            "UPDATE tracks SET play_count = ?1 WHERE id = ?2",
+            params![play_count, id],
+        )?;
+        Ok(())
+    }
+
     /// Updates track info fields (title, artist, album, bpm, comment_raw) in the database.
     /// Only updates fields that are Some; leaves existing values for None fields.
     pub fn update_track_info(
@@ -414,7 +944,7 @@ impl Database {
         title: Option<&str>,
         artist: Option<&str>,
         album: Option<&str>,
-        bpm: Option<i64>,
+        bpm: Option<f64>,
         comment_raw: Option<&str>,
     ) -> Result<()> {
         let mut sets = Vec::new();
@@ -437,6 +967,9 @@ impl Database {
             params_vec.push(Box::new(b));
         }
         if let Some(c) = comment_raw {
+            self.preserve_original(id, c)?;
+            self.adjust_tag_counts(id, c)?;
+            self.record_change(id, "comment_raw", Some(c.to_string()))?;
             sets.push("comment_raw = ?");
             params_vec.push(Box::new(c.to_string()));
         }
@@ -457,38 +990,106 @@ impl Database {
 
         let param_refs: Vec<&dyn rusqlite::types::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
         self.conn.execute(&sql, param_refs.as_slice())?;
+        for (field, set) in [("title", title.is_some()), ("artist", artist.is_some()), ("album", album.is_some()), ("bpm", bpm.is_some())] {
+            if set {
+                change_events::track_changed(id, field);
+            }
+        }
+        Ok(())
+    }
+
+    /// Tracks without any TagDeck tags, newest first. With `added_since`, only tracks added after it.
+    pub fn get_untagged_tracks(&self, added_since: Option<i64>) -> Result<Vec<Track>> {
+        self.get_tracks_where(
+            &format!("{} AND COALESCE(t.date_added, 0) >= ?1 ORDER BY t.date_added DESC", UNTAGGED_SQL),
+            params![added_since.unwrap_or(0)],
+        )
+    }
+
+    pub fn get_tracks_without_bpm(&self) -> Result<Vec<Track>> {
+        self.get_tracks_where("COALESCE(t.bpm, 0) <= 0 ORDER BY t.artist, t.title", [])
+    }
+
+    /// Tracks `analyze_track_structure` has not looked at yet. Tracks it failed on count as
+    /// looked at, so they are not decoded again on every run.
+    pub fn get_tracks_without_structure(&self) -> Result<Vec<Track>> {
+        self.get_tracks_where(
+            "t.structure_analyzed_at IS NULL AND COALESCE(t.missing, 0) = 0 AND COALESCE(t.is_cloud, 0) = 0 ORDER BY t.id",
+            [],
+        )
+    }
+
+    /// Stores `(id, intro_bars, outro_bars)` results of the intro/outro analysis, and marks
+    /// the `failed` tracks as analyzed without a result.
+    pub fn update_track_structure(&self, results: &[(i64, i64, i64)], failed: &[i64], analyzed_at: i64) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (id, intro_bars, outro_bars) in results {
+            self.conn.execute(
+                "UPDATE tracks SET intro_bars = ?1, outro_bars = ?2, structure_analyzed_at = ?3 WHERE id = ?4",
+                params![intro_bars, outro_bars, analyzed_at, id],
+            )?;
+            change_events::track_changed(*id, "intro_bars");
+            change_events::track_changed(*id, "outro_bars");
+        }
+        for id in failed {
+            self.conn.execute(
+                "UPDATE tracks SET structure_analyzed_at = ?1 WHERE id = ?2",
+                params![analyzed_at, id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Sets (or with `None` clears) the color label of each track.
+    pub fn update_track_colors(&self, colors: &[(i64, Option<crate::models::TrackColor>)]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (id, color) in colors {
+            let color = color.map(|c| c.as_str());
+            self.record_change(*id, "color_label", color.map(str::to_string))?;
+            self.conn.execute("UPDATE tracks SET color_label = ?1 WHERE id = ?2", params![color, id])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Sets `(id, bpm)` pairs in one transaction.
+    pub fn update_track_bpms(&self, bpms: &[(i64, f64)]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (id, bpm) in bpms {
+            self.conn.execute("UPDATE tracks SET bpm = ?1 WHERE id = ?2", params![bpm, id])?;
+            change_events::track_changed(*id, "bpm");
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Stores `(id, status)` verdicts of `verify_audio_files`.
+    pub fn update_audio_health(&self, results: &[(i64, &str)]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for (id, status) in results {
+            self.conn.execute("UPDATE tracks SET audio_health = ?1 WHERE id = ?2", params![status, id])?;
+            change_events::track_changed(*id, "audio_health");
+        }
+        tx.commit()?;
         Ok(())
     }
 
+    /// Tracks whose comment does not start with a Camelot key.
+    pub fn get_tracks_without_key(&self) -> Result<Vec<Track>> {
+        self.get_tracks_where(
+            &format!(
+                "NOT (COALESCE({n}, 0) BETWEEN 1 AND 12 AND COALESCE({l}, '') IN ('A', 'B')) ORDER BY t.artist, t.title",
+                n = KEY_NUMBER_SQL, l = KEY_LETTER_SQL
+            ),
+            [],
+        )
+    }
+
     pub fn get_all_tracks(&self) -> Result<Vec<crate::models::Track>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, persistent_id, file_path, artist, title, album, 
-             comment_raw, grouping_raw, duration_secs, format, size_bytes, bit_rate, modified_date,
-             rating, date_added, bpm, missing
-             FROM tracks", 
-        )?;
+        let mut stmt = self.conn.prepare(&format!("SELECT {} FROM tracks", TRACK_COLUMNS))?;
 
-        let track_iter = stmt.query_map([], |row| {
-            Ok(crate::models::Track {
-                id: row.get(0)?,
-                persistent_id: row.get(1)?,
-                file_path: row.get(2)?,
-                artist: row.get(3)?,
-                title: row.get(4)?,
-                album: row.get(5)?,
-                comment_raw: row.get(6)?,
-                grouping_raw: row.get(7)?,
-                duration_secs: row.get(8)?,
-                format: row.get(9)?,
-                size_bytes: row.get(10)?,
-                bit_rate: row.get(11)?,
-                modified_date: row.get(12)?,
-                rating: row.get(13)?,
-                date_added: row.get(14)?,
-                bpm: row.get(15)?,
-                missing: row.get(16).unwrap_or(false),
-            })
-        })?;
+        let track_iter = stmt.query_map([], Self::row_to_track)?;
 
         let mut tracks = Vec::new();
         for track in track_iter {
@@ -502,6 +1103,7 @@ impl Database {
             "DELETE FROM playlist_tracks WHERE playlist_id = ?1 AND track_id = ?2",
             params![playlist_id, track_id],
         )?;
+        change_events::playlist_changed(playlist_id);
         Ok(())
     }
 
@@ -521,6 +1123,7 @@ impl Database {
                 params![i as i64, playlist_id, tid],
             )?;
         }
+        change_events::playlist_changed(playlist_id);
         Ok(())
     }
 
@@ -533,13 +1136,15 @@ impl Database {
                 params![i as i64, playlist_id, tid],
             )?;
         }
+        change_events::playlist_changed(playlist_id);
         Ok(())
     }
 
-    /// Returns all playlists that contain the given track, with playlist id, persistent_id, and name.
-    pub fn get_playlists_for_track(&self, track_id: i64) -> Result<Vec<(i64, String, String)>> {
+    /// Returns all playlists that contain the given track, with playlist id, persistent_id, name
+    /// and whether it is a smart playlist.
+    pub fn get_playlists_for_track(&self, track_id: i64) -> Result<Vec<(i64, String, String, bool)>> {
         let mut stmt = self.conn.prepare(
-            "SELECT p.id, p.persistent_id, p.name 
+            "SELECT p.id, p.persistent_id, p.name, COALESCE(p.is_smart, 0)
              FROM playlist_tracks pt
              JOIN playlists p ON p.id = pt.playlist_id
              WHERE pt.track_id = ?1 AND p.name != 'Music'
@@ -550,11 +1155,33 @@ impl Database {
                 row.get::<_, i64>(0)?,
                 row.get::<_, String>(1)?,
                 row.get::<_, String>(2)?,
+                row.get::<_, bool>(3)?,
             ))
         })?.collect::<Result<Vec<_>, rusqlite::Error>>()?;
         Ok(rows)
     }
 
+    /// Returns track_id -> playlist names for every track, excluding the "Music" master library.
+    pub fn get_playlist_names_by_track(&self) -> Result<std::collections::HashMap<i64, Vec<String>>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT pt.track_id, p.name
+             FROM playlist_tracks pt
+             JOIN playlists p ON p.id = pt.playlist_id
+             WHERE p.name != 'Music' AND p.is_folder = 0
+             ORDER BY p.name ASC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut map: std::collections::HashMap<i64, Vec<String>> = std::collections::HashMap::new();
+        for row in rows {
+            let (track_id, name) = row?;
+            map.entry(track_id).or_default().push(name);
+        }
+        Ok(map)
+    }
+
     pub fn get_track_path(&self, id: i64) -> Result<String> {
         self.conn.query_row(
             "SELECT file_path FROM tracks WHERE id = ?1",
@@ -568,6 +1195,7 @@ impl Database {
             "UPDATE tracks SET file_path = ?1 WHERE id = ?2",
             params![path, id],
         )?;
+        change_events::track_changed(id, "file_path");
         Ok(())
     }
 
@@ -576,6 +1204,16 @@ impl Database {
             "UPDATE tracks SET missing = ?1 WHERE id = ?2",
             params![missing, id],
         )?;
+        change_events::track_changed(id, "missing");
+        Ok(())
+    }
+
+    pub fn set_track_offloaded(&self, id: i64, offloaded: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE tracks SET offloaded = ?1 WHERE id = ?2",
+            params![offloaded, id],
+        )?;
+        change_events::track_changed(id, "offloaded");
         Ok(())
     }
 
@@ -597,6 +1235,15 @@ impl Database {
                     "DELETE FROM playlist_tracks WHERE track_id = ?1",
                     params![id],
                 )?;
+                self.conn.execute(
+                    "DELETE FROM cue_points WHERE track_id = ?1",
+                    params![id],
+                )?;
+                self.conn.execute(
+                    "DELETE FROM listening_history WHERE track_id = ?1",
+                    params![id],
+                )?;
+                change_events::track_removed(id);
             }
 
             let rows = self.conn.execute(
@@ -608,17 +1255,229 @@ impl Database {
         Ok(deleted)
     }
 
-    // TAG GROUP METHODS
+    /// Moves a track to a new persistent ID, e.g. after Music.app re-imported its file. The
+    /// row keeps its id, so notes, cue points, history and playlist rows stay attached. If
+    /// a row with the new ID was imported already, its related rows are merged into the old
+    /// one (existing ones win) and it is dropped. Returns the track id.
+    pub fn remap_persistent_id(&self, old: &str, new: &str) -> Result<i64> {
+        let tx = self.conn.unchecked_transaction()?;
+        let id = self.get_track_id_by_persistent_id(old)?
+            .ok_or_else(|| anyhow::anyhow!("Track {} not found", old))?;
+        if let Some(duplicate) = self.get_track_id_by_persistent_id(new)?.filter(|d| *d != id) {
+            for table in TRACK_LINKED_TABLES {
+                self.conn.execute(
+                    &format!("UPDATE OR IGNORE {} SET track_id = ?1 WHERE track_id = ?2", table),
+                    params![id, duplicate],
+                )?;
+                self.conn.execute(&format!("DELETE FROM {} WHERE track_id = ?1", table), params![duplicate])?;
+            }
+            self.conn.execute("DELETE FROM tracks WHERE id = ?1", params![duplicate])?;
+            change_events::track_removed(duplicate);
+        }
+        self.conn.execute("UPDATE tracks SET persistent_id = ?1 WHERE id = ?2", params![new, id])?;
+        change_events::track_changed(id, "persistent_id");
+        tx.commit()?;
+        Ok(id)
+    }
 
-    pub fn get_tag_groups(&self) -> Result<Vec<crate::models::TagGroup>> {
-        let mut stmt = self.conn.prepare("SELECT id, name, position FROM tag_groups ORDER BY position ASC")?;
-        let group_iter = stmt.query_map([], |row| {
-            Ok(crate::models::TagGroup {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                position: row.get(2)?,
-            })
-        })?;
+    /// Deletes tracks and their playlist memberships in one transaction and returns what was
+    /// removed. Cue points, history and notes stay keyed by the track id so a restore brings
+    /// them back. The persistent IDs are remembered so the sync skips them while they are
+    /// still in Music.app.
+    pub fn delete_tracks(&self, ids: &[i64]) -> Result<Vec<DeletedTrack>> {
+        let tx = self.conn.unchecked_transaction()?;
+        let now = chrono::Utc::now().timestamp();
+        let mut deleted = Vec::new();
+        for &id in ids {
+            let Some(track) = self.get_track(id)? else { continue };
+            let mut stmt = self.conn.prepare(
+                "SELECT playlist_id, position FROM playlist_tracks WHERE track_id = ?1"
+            )?;
+            let playlists = stmt.query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<(i64, i64)>, rusqlite::Error>>()?;
+
+            self.move_tag_counts(track.comment_raw.as_deref().unwrap_or(""), "")?;
+            self.conn.execute("DELETE FROM playlist_tracks WHERE track_id = ?1", params![id])?;
+            self.conn.execute("DELETE FROM tracks WHERE id = ?1", params![id])?;
+            self.conn.execute(
+                "INSERT OR REPLACE INTO deleted_tracks (persistent_id, deleted_at) VALUES (?1, ?2)",
+                params![track.persistent_id, now],
+            )?;
+            change_events::track_removed(id);
+            for (playlist_id, _) in &playlists {
+                change_events::playlist_changed(*playlist_id);
+            }
+            deleted.push(DeletedTrack { track, playlists });
+        }
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// Puts tracks removed by `delete_tracks` back under their old ids and playlist positions.
+    pub fn restore_deleted_tracks(&self, tracks: &[DeletedTrack]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for DeletedTrack { track: t, playlists } in tracks {
+            // From whatever now holds the id (normally nothing) to the restored comment
+            let current: Option<String> = match self.conn.query_row(
+                "SELECT comment_raw FROM tracks WHERE id = ?1",
+                params![t.id],
+                |row| row.get(0),
+            ) {
+                Ok(v) => v,
+                Err(rusqlite::Error::QueryReturnedNoRows) => None,
+                Err(e) => return Err(e.into()),
+            };
+            self.move_tag_counts(current.as_deref().unwrap_or(""), t.comment_raw.as_deref().unwrap_or(""))?;
+            self.conn.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO tracks ({}) VALUES
+                     (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28)",
+                    TRACK_COLUMNS
+                ),
+                params![
+                    t.id, t.persistent_id, t.file_path, t.artist, t.title, t.album,
+                    t.comment_raw, t.grouping_raw, t.duration_secs, t.format, t.size_bytes, t.bit_rate,
+                    t.modified_date, t.rating, t.date_added, t.bpm, t.missing, t.play_count,
+                    t.skip_count, t.last_played, t.loved, t.notes, t.intro_bars, t.outro_bars,
+                    t.audio_health, t.color_label.map(|c| c.as_str()), t.offloaded,
+                    t.is_cloud
+                ],
+            )?;
+            change_events::track_replaced(t.id);
+            for (playlist_id, position) in playlists {
+                self.conn.execute(
+                    "INSERT OR IGNORE INTO playlist_tracks (playlist_id, track_id, position) VALUES (?1, ?2, ?3)",
+                    params![playlist_id, t.id, position],
+                )?;
+                change_events::playlist_changed(*playlist_id);
+            }
+            self.conn.execute("DELETE FROM deleted_tracks WHERE persistent_id = ?1", params![t.persistent_id])?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Persistent IDs of tracks deleted in TagDeck only.
+    pub fn get_deleted_track_pids(&self) -> Result<std::collections::HashSet<String>> {
+        let mut stmt = self.conn.prepare("SELECT persistent_id FROM deleted_tracks")?;
+        let pids = stmt.query_map([], |row| row.get(0))?
+            .collect::<Result<_, rusqlite::Error>>()?;
+        Ok(pids)
+    }
+
+    /// Drops remembered deletions, e.g. once the track is gone from Music.app as well.
+    pub fn forget_deleted_tracks(&self, pids: &[String]) -> Result<()> {
+        for pid in pids {
+            self.conn.execute("DELETE FROM deleted_tracks WHERE persistent_id = ?1", params![pid])?;
+        }
+        Ok(())
+    }
+
+    /// Removes cruft left by interrupted imports and old deletions, in one transaction:
+    /// playlist rows whose track or playlist is gone, tags no track uses that aren't in a
+    /// group or archived, and tracks without a persistent ID (with every row in
+    /// `TRACK_LINKED_TABLES`). Returns what was removed; with `dry_run` the transaction is
+    /// rolled back, so it returns what would be.
+    pub fn cleanup_orphans(&self, dry_run: bool) -> Result<OrphanCleanup> {
+        let tx = self.conn.unchecked_transaction()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, file_path FROM tracks WHERE persistent_id IS NULL OR persistent_id = ''"
+        )?;
+        let tracks = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        for (id, _) in &tracks {
+            for table in TRACK_LINKED_TABLES {
+                self.conn.execute(&format!("DELETE FROM {} WHERE track_id = ?1", table), params![id])?;
+            }
+            self.conn.execute("DELETE FROM tracks WHERE id = ?1", params![id])?;
+            if !dry_run {
+                change_events::track_removed(*id);
+            }
+        }
+
+        let playlist_rows = self.conn.execute(
+            "DELETE FROM playlist_tracks
+             WHERE track_id NOT IN (SELECT id FROM tracks) OR playlist_id NOT IN (SELECT id FROM playlists)",
+            [],
+        )?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT name FROM tags
+             WHERE COALESCE(usage_count, 0) <= 0 AND group_id IS NULL AND archived = 0 ORDER BY name"
+        )?;
+        let tags = stmt.query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+        self.conn.execute(
+            "DELETE FROM tag_shortcuts WHERE tag_id IN
+                (SELECT id FROM tags WHERE COALESCE(usage_count, 0) <= 0 AND group_id IS NULL AND archived = 0)",
+            [],
+        )?;
+        self.conn.execute(
+            "DELETE FROM tags WHERE COALESCE(usage_count, 0) <= 0 AND group_id IS NULL AND archived = 0",
+            [],
+        )?;
+
+        if !dry_run {
+            tx.commit()?;
+        }
+        Ok(OrphanCleanup {
+            playlist_rows,
+            tags,
+            tracks: tracks.into_iter().map(|(_, path)| path).collect(),
+        })
+    }
+
+    /// Runs `PRAGMA integrity_check`. Returns the problems it found; empty when the
+    /// database is intact.
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(rows.into_iter().filter(|r| r != "ok").collect())
+    }
+
+    /// Refreshes the query planner statistics.
+    pub fn analyze(&self) -> Result<()> {
+        self.conn.execute_batch("ANALYZE")?;
+        Ok(())
+    }
+
+    /// Rebuilds the file to reclaim the pages freed by deletions, then folds the WAL back
+    /// into it so the space is actually given back.
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM")?;
+        let _: (i64, i64, i64) = self.conn.query_row(
+            "PRAGMA wal_checkpoint(TRUNCATE)",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        Ok(())
+    }
+
+    /// Size on disk of the database file plus its WAL, in bytes.
+    pub fn file_size(&self) -> u64 {
+        let Some(path) = self.conn.path().filter(|p| !p.is_empty()) else {
+            return 0;
+        };
+        [path.to_string(), format!("{}-wal", path)]
+            .iter()
+            .filter_map(|p| std::fs::metadata(p).ok())
+            .map(|m| m.len())
+            .sum()
+    }
+
+    // TAG GROUP METHODS
+
+    pub fn get_tag_groups(&self) -> Result<Vec<crate::models::TagGroup>> {
+        let mut stmt = self.conn.prepare("SELECT id, name, position FROM tag_groups ORDER BY position ASC")?;
+        let group_iter = stmt.query_map([], |row| {
+            Ok(crate::models::TagGroup {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                position: row.get(2)?,
+            })
+        })?;
 
         let mut groups = Vec::new();
         for group in group_iter {
@@ -659,10 +1518,65 @@ impl Database {
         Ok(())
     }
 
+    // TAG SET METHODS
+
+    fn row_to_tag_set(row: &rusqlite::Row) -> rusqlite::Result<crate::models::TagSet> {
+        let tags: Option<String> = row.get(2)?;
+        Ok(crate::models::TagSet {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            tags: tags.unwrap_or_default().split(';')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+        })
+    }
+
+    pub fn get_tag_sets(&self) -> Result<Vec<crate::models::TagSet>> {
+        let mut stmt = self.conn.prepare("SELECT id, name, tags FROM tag_sets ORDER BY name COLLATE NOCASE ASC")?;
+        let sets = stmt.query_map([], Self::row_to_tag_set)?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(sets)
+    }
+
+    pub fn get_tag_set(&self, id: i64) -> Result<Option<crate::models::TagSet>> {
+        let mut stmt = self.conn.prepare("SELECT id, name, tags FROM tag_sets WHERE id = ?1")?;
+        let mut rows = stmt.query(params![id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(Self::row_to_tag_set(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn create_tag_set(&self, name: &str, tags: &[String]) -> Result<crate::models::TagSet> {
+        self.conn.execute(
+            "INSERT INTO tag_sets (name, tags) VALUES (?1, ?2)",
+            params![name, tags.join("; ")],
+        )?;
+        Ok(crate::models::TagSet {
+            id: self.conn.last_insert_rowid(),
+            name: name.to_string(),
+            tags: tags.to_vec(),
+        })
+    }
+
+    pub fn update_tag_set(&self, id: i64, name: &str, tags: &[String]) -> Result<()> {
+        self.conn.execute(
+            "UPDATE tag_sets SET name = ?1, tags = ?2 WHERE id = ?3",
+            params![name, tags.join("; "), id],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_tag_set(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM tag_sets WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
     // TAG METHODS
 
     pub fn get_all_tags(&self) -> Result<Vec<crate::models::Tag>> {
-        let mut stmt = self.conn.prepare("SELECT id, name, usage_count, group_id FROM tags ORDER BY name ASC")?;
+        let mut stmt = self.conn.prepare("SELECT id, name, usage_count, group_id FROM tags WHERE archived = 0 ORDER BY name ASC")?;
         let tag_iter = stmt.query_map([], |row| {
             Ok(crate::models::Tag {
                 id: row.get(0)?,
@@ -679,12 +1593,90 @@ impl Database {
         Ok(tags)
     }
 
+    /// Tags currently in use, with their group name, sorted case-insensitively.
+    pub fn get_tag_summaries(&self) -> Result<Vec<crate::models::TagSummary>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.name, t.usage_count, t.group_id, g.name
+             FROM tags t
+             LEFT JOIN tag_groups g ON g.id = t.group_id
+             WHERE t.usage_count > 0
+             ORDER BY t.name COLLATE NOCASE ASC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(crate::models::TagSummary {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                usage_count: row.get(2)?,
+                group_id: row.get(3)?,
+                group_name: row.get(4)?,
+            })
+        })?.collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(rows)
+    }
+
+    /// Returns the spelling a tag already has in the tags table (matched case-insensitively).
+    pub fn canonical_tag_name(&self, name: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare("SELECT name FROM tags WHERE name = ?1")?;
+        let mut rows = stmt.query(params![name])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Folds the tag row `from` into `to`: usage counts are summed, `to` keeps its group
+    /// (or inherits `from`'s), and `to` takes exactly that spelling.
+    pub fn merge_tag_into(&self, from: &str, to: &str) -> Result<()> {
+        let from_row: Option<(i64, Option<i64>)> = self.conn.query_row(
+            "SELECT usage_count, group_id FROM tags WHERE name = ?1 COLLATE BINARY",
+            params![from],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).map(Some).or_else(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Ok(None),
+            e => Err(e),
+        })?;
+
+        if from.to_lowercase() == to.to_lowercase() {
+            // Same row under NOCASE; only the spelling changes
+            self.conn.execute("UPDATE tags SET name = ?1 WHERE name = ?1", params![to])?;
+            return Ok(());
+        }
+
+        let (usage, group_id) = from_row.unwrap_or((0, None));
+        let created = !self.tag_exists(to)?;
+        self.conn.execute(
+            "INSERT INTO tags (name, usage_count, group_id) VALUES (?1, ?2, ?3)
+             ON CONFLICT(name) DO UPDATE SET
+                name = ?1,
+                usage_count = usage_count + ?2,
+                group_id = COALESCE(group_id, ?3),
+                archived = 0",
+            params![to, usage, group_id],
+        )?;
+        // Shortcuts follow the tag to its new row
+        self.conn.execute(
+            "UPDATE tag_shortcuts SET tag_id = (SELECT id FROM tags WHERE name = ?2)
+             WHERE tag_id = (SELECT id FROM tags WHERE name = ?1 COLLATE BINARY)",
+            params![from, to],
+        )?;
+        self.conn.execute("DELETE FROM tags WHERE name = ?1 COLLATE BINARY", params![from])?;
+        if created {
+            change_events::tag_created(to);
+        }
+        Ok(())
+    }
+
+    fn tag_exists(&self, name: &str) -> Result<bool> {
+        Ok(self.conn.query_row("SELECT EXISTS(SELECT 1 FROM tags WHERE name = ?1 COLLATE NOCASE)", params![name], |row| row.get(0))?)
+    }
+
     pub fn set_tag_group(&self, tag_id: i64, group_id: Option<i64>) -> Result<()> {
         self.conn.execute("UPDATE tags SET group_id = ?1 WHERE id = ?2", params![group_id, tag_id])?;
         Ok(())
     }
     
     pub fn delete_tag(&self, tag_id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM tag_shortcuts WHERE tag_id = ?1", params![tag_id])?;
         self.conn.execute("DELETE FROM tags WHERE id = ?1", params![tag_id])?;
         Ok(())
     }
@@ -694,30 +1686,605 @@ impl Database {
          self.conn.execute("UPDATE tags SET usage_count = 0", [])?;
          
          let tracks = self.get_all_tracks()?;
-         let mut tag_counts = std::collections::HashMap::new();
+         // Keyed case-insensitively like the tags table; the first spelling seen is kept
+         let mut tag_counts: std::collections::HashMap<String, (String, i64)> = std::collections::HashMap::new();
          
          for track in tracks {
-            if let Some(raw) = track.comment_raw {
-                if let Some(idx) = raw.find(" && ") {
-                    let tag_part = &raw[idx + 4..];
-                    for tag in tag_part.split(';') {
-                        let trimmed = tag.trim();
-                        if !trimmed.is_empty() {
-                           *tag_counts.entry(trimmed.to_string()).or_insert(0) += 1;
-                        }
-                    }
-                }
+            let (_, tags) = crate::comment::split_comment(track.comment_raw.as_deref().unwrap_or(""));
+            for tag in tags {
+                tag_counts.entry(tag.to_lowercase()).or_insert_with(|| (tag, 0)).1 += 1;
             }
          }
          
-         for (name, count) in tag_counts {
+         for (name, count) in tag_counts.into_values() {
+             if change_events::enabled() && !self.tag_exists(&name)? {
+                 change_events::tag_created(&name);
+             }
              self.conn.execute(
                  "INSERT INTO tags (name, usage_count) VALUES (?1, ?2) 
-                  ON CONFLICT(name) DO UPDATE SET usage_count = ?3",
+                  ON CONFLICT(name) DO UPDATE SET usage_count = ?3, archived = 0",
                  params![name, count, count],
              )?;
          }
-         
+
          Ok(())
     }
+
+    /// Moves tag usage counts from a track's stored comment to `new_comment`.
+    /// Called before the comment column is overwritten so counts stay current
+    /// without the full scan `sync_tags` does.
+    fn adjust_tag_counts(&self, track_id: i64, new_comment: &str) -> Result<()> {
+        let old_comment: Option<String> = match self.conn.query_row(
+            "SELECT comment_raw FROM tracks WHERE id = ?1",
+            params![track_id],
+            |row| row.get(0),
+        ) {
+            Ok(v) => v,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        self.move_tag_counts(old_comment.as_deref().unwrap_or(""), new_comment)
+    }
+
+    /// Moves tag usage counts from the tags of `old_comment` to those of `new_comment`; an
+    /// empty comment stands for a track that is gone or not there yet.
+    fn move_tag_counts(&self, old_comment: &str, new_comment: &str) -> Result<()> {
+        let (_, old_tags) = crate::comment::split_comment(old_comment);
+        let (_, new_tags) = crate::comment::split_comment(new_comment);
+        let contains = |tags: &[String], tag: &str| tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+
+        for tag in old_tags.iter().filter(|t| !contains(&new_tags, t)) {
+            self.conn.execute(
+                "UPDATE tags SET usage_count = MAX(usage_count - 1, 0) WHERE name = ?1 COLLATE NOCASE",
+                params![tag],
+            )?;
+        }
+        for tag in new_tags.iter().filter(|t| !contains(&old_tags, t)) {
+            if !self.tag_exists(tag)? {
+                change_events::tag_created(tag);
+            }
+            self.conn.execute(
+                "INSERT INTO tags (name, usage_count) VALUES (?1, 1)
+                 ON CONFLICT(name) DO UPDATE SET usage_count = usage_count + 1, archived = 0",
+                params![tag],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Removes tags no track uses, or hides them from `get_all_tags` when `archive` is set
+    /// (keeping their group assignment should they come back). Returns the number affected.
+    pub fn prune_unused_tags(&self, archive: bool) -> Result<usize> {
+        let affected = if archive {
+            self.conn.execute("UPDATE tags SET archived = 1 WHERE usage_count <= 0 AND archived = 0", [])?
+        } else {
+            self.conn.execute(
+                "DELETE FROM tag_shortcuts WHERE tag_id IN (SELECT id FROM tags WHERE usage_count <= 0)",
+                [],
+            )?;
+            self.conn.execute("DELETE FROM tags WHERE usage_count <= 0", [])?
+        };
+        Ok(affected)
+    }
+
+    // TAG SHORTCUT METHODS
+
+    fn row_to_tag_shortcut(row: &rusqlite::Row) -> rusqlite::Result<crate::models::TagShortcut> {
+        Ok(crate::models::TagShortcut {
+            key: row.get(0)?,
+            tag_id: row.get(1)?,
+            tag_name: row.get(2)?,
+        })
+    }
+
+    pub fn get_tag_shortcuts(&self) -> Result<Vec<crate::models::TagShortcut>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.key, s.tag_id, t.name FROM tag_shortcuts s JOIN tags t ON t.id = s.tag_id ORDER BY s.key ASC"
+        )?;
+        let shortcuts = stmt.query_map([], Self::row_to_tag_shortcut)?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(shortcuts)
+    }
+
+    pub fn get_tag_shortcut(&self, key: &str) -> Result<Option<crate::models::TagShortcut>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT s.key, s.tag_id, t.name FROM tag_shortcuts s JOIN tags t ON t.id = s.tag_id WHERE s.key = ?1"
+        )?;
+        let mut rows = stmt.query(params![key])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(Self::row_to_tag_shortcut(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Binds `key` to a tag, replacing whatever it was bound to. Returns false when the
+    /// tag doesn't exist.
+    pub fn set_tag_shortcut(&self, key: &str, tag_id: i64) -> Result<bool> {
+        let rows = self.conn.execute(
+            "INSERT INTO tag_shortcuts (key, tag_id) SELECT ?1, id FROM tags WHERE id = ?2
+             ON CONFLICT(key) DO UPDATE SET tag_id = excluded.tag_id",
+            params![key, tag_id],
+        )?;
+        Ok(rows > 0)
+    }
+
+    pub fn delete_tag_shortcut(&self, key: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM tag_shortcuts WHERE key = ?1", params![key])?;
+        Ok(())
+    }
+
+    // PENDING SYNC JOURNAL METHODS
+
+    /// Persists a serialized Music.app update and returns its journal row ID.
+    pub fn insert_pending_sync(&self, payload: &str, queued_at: i64) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO pending_sync (payload, attempts, queued_at) VALUES (?1, 0, ?2)",
+            params![payload, queued_at],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn update_pending_sync(&self, id: i64, attempts: u32, last_error: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE pending_sync SET attempts = ?1, last_error = ?2 WHERE id = ?3",
+            params![attempts, last_error, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_pending_sync(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM pending_sync WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Returns all journaled updates in queue order as (id, payload, attempts, last_error, queued_at).
+    pub fn get_pending_sync(&self) -> Result<Vec<(i64, String, u32, Option<String>, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, payload, attempts, last_error, queued_at FROM pending_sync ORDER BY id ASC"
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, u32>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<i64>>(4)?.unwrap_or(0),
+            ))
+        })?.collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(rows)
+    }
+
+    // SETTINGS METHODS
+
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let mut stmt = self.conn.prepare("SELECT value FROM settings WHERE key = ?1")?;
+        let mut rows = stmt.query(params![key])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = ?2",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    // JOB QUEUE METHODS
+
+    /// Inserts or replaces a job snapshot (serialized `Job`).
+    pub fn save_job(&self, id: i64, status: &str, data: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO jobs (id, status, data, updated_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(id) DO UPDATE SET status = ?2, data = ?3, updated_at = ?4",
+            params![id, status, data, chrono::Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// Returns serialized jobs that had not finished when the app last closed, oldest first.
+    pub fn get_unfinished_jobs(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT data FROM jobs WHERE status IN ('queued', 'running', 'paused') ORDER BY id ASC"
+        )?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(rows)
+    }
+
+    pub fn get_max_job_id(&self) -> Result<i64> {
+        let id: Option<i64> = self.conn.query_row("SELECT MAX(id) FROM jobs", [], |row| row.get(0))?;
+        Ok(id.unwrap_or(0))
+    }
+
+    // ORIGINAL METADATA VAULT
+
+    /// Saves the track's comment and grouping the first time its comment is about to change.
+    /// The DB holds what the file had at import, so this is the pre-TagDeck state.
+    fn preserve_original(&self, track_id: i64, new_comment: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO original_metadata (track_id, comment, grouping, saved_at)
+             SELECT id, comment_raw, grouping_raw, ?2 FROM tracks
+             WHERE id = ?1 AND COALESCE(comment_raw, '') != ?3",
+            params![track_id, chrono::Utc::now().timestamp(), new_comment],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the vaulted (comment, grouping) for a track, if TagDeck ever changed it.
+    pub fn get_original_metadata(&self, track_id: i64) -> Result<Option<(Option<String>, Option<String>)>> {
+        let mut stmt = self.conn.prepare("SELECT comment, grouping FROM original_metadata WHERE track_id = ?1")?;
+        let mut rows = stmt.query(params![track_id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some((row.get(0)?, row.get(1)?))),
+            None => Ok(None),
+        }
+    }
+
+    // AUDIT LOG METHODS
+
+    /// Appends a `track_history` row if `field` is about to change.
+    /// `field` must be a tracks column name; callers only pass literals.
+    fn record_change(&self, track_id: i64, field: &str, new_value: Option<String>) -> Result<()> {
+        let old_value: Option<String> = match self.conn.query_row(
+            &format!("SELECT CAST({} AS TEXT) FROM tracks WHERE id = ?1", field),
+            params![track_id],
+            |row| row.get(0),
+        ) {
+            Ok(v) => v,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        if old_value.as_deref().unwrap_or("") == new_value.as_deref().unwrap_or("") {
+            return Ok(());
+        }
+
+        self.conn.execute(
+            "INSERT INTO track_history (track_id, field, old_value, new_value, changed_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![track_id, field, old_value, new_value, chrono::Utc::now().timestamp()],
+        )?;
+        change_events::track_changed(track_id, field);
+        Ok(())
+    }
+
+    /// Returns the value `field` had at `timestamp`, or `None` if it has not changed since.
+    pub fn get_field_value_at(&self, track_id: i64, field: &str, timestamp: i64) -> Result<Option<Option<String>>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT old_value FROM track_history
+             WHERE track_id = ?1 AND field = ?2 AND changed_at > ?3
+             ORDER BY id ASC LIMIT 1"
+        )?;
+        let mut rows = stmt.query(params![track_id, field, timestamp])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The most recently edited tracks as (track_id, last change, changed fields), newest first.
+    /// Tracks without recorded edits fall back to their modification date, with no fields.
+    pub fn get_recent_changes(&self, limit: usize) -> Result<Vec<(i64, i64, Vec<String>)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT track_id, MAX(changed_at) AS at, group_concat(DISTINCT field) FROM track_history
+             WHERE track_id IN (SELECT id FROM tracks)
+             GROUP BY track_id
+             UNION ALL
+             SELECT id, modified_date, NULL FROM tracks
+             WHERE modified_date > 0 AND id NOT IN (SELECT track_id FROM track_history)
+             ORDER BY at DESC
+             LIMIT ?1"
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            let fields: Option<String> = row.get(2)?;
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                fields.map(|f| f.split(',').map(str::to_string).collect()).unwrap_or_default(),
+            ))
+        })?
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(rows)
+    }
+
+    /// All recorded comment edits as (old_value, new_value, changed_at), oldest first.
+    pub fn get_comment_history(&self) -> Result<Vec<(Option<String>, Option<String>, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT old_value, new_value, changed_at FROM track_history
+             WHERE field = 'comment_raw'
+             ORDER BY id ASC"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(rows)
+    }
+
+    // COMMENT CONFLICT METHODS
+
+    /// Whether TagDeck changed the track's comment after `since` (per the audit log).
+    pub fn comment_edited_since(&self, track_id: i64, since: i64) -> Result<bool> {
+        let edited: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM track_history WHERE track_id = ?1 AND field = 'comment_raw' AND changed_at > ?2)",
+            params![track_id, since],
+            |row| row.get(0),
+        )?;
+        Ok(edited)
+    }
+
+    /// Records (or refreshes) a conflict between TagDeck's and Music.app's comment.
+    pub fn set_comment_conflict(&self, track_id: i64, local_comment: &str, music_comment: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO comment_conflicts (track_id, local_comment, music_comment, detected_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(track_id) DO UPDATE SET local_comment = excluded.local_comment,
+                music_comment = excluded.music_comment, detected_at = excluded.detected_at",
+            params![track_id, local_comment, music_comment, chrono::Utc::now().timestamp()],
+        )?;
+        Ok(())
+    }
+
+    /// Open conflicts as (track_id, local_comment, music_comment, detected_at), newest first.
+    pub fn get_comment_conflicts(&self) -> Result<Vec<(i64, String, String, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT track_id, COALESCE(local_comment, ''), COALESCE(music_comment, ''), detected_at
+             FROM comment_conflicts ORDER BY detected_at DESC"
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(rows)
+    }
+
+    pub fn has_comment_conflict(&self, track_id: i64) -> Result<bool> {
+        let open = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM comment_conflicts WHERE track_id = ?1)",
+            params![track_id],
+            |row| row.get(0),
+        )?;
+        Ok(open)
+    }
+
+    pub fn delete_comment_conflict(&self, track_id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM comment_conflicts WHERE track_id = ?1", params![track_id])?;
+        Ok(())
+    }
+
+    // CUE POINT METHODS
+
+    fn row_to_cue_point(row: &rusqlite::Row) -> rusqlite::Result<crate::models::CuePoint> {
+        Ok(crate::models::CuePoint {
+            id: row.get(0)?,
+            track_id: row.get(1)?,
+            position_secs: row.get(2)?,
+            cue_type: crate::models::CueType::parse(&row.get::<_, String>(3)?),
+            end_secs: row.get(4)?,
+            label: row.get(5)?,
+            color: row.get(6)?,
+        })
+    }
+
+    /// Cue points of a track, by position.
+    pub fn get_cue_points(&self, track_id: i64) -> Result<Vec<crate::models::CuePoint>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, track_id, position_secs, cue_type, end_secs, label, color
+             FROM cue_points WHERE track_id = ?1 ORDER BY position_secs ASC, id ASC"
+        )?;
+        let cues = stmt.query_map(params![track_id], Self::row_to_cue_point)?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(cues)
+    }
+
+    pub fn get_cue_point(&self, id: i64) -> Result<Option<crate::models::CuePoint>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, track_id, position_secs, cue_type, end_secs, label, color FROM cue_points WHERE id = ?1"
+        )?;
+        let mut rows = stmt.query(params![id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(Self::row_to_cue_point(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Inserts a cue point (its `id` is ignored) and returns the new ID.
+    pub fn insert_cue_point(&self, cue: &crate::models::CuePoint) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO cue_points (track_id, position_secs, cue_type, end_secs, label, color) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![cue.track_id, cue.position_secs, cue.cue_type.as_str(), cue.end_secs, cue.label, cue.color],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn update_cue_point(&self, cue: &crate::models::CuePoint) -> Result<()> {
+        self.conn.execute(
+            "UPDATE cue_points SET position_secs = ?1, cue_type = ?2, end_secs = ?3, label = ?4, color = ?5 WHERE id = ?6",
+            params![cue.position_secs, cue.cue_type.as_str(), cue.end_secs, cue.label, cue.color, cue.id],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_cue_point(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM cue_points WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    // SESSION METHODS
+
+    pub fn create_session(&self, name: &str, started_at: i64) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO sessions (name, started_at) VALUES (?1, ?2)",
+            params![name, started_at],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    pub fn end_session(&self, id: i64, ended_at: i64) -> Result<()> {
+        self.conn.execute(
+            "UPDATE sessions SET ended_at = ?1 WHERE id = ?2 AND ended_at IS NULL",
+            params![ended_at, id],
+        )?;
+        Ok(())
+    }
+
+    fn row_to_session(row: &rusqlite::Row) -> rusqlite::Result<crate::models::Session> {
+        Ok(crate::models::Session {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            started_at: row.get(2)?,
+            ended_at: row.get(3)?,
+        })
+    }
+
+    pub fn get_session(&self, id: i64) -> Result<Option<crate::models::Session>> {
+        let mut stmt = self.conn.prepare("SELECT id, name, started_at, ended_at FROM sessions WHERE id = ?1")?;
+        let mut rows = stmt.query(params![id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(Self::row_to_session(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The most recent session that has not been stopped yet.
+    pub fn get_active_session(&self) -> Result<Option<crate::models::Session>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, started_at, ended_at FROM sessions WHERE ended_at IS NULL ORDER BY id DESC LIMIT 1"
+        )?;
+        let mut rows = stmt.query([])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(Self::row_to_session(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// All sessions, newest first.
+    pub fn get_sessions(&self) -> Result<Vec<crate::models::Session>> {
+        let mut stmt = self.conn.prepare("SELECT id, name, started_at, ended_at FROM sessions ORDER BY started_at DESC, id DESC")?;
+        let sessions = stmt.query_map([], Self::row_to_session)?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(sessions)
+    }
+
+    /// Appends a played track to the end of a session.
+    pub fn add_session_track(&self, session_id: i64, track_id: i64, played_at: i64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO session_tracks (session_id, position, track_id, played_at)
+             SELECT ?1, COALESCE(MAX(position) + 1, 0), ?2, ?3 FROM session_tracks WHERE session_id = ?1",
+            params![session_id, track_id, played_at],
+        )?;
+        Ok(())
+    }
+
+    /// (track_id, played_at) pairs of a session in play order.
+    pub fn get_session_tracks(&self, session_id: i64) -> Result<Vec<(i64, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT track_id, played_at FROM session_tracks WHERE session_id = ?1 ORDER BY position ASC"
+        )?;
+        let rows = stmt.query_map(params![session_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(rows)
+    }
+
+    pub fn add_listening_event(&self, track_id: i64, played_at: i64, source: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO listening_history (track_id, played_at, source) VALUES (?1, ?2, ?3)",
+            params![track_id, played_at, source],
+        )?;
+        Ok(())
+    }
+
+    /// (track_id, plays) for tracks played between `from` and `to`, most played first.
+    pub fn get_play_counts_between(&self, from: i64, to: i64, limit: usize) -> Result<Vec<(i64, i64)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT track_id, COUNT(*) AS plays FROM listening_history
+             WHERE played_at BETWEEN ?1 AND ?2
+             GROUP BY track_id ORDER BY plays DESC, MAX(played_at) DESC LIMIT ?3"
+        )?;
+        let rows = stmt.query_map(params![from, to, limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(rows)
+    }
+
+    /// (track_id, played_at, source) between `from` and `to`, in play order.
+    pub fn get_listening_history(&self, from: i64, to: i64) -> Result<Vec<(i64, i64, String)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT track_id, played_at, source FROM listening_history
+             WHERE played_at BETWEEN ?1 AND ?2 ORDER BY played_at ASC, id ASC"
+        )?;
+        let rows = stmt.query_map(params![from, to], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(rows)
+    }
+
+    /// Tracks with no recorded plays, neither in TagDeck's history nor Music.app's play count.
+    pub fn get_never_played_tracks(&self) -> Result<Vec<Track>> {
+        self.get_tracks_where(
+            "COALESCE(t.play_count, 0) = 0
+             AND NOT EXISTS (SELECT 1 FROM listening_history h WHERE h.track_id = t.id)
+             ORDER BY t.date_added DESC",
+            [],
+        )
+    }
+
+    pub fn create_library_snapshot(&self, label: &str, created_at: i64, data: &str) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO library_snapshots (label, created_at, data) VALUES (?1, ?2, ?3)",
+            params![label, created_at, data],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Snapshots without their data, newest first.
+    pub fn get_library_snapshots(&self) -> Result<Vec<crate::models::LibrarySnapshot>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, label, created_at FROM library_snapshots ORDER BY created_at DESC, id DESC"
+        )?;
+        let snapshots = stmt.query_map([], |row| {
+            Ok(crate::models::LibrarySnapshot {
+                id: row.get(0)?,
+                label: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?.collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        Ok(snapshots)
+    }
+
+    pub fn get_library_snapshot(&self, id: i64) -> Result<Option<(crate::models::LibrarySnapshot, String)>> {
+        let mut stmt = self.conn.prepare("SELECT id, label, created_at, data FROM library_snapshots WHERE id = ?1")?;
+        let mut rows = stmt.query(params![id])?;
+        match rows.next()? {
+            Some(row) => Ok(Some((
+                crate::models::LibrarySnapshot { id: row.get(0)?, label: row.get(1)?, created_at: row.get(2)? },
+                row.get(3)?,
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn delete_library_snapshot(&self, id: i64) -> Result<()> {
+        self.conn.execute("DELETE FROM library_snapshots WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// persistent ID -> header signature recorded by the last playlist sync.
+    pub fn get_playlist_sync_headers(&self) -> Result<std::collections::HashMap<String, String>> {
+        let mut stmt = self.conn.prepare("SELECT persistent_id, signature FROM playlist_sync_headers")?;
+        let headers = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, rusqlite::Error>>()?;
+        Ok(headers)
+    }
+
+    /// Replaces the recorded headers with `headers` in one transaction.
+    pub fn set_playlist_sync_headers(&self, headers: &std::collections::HashMap<String, String>) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        self.conn.execute("DELETE FROM playlist_sync_headers", [])?;
+        for (pid, signature) in headers {
+            self.conn.execute(
+                "INSERT INTO playlist_sync_headers (persistent_id, signature) VALUES (?1, ?2)",
+                params![pid, signature],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
 }
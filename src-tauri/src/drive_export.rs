@@ -0,0 +1,211 @@
+//! Gig prep: copies the audio of selected playlists onto a USB drive together with
+//! playlists that point at the copies, so the stick works without the library.
+//!
+//! Layout under the destination:
+//! `TagDeck/Music/<Artist>/<file>`, `TagDeck/Playlists/<name>.m3u8` and, optionally,
+//! `TagDeck/rekordbox.xml`.
+
+use crate::commands::AppState;
+use crate::file_organizer::sanitize_component;
+use crate::models::Track;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+
+const EXPORT_DIR: &str = "TagDeck";
+
+#[derive(Clone, serde::Serialize)]
+pub struct DriveExportProgress {
+    pub processed: usize,
+    pub total: usize,
+    pub current: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct DriveExportResult {
+    pub copied: usize,
+    /// Already on the drive with the same size
+    pub up_to_date: usize,
+    /// "Artist - Title (path)" of tracks whose files are missing or offline
+    pub missing: Vec<String>,
+    /// "path: error" for files that could not be copied
+    pub failed: Vec<String>,
+    pub playlists_written: usize,
+}
+
+fn describe(track: &Track) -> String {
+    format!(
+        "{} - {} ({})",
+        track.artist.as_deref().unwrap_or("Unknown Artist"),
+        track.title.as_deref().unwrap_or("Untitled"),
+        track.file_path
+    )
+}
+
+/// `Music/<Artist>/<file name>` relative to the export folder, de-duplicated against `taken`.
+fn relative_target(track: &Track, taken: &[PathBuf]) -> PathBuf {
+    let artist = sanitize_component(track.artist.as_deref().unwrap_or(""));
+    let artist = if artist.is_empty() { "Unknown Artist".to_string() } else { artist };
+    let source = Path::new(&track.file_path);
+    let stem = source.file_stem().map(|s| sanitize_component(&s.to_string_lossy())).unwrap_or_default();
+    let ext = source.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+
+    let dir = Path::new("Music").join(artist);
+    let mut candidate = dir.join(format!("{}{}", stem, ext));
+    let mut n = 2;
+    while taken.contains(&candidate) {
+        candidate = dir.join(format!("{} ({}){}", stem, n, ext));
+        n += 1;
+    }
+    candidate
+}
+
+fn is_up_to_date(source: &Path, target: &Path) -> bool {
+    match (std::fs::metadata(source), std::fs::metadata(target)) {
+        (Ok(s), Ok(t)) => s.len() == t.len(),
+        _ => false,
+    }
+}
+
+/// Copies the files of `playlist_ids` to the drive at `dest`, writes an M3U8 per playlist
+/// (and a Rekordbox XML with `rekordbox`) referencing the copies, and reports tracks that
+/// could not be exported. Files already on the drive are not copied again. Emits
+/// `drive-export-progress` per file.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn export_to_drive(
+    app: AppHandle,
+    playlist_ids: Vec<i64>,
+    dest: String,
+    rekordbox: Option<bool>,
+) -> Result<DriveExportResult, String> {
+    let dest_root = PathBuf::from(&dest);
+    if !dest_root.is_dir() {
+        return Err(format!("Destination does not exist: {}", dest));
+    }
+    let export_root = dest_root.join(EXPORT_DIR);
+    let handle = app.clone();
+
+    let result = tauri::async_runtime::spawn_blocking(move || -> Result<DriveExportResult, String> {
+        let state = handle.state::<AppState>();
+
+        // (name, track ids) per playlist, plus every distinct track once
+        let mut playlists: Vec<(String, Vec<i64>)> = Vec::new();
+        let mut tracks: Vec<Track> = Vec::new();
+        {
+            let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+            let all = db.get_playlists().map_err(|e| e.to_string())?;
+            for id in &playlist_ids {
+                let playlist = all.iter().find(|p| p.id == *id)
+                    .ok_or_else(|| format!("Playlist {} not found", id))?;
+                let ids = db.get_playlist_track_ids(*id).map_err(|e| e.to_string())?;
+                for tid in &ids {
+                    if !tracks.iter().any(|t| t.id == *tid) {
+                        if let Some(track) = db.get_track(*tid).map_err(|e| e.to_string())? {
+                            tracks.push(track);
+                        }
+                    }
+                }
+                playlists.push((playlist.name.clone(), ids));
+            }
+        }
+
+        let total = tracks.len();
+        let mut copied = 0;
+        let mut up_to_date = 0;
+        let mut missing = Vec::new();
+        let mut failed = Vec::new();
+        let mut taken: Vec<PathBuf> = Vec::new();
+        // track id -> path relative to the export folder
+        let mut exported: HashMap<i64, PathBuf> = HashMap::new();
+
+        for (i, track) in tracks.iter().enumerate() {
+            let _ = handle.emit("drive-export-progress", DriveExportProgress {
+                processed: i,
+                total,
+                current: track.title.clone().unwrap_or_else(|| track.file_path.clone()),
+            });
+
+            let source = Path::new(&track.file_path);
+            if track.missing || !source.is_file() {
+                missing.push(describe(track));
+                continue;
+            }
+
+            let relative = relative_target(track, &taken);
+            let target = export_root.join(&relative);
+            taken.push(relative.clone());
+
+            if is_up_to_date(source, &target) {
+                up_to_date += 1;
+            } else {
+                let copy = target.parent()
+                    .map(std::fs::create_dir_all)
+                    .unwrap_or(Ok(()))
+                    .and_then(|_| std::fs::copy(source, &target));
+                if let Err(e) = copy {
+                    failed.push(format!("{}: {}", track.file_path, e));
+                    continue;
+                }
+                copied += 1;
+            }
+            exported.insert(track.id, relative);
+        }
+        let _ = handle.emit("drive-export-progress", DriveExportProgress { processed: total, total, current: String::new() });
+
+        // Playlists reference the copies; tracks that did not make it are left out
+        let playlist_dir = export_root.join("Playlists");
+        std::fs::create_dir_all(&playlist_dir).map_err(|e| format!("Failed to create {}: {}", playlist_dir.display(), e))?;
+        let by_id: HashMap<i64, &Track> = tracks.iter().map(|t| (t.id, t)).collect();
+        for (name, ids) in &playlists {
+            let mut out = format!("#EXTM3U\n#PLAYLIST:{}\n", name);
+            for id in ids {
+                if let (Some(relative), Some(track)) = (exported.get(id), by_id.get(id)) {
+                    out.push_str(&format!(
+                        "#EXTINF:{},{} - {}\n../{}\n",
+                        track.duration_secs.round() as i64,
+                        track.artist.as_deref().unwrap_or("Unknown Artist"),
+                        track.title.as_deref().unwrap_or("Untitled"),
+                        relative.to_string_lossy(),
+                    ));
+                }
+            }
+            let file = playlist_dir.join(format!("{}.m3u8", sanitize_component(name)));
+            std::fs::write(&file, out).map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+        }
+
+        if rekordbox.unwrap_or(false) {
+            let collection: Vec<(Track, String)> = tracks.iter()
+                .filter_map(|t| exported.get(&t.id).map(|rel| (t.clone(), export_root.join(rel).to_string_lossy().to_string())))
+                .collect();
+            let rb_playlists: Vec<(String, Vec<i64>)> = playlists.iter()
+                .map(|(name, ids)| (name.clone(), ids.iter().copied().filter(|id| exported.contains_key(id)).collect()))
+                .collect();
+            let cues: HashMap<i64, Vec<crate::models::CuePoint>> = {
+                let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+                collection.iter()
+                    .map(|(t, _)| Ok((t.id, db.get_cue_points(t.id).map_err(|e| e.to_string())?)))
+                    .collect::<Result<_, String>>()?
+            };
+            let xml = crate::rekordbox::render_xml(&collection, &cues, &rb_playlists);
+            let file = export_root.join("rekordbox.xml");
+            std::fs::write(&file, xml).map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+        }
+
+        Ok(DriveExportResult {
+            copied,
+            up_to_date,
+            missing,
+            failed,
+            playlists_written: playlists.len(),
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    tracing::info!(
+        "Exported to {}: {} copied, {} up to date, {} missing, {} failed",
+        dest, result.copied, result.up_to_date, result.missing.len(), result.failed.len()
+    );
+    Ok(result)
+}
@@ -0,0 +1,170 @@
+use crate::commands::AppState;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::Command;
+use tauri::State;
+
+/// AppleScript error code returned when the user has denied (or not yet granted)
+/// TagDeck permission to control Music.app under Privacy → Automation.
+const ERR_AUTOMATION_DENIED: &str = "-1743";
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self { status, detail: detail.into() }
+    }
+}
+
+/// Structured report rendered by the setup screen.
+#[derive(Debug, Clone, Serialize)]
+pub struct EnvironmentReport {
+    pub music_automation: CheckResult,
+    pub music_folder_access: CheckResult,
+    pub sidecar: CheckResult,
+    pub database: CheckResult,
+    /// True when no check failed outright (warnings are allowed).
+    pub ready: bool,
+}
+
+/// Verifies that Music.app accepts Apple Events from TagDeck.
+/// Music is not launched just for the check; if it is closed the result is a warning.
+fn check_music_automation() -> CheckResult {
+    #[cfg(target_os = "macos")]
+    {
+        let running = Command::new("osascript")
+            .arg("-e")
+            .arg(r#"application "Music" is running"#)
+            .output();
+
+        match running {
+            Ok(out) if String::from_utf8_lossy(&out.stdout).trim() == "true" => {}
+            Ok(_) => {
+                return CheckResult::new(CheckStatus::Warning, "Music.app is not running; permission will be requested on first use");
+            }
+            Err(e) => return CheckResult::new(CheckStatus::Failed, format!("Failed to run osascript: {}", e)),
+        }
+
+        match Command::new("osascript")
+            .arg("-e")
+            .arg(r#"tell application "Music" to get version"#)
+            .output()
+        {
+            Ok(out) if out.status.success() => {
+                let version = String::from_utf8_lossy(&out.stdout).trim().to_string();
+                CheckResult::new(CheckStatus::Ok, format!("Music.app {} is scriptable", version))
+            }
+            Ok(out) => {
+                let err = String::from_utf8_lossy(&out.stderr).to_string();
+                if err.contains(ERR_AUTOMATION_DENIED) {
+                    CheckResult::new(
+                        CheckStatus::Failed,
+                        "Automation permission denied. Enable TagDeck → Music in System Settings → Privacy & Security → Automation",
+                    )
+                } else {
+                    CheckResult::new(CheckStatus::Failed, format!("AppleScript error: {}", err.trim()))
+                }
+            }
+            Err(e) => CheckResult::new(CheckStatus::Failed, format!("Failed to run osascript: {}", e)),
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        CheckResult::new(CheckStatus::Unsupported, "Music.app integration is only available on macOS")
+    }
+}
+
+/// Verifies the Music library folder can be listed. Without Full Disk Access
+/// macOS returns EPERM for `~/Music/Music` even though the folder exists.
+fn check_music_folder_access() -> CheckResult {
+    let home = dirs::home_dir().unwrap_or(PathBuf::from("/Users/Shared"));
+    let candidates = [home.join("Music/Music"), home.join("Music/iTunes")];
+
+    let Some(dir) = candidates.iter().find(|p| p.exists()) else {
+        return CheckResult::new(CheckStatus::Warning, "No Music or iTunes library folder found in ~/Music");
+    };
+
+    match std::fs::read_dir(dir) {
+        Ok(_) => CheckResult::new(CheckStatus::Ok, format!("Readable: {}", dir.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => CheckResult::new(
+            CheckStatus::Failed,
+            format!("Access to {} denied. Grant Full Disk Access in System Settings → Privacy & Security", dir.display()),
+        ),
+        Err(e) => CheckResult::new(CheckStatus::Failed, format!("Failed to read {}: {}", dir.display(), e)),
+    }
+}
+
+/// Verifies the `fetch-library` sidecar shipped next to the app binary.
+fn check_sidecar() -> CheckResult {
+    let exe_dir = match std::env::current_exe() {
+        Ok(exe) => exe.parent().map(|p| p.to_path_buf()),
+        Err(e) => return CheckResult::new(CheckStatus::Failed, format!("Failed to locate app binary: {}", e)),
+    };
+
+    let Some(dir) = exe_dir else {
+        return CheckResult::new(CheckStatus::Failed, "Failed to locate app binary directory");
+    };
+
+    let sidecar = dir.join("fetch-library");
+    if sidecar.exists() {
+        CheckResult::new(CheckStatus::Ok, format!("Found {}", sidecar.display()))
+    } else {
+        CheckResult::new(CheckStatus::Failed, format!("fetch-library not found in {}", dir.display()))
+    }
+}
+
+fn check_database(state: &AppState) -> CheckResult {
+    let db = match state.db.lock() {
+        Ok(db) => db,
+        Err(_) => return CheckResult::new(CheckStatus::Failed, "Failed to lock DB"),
+    };
+    match db.check_writable() {
+        Ok(()) => CheckResult::new(CheckStatus::Ok, "Database is writable"),
+        Err(e) => CheckResult::new(CheckStatus::Failed, format!("Database is not writable: {}", e)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn check_environment(state: State<'_, AppState>) -> Result<EnvironmentReport, String> {
+    let music_automation = check_music_automation();
+    let music_folder_access = check_music_folder_access();
+    let sidecar = check_sidecar();
+    let database = check_database(&state);
+
+    let ready = [&music_automation, &music_folder_access, &sidecar, &database]
+        .iter()
+        .all(|c| c.status != CheckStatus::Failed);
+
+    let msg = format!(
+        "Environment check: automation={:?}, music_folder={:?}, sidecar={:?}, database={:?}",
+        music_automation.status, music_folder_access.status, sidecar.status, database.status
+    );
+    if ready {
+        tracing::info!("{}", msg);
+    } else {
+        tracing::warn!("{}", msg);
+    }
+
+    Ok(EnvironmentReport {
+        music_automation,
+        music_folder_access,
+        sidecar,
+        database,
+        ready,
+    })
+}
@@ -0,0 +1,661 @@
+//! Key/BPM analysis by outside tools. An `ExternalAnalyzer` takes a chunk of files and
+//! either finishes them on the spot (a command-line tool whose output is parsed) or
+//! rewrites their tags in its own time (Mixed In Key), in which case the worker waits for
+//! the files to change and reads them back. Besides Mixed In Key, users can configure
+//! command-line analyzers such as KeyFinder or an Essentia script.
+//!
+//! One run goes at a time; progress is reported through `analysis-chunk-submitted`,
+//! `analysis-track-analyzed`, `analysis-progress` and finally `analysis-complete`.
+
+use crate::apple_music::{touch_file, AppleUpdate};
+use crate::commands::AppState;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const ANALYZERS_KEY: &str = "external_analyzers";
+/// Replaced with the audio file's path in an analyzer's arguments
+const FILE_PLACEHOLDER: &str = "{file}";
+/// Files handed to an analyzer at once
+const CHUNK_SIZE: usize = 20;
+/// Time allowed for a chunk's files to be rewritten: a base plus this much per file
+const CHUNK_BASE_TIMEOUT: Duration = Duration::from_secs(10);
+const PER_FILE_TIMEOUT: Duration = Duration::from_secs(15);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// An analyzer process still running after this long on one file is killed
+const RUN_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Key and/or BPM reported by an analyzer.
+#[derive(Debug, Clone, Default)]
+pub struct AnalyzerReading {
+    pub key: Option<String>,
+    pub bpm: Option<f64>,
+}
+
+/// What became of one file handed to an analyzer.
+pub enum Submission {
+    /// The analyzer will write its results into the file
+    Pending,
+    Done(AnalyzerReading),
+    Refused(String),
+}
+
+pub trait ExternalAnalyzer: Send + Sync {
+    fn name(&self) -> &str;
+    /// Errors when the analyzer can't run on this machine.
+    fn check_available(&self) -> Result<(), String>;
+    /// Hands over a chunk of files; returns one `Submission` per path, in order.
+    fn submit(&self, paths: &[&str]) -> Result<Vec<Submission>, String>;
+    /// Called once the queue is empty.
+    fn finish(&self) {}
+}
+
+/// How a command-line analyzer's results get back into TagDeck.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ResultStrategy {
+    /// The tool writes key/BPM tags into the file; they are read back once it has
+    FileTags,
+    /// Regular expressions run on stdout; the first capture group is the value
+    Regex {
+        #[serde(default)]
+        key_pattern: Option<String>,
+        #[serde(default)]
+        bpm_pattern: Option<String>,
+    },
+    /// Stdout is a JSON object; fields are dotted paths such as `tonal.key_edma.key`
+    Json {
+        #[serde(default)]
+        key_field: Option<String>,
+        #[serde(default)]
+        bpm_field: Option<String>,
+    },
+}
+
+/// A user-configured command-line analyzer, run once per file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyzerConfig {
+    pub name: String,
+    pub executable: String,
+    /// Argument template; `{file}` is replaced with the audio file's path
+    pub args: Vec<String>,
+    pub results: ResultStrategy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisRejection {
+    pub track_id: i64,
+    pub reason: String,
+}
+
+/// What a queueing command queued and what it turned away up front.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisSubmission {
+    pub queued: Vec<i64>,
+    pub rejected: Vec<AnalysisRejection>,
+}
+
+/// Where the current analysis run stands; also the payload of `analysis-progress` and
+/// `analysis-complete`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AnalysisStatus {
+    pub running: bool,
+    pub analyzer: Option<String>,
+    /// Tracks waiting for a chunk
+    pub queued: usize,
+    /// The chunk being analyzed
+    pub in_progress: Vec<i64>,
+    /// Tracks the analyzer took without an error
+    pub accepted: Vec<i64>,
+    /// Tracks whose results are in the DB
+    pub analyzed: Vec<i64>,
+    pub rejected: Vec<AnalysisRejection>,
+    /// Accepted tracks whose file was not rewritten in time
+    pub timed_out: Vec<i64>,
+}
+
+/// Payload of `analysis-chunk-submitted`.
+#[derive(Clone, Serialize)]
+pub struct AnalysisChunkSubmitted {
+    pub accepted: Vec<i64>,
+    pub rejected: Vec<i64>,
+}
+
+/// Payload of `analysis-track-analyzed`, sent as soon as a track's results are in the DB.
+#[derive(Clone, Serialize)]
+pub struct AnalysisTrackAnalyzed {
+    pub track_id: i64,
+}
+
+struct QueuedFile {
+    track_id: i64,
+    path: String,
+    modified: SystemTime,
+}
+
+#[derive(Default)]
+struct AnalysisSession {
+    pending: VecDeque<QueuedFile>,
+    status: AnalysisStatus,
+}
+
+fn session() -> &'static Mutex<AnalysisSession> {
+    static SESSION: OnceLock<Mutex<AnalysisSession>> = OnceLock::new();
+    SESSION.get_or_init(Default::default)
+}
+
+fn with_session<T>(f: impl FnOnce(&mut AnalysisSession) -> T) -> Option<T> {
+    session().lock().ok().map(|mut s| f(&mut s))
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Camelot notation for a key such as "Am", "F# minor", "Dbmaj" or "8A".
+pub(crate) fn to_camelot(key: &str) -> Option<String> {
+    let key = key.trim();
+    let camelot = crate::import_export::camelot_key(key);
+    if !camelot.is_empty() {
+        return Some(camelot);
+    }
+
+    let mut chars = key.chars();
+    let mut pitch: i32 = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let rest = if let Some(r) = rest.strip_prefix(['#', '♯']) {
+        pitch += 1;
+        r
+    } else if let Some(r) = rest.strip_prefix(['b', '♭']) {
+        pitch -= 1;
+        r
+    } else {
+        rest
+    };
+    let mode = rest.trim().to_lowercase();
+    let minor = mode.starts_with('m') && !mode.starts_with("maj");
+    let pitch = pitch.rem_euclid(12);
+    let (offset, letter) = if minor { (4, 'A') } else { (7, 'B') };
+    Some(format!("{}{}", (pitch * 7 + offset) % 12 + 1, letter))
+}
+
+/// The user comment with `camelot` at the front, replacing a Camelot key already there.
+fn with_camelot_prefix(user_comment: &str, camelot: &str) -> String {
+    let user_comment = user_comment.trim();
+    let rest = if crate::import_export::camelot_key(user_comment).is_empty() {
+        user_comment
+    } else {
+        user_comment.split_once(char::is_whitespace).map(|(_, r)| r).unwrap_or("")
+    };
+    let rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '-').trim();
+    if rest.is_empty() { camelot.to_string() } else { format!("{} - {}", camelot, rest) }
+}
+
+impl AnalyzerConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Analyzer name cannot be empty".to_string());
+        }
+        if self.executable.trim().is_empty() {
+            return Err(format!("{}: no executable given", self.name));
+        }
+        if !self.args.iter().any(|a| a.contains(FILE_PLACEHOLDER)) {
+            return Err(format!("{}: the arguments must contain {}", self.name, FILE_PLACEHOLDER));
+        }
+        if let ResultStrategy::Regex { key_pattern, bpm_pattern } = &self.results {
+            for pattern in key_pattern.iter().chain(bpm_pattern) {
+                Regex::new(pattern).map_err(|e| format!("{}: invalid pattern '{}': {}", self.name, pattern, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_output(&self, stdout: &str) -> Result<AnalyzerReading, String> {
+        let reading = match &self.results {
+            ResultStrategy::FileTags => return Ok(AnalyzerReading::default()),
+            ResultStrategy::Regex { key_pattern, bpm_pattern } => {
+                let capture = |pattern: &Option<String>| -> Option<String> {
+                    let re = Regex::new(pattern.as_deref()?).ok()?;
+                    let caps = re.captures(stdout)?;
+                    caps.get(1).or_else(|| caps.get(0)).map(|m| m.as_str().trim().to_string())
+                };
+                AnalyzerReading {
+                    key: capture(key_pattern),
+                    bpm: capture(bpm_pattern).and_then(|b| b.parse().ok()),
+                }
+            }
+            ResultStrategy::Json { key_field, bpm_field } => {
+                let json: serde_json::Value = serde_json::from_str(stdout.trim())
+                    .map_err(|e| format!("Output is not JSON: {}", e))?;
+                let field = |path: &Option<String>| -> Option<&serde_json::Value> {
+                    path.as_deref()?.split('.').try_fold(&json, |v, part| v.get(part))
+                };
+                AnalyzerReading {
+                    key: field(key_field).and_then(|v| v.as_str()).map(str::to_string),
+                    bpm: field(bpm_field).and_then(|v| v.as_f64().or_else(|| v.as_str()?.parse().ok())),
+                }
+            }
+        };
+        if reading.key.is_none() && reading.bpm.is_none() {
+            return Err("No key or BPM in the analyzer's output".to_string());
+        }
+        Ok(reading)
+    }
+
+    /// Runs the analyzer on one file, killing it after `RUN_TIMEOUT`, so a hung analyzer
+    /// can't keep the worker busy forever.
+    fn run(&self, path: &str) -> Submission {
+        use std::io::Read;
+        use std::process::{Command, Stdio};
+
+        let args = self.args.iter().map(|a| a.replace(FILE_PLACEHOLDER, path));
+        let mut child = match Command::new(&self.executable)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => return Submission::Refused(format!("Failed to run {}: {}", self.executable, e)),
+        };
+
+        // Drain the pipes while waiting, or a chatty analyzer would block on a full pipe
+        let mut stdout = child.stdout.take();
+        let mut stderr = child.stderr.take();
+        let stdout_reader = std::thread::spawn(move || {
+            let mut out = String::new();
+            if let Some(pipe) = stdout.as_mut() {
+                let _ = pipe.read_to_string(&mut out);
+            }
+            out
+        });
+        let stderr_reader = std::thread::spawn(move || {
+            let mut err = String::new();
+            if let Some(pipe) = stderr.as_mut() {
+                let _ = pipe.read_to_string(&mut err);
+            }
+            err
+        });
+
+        let started = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if started.elapsed() > RUN_TIMEOUT => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Submission::Refused("timed out".to_string());
+                }
+                Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+                Err(e) => return Submission::Refused(format!("Failed to wait for {}: {}", self.executable, e)),
+            }
+        };
+
+        let stdout = stdout_reader.join().unwrap_or_default();
+        let stderr = stderr_reader.join().unwrap_or_default();
+        if !status.success() {
+            return Submission::Refused(stderr.trim().to_string());
+        }
+        match self.results {
+            ResultStrategy::FileTags => Submission::Pending,
+            _ => match self.parse_output(&stdout) {
+                Ok(reading) => Submission::Done(reading),
+                Err(e) => Submission::Refused(e),
+            },
+        }
+    }
+}
+
+impl ExternalAnalyzer for AnalyzerConfig {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn check_available(&self) -> Result<(), String> {
+        let path = Path::new(&self.executable);
+        // Bare names are looked up on PATH when run
+        if path.components().count() > 1 && !path.exists() {
+            return Err(format!("{}: {} not found", self.name, self.executable));
+        }
+        Ok(())
+    }
+
+    fn submit(&self, paths: &[&str]) -> Result<Vec<Submission>, String> {
+        use rayon::prelude::*;
+        Ok(paths.par_iter().map(|path| self.run(path)).collect())
+    }
+}
+
+/// Writes an analyzer's key/BPM to the file, the DB and Music.app. The key also goes to the
+/// front of the user comment in Camelot notation, where Mixed In Key puts it. The file is
+/// written before the DB writer lock is taken.
+fn apply_reading(state: &AppState, track_id: i64, reading: &AnalyzerReading) -> Result<(), String> {
+    let track = state.db.read().map_err(|_| "Failed to lock DB".to_string())?
+        .get_track(track_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Track {} not found", track_id))?;
+
+    let old_comment = track.comment_raw.clone().unwrap_or_default();
+    let new_comment = reading.key.as_deref().and_then(to_camelot).map(|camelot| {
+        let (user_comment, tags) = crate::comment::split_comment(&old_comment);
+        crate::comment::build_comment(&with_camelot_prefix(user_comment, &camelot), &tags)
+    }).filter(|c| *c != old_comment);
+
+    crate::metadata::write_track_info(&track.file_path, None, None, None, reading.bpm, reading.key.as_deref())
+        .map_err(|e| format!("Failed to write key/BPM to file: {}", e))?;
+    if let Some(comment) = &new_comment {
+        crate::metadata::write_metadata(&track.file_path, comment)
+            .map_err(|e| format!("Failed to write comment to file: {}", e))?;
+    }
+    if let Err(e) = touch_file(&track.file_path) {
+        tracing::warn!("Failed to touch file: {}", e);
+    }
+
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .update_track_info(track_id, None, None, None, reading.bpm, new_comment.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    if !track.persistent_id.is_empty() {
+        if let Some(bpm) = reading.bpm {
+            state.apple_queue.enqueue(AppleUpdate::TrackInfo {
+                persistent_id: track.persistent_id.clone(),
+                name: None,
+                artist: None,
+                album: None,
+                // Music.app only stores whole BPMs
+                bpm: Some(bpm.round() as i64),
+            });
+        }
+        if let Some(comment) = new_comment {
+            state.apple_queue.enqueue(AppleUpdate::Comment { persistent_id: track.persistent_id, comment });
+        }
+    }
+    Ok(())
+}
+
+fn record_result(app: &AppHandle, track_id: i64, result: Result<(), String>) {
+    match result {
+        Ok(()) => {
+            with_session(|s| s.status.analyzed.push(track_id));
+            let _ = app.emit("analysis-track-analyzed", AnalysisTrackAnalyzed { track_id });
+        }
+        Err(e) => {
+            tracing::error!("Failed to store analysis of track {}: {}", track_id, e);
+            with_session(|s| s.status.rejected.push(AnalysisRejection { track_id, reason: e }));
+        }
+    }
+}
+
+fn emit_status(app: &AppHandle) {
+    if let Some(status) = with_session(|s| s.status.clone()) {
+        let _ = app.emit("analysis-progress", status);
+    }
+}
+
+/// Waits until the analyzer has rewritten the files and reads each one back as soon as its
+/// modification time has moved and then held still for a poll. Returns the tracks that
+/// never changed.
+fn collect_rewritten(app: &AppHandle, files: Vec<QueuedFile>) -> Vec<i64> {
+    let deadline = Instant::now() + CHUNK_BASE_TIMEOUT + PER_FILE_TIMEOUT * files.len() as u32;
+    let mut waiting: HashMap<i64, (QueuedFile, Option<SystemTime>)> = files.into_iter()
+        .map(|file| (file.track_id, (file, None)))
+        .collect();
+
+    while !waiting.is_empty() && Instant::now() < deadline {
+        std::thread::sleep(POLL_INTERVAL);
+        let mut finished = Vec::new();
+        for (track_id, (file, last_seen)) in waiting.iter_mut() {
+            let Some(current) = modified(&file.path) else { continue };
+            if current <= file.modified {
+                continue;
+            }
+            // Still being written if it changed since the last poll
+            if *last_seen == Some(current) {
+                finished.push(*track_id);
+            }
+            *last_seen = Some(current);
+        }
+
+        for track_id in finished {
+            waiting.remove(&track_id);
+            let state = app.state::<AppState>();
+            let result = match state.db.lock() {
+                Ok(db) => crate::commands::refresh_track_metadata_from_file(&db, &state.apple_queue, track_id),
+                Err(_) => Err("Failed to lock DB".to_string()),
+            };
+            record_result(app, track_id, result);
+        }
+    }
+    waiting.into_keys().collect()
+}
+
+/// Feeds the queue to the analyzer chunk by chunk until it is empty.
+fn run_worker(app: AppHandle, analyzer: Arc<dyn ExternalAnalyzer>) {
+    loop {
+        let chunk: Vec<QueuedFile> = with_session(|s| {
+            let n = s.pending.len().min(CHUNK_SIZE);
+            let chunk: Vec<QueuedFile> = s.pending.drain(..n).collect();
+            s.status.queued = s.pending.len();
+            s.status.in_progress = chunk.iter().map(|f| f.track_id).collect();
+            if chunk.is_empty() {
+                s.status.running = false;
+            }
+            chunk
+        }).unwrap_or_default();
+        if chunk.is_empty() {
+            break;
+        }
+        emit_status(&app);
+        process_chunk(&app, analyzer.as_ref(), chunk);
+    }
+
+    analyzer.finish();
+    let status = with_session(|s| s.status.clone()).unwrap_or_default();
+    tracing::info!(
+        "{} analysis complete: {} analyzed, {} rejected, {} timed out",
+        analyzer.name(), status.analyzed.len(), status.rejected.len(), status.timed_out.len()
+    );
+    let _ = app.emit("analysis-complete", status);
+}
+
+/// Hands one chunk to the analyzer and stores its results, waiting for files it rewrites.
+fn process_chunk(app: &AppHandle, analyzer: &dyn ExternalAnalyzer, chunk: Vec<QueuedFile>) {
+    let paths: Vec<&str> = chunk.iter().map(|f| f.path.as_str()).collect();
+    let submissions = analyzer.submit(&paths).unwrap_or_else(|e| {
+        tracing::error!("{} did not take the chunk: {}", analyzer.name(), e);
+        paths.iter().map(|_| Submission::Refused(e.clone())).collect()
+    });
+
+    let mut accepted = Vec::new();
+    let mut refused = Vec::new();
+    let mut pending = Vec::new();
+    let mut done = Vec::new();
+    for (file, submission) in chunk.into_iter().zip(submissions) {
+        match submission {
+            Submission::Refused(reason) => refused.push(AnalysisRejection { track_id: file.track_id, reason }),
+            Submission::Pending => {
+                accepted.push(file.track_id);
+                pending.push(file);
+            }
+            Submission::Done(reading) => {
+                accepted.push(file.track_id);
+                done.push((file.track_id, reading));
+            }
+        }
+    }
+    let refused_ids: Vec<i64> = refused.iter().map(|r| r.track_id).collect();
+    with_session(|s| {
+        s.status.accepted.extend(&accepted);
+        s.status.rejected.extend(refused);
+    });
+    tracing::info!("{} accepted {} of {} file(s)", analyzer.name(), accepted.len(), accepted.len() + refused_ids.len());
+    let _ = app.emit("analysis-chunk-submitted", AnalysisChunkSubmitted { accepted, rejected: refused_ids });
+
+    for (track_id, reading) in done {
+        record_result(app, track_id, apply_reading(&app.state::<AppState>(), track_id, &reading));
+    }
+    let timed_out = collect_rewritten(app, pending);
+    if !timed_out.is_empty() {
+        tracing::error!("{} timeout: {} file(s) were not rewritten", analyzer.name(), timed_out.len());
+    }
+    with_session(|s| {
+        s.status.timed_out.extend(timed_out);
+        s.status.in_progress.clear();
+    });
+    emit_status(app);
+}
+
+/// Splits tracks into files an analyzer can be given and those turned away up front
+/// (unknown tracks, cloud-only tracks and missing files).
+fn queueable_files(state: &AppState, track_ids: Vec<i64>) -> Result<(Vec<QueuedFile>, Vec<AnalysisRejection>), String> {
+    let mut files = Vec::new();
+    let mut rejected = Vec::new();
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    for track_id in track_ids {
+        let Some(track) = db.get_track(track_id).map_err(|e| e.to_string())? else {
+            rejected.push(AnalysisRejection { track_id, reason: "Track not found".to_string() });
+            continue;
+        };
+        if track.is_cloud {
+            rejected.push(AnalysisRejection { track_id, reason: "Apple Music cloud track without a local file".to_string() });
+            continue;
+        }
+        match modified(&track.file_path) {
+            Some(modified) => files.push(QueuedFile { track_id, path: track.file_path, modified }),
+            None => rejected.push(AnalysisRejection { track_id, reason: format!("File not found: {}", track.file_path) }),
+        }
+    }
+    Ok((files, rejected))
+}
+
+/// Queues tracks for `analyzer`. Tracks whose file is missing are turned away here; the
+/// rest go to the analyzer in chunks on a background worker. Tracks added while the same
+/// analyzer is running join its queue.
+pub(crate) fn queue_analysis(
+    app: AppHandle,
+    state: &AppState,
+    analyzer: Arc<dyn ExternalAnalyzer>,
+    track_ids: Vec<i64>,
+) -> Result<AnalysisSubmission, String> {
+    analyzer.check_available()?;
+
+    let (files, rejected) = queueable_files(state, track_ids)?;
+    let queued: Vec<i64> = files.iter().map(|f| f.track_id).collect();
+
+    let start_worker = with_session(|s| {
+        if s.status.running && s.status.analyzer.as_deref() != Some(analyzer.name()) {
+            return Err(format!("{} is still analyzing; try again when it is done", s.status.analyzer.as_deref().unwrap_or_default()));
+        }
+        let start = !s.status.running;
+        if start {
+            s.status = AnalysisStatus { running: true, analyzer: Some(analyzer.name().to_string()), ..Default::default() };
+        }
+        s.status.rejected.extend(rejected.iter().cloned());
+        if !files.is_empty() {
+            s.pending.extend(files);
+            s.status.queued = s.pending.len();
+        } else if start {
+            s.status.running = false;
+        }
+        Ok(start && s.status.running)
+    }).ok_or_else(|| "Failed to lock analysis queue".to_string())??;
+
+    tracing::info!("Queued {} track(s) for {}, {} rejected", queued.len(), analyzer.name(), rejected.len());
+    if start_worker {
+        std::thread::spawn(move || run_worker(app, analyzer));
+    }
+    Ok(AnalysisSubmission { queued, rejected })
+}
+
+/// Runs tracks through an analyzer on the calling thread, a chunk at a time. Used by
+/// analysis jobs, which do their own queueing; results are recorded in the same status
+/// and events as a queued run.
+pub(crate) fn analyze_blocking(app: &AppHandle, analyzer: &dyn ExternalAnalyzer, track_ids: Vec<i64>) -> Result<(), String> {
+    analyzer.check_available()?;
+    let running = with_session(|s| s.status.running.then(|| s.status.analyzer.clone().unwrap_or_default())).flatten();
+    if let Some(running) = running {
+        return Err(format!("{} is still analyzing; try again when it is done", running));
+    }
+
+    let (files, rejected) = queueable_files(&app.state::<AppState>(), track_ids)?;
+    with_session(|s| s.status.rejected.extend(rejected));
+    let mut files = files.into_iter().peekable();
+    while files.peek().is_some() {
+        let chunk: Vec<QueuedFile> = files.by_ref().take(CHUNK_SIZE).collect();
+        with_session(|s| s.status.in_progress = chunk.iter().map(|f| f.track_id).collect());
+        process_chunk(app, analyzer, chunk);
+    }
+    Ok(())
+}
+
+fn load_analyzers(state: &AppState) -> Vec<AnalyzerConfig> {
+    state.db.read().ok()
+        .and_then(|db| db.get_setting(ANALYZERS_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Mixed In Key or the configured command-line analyzer called `name`.
+pub(crate) fn find_analyzer(state: &AppState, name: &str) -> Result<Arc<dyn ExternalAnalyzer>, String> {
+    if name.eq_ignore_ascii_case(crate::mixed_in_key::MIK_NAME) {
+        return Ok(Arc::new(crate::mixed_in_key::MixedInKey));
+    }
+    load_analyzers(state).into_iter()
+        .find(|a| a.name.eq_ignore_ascii_case(name))
+        .map(|config| Arc::new(config) as Arc<dyn ExternalAnalyzer>)
+        .ok_or_else(|| format!("No analyzer named {}", name))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_external_analyzers(state: State<'_, AppState>) -> Result<Vec<AnalyzerConfig>, String> {
+    Ok(load_analyzers(&state))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn set_external_analyzers(analyzers: Vec<AnalyzerConfig>, state: State<'_, AppState>) -> Result<(), String> {
+    for (i, analyzer) in analyzers.iter().enumerate() {
+        analyzer.validate()?;
+        if analyzer.name.eq_ignore_ascii_case(crate::mixed_in_key::MIK_NAME) {
+            return Err(format!("{} is built in", crate::mixed_in_key::MIK_NAME));
+        }
+        if analyzers[..i].iter().any(|a| a.name.eq_ignore_ascii_case(&analyzer.name)) {
+            return Err(format!("Duplicate analyzer name: {}", analyzer.name));
+        }
+    }
+    let json = serde_json::to_string(&analyzers).map_err(|e| e.to_string())?;
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_setting(ANALYZERS_KEY, &json).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Queues tracks for the configured analyzer called `analyzer`.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn analyze_with_external(
+    analyzer: String,
+    track_ids: Vec<i64>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<AnalysisSubmission, String> {
+    queue_analysis(app, &state, find_analyzer(&state, &analyzer)?, track_ids)
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_analysis_status() -> Result<AnalysisStatus, String> {
+    with_session(|s| s.status.clone()).ok_or_else(|| "Failed to lock analysis queue".to_string())
+}
@@ -0,0 +1,472 @@
+//! File-only library mode for people who don't use Music.app: a folder tree is indexed
+//! directly (tags read with lofty), playlists are the `.m3u`/`.m3u8` files inside it,
+//! and a watcher rescans when files change.
+//!
+//! Tracks and playlists get `file:<absolute path>` persistent IDs. While the mode is on,
+//! the sync queue hands its updates to `apply_file_update` instead of Music.app, so
+//! playlist edits rewrite the M3U files; tag edits already go to the audio files.
+
+use crate::apple_music::AppleUpdate;
+use crate::commands::AppState;
+use crate::db::Database;
+use crate::file_organizer::sanitize_component;
+use crate::models::{Playlist, Track};
+use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const FILE_LIBRARY_KEY: &str = "file_library_root";
+
+/// Folder (inside the library root) that new playlists are written to.
+const PLAYLISTS_DIR: &str = "Playlists";
+
+const PLAYLIST_EXTENSIONS: &[&str] = &["m3u", "m3u8"];
+
+/// Prefix of persistent IDs for tracks and playlists that only exist on disk.
+const FILE_PID_PREFIX: &str = "file:";
+
+/// Root of the file library; `Some` means file-only mode is on.
+static LIBRARY_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Dropping the watcher ends its debounce thread.
+#[derive(Default)]
+pub struct FileLibraryState {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct FileScanProgress {
+    pub processed: usize,
+    pub total: usize,
+    pub current: String,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct FileScanResult {
+    pub added: usize,
+    pub updated: usize,
+    /// Tracks under the root whose files are gone
+    pub missing: usize,
+    pub playlists: usize,
+}
+
+pub fn is_file_only() -> bool {
+    library_root().is_some()
+}
+
+pub fn library_root() -> Option<PathBuf> {
+    LIBRARY_ROOT.read().ok().and_then(|root| root.clone())
+}
+
+fn set_library_root(root: Option<PathBuf>) {
+    if let Ok(mut slot) = LIBRARY_ROOT.write() {
+        *slot = root;
+    }
+}
+
+/// Restores the saved library root. Called once at startup.
+pub fn load_file_library(state: &AppState) {
+    let root = state.db.read().ok()
+        .and_then(|db| db.get_setting(FILE_LIBRARY_KEY).ok().flatten())
+        .filter(|root| !root.is_empty())
+        .map(PathBuf::from);
+    set_library_root(root);
+}
+
+fn is_playlist_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| PLAYLIST_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn file_pid(path: &Path) -> String {
+    format!("{}{}", FILE_PID_PREFIX, path.to_string_lossy())
+}
+
+/// `target` relative to the folder `base`, falling back to the absolute path when the
+/// two don't share a root (e.g. different drives).
+fn relative_path(base: &Path, target: &Path) -> String {
+    let base: Vec<Component> = base.components().collect();
+    let target_parts: Vec<Component> = target.components().collect();
+    let common = base.iter().zip(&target_parts).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return target.to_string_lossy().to_string();
+    }
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for part in &target_parts[common..] {
+        relative.push(part);
+    }
+    relative.to_string_lossy().to_string()
+}
+
+/// Indexes every audio file and playlist under `root`. Unchanged files (same mtime)
+/// are not re-read; files that disappeared are marked missing rather than removed.
+fn scan(db: &crate::db::DbPool, root: &Path, progress: impl Fn(usize, usize, &Path)) -> Result<FileScanResult, String> {
+    let mut audio_files = Vec::new();
+    let mut playlist_files = Vec::new();
+    for entry in walkdir::WalkDir::new(root).follow_links(true).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.')) {
+            continue;
+        }
+        if crate::library_watcher::is_audio_file(path) {
+            audio_files.push(path.to_path_buf());
+        } else if is_playlist_file(path) {
+            playlist_files.push(path.to_path_buf());
+        }
+    }
+
+    let known: HashMap<String, Track> = {
+        let db = db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        db.get_all_tracks().map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|t| (t.file_path.clone(), t))
+            .collect()
+    };
+
+    let mut result = FileScanResult::default();
+    let mut changed = Vec::new();
+    let mut found_again = Vec::new();
+    let total = audio_files.len();
+    for (i, path) in audio_files.iter().enumerate() {
+        progress(i, total, path);
+        let path_str = path.to_string_lossy().to_string();
+        let existing = known.get(&path_str);
+        let mtime = std::fs::metadata(path).ok()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        if let Some(existing) = existing {
+            if existing.modified_date >= mtime {
+                if existing.missing {
+                    found_again.push(existing.id);
+                }
+                continue;
+            }
+        }
+
+        let mut track = match crate::metadata::read_file_track(path) {
+            Ok(track) => track,
+            Err(e) => {
+                tracing::warn!("Skipping {}: {}", path_str, e);
+                continue;
+            }
+        };
+        match existing {
+            Some(existing) => {
+                // Keep the row (and whatever library it came from) and its local history
+                track.persistent_id = existing.persistent_id.clone();
+                track.date_added = 0;
+                track.play_count = existing.play_count;
+                track.skip_count = existing.skip_count;
+                if existing.missing {
+                    found_again.push(existing.id);
+                }
+                result.updated += 1;
+            }
+            None => result.added += 1,
+        }
+        changed.push(track);
+    }
+    progress(total, total, Path::new(""));
+
+    let seen: HashSet<&str> = audio_files.iter().filter_map(|p| p.to_str()).collect();
+    let gone: Vec<i64> = known.values()
+        .filter(|t| !t.missing && Path::new(&t.file_path).starts_with(root) && !seen.contains(t.file_path.as_str()))
+        .map(|t| t.id)
+        .collect();
+    result.missing = gone.len();
+
+    let db = db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    db.insert_tracks(&changed).map_err(|e| e.to_string())?;
+    for id in found_again {
+        db.set_track_missing(id, false).map_err(|e| e.to_string())?;
+    }
+    for id in gone {
+        db.set_track_missing(id, true).map_err(|e| e.to_string())?;
+    }
+
+    let mut playlists = Vec::new();
+    for path in &playlist_files {
+        let (name, entries) = match crate::import_export::parse_m3u(path) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                tracing::warn!("Skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let track_pids = entries.iter()
+            .filter_map(|entry| db.get_track_by_path(entry).ok().flatten())
+            .map(|t| t.persistent_id)
+            .collect();
+        playlists.push(Playlist {
+            id: 0,
+            persistent_id: file_pid(path),
+            parent_persistent_id: None,
+            name: name.unwrap_or_else(|| path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()),
+            is_folder: false,
+            track_ids: Some(track_pids),
+            is_smart: false,
+        });
+    }
+    db.insert_playlists(&playlists).map_err(|e| e.to_string())?;
+    result.playlists = playlists.len();
+
+    let current: HashSet<String> = playlists.into_iter().map(|p| p.persistent_id).collect();
+    let stale: Vec<String> = db.get_playlists().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|p| p.persistent_id)
+        .filter(|pid| pid.starts_with(FILE_PID_PREFIX) && !current.contains(pid))
+        .collect();
+    db.remove_playlists_by_persistent_ids(&stale).map_err(|e| e.to_string())?;
+
+    Ok(result)
+}
+
+/// Scans the library root, emitting `file-scan-progress`. Shares the sync lock so it
+/// never overlaps another scan.
+fn run_scan(app: &AppHandle) -> Result<FileScanResult, String> {
+    let root = library_root().ok_or_else(|| "File-only mode is off".to_string())?;
+    if !root.is_dir() {
+        return Err(format!("Library folder not found: {}", root.display()));
+    }
+    let state = app.state::<AppState>();
+    if state.is_syncing.swap(true, Ordering::SeqCst) {
+        return Err("Sync already in progress".to_string());
+    }
+    let result = scan(&state.db, &root, |processed, total, current| {
+        let _ = app.emit("file-scan-progress", FileScanProgress {
+            processed,
+            total,
+            current: current.to_string_lossy().to_string(),
+        });
+    });
+    state.is_syncing.store(false, Ordering::SeqCst);
+
+    if let Ok(result) = &result {
+        tracing::info!(
+            "File library scan: {} added, {} updated, {} missing, {} playlists",
+            result.added, result.updated, result.missing, result.playlists
+        );
+    }
+    result
+}
+
+/// The M3U entries of a playlist file as absolute paths; a file that doesn't exist yet is empty.
+fn read_playlist(path: &Path) -> anyhow::Result<(Option<String>, Vec<String>)> {
+    if !path.exists() {
+        return Ok((None, Vec::new()));
+    }
+    crate::import_export::parse_m3u(path).map_err(|e| anyhow::anyhow!(e))
+}
+
+/// Writes an extended M3U with paths relative to the playlist's folder.
+fn write_playlist(db: &Database, path: &Path, name: &str, entries: &[String]) -> anyhow::Result<()> {
+    let base = path.parent().unwrap_or(Path::new("/"));
+    let mut out = format!("#EXTM3U\n#PLAYLIST:{}\n", name);
+    for entry in entries {
+        if let Ok(Some(track)) = db.get_track_by_path(entry) {
+            out.push_str(&format!(
+                "#EXTINF:{},{} - {}\n",
+                track.duration_secs.round() as i64,
+                track.artist.as_deref().unwrap_or("Unknown Artist"),
+                track.title.as_deref().unwrap_or("Untitled"),
+            ));
+        }
+        out.push_str(&relative_path(base, Path::new(entry)));
+        out.push('\n');
+    }
+    std::fs::write(path, out)?;
+    Ok(())
+}
+
+fn track_path(db: &Database, track_pid: &str) -> anyhow::Result<String> {
+    let id = db.get_track_id_by_persistent_id(track_pid)?
+        .ok_or_else(|| anyhow::anyhow!("Track {} not found", track_pid))?;
+    Ok(db.get_track_path(id)?)
+}
+
+/// Applies a queued update in file-only mode. Playlist membership and order are written
+/// to the M3U file; everything else already lives in the audio files and the DB.
+pub fn apply_file_update(db: &Database, update: &AppleUpdate) -> anyhow::Result<()> {
+    if let AppleUpdate::PlaylistAdds { adds } = update {
+        for (track_pid, playlist_pid) in adds {
+            apply_file_update(db, &AppleUpdate::AddToPlaylist {
+                track_pid: track_pid.clone(),
+                playlist_pid: playlist_pid.clone(),
+            })?;
+        }
+        return Ok(());
+    }
+
+    let playlist_pid = match update {
+        AppleUpdate::AddToPlaylist { playlist_pid, .. }
+        | AppleUpdate::RemoveFromPlaylist { playlist_pid, .. }
+        | AppleUpdate::ReorderPlaylist { playlist_pid, .. } => playlist_pid,
+        _ => return Ok(()),
+    };
+    let Some(file) = playlist_pid.strip_prefix(FILE_PID_PREFIX).map(PathBuf::from) else {
+        // A Music.app playlist left over from before the switch; nothing to write
+        return Ok(());
+    };
+
+    let (name, mut entries) = read_playlist(&file)?;
+    match update {
+        AppleUpdate::AddToPlaylist { track_pid, .. } => {
+            let path = track_path(db, track_pid)?;
+            if !entries.contains(&path) {
+                entries.push(path);
+            }
+        }
+        AppleUpdate::RemoveFromPlaylist { track_pid, .. } => {
+            let path = track_path(db, track_pid)?;
+            entries.retain(|e| e != &path);
+        }
+        AppleUpdate::ReorderPlaylist { track_pids, .. } => {
+            entries = track_pids.iter()
+                .map(|pid| track_path(db, pid))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+        }
+        _ => {}
+    }
+    let name = name.unwrap_or_else(|| file.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default());
+    write_playlist(db, &file, &name, &entries)
+}
+
+/// Creates an empty playlist file in the library's `Playlists` folder and returns its
+/// persistent ID.
+pub fn create_playlist_file(name: &str) -> anyhow::Result<String> {
+    let root = library_root().ok_or_else(|| anyhow::anyhow!("File-only mode is off"))?;
+    let dir = root.join(PLAYLISTS_DIR);
+    std::fs::create_dir_all(&dir)?;
+
+    let stem = sanitize_component(name);
+    let stem = if stem.is_empty() { "Untitled Playlist".to_string() } else { stem };
+    let mut file = dir.join(format!("{}.m3u8", stem));
+    let mut n = 2;
+    while file.exists() {
+        file = dir.join(format!("{} ({}).m3u8", stem, n));
+        n += 1;
+    }
+    std::fs::write(&file, format!("#EXTM3U\n#PLAYLIST:{}\n", name))?;
+    Ok(file_pid(&file))
+}
+
+/// (Re)starts watching the library root, or stops when file-only mode is off.
+/// Changes are debounced and trigger a rescan followed by `file-library-changed`.
+pub fn start_file_library_watcher(app: AppHandle) {
+    let watcher_state = app.state::<FileLibraryState>();
+    // Dropping the watcher ends its thread
+    if let Ok(mut slot) = watcher_state.watcher.lock() {
+        *slot = None;
+    }
+    let Some(root) = library_root() else { return };
+    if !root.is_dir() {
+        tracing::warn!("Library folder not found, not watching: {:?}", root);
+        return;
+    }
+
+    let (tx, rx) = channel();
+    let mut watcher = match RecommendedWatcher::new(tx, Config::default()) {
+        Ok(w) => w,
+        Err(e) => {
+            tracing::error!("Failed to create file library watcher: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(&root, RecursiveMode::Recursive) {
+        tracing::error!("Failed to watch {}: {}", root.display(), e);
+        return;
+    }
+    if let Ok(mut slot) = watcher_state.watcher.lock() {
+        *slot = Some(watcher);
+    }
+
+    std::thread::spawn(move || {
+        // Same trailing debounce as the library watcher
+        let debounce_duration = Duration::from_secs(2);
+        let mut pending = false;
+        loop {
+            let evt = if pending {
+                rx.recv_timeout(debounce_duration)
+            } else {
+                rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            };
+            match evt {
+                Ok(Ok(event)) => {
+                    if event.paths.iter().any(|p| crate::library_watcher::is_audio_file(p) || is_playlist_file(p)) {
+                        pending = true;
+                    }
+                }
+                Ok(Err(e)) => tracing::error!("Watch error: {:?}", e),
+                Err(RecvTimeoutError::Timeout) => {
+                    pending = false;
+                    match run_scan(&app) {
+                        Ok(result) => {
+                            let _ = app.emit("file-library-changed", result);
+                        }
+                        Err(e) => tracing::warn!("Rescan failed: {}", e),
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_file_library_root() -> Result<Option<String>, String> {
+    Ok(library_root().map(|root| root.to_string_lossy().to_string()))
+}
+
+/// Turns file-only mode on for `root` (and scans it), or off with `None`.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_file_library_root(
+    app: AppHandle,
+    root: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<FileScanResult>, String> {
+    let root = root.filter(|r| !r.trim().is_empty()).map(PathBuf::from);
+    if let Some(root) = &root {
+        if !root.is_dir() {
+            return Err(format!("Not a folder: {}", root.display()));
+        }
+    }
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        let value = root.as_ref().map(|r| r.to_string_lossy().to_string()).unwrap_or_default();
+        db.set_setting(FILE_LIBRARY_KEY, &value).map_err(|e| e.to_string())?;
+    }
+    let enabled = root.is_some();
+    set_library_root(root);
+    start_file_library_watcher(app.clone());
+    if !enabled {
+        return Ok(None);
+    }
+
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || run_scan(&handle))
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok(Some(result))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn scan_file_library(app: AppHandle) -> Result<FileScanResult, String> {
+    tauri::async_runtime::spawn_blocking(move || run_scan(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,277 @@
+//! Renames and moves audio files from metadata patterns like `{artist} - {title}`,
+//! keeping the DB and Music.app pointed at the new locations.
+
+use crate::apple_music::AppleUpdate;
+use crate::commands::AppState;
+use crate::models::Track;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// Placeholders understood by `render_pattern`.
+const PLACEHOLDERS: &[&str] = &["artist", "title", "album", "bpm", "key", "rating"];
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FileMove {
+    pub id: i64,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(serde::Serialize)]
+pub struct OrganizeResult {
+    /// Planned (dry run) or completed moves
+    pub moves: Vec<FileMove>,
+    /// Tracks already at their target path
+    pub unchanged: usize,
+    /// "path: error" for files that could not be moved
+    pub failed: Vec<String>,
+}
+
+/// Makes a metadata value safe to use as a single path component.
+pub(crate) fn sanitize_component(value: &str) -> String {
+    let cleaned: String = value.chars()
+        .map(|c| if c == '/' || c == ':' || c.is_control() { '_' } else { c })
+        .collect();
+    // Leading dots would hide the file; trailing dots and spaces confuse Finder/SMB
+    cleaned.trim().trim_start_matches('.').trim_end_matches(['.', ' ']).to_string()
+}
+
+fn placeholder_value(name: &str, track: &Track) -> String {
+    let value = match name {
+        "artist" => track.artist.clone().unwrap_or_default(),
+        "title" => track.title.clone().unwrap_or_default(),
+        "album" => track.album.clone().unwrap_or_default(),
+        "bpm" => if track.bpm > 0.0 { crate::metadata::format_bpm(track.bpm) } else { String::new() },
+        "key" => {
+            let (user_comment, _) = crate::comment::split_comment(track.comment_raw.as_deref().unwrap_or(""));
+            crate::import_export::camelot_key(user_comment)
+        }
+        "rating" => (track.rating / 20).to_string(),
+        _ => String::new(),
+    };
+    let value = sanitize_component(&value);
+    if value.is_empty() {
+        match name {
+            "artist" => "Unknown Artist".to_string(),
+            "album" => "Unknown Album".to_string(),
+            "title" => "Untitled".to_string(),
+            _ => value,
+        }
+    } else {
+        value
+    }
+}
+
+/// Expands `{placeholder}`s in `pattern`. `/` in the pattern separates folders;
+/// values themselves can never introduce one.
+fn render_pattern(pattern: &str, track: &Track) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..].find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in pattern: {}", pattern))?;
+        let name = &rest[start + 1..start + end];
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(format!("Unknown placeholder: {{{}}}", name));
+        }
+        out.push_str(&placeholder_value(name, track));
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+
+    let components: Vec<String> = out.split('/')
+        .map(sanitize_component)
+        .filter(|c| !c.is_empty())
+        .collect();
+    if components.is_empty() {
+        return Err(format!("Pattern produced an empty name: {}", pattern));
+    }
+    Ok(components.join("/"))
+}
+
+/// Appends the source extension and, if another file already sits there, " (2)", " (3)", ...
+fn target_path(base: PathBuf, source: &Path, taken: &[PathBuf]) -> PathBuf {
+    let ext = source.extension().map(|e| e.to_string_lossy().to_string());
+    let with_ext = |suffix: &str| {
+        let mut name = base.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        name.push_str(suffix);
+        if let Some(ext) = &ext {
+            name.push('.');
+            name.push_str(ext);
+        }
+        base.with_file_name(name)
+    };
+
+    let mut candidate = with_ext("");
+    let mut n = 2;
+    while candidate != source && (candidate.exists() || taken.contains(&candidate)) {
+        candidate = with_ext(&format!(" ({})", n));
+        n += 1;
+    }
+    candidate
+}
+
+/// Renames, falling back to copy + delete only when moving across volumes. Never replaces
+/// an existing file, and the source is only removed once the copy has its full length.
+fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    use std::io::{Error, ErrorKind};
+
+    if to.exists() {
+        return Err(Error::new(ErrorKind::AlreadyExists, format!("{} already exists", to.display())));
+    }
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+            let expected = std::fs::metadata(from)?.len();
+            let copied = std::fs::copy(from, to)?;
+            if copied != expected || std::fs::metadata(to)?.len() != expected {
+                let _ = std::fs::remove_file(to);
+                return Err(Error::other(format!("copy is incomplete ({} of {} bytes)", copied, expected)));
+            }
+            std::fs::remove_file(from)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Plans moves for `tracks` using `target_for`, then performs them unless `dry_run`.
+fn organize(
+    state: &AppState,
+    tracks: Vec<Track>,
+    dry_run: bool,
+    target_for: impl Fn(&Track) -> Result<PathBuf, String>,
+) -> Result<OrganizeResult, String> {
+    let mut moves = Vec::new();
+    let mut taken: Vec<PathBuf> = Vec::new();
+    let mut unchanged = 0;
+    let mut failed = Vec::new();
+
+    for track in &tracks {
+        if track.missing || !Path::new(&track.file_path).exists() {
+            failed.push(format!("{}: file not found", track.file_path));
+            continue;
+        }
+        let source = Path::new(&track.file_path);
+        let target = target_path(target_for(track)?, source, &taken);
+        if target == source {
+            unchanged += 1;
+            continue;
+        }
+        taken.push(target.clone());
+        moves.push(FileMove {
+            id: track.id,
+            from: track.file_path.clone(),
+            to: target.to_string_lossy().to_string(),
+        });
+    }
+
+    if dry_run {
+        return Ok(OrganizeResult { moves, unchanged, failed });
+    }
+
+    let mut done = Vec::new();
+    for mv in moves {
+        if let Err(e) = move_file(Path::new(&mv.from), Path::new(&mv.to)) {
+            failed.push(format!("{}: {}", mv.from, e));
+            continue;
+        }
+
+        let persistent_id = match state.db.lock() {
+            Ok(db) => {
+                if let Err(e) = db.update_track_path(mv.id, &mv.to) {
+                    failed.push(format!("{}: moved but DB update failed: {}", mv.to, e));
+                }
+                db.get_track(mv.id).ok().flatten().map(|t| t.persistent_id).unwrap_or_default()
+            }
+            Err(_) => String::new(),
+        };
+        if !persistent_id.is_empty() {
+            state.apple_queue.enqueue(AppleUpdate::Location { persistent_id, path: mv.to.clone() });
+        }
+        done.push(mv);
+    }
+
+    Ok(OrganizeResult { moves: done, unchanged, failed })
+}
+
+fn load_tracks(state: &AppState, ids: Option<&[i64]>) -> Result<Vec<Track>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    match ids {
+        Some(ids) => Ok(ids.iter().filter_map(|id| db.get_track(*id).ok().flatten()).collect()),
+        None => db.get_all_tracks().map_err(|e| e.to_string()),
+    }
+}
+
+/// Renames files in place from `pattern` (e.g. `{artist} - {title}`); the extension is kept.
+/// With `dry_run` nothing is touched and the planned renames are returned.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn rename_files_from_pattern(
+    app: AppHandle,
+    ids: Vec<i64>,
+    pattern: String,
+    dry_run: Option<bool>,
+) -> Result<OrganizeResult, String> {
+    if pattern.contains('/') {
+        return Err("Rename patterns cannot contain '/'; use organize_into_folders to move files".to_string());
+    }
+    let dry_run = dry_run.unwrap_or(false);
+
+    let task_pattern = pattern.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let tracks = load_tracks(&state, Some(&ids))?;
+        organize(&state, tracks, dry_run, |track| {
+            let name = render_pattern(&task_pattern, track)?;
+            Ok(Path::new(&track.file_path).with_file_name(name))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    if !dry_run {
+        tracing::info!("Renamed {} files from pattern '{}' ({} failed)", result.moves.len(), pattern, result.failed.len());
+    }
+    Ok(result)
+}
+
+/// Moves files into `root` following a folder pattern such as `{artist}/{album}/{title}`.
+/// Applies to `ids`, or the whole library when omitted.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn organize_into_folders(
+    app: AppHandle,
+    root: String,
+    pattern: String,
+    ids: Option<Vec<i64>>,
+    dry_run: Option<bool>,
+) -> Result<OrganizeResult, String> {
+    let root = PathBuf::from(&root);
+    if !root.is_dir() {
+        return Err(format!("Folder does not exist: {}", root.display()));
+    }
+    let dry_run = dry_run.unwrap_or(false);
+
+    let (task_root, task_pattern) = (root.clone(), pattern.clone());
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let tracks = load_tracks(&state, ids.as_deref())?;
+        organize(&state, tracks, dry_run, |track| {
+            Ok(task_root.join(render_pattern(&task_pattern, track)?))
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    if !dry_run {
+        tracing::info!(
+            "Organized {} files into {} with pattern '{}' ({} failed)",
+            result.moves.len(), root.display(), pattern, result.failed.len()
+        );
+    }
+    Ok(result)
+}
@@ -0,0 +1,153 @@
+//! Files tag writes can't touch: read-only, locked in Finder, or owned by another user.
+//! Writers check up front (see `metadata::TagWriteErrorKind`) instead of failing halfway
+//! with whatever lofty or the rename reports; `fix_permissions` makes the user's own
+//! files writable again on request.
+
+use crate::commands::AppState;
+use crate::metadata::TagWriteErrorKind;
+use serde::Serialize;
+use std::path::Path;
+use tauri::State;
+
+/// `UF_IMMUTABLE` from <sys/stat.h>: the "Locked" checkbox in Finder's Get Info
+#[cfg(target_os = "macos")]
+const UF_IMMUTABLE: u32 = 0x0000_0002;
+
+#[cfg(target_os = "macos")]
+fn is_locked(meta: &std::fs::Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    meta.st_flags() & UF_IMMUTABLE != 0
+}
+
+#[cfg(not(target_os = "macos"))]
+fn is_locked(_meta: &std::fs::Metadata) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn is_writable(path: &Path, _meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    match std::ffi::CString::new(path.as_os_str().as_bytes()) {
+        Ok(c_path) => unsafe { libc::access(c_path.as_ptr(), libc::W_OK) == 0 },
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_writable(_path: &Path, meta: &std::fs::Metadata) -> bool {
+    !meta.permissions().readonly()
+}
+
+#[cfg(unix)]
+fn is_owned(meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    meta.uid() == unsafe { libc::geteuid() }
+}
+
+#[cfg(not(unix))]
+fn is_owned(_meta: &std::fs::Metadata) -> bool {
+    true
+}
+
+/// Why `path` can't be written, if it can't. Files that don't exist pass; the write
+/// reports those itself.
+pub fn write_block(path: &Path) -> Option<(TagWriteErrorKind, String)> {
+    let meta = std::fs::metadata(path).ok()?;
+    if is_locked(&meta) {
+        return Some((TagWriteErrorKind::Locked, format!("{:?} is locked in Finder", path)));
+    }
+    if is_writable(path, &meta) {
+        return None;
+    }
+    Some(if is_owned(&meta) {
+        (TagWriteErrorKind::ReadOnly, format!("{:?} is read-only", path))
+    } else {
+        (TagWriteErrorKind::NotOwner, format!("{:?} belongs to another user and is not writable", path))
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn unlock(path: &Path, meta: &std::fs::Metadata) -> Result<(), String> {
+    use std::os::macos::fs::MetadataExt;
+    use std::os::unix::ffi::OsStrExt;
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+    if unsafe { libc::chflags(c_path.as_ptr(), (meta.st_flags() & !UF_IMMUTABLE) as _) } != 0 {
+        return Err(format!("Failed to unlock: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn unlock(_path: &Path, _meta: &std::fs::Metadata) -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_writable(path: &Path, meta: &std::fs::Metadata) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = meta.permissions();
+    permissions.set_mode(permissions.mode() | 0o200);
+    std::fs::set_permissions(path, permissions).map_err(|e| format!("Failed to chmod: {}", e))
+}
+
+#[cfg(not(unix))]
+#[allow(clippy::permissions_set_readonly_false)]
+fn make_writable(path: &Path, meta: &std::fs::Metadata) -> Result<(), String> {
+    let mut permissions = meta.permissions();
+    permissions.set_readonly(false);
+    std::fs::set_permissions(path, permissions).map_err(|e| format!("Failed to clear read-only: {}", e))
+}
+
+/// Unlocks and adds owner write permission to a file the user owns. Returns whether
+/// anything had to change.
+fn fix_file(path: &Path) -> Result<bool, String> {
+    let meta = std::fs::metadata(path).map_err(|_| "File not found".to_string())?;
+    if !is_owned(&meta) {
+        return Err("Owned by another user".to_string());
+    }
+    let locked = is_locked(&meta);
+    if locked {
+        unlock(path, &meta)?;
+    }
+    let read_only = !is_writable(path, &meta);
+    if read_only {
+        make_writable(path, &meta)?;
+    }
+    Ok(locked || read_only)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionFixResult {
+    pub fixed: usize,
+    /// Files left alone (another user's, or the change failed), with the reason
+    pub skipped: Vec<(i64, String)>,
+}
+
+/// Makes the files of `ids` writable: unlocks them in Finder and adds owner write
+/// permission. Only files the user owns are changed.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn fix_permissions(ids: Vec<i64>, state: State<'_, AppState>) -> Result<PermissionFixResult, String> {
+    let tracks: Vec<(i64, String)> = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        ids.iter()
+            .filter_map(|id| db.get_track(*id).ok().flatten())
+            .map(|t| (t.id, t.file_path))
+            .collect()
+    };
+
+    let mut result = PermissionFixResult { fixed: 0, skipped: Vec::new() };
+    for (id, path) in tracks {
+        match fix_file(Path::new(&path)) {
+            Ok(true) => result.fixed += 1,
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!("Failed to fix permissions of {}: {}", path, e);
+                result.skipped.push((id, e));
+            }
+        }
+    }
+
+    tracing::info!("Fixed permissions of {} file(s), {} skipped", result.fixed, result.skipped.len());
+    Ok(result)
+}
@@ -0,0 +1,235 @@
+//! Genre and energy estimates from the audio itself, using an ONNX model bundled as a
+//! resource. Optional: built with the `genre-model` feature, and the model files are
+//! shipped by adding `tauri.genre-model.conf.json` to the build config.
+//!
+//! Model contract: input is mono float32 audio at 16 kHz, shape `[1, WINDOW_SAMPLES]`;
+//! output `genre` holds one probability per line of `genre_labels.txt`, and an optional
+//! output `energy` holds a single 0.0–1.0 value. See `Docs/GenreClassification.md` for
+//! where the model files go.
+
+use crate::commands::AppState;
+use crate::models::Track;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Suggestions below this confidence are dropped unless the caller asks for a lower threshold.
+const DEFAULT_MIN_CONFIDENCE: f32 = 0.2;
+
+const MAX_SUGGESTIONS: usize = 3;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct GenreSuggestion {
+    /// Genre name, usable as a tag
+    pub tag: String,
+    pub confidence: f32,
+}
+
+#[derive(serde::Serialize)]
+pub struct TrackClassification {
+    pub track_id: i64,
+    /// Best first
+    pub suggestions: Vec<GenreSuggestion>,
+    /// 1–10, on the same scale as Mixed In Key's energy level
+    pub energy: Option<u8>,
+    /// Set when the track could not be analyzed
+    pub error: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ClassifyProgress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+/// Suggests genre tags (with confidence) and an energy level for each track. By default
+/// nothing is written and suggestions are returned for review; with `apply_tags` the
+/// suggested tags are added to the tracks as one undoable batch. Emits `classify-progress`.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn classify_tracks(
+    app: AppHandle,
+    ids: Vec<i64>,
+    min_confidence: Option<f32>,
+    apply_tags: Option<bool>,
+) -> Result<Vec<TrackClassification>, String> {
+    let min_confidence = min_confidence.unwrap_or(DEFAULT_MIN_CONFIDENCE);
+    let handle = app.clone();
+    let results = tauri::async_runtime::spawn_blocking(move || -> Result<Vec<TrackClassification>, String> {
+        let state = handle.state::<AppState>();
+        let tracks: Vec<(i64, String)> = {
+            let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+            ids.iter()
+                .filter_map(|id| db.get_track(*id).ok().flatten())
+                .map(|t| (t.id, t.file_path))
+                .collect()
+        };
+
+        let mut classifier = model::Classifier::load(&handle)?;
+        let total = tracks.len();
+        let mut results = Vec::with_capacity(total);
+        for (i, (track_id, file_path)) in tracks.into_iter().enumerate() {
+            let result = match classifier.classify(&file_path) {
+                Ok((scores, energy)) => {
+                    let mut suggestions: Vec<GenreSuggestion> = scores.into_iter()
+                        .filter(|s| s.confidence >= min_confidence)
+                        .collect();
+                    suggestions.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+                    suggestions.truncate(MAX_SUGGESTIONS);
+                    TrackClassification {
+                        track_id,
+                        suggestions,
+                        energy: energy.map(|e| (e.clamp(0.0, 1.0) * 9.0).round() as u8 + 1),
+                        error: None,
+                    }
+                }
+                Err(e) => TrackClassification { track_id, suggestions: Vec::new(), energy: None, error: Some(e) },
+            };
+            results.push(result);
+            let _ = handle.emit("classify-progress", ClassifyProgress { processed: i + 1, total });
+        }
+        Ok(results)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let classified = results.iter().filter(|r| r.error.is_none()).count();
+    tracing::info!("Genre classification: analyzed {} of {} tracks", classified, results.len());
+
+    if apply_tags.unwrap_or(false) {
+        let state = app.state::<AppState>();
+        let tagged = crate::commands::apply_comment_batch(&app, &state, suggested_tag_changes(&state, &results)?).await?;
+        tracing::info!("Genre classification: tagged {} track(s)", tagged);
+    }
+    Ok(results)
+}
+
+/// Comment changes adding each track's suggested genres as tags, normalized like typed tags.
+fn suggested_tag_changes(state: &AppState, results: &[TrackClassification]) -> Result<Vec<(Track, String)>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let mut changes = Vec::new();
+    for result in results.iter().filter(|r| !r.suggestions.is_empty()) {
+        let Some(track) = db.get_track(result.track_id).map_err(|e| e.to_string())? else { continue };
+        let tags: Vec<String> = result.suggestions.iter()
+            .map(|s| crate::commands::canonical_tag(&db, &s.tag))
+            .filter(|t| !t.is_empty())
+            .collect();
+        let old_comment = track.comment_raw.clone().unwrap_or_default();
+        if let Some(new_comment) = crate::comment::add_tags(&old_comment, &tags) {
+            changes.push((track, new_comment));
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(feature = "genre-model")]
+mod model {
+    use super::GenreSuggestion;
+    use ort::session::Session;
+    use ort::value::Tensor;
+    use tauri::path::BaseDirectory;
+    use tauri::{AppHandle, Manager};
+
+    const MODEL_RESOURCE: &str = "models/genre.onnx";
+    const LABELS_RESOURCE: &str = "models/genre_labels.txt";
+
+    const SAMPLE_RATE: u32 = 16_000;
+
+    /// 10 seconds per window
+    const WINDOW_SAMPLES: usize = 160_000;
+
+    /// Windows are taken at these points of the track and their scores averaged, so an
+    /// ambient intro doesn't decide the genre on its own.
+    const WINDOW_POSITIONS: [f64; 3] = [0.25, 0.5, 0.75];
+
+    pub struct Classifier {
+        session: Session,
+        labels: Vec<String>,
+    }
+
+    impl Classifier {
+        pub fn load(app: &AppHandle) -> Result<Self, String> {
+            let resolve = |resource: &str| {
+                app.path().resolve(resource, BaseDirectory::Resource).map_err(|e| e.to_string())
+            };
+            let model_path = resolve(MODEL_RESOURCE)?;
+            let labels = std::fs::read_to_string(resolve(LABELS_RESOURCE)?)
+                .map_err(|e| format!("Failed to read genre labels: {}", e))?
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(str::to_string)
+                .collect();
+            let session = Session::builder()
+                .and_then(|b| b.commit_from_file(&model_path))
+                .map_err(|e| format!("Failed to load genre model: {}", e))?;
+            Ok(Self { session, labels })
+        }
+
+        /// Per-label scores averaged over the analysis windows, plus energy if the model has it.
+        pub fn classify(&mut self, path: &str) -> Result<(Vec<GenreSuggestion>, Option<f32>), String> {
+            let audio = crate::audio::decode_mono(path, SAMPLE_RATE).map_err(|e| format!("Failed to decode audio: {}", e))?;
+            let mut totals = vec![0.0f32; self.labels.len()];
+            let mut energy_total = 0.0f32;
+            let mut has_energy = false;
+
+            for position in WINDOW_POSITIONS {
+                let window = window_at(&audio, position);
+                let input = Tensor::from_array(([1usize, WINDOW_SAMPLES], window)).map_err(|e| e.to_string())?;
+                let outputs = self.session.run(ort::inputs![input].map_err(|e| e.to_string())?)
+                    .map_err(|e| format!("Genre model failed: {}", e))?;
+
+                let (_, scores) = outputs["genre"].try_extract_raw_tensor::<f32>().map_err(|e| e.to_string())?;
+                if scores.len() != totals.len() {
+                    return Err(format!("Genre model returned {} scores for {} labels", scores.len(), totals.len()));
+                }
+                for (total, score) in totals.iter_mut().zip(scores) {
+                    *total += score;
+                }
+                if let Some(energy) = outputs.get("energy") {
+                    let (_, value) = energy.try_extract_raw_tensor::<f32>().map_err(|e| e.to_string())?;
+                    if let Some(v) = value.first() {
+                        energy_total += v;
+                        has_energy = true;
+                    }
+                }
+            }
+
+            let windows = WINDOW_POSITIONS.len() as f32;
+            let scores = self.labels.iter().zip(totals)
+                .map(|(label, total)| GenreSuggestion { tag: label.clone(), confidence: total / windows })
+                .collect();
+            Ok((scores, has_energy.then(|| energy_total / windows)))
+        }
+    }
+
+    /// `WINDOW_SAMPLES` centered on `position` (0.0–1.0), zero-padded for short tracks.
+    fn window_at(audio: &[f32], position: f64) -> Vec<f32> {
+        let mut window = vec![0.0f32; WINDOW_SAMPLES];
+        if audio.len() <= WINDOW_SAMPLES {
+            window[..audio.len()].copy_from_slice(audio);
+            return window;
+        }
+        let center = (audio.len() as f64 * position) as usize;
+        let start = center.saturating_sub(WINDOW_SAMPLES / 2).min(audio.len() - WINDOW_SAMPLES);
+        window.copy_from_slice(&audio[start..start + WINDOW_SAMPLES]);
+        window
+    }
+}
+
+#[cfg(not(feature = "genre-model"))]
+mod model {
+    use super::GenreSuggestion;
+    use tauri::AppHandle;
+
+    /// Uninhabited: `load` always fails, so there is never a classifier to call.
+    pub enum Classifier {}
+
+    impl Classifier {
+        pub fn load(_app: &AppHandle) -> Result<Self, String> {
+            Err("Genre classification is not included in this build (enable the genre-model feature)".to_string())
+        }
+
+        pub fn classify(&mut self, _path: &str) -> Result<(Vec<GenreSuggestion>, Option<f32>), String> {
+            match *self {}
+        }
+    }
+}
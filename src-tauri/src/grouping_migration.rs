@@ -0,0 +1,189 @@
+//! Moving off the Grouping mirror. Older versions copied the tag block into Grouping;
+//! the comment is the source of truth now. This audits how far Grouping has drifted
+//! from the comment tags and either re-mirrors or wipes it across the library in one
+//! undoable batch.
+
+use crate::apple_music::AppleUpdate;
+use crate::commands::AppState;
+use crate::models::Track;
+use crate::undo::{Action, GroupingState};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupingStatus {
+    /// Grouping holds exactly the comment tags (or both are empty)
+    InSync,
+    /// Comment has tags, Grouping is empty
+    Missing,
+    /// Both set, but the tag lists differ
+    Stale,
+    /// Grouping is set but the comment has no tags
+    Orphaned,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupingMigrationMode {
+    /// Copy the comment tags into Grouping
+    Mirror,
+    /// Clear Grouping
+    Wipe,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupingMismatch {
+    pub track_id: i64,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub status: GroupingStatus,
+    pub grouping: String,
+    /// What a mirror would write
+    pub expected: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GroupingAudit {
+    pub total: usize,
+    pub in_sync: usize,
+    pub missing: usize,
+    pub stale: usize,
+    pub orphaned: usize,
+    pub mismatches: Vec<GroupingMismatch>,
+}
+
+/// The comment tags as Grouping would hold them.
+fn mirrored_grouping(track: &Track) -> String {
+    let (_, tags) = crate::comment::split_comment(track.comment_raw.as_deref().unwrap_or_default());
+    tags.join("; ")
+}
+
+fn grouping_status(track: &Track) -> GroupingStatus {
+    let grouping: Vec<String> = track.grouping_raw.as_deref().unwrap_or_default()
+        .split(';')
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    let (_, tags) = crate::comment::split_comment(track.comment_raw.as_deref().unwrap_or_default());
+    let tags: Vec<String> = tags.iter().map(|t| t.to_lowercase()).collect();
+
+    match (grouping.is_empty(), tags.is_empty()) {
+        (true, true) => GroupingStatus::InSync,
+        (true, false) => GroupingStatus::Missing,
+        (false, true) => GroupingStatus::Orphaned,
+        (false, false) => {
+            let same = grouping.len() == tags.len() && grouping.iter().all(|g| tags.contains(g));
+            if same { GroupingStatus::InSync } else { GroupingStatus::Stale }
+        }
+    }
+}
+
+/// Compares every track's Grouping with its comment tags.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn audit_grouping(state: State<'_, AppState>) -> Result<GroupingAudit, String> {
+    let tracks = state.db.read().map_err(|_| "Failed to lock DB".to_string())?
+        .get_all_tracks().map_err(|e| e.to_string())?;
+
+    let mut audit = GroupingAudit { total: tracks.len(), ..Default::default() };
+    for track in &tracks {
+        let status = grouping_status(track);
+        match status {
+            GroupingStatus::InSync => {
+                audit.in_sync += 1;
+                continue;
+            }
+            GroupingStatus::Missing => audit.missing += 1,
+            GroupingStatus::Stale => audit.stale += 1,
+            GroupingStatus::Orphaned => audit.orphaned += 1,
+        }
+        audit.mismatches.push(GroupingMismatch {
+            track_id: track.id,
+            artist: track.artist.clone(),
+            title: track.title.clone(),
+            status,
+            grouping: track.grouping_raw.clone().unwrap_or_default(),
+            expected: mirrored_grouping(track),
+        });
+    }
+    Ok(audit)
+}
+
+/// Re-mirrors the comment tags into Grouping, or wipes Grouping, for `ids` or the whole
+/// library. Files, the DB and Music.app are updated as one undo step; returns the number
+/// of tracks changed.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn migrate_grouping(
+    mode: GroupingMigrationMode,
+    ids: Option<Vec<i64>>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    use rayon::prelude::*;
+
+    let tracks = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        match ids {
+            Some(ids) => ids.iter().filter_map(|id| db.get_track(*id).ok().flatten()).collect(),
+            None => db.get_all_tracks().map_err(|e| e.to_string())?,
+        }
+    };
+
+    let changes: Vec<GroupingState> = tracks.iter()
+        .filter_map(|track| {
+            let old_grouping = track.grouping_raw.clone().unwrap_or_default();
+            let new_grouping = match mode {
+                GroupingMigrationMode::Mirror => mirrored_grouping(track),
+                GroupingMigrationMode::Wipe => String::new(),
+            };
+            (old_grouping != new_grouping).then(|| GroupingState {
+                id: track.id,
+                persistent_id: track.persistent_id.clone(),
+                file_path: track.file_path.clone(),
+                old_grouping,
+                new_grouping,
+            })
+        })
+        .collect();
+    let planned = changes.len();
+
+    let written: Vec<GroupingState> = tauri::async_runtime::spawn_blocking(move || {
+        changes.into_par_iter()
+            // Apple Music cloud tracks (no file, empty path) only change in Music.app
+            .filter(|change| change.file_path.is_empty() || match crate::metadata::write_grouping(&change.file_path, &change.new_grouping) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::warn!("Failed to write grouping of {}: {}", change.file_path, e);
+                    false
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if written.is_empty() {
+        return Ok(0);
+    }
+
+    let db_updates: Vec<(i64, String)> = written.iter().map(|c| (c.id, c.new_grouping.clone())).collect();
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .update_track_groupings(&db_updates)
+        .map_err(|e| format!("DB Error (update groupings): {}", e))?;
+
+    let updates: Vec<(String, String)> = written.iter()
+        .filter(|c| !c.persistent_id.is_empty())
+        .map(|c| (c.persistent_id.clone(), c.new_grouping.clone()))
+        .collect();
+    if !updates.is_empty() {
+        state.apple_queue.enqueue(AppleUpdate::Groupings { updates });
+    }
+
+    let updated = written.len();
+    if let Ok(mut stack) = state.undo_stack.lock() {
+        stack.push(Action::UpdateGroupings { tracks: written });
+    }
+    tracing::info!("Grouping migration ({:?}): updated {} of {} tracks", mode, updated, planned);
+    Ok(updated)
+}
@@ -0,0 +1,163 @@
+//! Audio fingerprint identification for files with no usable artist/title
+//! (`audio_final_v3.mp3`). The fingerprint comes from Chromaprint's `fpcalc` tool and is
+//! matched against AcoustID, which links to MusicBrainz recordings.
+
+use crate::commands::AppState;
+use crate::online_metadata::{get_json, non_empty, MetadataApiKeys};
+use std::path::Path;
+use std::process::Command;
+use tauri::{AppHandle, State};
+
+const ACOUSTID_LOOKUP_URL: &str = "https://api.acoustid.org/v2/lookup";
+
+/// Apps started from Finder don't inherit the shell PATH, so check Homebrew's locations too.
+const FPCALC_LOCATIONS: &[&str] = &["fpcalc", "/opt/homebrew/bin/fpcalc", "/usr/local/bin/fpcalc"];
+
+const MAX_CANDIDATES: usize = 5;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct IdentifyCandidate {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    /// AcoustID match score, 0.0–1.0
+    pub score: f64,
+    pub musicbrainz_id: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Fingerprint {
+    duration: f64,
+    fingerprint: String,
+}
+
+#[derive(serde::Deserialize)]
+struct LookupResponse {
+    status: String,
+    #[serde(default)]
+    results: Vec<LookupResult>,
+    error: Option<LookupError>,
+}
+
+#[derive(serde::Deserialize)]
+struct LookupError {
+    message: String,
+}
+
+#[derive(serde::Deserialize)]
+struct LookupResult {
+    score: f64,
+    #[serde(default)]
+    recordings: Vec<Recording>,
+}
+
+#[derive(serde::Deserialize)]
+struct Recording {
+    id: String,
+    title: Option<String>,
+    #[serde(default)]
+    artists: Vec<Artist>,
+    #[serde(default)]
+    releasegroups: Vec<ReleaseGroup>,
+}
+
+#[derive(serde::Deserialize)]
+struct Artist {
+    name: String,
+    joinphrase: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ReleaseGroup {
+    title: Option<String>,
+}
+
+fn fingerprint(path: &str) -> Result<Fingerprint, String> {
+    if !Path::new(path).exists() {
+        return Err(format!("File not found: {}", path));
+    }
+    for fpcalc in FPCALC_LOCATIONS {
+        let output = match Command::new(fpcalc).arg("-json").arg(path).output() {
+            Ok(output) => output,
+            Err(_) => continue,
+        };
+        if !output.status.success() {
+            return Err(format!("fpcalc failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        return serde_json::from_slice(&output.stdout).map_err(|e| format!("Unexpected fpcalc output: {}", e));
+    }
+    Err("fpcalc not found; install Chromaprint (brew install chromaprint)".to_string())
+}
+
+/// Fingerprints the track's audio and returns the artist/title matches AcoustID knows,
+/// best first. Nothing is written; apply a match with `apply_identification`.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn identify_track(track_id: i64, state: State<'_, AppState>) -> Result<Vec<IdentifyCandidate>, String> {
+    let (file_path, client_key) = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let track = db.get_track(track_id).map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Track {} not found", track_id))?;
+        (track.file_path, MetadataApiKeys::load(&db).acoustid_key)
+    };
+    let client_key = non_empty(client_key).ok_or_else(|| "No AcoustID API key configured".to_string())?;
+
+    let path = file_path.clone();
+    let print = tauri::async_runtime::spawn_blocking(move || fingerprint(&path))
+        .await
+        .map_err(|e| e.to_string())??;
+    let duration = (print.duration.round() as i64).to_string();
+    let response: LookupResponse = get_json(
+        crate::artwork::http_client()?.post(ACOUSTID_LOOKUP_URL).form(&[
+            ("client", client_key.as_str()),
+            ("duration", duration.as_str()),
+            ("fingerprint", print.fingerprint.as_str()),
+            ("meta", "recordings releasegroups"),
+        ]),
+        "AcoustID",
+    ).await?;
+    if response.status != "ok" {
+        let message = response.error.map(|e| e.message).unwrap_or(response.status);
+        return Err(format!("AcoustID lookup failed: {}", message));
+    }
+
+    let mut candidates: Vec<IdentifyCandidate> = Vec::new();
+    for result in response.results {
+        for recording in result.recordings {
+            let Some(title) = non_empty(recording.title) else { continue };
+            // The same recording is often listed under several fingerprints
+            if candidates.iter().any(|c| c.musicbrainz_id == recording.id) {
+                continue;
+            }
+            let artist: String = recording.artists.iter()
+                .map(|a| format!("{}{}", a.name, a.joinphrase.as_deref().unwrap_or("")))
+                .collect();
+            candidates.push(IdentifyCandidate {
+                artist,
+                title,
+                album: recording.releasegroups.into_iter().find_map(|g| non_empty(g.title)),
+                score: result.score,
+                musicbrainz_id: recording.id,
+            });
+        }
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates.truncate(MAX_CANDIDATES);
+
+    tracing::info!("Identified {} candidate(s) for {}", candidates.len(), file_path);
+    Ok(candidates)
+}
+
+/// Writes a chosen match's artist and title through `update_track_info`, so it reaches
+/// the file and Music.app and can be undone like a manual edit.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn apply_identification(
+    app: AppHandle,
+    track_id: i64,
+    artist: String,
+    title: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    crate::commands::update_track_info(app, track_id, Some(title), Some(artist), None, None, None, None, state).await
+}
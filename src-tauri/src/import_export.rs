@@ -0,0 +1,478 @@
+//! Importing and exporting library data in formats other tools understand.
+
+use crate::apple_music::{create_playlist, AppleUpdate};
+use crate::comment::split_comment;
+use crate::commands::AppState;
+use crate::db::Database;
+use crate::library_parser::decode_location;
+use crate::path_rules::PathResolver;
+use crate::models::{Playlist, Track};
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+#[derive(serde::Serialize)]
+pub struct M3uImportResult {
+    pub playlist_id: i64,
+    pub name: String,
+    pub matched: usize,
+    /// Entries that could not be matched to a track in the library
+    pub unresolved: Vec<String>,
+}
+
+/// Parses an M3U/M3U8 file into its `#PLAYLIST:` name (if any) and entry paths.
+/// Relative entries are resolved against the playlist file's folder.
+pub(crate) fn parse_m3u(path: &Path) -> Result<(Option<String>, Vec<String>), String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read playlist: {}", e))?;
+    let content = String::from_utf8_lossy(&bytes);
+    let base_dir = path.parent().unwrap_or(Path::new("/"));
+
+    let mut name = None;
+    let mut entries = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(title) = line.strip_prefix("#PLAYLIST:") {
+            name = Some(title.trim().to_string());
+            continue;
+        }
+        if line.starts_with('#') {
+            // #EXTM3U, #EXTINF and other directives
+            continue;
+        }
+
+        let entry = if line.starts_with("file:") {
+            decode_location(line)
+        } else if Path::new(line).is_absolute() {
+            line.to_string()
+        } else {
+            base_dir.join(line).to_string_lossy().to_string()
+        };
+        entries.push(entry);
+    }
+
+    Ok((name, entries))
+}
+
+/// Finds a track by file path, trying the path rules and iTunes variants used by `mark_track_missing`.
+fn resolve_track(db: &Database, resolver: &PathResolver, path: &str) -> Option<(i64, String)> {
+    std::iter::once(path.to_string())
+        .chain(resolver.candidates(path))
+        .find_map(|candidate| db.get_track_by_path(&candidate).ok().flatten())
+        .map(|t| (t.id, t.persistent_id))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn import_playlist_m3u(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<M3uImportResult, String> {
+    let m3u_path = Path::new(&path);
+    let (declared_name, entries) = parse_m3u(m3u_path)?;
+    let name = declared_name
+        .filter(|n| !n.is_empty())
+        .or_else(|| m3u_path.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "Imported Playlist".to_string());
+
+    // 1. Resolve entries against the library
+    let mut matched: Vec<(i64, String)> = Vec::new();
+    let mut unresolved = Vec::new();
+    {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let resolver = PathResolver::load(&db);
+        for entry in &entries {
+            match resolve_track(&db, &resolver, entry) {
+                Some(track) => {
+                    if !matched.iter().any(|(id, _)| *id == track.0) {
+                        matched.push(track);
+                    }
+                }
+                None => unresolved.push(entry.clone()),
+            }
+        }
+    }
+
+    // 2. Create the playlist in Music.app (we need its persistent ID)
+    let playlist_pid = create_playlist(&name).map_err(|e| e.to_string())?;
+
+    // 3. Local DB
+    let playlist = Playlist {
+        id: 0,
+        persistent_id: playlist_pid.clone(),
+        parent_persistent_id: None,
+        name: name.clone(),
+        is_folder: false,
+        is_smart: false,
+        track_ids: Some(matched.iter().map(|(_, pid)| pid.clone()).collect()),
+    };
+    let playlist_id = {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        db.insert_playlist(&playlist).map_err(|e| e.to_string())?;
+        db.get_playlist_id_by_persistent_id(&playlist_pid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Imported playlist missing from DB".to_string())?
+    };
+
+    // 4. Fill the Music.app playlist in order
+    state.apple_queue.enqueue(AppleUpdate::playlist_adds(
+        matched.iter().map(|(_, pid)| pid.as_str()),
+        &playlist_pid,
+    ));
+
+    tracing::info!(
+        "Imported M3U playlist '{}': {} tracks matched, {} unresolved",
+        name, matched.len(), unresolved.len()
+    );
+
+    Ok(M3uImportResult {
+        playlist_id,
+        name,
+        matched: matched.len(),
+        unresolved,
+    })
+}
+
+/// Columns `export_csv` understands, in their default order.
+const CSV_COLUMNS: &[&str] = &["artist", "title", "album", "bpm", "key", "rating", "tags", "playlists", "file_path"];
+
+/// Optional filters for `export_csv`; all given criteria must match.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ExportFilter {
+    pub track_ids: Option<Vec<i64>>,
+    pub playlist_id: Option<i64>,
+    /// Only tracks carrying this tag (case-insensitive)
+    pub tag: Option<String>,
+    pub min_rating: Option<i64>,
+    pub bpm_min: Option<f64>,
+    pub bpm_max: Option<f64>,
+    /// Substring match on artist, title or album
+    pub search: Option<String>,
+    pub include_missing: bool,
+    pub color_label: Option<crate::models::TrackColor>,
+}
+
+/// Mixed In Key writes the Camelot key at the start of the comment, e.g. "8A - Energy 6".
+pub(crate) fn camelot_key(user_comment: &str) -> String {
+    let token = user_comment.split_whitespace().next().unwrap_or("");
+    let number = token.trim_end_matches(['A', 'B', 'a', 'b']);
+    let valid = token.len() == number.len() + 1
+        && number.parse::<u8>().map(|n| (1..=12).contains(&n)).unwrap_or(false);
+    if valid { token.to_uppercase() } else { String::new() }
+}
+
+pub(crate) fn csv_escape(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') || value.contains('\r') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn matches_filter(track: &Track, tags: &[String], filter: &ExportFilter) -> bool {
+    if track.missing && !filter.include_missing {
+        return false;
+    }
+    if let Some(ids) = &filter.track_ids {
+        if !ids.contains(&track.id) {
+            return false;
+        }
+    }
+    if let Some(tag) = &filter.tag {
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            return false;
+        }
+    }
+    if let Some(min) = filter.min_rating {
+        if track.rating < min {
+            return false;
+        }
+    }
+    if let Some(min) = filter.bpm_min {
+        if track.bpm < min {
+            return false;
+        }
+    }
+    if let Some(max) = filter.bpm_max {
+        if track.bpm > max {
+            return false;
+        }
+    }
+    if filter.color_label.is_some() && track.color_label != filter.color_label {
+        return false;
+    }
+    if let Some(search) = &filter.search {
+        let needle = search.to_lowercase();
+        let hit = [&track.artist, &track.title, &track.album].iter()
+            .any(|f| f.as_deref().map(|v| v.to_lowercase().contains(&needle)).unwrap_or(false));
+        if !hit {
+            return false;
+        }
+    }
+    true
+}
+
+fn csv_field(column: &str, track: &Track, user_comment: &str, tags: &[String], playlists: Option<&Vec<String>>) -> String {
+    match column {
+        "artist" => track.artist.clone().unwrap_or_default(),
+        "title" => track.title.clone().unwrap_or_default(),
+        "album" => track.album.clone().unwrap_or_default(),
+        "bpm" => if track.bpm > 0.0 { crate::metadata::format_bpm(track.bpm) } else { String::new() },
+        "key" => camelot_key(user_comment),
+        // Stored 0-100 like Music.app; export as stars
+        "rating" => (track.rating / 20).to_string(),
+        "tags" => tags.join("; "),
+        "playlists" => playlists.map(|p| p.join("; ")).unwrap_or_default(),
+        "file_path" => track.file_path.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Writes the (filtered) library to a CSV file and returns the number of rows written.
+/// `columns` selects and orders the output; empty means all columns.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn export_csv(
+    state: State<'_, AppState>,
+    path: String,
+    columns: Vec<String>,
+    filter: Option<ExportFilter>,
+) -> Result<usize, String> {
+    let rows = write_csv(&state, &path, columns, filter)?;
+
+    tracing::info!("Exported {} tracks to CSV: {}", rows, path);
+    Ok(rows)
+}
+
+/// Shared by `export_csv` and the job queue's export jobs.
+pub(crate) fn write_csv(
+    state: &AppState,
+    path: &str,
+    columns: Vec<String>,
+    filter: Option<ExportFilter>,
+) -> Result<usize, String> {
+    let columns: Vec<String> = if columns.is_empty() {
+        CSV_COLUMNS.iter().map(|c| c.to_string()).collect()
+    } else {
+        columns
+    };
+    if let Some(unknown) = columns.iter().find(|c| !CSV_COLUMNS.contains(&c.as_str())) {
+        return Err(format!("Unknown CSV column: {}", unknown));
+    }
+    let filter = filter.unwrap_or_default();
+
+    let (tracks, playlist_names, playlist_members) = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let tracks = db.get_all_tracks().map_err(|e| e.to_string())?;
+        let names: HashMap<i64, Vec<String>> = if columns.iter().any(|c| c == "playlists") {
+            db.get_playlist_names_by_track().map_err(|e| e.to_string())?
+        } else {
+            HashMap::new()
+        };
+        let members = match filter.playlist_id {
+            Some(pid) => Some(db.get_playlist_track_ids(pid).map_err(|e| e.to_string())?),
+            None => None,
+        };
+        (tracks, names, members)
+    };
+
+    let mut out = String::new();
+    out.push_str(&columns.join(","));
+    out.push('\n');
+
+    let mut rows = 0;
+    for track in &tracks {
+        if let Some(members) = &playlist_members {
+            if !members.contains(&track.id) {
+                continue;
+            }
+        }
+
+        let comment = track.comment_raw.as_deref().unwrap_or("");
+        let (user_comment, tags) = split_comment(comment);
+        if !matches_filter(track, &tags, &filter) {
+            continue;
+        }
+
+        let line: Vec<String> = columns.iter()
+            .map(|c| csv_escape(&csv_field(c, track, user_comment, &tags, playlist_names.get(&track.id))))
+            .collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+        rows += 1;
+    }
+
+    std::fs::write(path, out).map_err(|e| format!("Failed to write CSV: {}", e))?;
+    Ok(rows)
+}
+
+/// Tells `import_tags_csv` which columns (or JSON keys) hold what.
+#[derive(serde::Deserialize)]
+pub struct TagImportMapping {
+    pub persistent_id: Option<String>,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub tags: String,
+    /// Separator between tags inside the tags column (default ";")
+    pub separator: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+pub struct TagImportResult {
+    pub rows: usize,
+    pub matched: usize,
+    pub updated: usize,
+    /// 1-based row numbers (excluding the header) that matched no track
+    pub unmatched_rows: Vec<usize>,
+}
+
+/// Minimal RFC 4180 parser: quoted fields, doubled quotes, and newlines inside quotes.
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    rows
+}
+
+/// Loads a CSV (header row required) or JSON array of objects into key -> value rows.
+/// JSON arrays of strings are joined with `separator` so both formats share one code path.
+fn load_tag_rows(path: &str, separator: &str) -> Result<Vec<HashMap<String, String>>, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+    if path.to_lowercase().ends_with(".json") {
+        let value: serde_json::Value = serde_json::from_str(&content).map_err(|e| format!("Invalid JSON: {}", e))?;
+        let items = value.as_array().ok_or("JSON tag import expects an array of objects")?;
+        return Ok(items.iter().filter_map(|item| item.as_object()).map(|obj| {
+            obj.iter().map(|(k, v)| {
+                let text = match v {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Array(list) => list.iter()
+                        .filter_map(|x| x.as_str())
+                        .collect::<Vec<_>>()
+                        .join(separator),
+                    serde_json::Value::Null => String::new(),
+                    other => other.to_string(),
+                };
+                (k.clone(), text)
+            }).collect()
+        }).collect());
+    }
+
+    let mut rows = parse_csv(&content).into_iter();
+    let header: Vec<String> = rows.next().ok_or("CSV file is empty")?
+        .into_iter().map(|h| h.trim().to_string()).collect();
+    Ok(rows.map(|row| {
+        header.iter().cloned().zip(row.into_iter().map(|f| f.trim().to_string())).collect()
+    }).collect())
+}
+
+/// Bulk-adds tags from a CSV/JSON file. Rows are matched by persistent ID, falling back
+/// to artist + title (case-insensitive). All changes form a single undo step.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn import_tags_csv(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    mapping: TagImportMapping,
+) -> Result<TagImportResult, String> {
+    if mapping.persistent_id.is_none() && (mapping.artist.is_none() || mapping.title.is_none()) {
+        return Err("Mapping needs a persistent ID column or both artist and title columns".to_string());
+    }
+    let separator = mapping.separator.clone().unwrap_or_else(|| ";".to_string());
+    let rows = load_tag_rows(&path, &separator)?;
+
+    let tracks = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        db.get_all_tracks().map_err(|e| e.to_string())?
+    };
+    let by_pid: HashMap<String, usize> = tracks.iter().enumerate()
+        .map(|(i, t)| (t.persistent_id.to_uppercase(), i))
+        .collect();
+    let by_name: HashMap<(String, String), usize> = tracks.iter().enumerate()
+        .map(|(i, t)| ((
+            t.artist.clone().unwrap_or_default().to_lowercase(),
+            t.title.clone().unwrap_or_default().to_lowercase(),
+        ), i))
+        .collect();
+
+    // Collect tags per track first so duplicate rows for one track merge into one write
+    let mut additions: HashMap<usize, Vec<String>> = HashMap::new();
+    let mut unmatched_rows = Vec::new();
+    for (row_idx, row) in rows.iter().enumerate() {
+        let get = |col: &Option<String>| col.as_ref().and_then(|c| row.get(c)).map(|v| v.trim().to_string());
+
+        let by_id = get(&mapping.persistent_id)
+            .filter(|pid| !pid.is_empty())
+            .and_then(|pid| by_pid.get(&pid.to_uppercase()).copied());
+        let track_idx = by_id.or_else(|| {
+            let artist = get(&mapping.artist)?.to_lowercase();
+            let title = get(&mapping.title)?.to_lowercase();
+            by_name.get(&(artist, title)).copied()
+        });
+
+        match track_idx {
+            Some(idx) => {
+                let tags = row.get(&mapping.tags).map(|v| {
+                    v.split(separator.as_str()).map(crate::comment::normalize_tag).filter(|t| !t.is_empty()).collect::<Vec<_>>()
+                }).unwrap_or_default();
+                additions.entry(idx).or_default().extend(tags);
+            }
+            None => unmatched_rows.push(row_idx + 1),
+        }
+    }
+
+    let matched = additions.len();
+    let changes: Vec<(Track, String)> = additions.into_iter()
+        .filter_map(|(idx, new_tags)| {
+            let track = &tracks[idx];
+            crate::comment::add_tags(track.comment_raw.as_deref().unwrap_or_default(), &new_tags)
+                .map(|c| (track.clone(), c))
+        })
+        .collect();
+    let updated = crate::commands::apply_comment_batch(&app, &state, changes).await?;
+
+    tracing::info!(
+        "Tag import from {}: {} rows, {} matched, {} updated, {} unmatched",
+        path, rows.len(), matched, updated, unmatched_rows.len()
+    );
+
+    Ok(TagImportResult {
+        rows: rows.len(),
+        matched,
+        updated,
+        unmatched_rows,
+    })
+}
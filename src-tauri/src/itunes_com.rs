@@ -0,0 +1,232 @@
+//! Windows backend for the Music.app sync queue, driving iTunes through its COM
+//! automation interface (`iTunes.Application`). Covers comments, ratings, track info,
+//! play counts and playlist membership; other updates are no-ops on Windows.
+//!
+//! Only attaches to an iTunes that is already running (via the running object table),
+//! so queued updates wait for the user to open iTunes instead of launching it.
+
+use crate::apple_music::AppleUpdate;
+use anyhow::{anyhow, Result};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use windows::core::{w, Interface, BSTR, GUID, IUnknown, PCWSTR, VARIANT};
+use windows::Win32::System::Com::{
+    CLSIDFromProgID, CoInitializeEx, IDispatch, COINIT_APARTMENTTHREADED, DISPATCH_FLAGS,
+    DISPATCH_METHOD, DISPATCH_PROPERTYGET, DISPATCH_PROPERTYPUT, DISPPARAMS,
+};
+use windows::Win32::System::Ole::{GetActiveObject, DISPID_PROPERTYPUT};
+
+/// Late-bound wrapper over an iTunes COM object.
+struct Dispatch(IDispatch);
+
+impl Dispatch {
+    fn from_variant(value: &VARIANT) -> Result<Self> {
+        let unknown = IUnknown::try_from(value).map_err(|e| anyhow!("Expected a COM object: {}", e))?;
+        Ok(Self(unknown.cast::<IDispatch>()?))
+    }
+
+    fn dispid(&self, name: &str) -> Result<i32> {
+        let wide: Vec<u16> = name.encode_utf16().chain(std::iter::once(0)).collect();
+        let names = [PCWSTR(wide.as_ptr())];
+        let mut dispid = 0;
+        unsafe { self.0.GetIDsOfNames(&GUID::zeroed(), names.as_ptr(), 1, 0, &mut dispid)? };
+        Ok(dispid)
+    }
+
+    fn invoke(&self, name: &str, flags: DISPATCH_FLAGS, args: &[VARIANT]) -> Result<VARIANT> {
+        let dispid = self.dispid(name)?;
+        // IDispatch takes arguments last to first
+        let mut args: Vec<VARIANT> = args.iter().rev().cloned().collect();
+        let mut put_id = DISPID_PROPERTYPUT;
+        let params = DISPPARAMS {
+            rgvarg: args.as_mut_ptr(),
+            cArgs: args.len() as u32,
+            rgdispidNamedArgs: if flags == DISPATCH_PROPERTYPUT { &mut put_id } else { std::ptr::null_mut() },
+            cNamedArgs: if flags == DISPATCH_PROPERTYPUT { 1 } else { 0 },
+        };
+        let mut result = VARIANT::default();
+        unsafe {
+            self.0.Invoke(dispid, &GUID::zeroed(), 0, flags, &params, Some(&mut result), None, None)
+                .map_err(|e| anyhow!("iTunes {} failed: {}", name, e))?;
+        }
+        Ok(result)
+    }
+
+    fn get(&self, name: &str, args: &[VARIANT]) -> Result<VARIANT> {
+        self.invoke(name, DISPATCH_PROPERTYGET, args)
+    }
+
+    fn object(&self, name: &str, args: &[VARIANT]) -> Result<Dispatch> {
+        Dispatch::from_variant(&self.get(name, args)?)
+    }
+
+    fn put(&self, name: &str, value: VARIANT) -> Result<()> {
+        self.invoke(name, DISPATCH_PROPERTYPUT, &[value]).map(|_| ())
+    }
+
+    fn call(&self, name: &str, args: &[VARIANT]) -> Result<VARIANT> {
+        self.invoke(name, DISPATCH_METHOD, args)
+    }
+}
+
+/// Persistent IDs are 16 hex digits; COM takes them as high and low 32-bit halves.
+fn split_persistent_id(persistent_id: &str) -> Result<(VARIANT, VARIANT)> {
+    let value = u64::from_str_radix(persistent_id, 16)
+        .map_err(|_| anyhow!("Invalid persistent ID: {}", persistent_id))?;
+    Ok((VARIANT::from((value >> 32) as u32 as i32), VARIANT::from(value as u32 as i32)))
+}
+
+struct ITunes {
+    app: Dispatch,
+}
+
+impl ITunes {
+    /// Attaches to the running iTunes. Never launches it.
+    fn connect() -> Result<Self> {
+        unsafe {
+            // S_FALSE (already initialized on this thread) is fine
+            let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+            let clsid = CLSIDFromProgID(w!("iTunes.Application"))?;
+            let mut unknown: Option<IUnknown> = None;
+            GetActiveObject(&clsid, None, &mut unknown).map_err(|_| anyhow!("iTunes is not running"))?;
+            let unknown = unknown.ok_or_else(|| anyhow!("iTunes is not running"))?;
+            Ok(Self { app: Dispatch(unknown.cast::<IDispatch>()?) })
+        }
+    }
+
+    fn track(&self, persistent_id: &str) -> Result<Dispatch> {
+        let (high, low) = split_persistent_id(persistent_id)?;
+        let tracks = self.app.object("LibraryPlaylist", &[])?.object("Tracks", &[])?;
+        let track = tracks.get("ItemByPersistentID", &[high, low])?;
+        Dispatch::from_variant(&track).map_err(|_| anyhow!("Track {} not found in iTunes", persistent_id))
+    }
+
+    fn playlist(&self, persistent_id: &str) -> Result<Dispatch> {
+        let (high, low) = split_persistent_id(persistent_id)?;
+        let playlists = self.app.object("LibrarySource", &[])?.object("Playlists", &[])?;
+        let playlist = playlists.get("ItemByPersistentID", &[high, low])?;
+        Dispatch::from_variant(&playlist).map_err(|_| anyhow!("Playlist {} not found in iTunes", persistent_id))
+    }
+
+    /// Persistent ID of any iTunes object, as 16 hex digits.
+    fn persistent_id_of(&self, object: &Dispatch) -> Result<String> {
+        let arg = VARIANT::from(object.0.cast::<IUnknown>()?);
+        let high = i32::try_from(&self.app.get("ITObjectPersistentIDHigh", &[arg.clone()])?)?;
+        let low = i32::try_from(&self.app.get("ITObjectPersistentIDLow", &[arg])?)?;
+        Ok(format!("{:08X}{:08X}", high as u32, low as u32))
+    }
+
+    fn set_string(&self, persistent_id: &str, property: &str, value: &str) -> Result<()> {
+        self.track(persistent_id)?.put(property, VARIANT::from(BSTR::from(value)))
+    }
+
+    fn set_integer(&self, persistent_id: &str, property: &str, value: i64) -> Result<()> {
+        self.track(persistent_id)?.put(property, VARIANT::from(value as i32))
+    }
+
+    fn add_to_playlist(&self, track_pid: &str, playlist_pid: &str) -> Result<()> {
+        let track = self.track(track_pid)?;
+        let playlist = self.playlist(playlist_pid)?;
+        playlist.call("AddTrack", &[VARIANT::from(track.0.cast::<IUnknown>()?)])?;
+        Ok(())
+    }
+
+    /// Persistent IDs (upper case) of the tracks in a playlist.
+    fn playlist_members(&self, playlist_pid: &str) -> Result<HashSet<String>> {
+        let tracks = self.playlist(playlist_pid)?.object("Tracks", &[])?;
+        let count = i32::try_from(&tracks.get("Count", &[])?)?;
+        (1..=count)
+            .map(|index| self.persistent_id_of(&tracks.object("Item", &[VARIANT::from(index)])?))
+            .collect()
+    }
+
+    fn remove_from_playlist(&self, track_pid: &str, playlist_pid: &str) -> Result<()> {
+        let tracks = self.playlist(playlist_pid)?.object("Tracks", &[])?;
+        let count = i32::try_from(&tracks.get("Count", &[])?)?;
+        // Deleting shifts later items down, so walk from the end
+        for index in (1..=count).rev() {
+            let entry = tracks.object("Item", &[VARIANT::from(index)])?;
+            if self.persistent_id_of(&entry)?.eq_ignore_ascii_case(track_pid) {
+                entry.call("Delete", &[])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether iTunes is running. Never launches it.
+pub fn is_running() -> bool {
+    ITunes::connect().is_ok()
+}
+
+/// Applies an update through iTunes COM.
+/// Returns `None` for update kinds this backend does not implement.
+pub fn apply(update: &AppleUpdate) -> Option<Result<()>> {
+    let itunes = match ITunes::connect() {
+        Ok(itunes) => itunes,
+        Err(e) => return Some(Err(e)),
+    };
+
+    match update {
+        AppleUpdate::Comment { persistent_id, comment } => Some(itunes.set_string(persistent_id, "Comment", comment)),
+        AppleUpdate::Comments { updates } => {
+            // One failure doesn't stop the rest
+            let failed = updates.iter()
+                .filter(|(pid, comment)| itunes.set_string(pid, "Comment", comment).is_err())
+                .count();
+            if failed > 0 {
+                // Comment writes are idempotent, so the queue can retry the whole batch
+                return Some(Err(anyhow!("{} of {} comment updates failed", failed, updates.len())));
+            }
+            Some(Ok(()))
+        }
+        AppleUpdate::Rating { persistent_id, rating } => Some(itunes.set_integer(persistent_id, "Rating", *rating as i64)),
+        AppleUpdate::TrackInfo { persistent_id, name, artist, album, bpm } => {
+            let result = (|| -> Result<()> {
+                if let Some(n) = name {
+                    itunes.set_string(persistent_id, "Name", n)?;
+                }
+                if let Some(a) = artist {
+                    itunes.set_string(persistent_id, "Artist", a)?;
+                }
+                if let Some(al) = album {
+                    itunes.set_string(persistent_id, "Album", al)?;
+                }
+                if let Some(b) = bpm {
+                    itunes.set_integer(persistent_id, "BPM", *b)?;
+                }
+                Ok(())
+            })();
+            Some(result)
+        }
+        AppleUpdate::PlayCount { persistent_id, count } => Some(itunes.set_integer(persistent_id, "PlayedCount", *count)),
+        AppleUpdate::AddToPlaylist { track_pid, playlist_pid } => Some(itunes.add_to_playlist(track_pid, playlist_pid)),
+        AppleUpdate::PlaylistAdds { adds } => {
+            // Tracks already in the playlist are skipped, so a retry after a partial failure
+            // doesn't add the ones that went through twice
+            let mut members: HashMap<&str, HashSet<String>> = HashMap::new();
+            let mut failed = 0;
+            for (track_pid, playlist_pid) in adds {
+                let result = (|| -> Result<()> {
+                    let present = match members.entry(playlist_pid.as_str()) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => entry.insert(itunes.playlist_members(playlist_pid)?),
+                    };
+                    if present.insert(track_pid.to_uppercase()) {
+                        itunes.add_to_playlist(track_pid, playlist_pid)?;
+                    }
+                    Ok(())
+                })();
+                if result.is_err() {
+                    failed += 1;
+                }
+            }
+            if failed > 0 {
+                return Some(Err(anyhow!("{} of {} playlist adds failed", failed, adds.len())));
+            }
+            Some(Ok(()))
+        }
+        AppleUpdate::RemoveFromPlaylist { track_pid, playlist_pid } => Some(itunes.remove_from_playlist(track_pid, playlist_pid)),
+        _ => None,
+    }
+}
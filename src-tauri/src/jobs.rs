@@ -0,0 +1,411 @@
+//! Generic background job queue for long-running operations.
+//!
+//! Jobs run one at a time on a worker thread in small steps, so they can be paused,
+//! resumed or cancelled between steps. Every state change is persisted to the `jobs`
+//! table and emitted as a `job-progress` event; unfinished jobs resume on next launch.
+
+use crate::commands::{write_comment_batch, AppState};
+use crate::external_analyzer::{analyze_blocking, find_analyzer};
+use crate::import_export::{write_csv, ExportFilter};
+use crate::undo::{Action, TrackState};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Tracks processed per step; pause/cancel take effect between steps.
+const STEP_SIZE: usize = 25;
+/// Finished jobs kept in memory for `list_jobs`.
+const MAX_FINISHED_JOBS: usize = 50;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobKind {
+    BatchAddTag { ids: Vec<i64>, tag: String },
+    BatchRemoveTag { ids: Vec<i64>, tag: String },
+    ExportCsv { path: String, columns: Vec<String>, filter: Option<ExportFilter> },
+    /// Key/BPM analysis by Mixed In Key or a configured command-line analyzer
+    Analyze { analyzer: String, ids: Vec<i64> },
+}
+
+impl JobKind {
+    pub fn describe(&self) -> String {
+        match self {
+            JobKind::BatchAddTag { ids, tag } => format!("Add tag '{}' to {} track(s)", tag, ids.len()),
+            JobKind::BatchRemoveTag { ids, tag } => format!("Remove tag '{}' from {} track(s)", tag, ids.len()),
+            JobKind::ExportCsv { path, .. } => format!("Export CSV to {}", path),
+            JobKind::Analyze { analyzer, ids } => format!("Analyze {} track(s) with {}", ids.len(), analyzer),
+        }
+    }
+
+    fn total(&self) -> usize {
+        match self {
+            JobKind::BatchAddTag { ids, .. } | JobKind::BatchRemoveTag { ids, .. } | JobKind::Analyze { ids, .. } => ids.len(),
+            JobKind::ExportCsv { .. } => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Paused,
+    Cancelled,
+    Completed,
+    Failed,
+}
+
+impl JobStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Paused => "paused",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Cancelled | JobStatus::Completed | JobStatus::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: u64,
+    pub description: String,
+    pub kind: JobKind,
+    pub status: JobStatus,
+    pub progress: usize,
+    pub total: usize,
+    pub error: Option<String>,
+    pub created_at: i64,
+    /// Comment changes made so far, one segment per run between pauses. Pushed as a single
+    /// undo step once the job finishes, so a paused and resumed job still undoes in one go.
+    #[serde(default)]
+    pub undo: Vec<Vec<TrackState>>,
+}
+
+struct JobsInner {
+    jobs: Mutex<VecDeque<Job>>,
+    changed: Condvar,
+    next_id: AtomicU64,
+}
+
+pub struct JobQueue {
+    inner: Arc<JobsInner>,
+    app: AppHandle,
+}
+
+impl JobQueue {
+    /// Restores unfinished jobs from the DB and spawns the worker.
+    /// Must be called after `AppState` is managed.
+    pub fn start(app: AppHandle) -> Self {
+        let mut restored = VecDeque::new();
+        let mut max_id = 0;
+
+        if let Ok(db) = app.state::<AppState>().db.read() {
+            max_id = db.get_max_job_id().unwrap_or(0).max(0) as u64;
+            for data in db.get_unfinished_jobs().unwrap_or_default() {
+                match serde_json::from_str::<Job>(&data) {
+                    Ok(mut job) => {
+                        // A job that was running when the app quit starts over from its last saved step
+                        if job.status == JobStatus::Running {
+                            job.status = JobStatus::Queued;
+                        }
+                        restored.push_back(job);
+                    }
+                    Err(e) => tracing::warn!("Discarding unreadable job: {}", e),
+                }
+            }
+        }
+        if !restored.is_empty() {
+            tracing::debug!("Restored {} unfinished job(s)", restored.len());
+        }
+
+        let inner = Arc::new(JobsInner {
+            jobs: Mutex::new(restored),
+            changed: Condvar::new(),
+            next_id: AtomicU64::new(max_id + 1),
+        });
+
+        let worker_inner = inner.clone();
+        let worker_app = app.clone();
+        thread::spawn(move || run_worker(worker_inner, worker_app));
+
+        Self { inner, app }
+    }
+
+    pub fn submit(&self, kind: JobKind) -> u64 {
+        let job = Job {
+            id: self.inner.next_id.fetch_add(1, Ordering::SeqCst),
+            description: kind.describe(),
+            total: kind.total(),
+            kind,
+            status: JobStatus::Queued,
+            progress: 0,
+            error: None,
+            created_at: chrono::Utc::now().timestamp(),
+            undo: Vec::new(),
+        };
+        let id = job.id;
+        persist(&self.app, &job);
+        let _ = self.app.emit("job-progress", &job);
+
+        let mut jobs = self.inner.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.push_back(job);
+        self.inner.changed.notify_all();
+        id
+    }
+
+    pub fn list(&self) -> Vec<Job> {
+        let jobs = self.inner.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.iter().cloned().collect()
+    }
+
+    /// Moves a job between states if the transition is allowed from its current state.
+    fn transition(&self, id: u64, allowed_from: &[JobStatus], to: JobStatus) -> Result<Job, String> {
+        let mut jobs = self.inner.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let job = jobs.iter_mut().find(|j| j.id == id).ok_or_else(|| format!("Job {} not found", id))?;
+        if !allowed_from.contains(&job.status) {
+            return Err(format!("Job {} is {}", id, job.status.as_str()));
+        }
+        let was_running = job.status == JobStatus::Running;
+        job.status = to;
+        // A running job pushes its own undo step when it notices; one that is not running
+        // never will
+        let undo = if to.is_finished() && !was_running { std::mem::take(&mut job.undo) } else { Vec::new() };
+        let snapshot = job.clone();
+        drop(jobs);
+
+        push_undo(&self.app, &snapshot.description, undo);
+        persist(&self.app, &snapshot);
+        let _ = self.app.emit("job-progress", &snapshot);
+        self.inner.changed.notify_all();
+        Ok(snapshot)
+    }
+}
+
+fn persist(app: &AppHandle, job: &Job) {
+    let data = match serde_json::to_string(job) {
+        Ok(d) => d,
+        Err(e) => {
+            tracing::error!("Failed to serialize job {}: {}", job.id, e);
+            return;
+        }
+    };
+    if let Ok(db) = app.state::<AppState>().db.lock() {
+        if let Err(e) = db.save_job(job.id as i64, job.status.as_str(), &data) {
+            tracing::error!("Failed to persist job {}: {}", job.id, e);
+        }
+    }
+}
+
+fn run_worker(inner: Arc<JobsInner>, app: AppHandle) {
+    loop {
+        // Wait for a queued job
+        let job = {
+            let mut jobs = inner.jobs.lock().unwrap_or_else(|e| e.into_inner());
+            loop {
+                if let Some(job) = jobs.iter_mut().find(|j| j.status == JobStatus::Queued) {
+                    job.status = JobStatus::Running;
+                    break job.clone();
+                }
+                jobs = inner.changed.wait(jobs).unwrap_or_else(|e| e.into_inner());
+            }
+        };
+
+        persist(&app, &job);
+        let _ = app.emit("job-progress", &job);
+        tracing::info!("Job {} started: {}", job.id, job.description);
+
+        run_job(&inner, &app, job.id);
+        prune_finished(&inner);
+    }
+}
+
+/// Runs a job step by step until it completes, fails, or is paused or cancelled.
+fn run_job(inner: &JobsInner, app: &AppHandle, job_id: u64) {
+    {
+        let mut jobs = inner.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        match jobs.iter_mut().find(|j| j.id == job_id) {
+            Some(j) => j.undo.push(Vec::new()),
+            None => return,
+        }
+    }
+
+    loop {
+        // Honour pause/cancel between steps. A paused job is released so other queued
+        // jobs can run; resuming re-queues it and it continues from its saved progress.
+        let job = {
+            let jobs = inner.jobs.lock().unwrap_or_else(|e| e.into_inner());
+            match jobs.iter().find(|j| j.id == job_id) {
+                Some(j) => j.clone(),
+                None => return,
+            }
+        };
+
+        if job.status == JobStatus::Cancelled || job.status == JobStatus::Paused {
+            if job.status == JobStatus::Cancelled {
+                push_undo(app, &job.description, take_undo(inner, job_id));
+            }
+            tracing::info!("Job {} {} at {}/{}", job.id, job.status.as_str(), job.progress, job.total);
+            return;
+        }
+
+        let mut undo_states = Vec::new();
+        let step = run_step(app, &job, &mut undo_states);
+
+        let snapshot = {
+            let mut jobs = inner.jobs.lock().unwrap_or_else(|e| e.into_inner());
+            let entry = match jobs.iter_mut().find(|j| j.id == job_id) {
+                Some(j) => j,
+                None => return,
+            };
+            if let Some(segment) = entry.undo.last_mut() {
+                segment.extend(undo_states);
+            }
+            match &step {
+                Ok(done) => {
+                    entry.progress = (entry.progress + done).min(entry.total);
+                    if entry.progress >= entry.total && entry.status != JobStatus::Cancelled {
+                        entry.status = JobStatus::Completed;
+                    }
+                }
+                Err(e) => {
+                    entry.status = JobStatus::Failed;
+                    entry.error = Some(e.clone());
+                }
+            }
+            entry.clone()
+        };
+
+        persist(app, &snapshot);
+        let _ = app.emit("job-progress", &snapshot);
+
+        if snapshot.status == JobStatus::Completed || snapshot.status == JobStatus::Failed {
+            push_undo(app, &snapshot.description, take_undo(inner, job_id));
+            match &snapshot.error {
+                Some(e) => tracing::error!("Job {} failed: {}", snapshot.id, e),
+                None => tracing::info!("Job {} completed: {}", snapshot.id, snapshot.description),
+            }
+            return;
+        }
+    }
+}
+
+/// Executes the next step of a job and returns how many units it completed.
+fn run_step(app: &AppHandle, job: &Job, undo_states: &mut Vec<TrackState>) -> Result<usize, String> {
+    let state = app.state::<AppState>();
+
+    match &job.kind {
+        JobKind::BatchAddTag { ids, tag } | JobKind::BatchRemoveTag { ids, tag } => {
+            let adding = matches!(job.kind, JobKind::BatchAddTag { .. });
+            let chunk: Vec<i64> = ids.iter().skip(job.progress).take(STEP_SIZE).copied().collect();
+            let tags = vec![tag.trim().to_string()];
+
+            let changes: Vec<_> = {
+                let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+                chunk.iter()
+                    .filter_map(|id| db.get_track(*id).ok().flatten())
+                    .filter_map(|track| {
+                        let old_comment = track.comment_raw.clone().unwrap_or_default();
+                        let new_comment = if adding {
+                            crate::comment::add_tags(&old_comment, &tags)
+                        } else {
+                            crate::comment::remove_tags(&old_comment, &tags)
+                        };
+                        new_comment.map(|c| (track, c))
+                    })
+                    .collect()
+            };
+
+            undo_states.extend(write_comment_batch(app, changes)?);
+            Ok(chunk.len().max(1))
+        }
+        JobKind::ExportCsv { path, columns, filter } => {
+            write_csv(&state, path, columns.clone(), filter.clone())?;
+            Ok(1)
+        }
+        JobKind::Analyze { analyzer, ids } => {
+            let analyzer = find_analyzer(&state, analyzer)?;
+            let chunk: Vec<i64> = ids.iter().skip(job.progress).take(STEP_SIZE).copied().collect();
+            let done = chunk.len();
+            analyze_blocking(app, analyzer.as_ref(), chunk)?;
+            if job.progress + done >= ids.len() {
+                analyzer.finish();
+            }
+            Ok(done.max(1))
+        }
+    }
+}
+
+/// Takes the job's recorded comment changes out of the queue.
+fn take_undo(inner: &JobsInner, job_id: u64) -> Vec<Vec<TrackState>> {
+    let mut jobs = inner.jobs.lock().unwrap_or_else(|e| e.into_inner());
+    jobs.iter_mut().find(|j| j.id == job_id).map(|j| std::mem::take(&mut j.undo)).unwrap_or_default()
+}
+
+/// Pushes a job's segments as one undo step.
+fn push_undo(app: &AppHandle, description: &str, segments: Vec<Vec<TrackState>>) {
+    let actions: Vec<Action> = segments.into_iter()
+        .filter(|tracks| !tracks.is_empty())
+        .map(|tracks| Action::UpdateTrackComments { tracks })
+        .collect();
+    if actions.is_empty() {
+        return;
+    }
+    if let Ok(mut stack) = app.state::<AppState>().undo_stack.lock() {
+        stack.push(Action::Composite { description: description.to_string(), actions });
+    }
+}
+
+fn prune_finished(inner: &JobsInner) {
+    let mut jobs = inner.jobs.lock().unwrap_or_else(|e| e.into_inner());
+    let finished = jobs.iter().filter(|j| j.status.is_finished()).count();
+    let mut excess = finished.saturating_sub(MAX_FINISHED_JOBS);
+    jobs.retain(|j| {
+        if excess > 0 && j.status.is_finished() {
+            excess -= 1;
+            false
+        } else {
+            true
+        }
+    });
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn submit_job(kind: JobKind, jobs: State<'_, JobQueue>) -> Result<u64, String> {
+    Ok(jobs.submit(kind))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn list_jobs(jobs: State<'_, JobQueue>) -> Result<Vec<Job>, String> {
+    Ok(jobs.list())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn pause_job(id: u64, jobs: State<'_, JobQueue>) -> Result<Job, String> {
+    jobs.transition(id, &[JobStatus::Queued, JobStatus::Running], JobStatus::Paused)
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn resume_job(id: u64, jobs: State<'_, JobQueue>) -> Result<Job, String> {
+    jobs.transition(id, &[JobStatus::Paused], JobStatus::Queued)
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn cancel_job(id: u64, jobs: State<'_, JobQueue>) -> Result<Job, String> {
+    jobs.transition(id, &[JobStatus::Queued, JobStatus::Running, JobStatus::Paused], JobStatus::Cancelled)
+}
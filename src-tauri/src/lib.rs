@@ -1,4 +1,5 @@
 pub mod commands;
+pub mod comment;
 pub mod apple_music;
 pub mod db;
 pub mod library_parser;
@@ -9,9 +10,53 @@ pub mod models;
 pub mod toggle_logs;
 pub mod undo;
 pub mod library_watcher;
+pub mod environment;
+pub mod import_export;
+pub mod jobs;
+pub mod tag_insights;
+pub mod path_rules;
+pub mod file_organizer;
+pub mod rating;
+pub mod playlists;
+pub mod sessions;
+pub mod rekordbox;
+pub mod drive_export;
+pub mod library_health;
+pub mod conflicts;
+pub mod cue_points;
+pub mod artwork;
+pub mod online_metadata;
+pub mod identify;
+pub mod genre_classifier;
+pub mod automation_api;
+pub mod tokens;
+pub mod midi_tagging;
+pub mod now_playing;
+pub mod listening_history;
+pub mod file_library;
+pub mod snapshots;
+pub mod performance;
+pub mod crash_report;
+pub mod track_deletion;
+pub mod comment_cleaner;
+pub mod grouping_migration;
+pub mod tag_storage;
+pub mod external_analyzer;
+pub mod mixed_in_key;
+#[cfg(feature = "audio-analysis")]
+pub mod audio;
+pub mod track_structure;
+pub mod cloud_files;
+pub mod file_permissions;
+pub mod change_events;
+pub mod maintenance;
+#[cfg(all(target_os = "macos", feature = "scripting-bridge"))]
+pub mod scripting_bridge;
+#[cfg(target_os = "windows")]
+pub mod itunes_com;
 
 use commands::AppState;
-use db::Database;
+use db::{Database, DbPool};
 use undo::UndoStack;
 
 use std::sync::Mutex;
@@ -21,6 +66,7 @@ use tauri::menu::{Menu, MenuItem, Submenu, PredefinedMenuItem};
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
 fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
@@ -34,6 +80,8 @@ pub fn run() {
             let log_state = logging::LogState::new();
             log_state.init_log_dir();
             app.manage(log_state);
+            logging::init_tracing(app.handle());
+            crash_report::install_panic_hook(app.handle());
 
             // Menu construction
             let window_menu = Submenu::with_items(app, "Window", true, &[
@@ -97,16 +145,61 @@ pub fn run() {
             std::fs::create_dir_all(&app_data_dir).expect("failed to create app data dir");
             let db_path = app_data_dir.join("tagdeck.db");
 
-            let db = Database::new(db_path).expect("failed to initialize database");
+            let db = DbPool::open(&db_path).expect("failed to initialize database");
 
-            app.manage(AppState { 
-                db: Mutex::new(db),
+            // Separate connection for the Music.app offline journal (pending_sync table)
+            let journal = Database::new(&db_path)
+                .map_err(|e| tracing::error!("Failed to open sync journal: {}", e))
+                .ok();
+
+            app.manage(AppState {
+                db,
                 undo_stack: Mutex::new(UndoStack::new()),
-                is_syncing: AtomicBool::new(false), 
+                is_syncing: AtomicBool::new(false),
+                tag_clipboard: Mutex::new(Vec::new()),
+                apple_queue: apple_music::SyncQueue::start(app.handle().clone(), journal),
             });
 
+            logging::load_log_levels(app.handle());
+            commands::load_preserve_mtime(&app.state::<AppState>());
+            tag_storage::load_tag_storage(&app.state::<AppState>());
+            commands::load_tag_normalization(&app.state::<AppState>());
+            commands::load_file_ratings(&app.state::<AppState>());
+            cloud_files::load_materialize_before_write(&app.state::<AppState>());
+            maintenance::load_nightly_maintenance(&app.state::<AppState>());
+            now_playing::load_now_playing_watcher(&app.state::<AppState>());
+
+            // Granular `track-updated`/`playlist-updated`/`tag-created` events
+            change_events::start(app.handle().clone());
+
+            // Background job queue (needs AppState for persistence)
+            app.manage(jobs::JobQueue::start(app.handle().clone()));
+
             // Start Library Watcher
+            app.manage(library_watcher::WatcherState::default());
             library_watcher::start_library_watcher(app.handle().clone());
+            library_watcher::start_audio_watcher(app.handle().clone());
+            library_watcher::start_inbox_watcher(app.handle().clone());
+
+            // Opt-in localhost API for external tools
+            app.manage(automation_api::AutomationState::default());
+            automation_api::start_automation_server(app.handle().clone());
+
+            // MIDI controller tagging
+            app.manage(midi_tagging::MidiState::default());
+            midi_tagging::track_now_playing(app.handle());
+            midi_tagging::start_midi_listener(app.handle().clone());
+
+            // Music.app player watcher (`now-playing` events), opt-in
+            now_playing::start_now_playing_watcher(app.handle().clone());
+
+            // File-only library (folder + M3U playlists instead of Music.app)
+            app.manage(file_library::FileLibraryState::default());
+            file_library::load_file_library(&app.state::<AppState>());
+            file_library::start_file_library_watcher(app.handle().clone());
+
+            // Opt-in nightly full reconcile
+            maintenance::start_nightly_maintenance(app.handle().clone());
 
             Ok(())
         })
@@ -115,27 +208,134 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             greet,
             logging::get_logs,
+            logging::query_logs,
             logging::log_error,
             logging::log_from_frontend,
             logging::get_debug_mode,
             logging::set_debug_mode,
+            logging::get_log_levels,
+            logging::set_log_levels,
             logging::open_log_folder,
             logging::get_log_file_path,
             logging::get_log_stats,
+            crash_report::get_last_crash_report,
             toggle_logs::toggle_logs,
+            environment::check_environment,
+            library_watcher::list_watch_paths,
+            library_watcher::add_watch_path,
+            library_watcher::remove_watch_path,
+            library_watcher::restart_library_watcher,
+            library_watcher::get_watcher_status,
+            library_watcher::get_real_time_sync,
+            library_watcher::set_real_time_sync,
+            library_watcher::get_audio_watch_settings,
+            library_watcher::set_audio_watch_settings,
+            library_watcher::get_inbox_folder,
+            library_watcher::set_inbox_folder,
+            import_export::import_playlist_m3u,
+            import_export::export_csv,
+            import_export::import_tags_csv,
+            path_rules::list_path_rules,
+            path_rules::add_path_rule,
+            path_rules::remove_path_rule,
+            path_rules::set_itunes_path_heuristics,
+            file_organizer::rename_files_from_pattern,
+            file_organizer::organize_into_folders,
+            rating::get_rating_settings,
+            rating::set_rating_settings,
+            rating::batch_update_rating,
+            playlists::get_playlist_tree,
+            playlists::create_playlist_folder,
+            playlists::move_playlist,
+            playlists::duplicate_playlist,
+            playlists::merge_playlists,
+            playlists::sort_playlist,
+            sessions::start_session,
+            sessions::stop_session,
+            sessions::get_active_session,
+            sessions::get_sessions,
+            sessions::append_to_session,
+            sessions::get_session_tracks,
+            sessions::export_session,
+            drive_export::export_to_drive,
+            library_health::get_untagged_tracks,
+            library_health::get_recently_added_untagged,
+            library_health::get_tracks_missing,
+            library_health::verify_audio_files,
+            library_health::get_quality_audit,
+            conflicts::get_conflicts,
+            conflicts::resolve_conflict,
+            cue_points::get_cue_points,
+            cue_points::add_cue_point,
+            cue_points::update_cue_point,
+            cue_points::delete_cue_point,
+            artwork::fetch_missing_artwork,
+            artwork::apply_artwork,
+            online_metadata::get_metadata_api_keys,
+            online_metadata::set_metadata_api_keys,
+            online_metadata::lookup_track_metadata,
+            online_metadata::apply_track_metadata,
+            identify::identify_track,
+            identify::apply_identification,
+            genre_classifier::classify_tracks,
+            automation_api::get_automation_settings,
+            automation_api::set_automation_settings,
+            midi_tagging::list_midi_inputs,
+            midi_tagging::get_midi_settings,
+            midi_tagging::set_midi_settings,
+            midi_tagging::set_selected_tracks,
+            now_playing::get_now_playing,
+            now_playing::get_now_playing_watcher,
+            now_playing::set_now_playing_watcher,
+            listening_history::get_most_played,
+            listening_history::get_never_played,
+            listening_history::get_played_between,
+            file_library::get_file_library_root,
+            file_library::set_file_library_root,
+            file_library::scan_file_library,
+            snapshots::create_snapshot,
+            snapshots::list_snapshots,
+            snapshots::delete_snapshot,
+            snapshots::diff_snapshots,
+            snapshots::restore_snapshot,
+            performance::get_performance_stats,
+            performance::reset_performance_stats,
+            track_deletion::preview_delete_tracks,
+            track_deletion::delete_tracks,
+            jobs::submit_job,
+            jobs::list_jobs,
+            jobs::pause_job,
+            jobs::resume_job,
+            jobs::cancel_job,
+            tag_insights::get_tag_stats,
+            tag_insights::suggest_tags,
             commands::import_library,
+            commands::get_schema_version,
             commands::get_tracks,
+            commands::get_tracks_by_ids,
             commands::get_global_tags,
             commands::show_in_finder,
-            commands::analyze_with_mixed_in_key,
+            mixed_in_key::analyze_with_mixed_in_key,
+            external_analyzer::get_external_analyzers,
+            external_analyzer::set_external_analyzers,
+            external_analyzer::analyze_with_external,
+            external_analyzer::get_analysis_status,
+            track_structure::analyze_track_structure,
             commands::write_tags,
             commands::batch_add_tag,
             commands::batch_remove_tag,
+            commands::copy_tags,
+            commands::copy_tag_block,
+            commands::paste_tag_block,
+            commands::restore_original_comment,
             commands::import_from_music_app,
             commands::get_playlists,
             commands::add_to_playlist,
             commands::get_playlist_track_ids,
             commands::mark_track_missing,
+            commands::scan_missing_files,
+            commands::relocate_track,
+            commands::relocate_folder,
             commands::get_track_artwork,
             commands::get_tag_groups,
             commands::create_tag_group,
@@ -143,17 +343,64 @@ pub fn run() {
             commands::delete_tag_group,
             commands::set_tag_group,
             commands::reorder_tag_groups,
+            commands::get_tag_normalization,
+            commands::set_tag_normalization,
+            commands::normalize_existing_tags,
+            commands::get_tag_sets,
+            commands::create_tag_set,
+            commands::update_tag_set,
+            commands::delete_tag_set,
+            commands::apply_tag_set,
+            commands::get_tag_shortcuts,
+            commands::set_tag_shortcut,
+            commands::delete_tag_shortcut,
+            comment_cleaner::get_user_comment_summary,
+            comment_cleaner::clean_user_comments,
+            grouping_migration::audit_grouping,
+            grouping_migration::migrate_grouping,
+            tag_storage::get_tag_storage,
+            tag_storage::set_tag_storage,
             commands::get_all_tags,
             commands::delete_tag,
+            commands::prune_unused_tags,
             commands::get_playlists_for_track,
             commands::copy_playlist_memberships,
+            commands::transfer_play_count,
+            commands::remap_persistent_id,
             commands::undo,
             commands::redo,
+            commands::list_undo_actions,
+            commands::undo_to,
             commands::update_rating,
+            commands::set_loved,
+            commands::revert_track_to,
+            commands::get_recent_changes,
+            commands::set_color_label,
+            commands::get_track_notes,
+            commands::set_track_notes,
             commands::update_track_info,
+            commands::fix_bpm,
             commands::sync_recent_changes,
+            commands::sync_now,
             commands::remove_from_playlist,
-            commands::reorder_playlist_tracks
+            commands::reorder_playlist_tracks,
+            commands::get_pending_apple_updates,
+            commands::retry_apple_update,
+            commands::discard_apple_update,
+            commands::get_preserve_mtime,
+            commands::set_preserve_mtime,
+            commands::get_file_ratings,
+            commands::set_file_ratings,
+            cloud_files::get_materialize_cloud_files,
+            cloud_files::set_materialize_cloud_files,
+            cloud_files::materialize_files,
+            maintenance::get_nightly_maintenance,
+            maintenance::set_nightly_maintenance,
+            maintenance::run_maintenance_now,
+            maintenance::get_last_maintenance_report,
+            maintenance::cleanup_orphans,
+            maintenance::run_db_maintenance,
+            file_permissions::fix_permissions
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
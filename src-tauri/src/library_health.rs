@@ -0,0 +1,342 @@
+//! Triage queries for keeping the library healthy: untagged tracks, tracks missing
+//! basic metadata, audio files that are empty, corrupt or cut short, and low-bitrate
+//! rips that have (or need) a better copy.
+
+use crate::commands::AppState;
+use crate::models::Track;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Window for `get_recently_added_untagged` when no `days` are given.
+const DEFAULT_RECENT_DAYS: u32 = 30;
+/// A file holding less than this share of the audio its header promises is truncated
+const TRUNCATION_RATIO: f64 = 0.9;
+/// Packets that may fail to decode before a file counts as corrupt
+const MAX_BAD_PACKETS: usize = 3;
+/// `verify-progress` is emitted every this many files
+const VERIFY_PROGRESS_INTERVAL: usize = 25;
+/// Lossy files below this bitrate are flagged by `get_quality_audit` unless told otherwise
+const DEFAULT_MIN_KBPS: u32 = 256;
+/// Copies whose lengths differ by more than this are different edits, not duplicates
+const DUPLICATE_DURATION_TOLERANCE_SECS: f64 = 3.0;
+/// Format names (Music.app kinds or file extensions) of lossless audio
+const LOSSLESS_FORMATS: &[&str] = &["lossless", "alac", "flac", "aiff", "aif", "wav"];
+/// Where a "featuring" credit starts in an artist or title
+const FEATURING_MARKERS: &[&str] = &["(feat.", "[feat.", " feat. ", " feat ", "(ft.", " ft. "];
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MetadataField {
+    Bpm,
+    /// Camelot key at the start of the comment
+    Key,
+    Genre,
+    Artwork,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioHealth {
+    Ok,
+    /// Zero-byte file
+    Empty,
+    /// Less audio than the header promises
+    Truncated,
+    /// Unreadable header or undecodable audio
+    Corrupt,
+    /// File not found
+    Missing,
+    /// Cloud placeholder (see `cloud_files`); not checked, as reading it would download it
+    Offloaded,
+}
+
+impl AudioHealth {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AudioHealth::Ok => "ok",
+            AudioHealth::Empty => "empty",
+            AudioHealth::Truncated => "truncated",
+            AudioHealth::Corrupt => "corrupt",
+            AudioHealth::Missing => "missing",
+            AudioHealth::Offloaded => "offloaded",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioCheck {
+    pub track_id: i64,
+    pub file_path: String,
+    pub health: AudioHealth,
+    /// What was wrong, for anything but `Ok`
+    pub detail: Option<String>,
+}
+
+/// Payload of `verify-progress`.
+#[derive(Clone, Serialize)]
+pub struct VerifyProgress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+/// Checks a file from its size and header: the audio properties must parse, and the file
+/// must be about as long as the header's duration and bitrate say. Returns the header's
+/// duration for the full decode.
+fn check_header(path: &str) -> Result<f64, (AudioHealth, String)> {
+    use lofty::prelude::*;
+
+    if crate::cloud_files::is_placeholder(path) {
+        return Err((AudioHealth::Offloaded, "Cloud placeholder, not downloaded".to_string()));
+    }
+    let size = match std::fs::metadata(path) {
+        Ok(meta) => meta.len(),
+        Err(_) => return Err((AudioHealth::Missing, "File not found".to_string())),
+    };
+    if size == 0 {
+        return Err((AudioHealth::Empty, "Zero-byte file".to_string()));
+    }
+    let tagged_file = lofty::read_from_path(path)
+        .map_err(|e| (AudioHealth::Corrupt, format!("Unreadable header: {}", e)))?;
+    let properties = tagged_file.properties();
+    let duration = properties.duration().as_secs_f64();
+    if duration <= 0.0 {
+        return Err((AudioHealth::Corrupt, "Header has no audio duration".to_string()));
+    }
+    if let Some(kbps) = properties.audio_bitrate().filter(|k| *k > 0) {
+        let expected = kbps as f64 * 1000.0 / 8.0 * duration;
+        if (size as f64) < expected * TRUNCATION_RATIO {
+            return Err((
+                AudioHealth::Truncated,
+                format!("File holds {:.0}% of the audio its header promises", size as f64 / expected * 100.0),
+            ));
+        }
+    }
+    Ok(duration)
+}
+
+/// Decodes the whole file and compares what decoded with the header's duration.
+#[cfg(feature = "audio-analysis")]
+fn check_stream(path: &str, header_secs: f64) -> Result<(), (AudioHealth, String)> {
+    let check = crate::audio::check_stream(path)
+        .map_err(|e| (AudioHealth::Corrupt, format!("Decoding failed: {}", e)))?;
+    if check.bad_packets > MAX_BAD_PACKETS {
+        return Err((AudioHealth::Corrupt, format!("{} packets failed to decode", check.bad_packets)));
+    }
+    if check.decoded_secs < header_secs * TRUNCATION_RATIO {
+        return Err((
+            AudioHealth::Truncated,
+            format!("Decoded {:.0}s of {:.0}s", check.decoded_secs, header_secs),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "audio-analysis"))]
+fn check_stream(_path: &str, _header_secs: f64) -> Result<(), (AudioHealth, String)> {
+    let _ = MAX_BAD_PACKETS;
+    Ok(())
+}
+
+/// Checks the audio files of `ids` (or the whole library) for empty, corrupt or truncated
+/// files and stores each verdict on the track. Header checks only by default; with
+/// `full_decode` every file is decoded end to end, which is slow but catches damage in the
+/// middle of the stream. Returns the files with problems. Emits `verify-progress`.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn verify_audio_files(
+    ids: Option<Vec<i64>>,
+    full_decode: Option<bool>,
+    app: AppHandle,
+) -> Result<Vec<AudioCheck>, String> {
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let full_decode = full_decode.unwrap_or(false);
+    if full_decode && !cfg!(feature = "audio-analysis") {
+        return Err("Full decoding is not included in this build (enable the audio-analysis feature)".to_string());
+    }
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || -> Result<Vec<AudioCheck>, String> {
+        let state = handle.state::<AppState>();
+        let tracks: Vec<Track> = {
+            let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+            let tracks: Vec<Track> = match ids {
+                Some(ids) => ids.iter().filter_map(|id| db.get_track(*id).ok().flatten()).collect(),
+                None => db.get_all_tracks().map_err(|e| e.to_string())?,
+            };
+            // Apple Music cloud tracks have no file to check
+            tracks.into_iter().filter(|t| !t.is_cloud).collect()
+        };
+
+        let total = tracks.len();
+        let processed = AtomicUsize::new(0);
+        let checks: Vec<AudioCheck> = tracks.into_par_iter()
+            .map(|track| {
+                let result = check_header(&track.file_path)
+                    .and_then(|secs| if full_decode { check_stream(&track.file_path, secs) } else { Ok(()) });
+                let n = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                if n % VERIFY_PROGRESS_INTERVAL == 0 || n == total {
+                    let _ = handle.emit("verify-progress", VerifyProgress { processed: n, total });
+                }
+                let (health, detail) = match result {
+                    Ok(()) => (AudioHealth::Ok, None),
+                    Err((health, detail)) => (health, Some(detail)),
+                };
+                AudioCheck { track_id: track.id, file_path: track.file_path, health, detail }
+            })
+            .collect();
+
+        let verdicts: Vec<(i64, &str)> = checks.iter().map(|c| (c.track_id, c.health.as_str())).collect();
+        state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+            .update_audio_health(&verdicts)
+            .map_err(|e| format!("DB Error (update audio health): {}", e))?;
+
+        let problems: Vec<AudioCheck> = checks.into_iter().filter(|c| c.health != AudioHealth::Ok).collect();
+        tracing::info!("Verified {} audio file(s): {} with problems", total, problems.len());
+        Ok(problems)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpgradeCandidate {
+    pub track: Track,
+    /// Copies of the same recording in the library at a higher quality, best first
+    pub better_copies: Vec<Track>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QualityAudit {
+    pub min_kbps: u32,
+    pub scanned: usize,
+    /// Low-quality tracks; those with a better copy already in the library come first
+    pub candidates: Vec<UpgradeCandidate>,
+}
+
+fn is_lossless(track: &Track) -> bool {
+    let format = track.format.to_lowercase();
+    let extension = std::path::Path::new(&track.file_path).extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    LOSSLESS_FORMATS.iter().any(|f| format.contains(f) || extension == *f)
+}
+
+/// Orders copies by quality: lossless above any lossy file, then by bitrate.
+fn quality_rank(track: &Track) -> (bool, i64) {
+    (is_lossless(track), track.bit_rate)
+}
+
+/// Artist and title folded so that re-releases and re-rips of a recording match:
+/// case, accents, punctuation, "feat." credits and "(Original Mix)" are ignored.
+fn recording_key(track: &Track) -> Option<String> {
+    use unicode_normalization::UnicodeNormalization;
+
+    let fold = |s: &str| -> String {
+        let mut s = s.to_lowercase().replace("(original mix)", "");
+        if let Some(at) = FEATURING_MARKERS.iter().filter_map(|m| s.find(m)).min() {
+            s.truncate(at);
+        }
+        s.nfkd()
+            .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+            .collect::<String>()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let artist = fold(track.artist.as_deref()?);
+    let title = fold(track.title.as_deref()?);
+    (!artist.is_empty() && !title.is_empty()).then(|| format!("{}\u{1f}{}", artist, title))
+}
+
+/// Lossy tracks below `min_kbps` (default 256), each with any higher-quality copies of the
+/// same recording found in the library, so old rips can be replaced systematically.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_quality_audit(min_kbps: Option<u32>, state: State<'_, AppState>) -> Result<QualityAudit, String> {
+    let min_kbps = min_kbps.unwrap_or(DEFAULT_MIN_KBPS);
+    let tracks = state.db.read().map_err(|_| "Failed to lock DB".to_string())?
+        .get_all_tracks().map_err(|e| e.to_string())?;
+    let scanned = tracks.len();
+
+    let mut by_recording: HashMap<String, Vec<&Track>> = HashMap::new();
+    for track in tracks.iter().filter(|t| !t.missing) {
+        if let Some(key) = recording_key(track) {
+            by_recording.entry(key).or_default().push(track);
+        }
+    }
+
+    let mut candidates: Vec<UpgradeCandidate> = tracks.iter()
+        .filter(|t| !t.missing && !is_lossless(t) && t.bit_rate > 0 && t.bit_rate < min_kbps as i64)
+        .map(|track| {
+            let mut better_copies: Vec<Track> = recording_key(track)
+                .and_then(|key| by_recording.get(&key))
+                .into_iter()
+                .flatten()
+                .filter(|other| other.id != track.id
+                    && quality_rank(other) > quality_rank(track)
+                    && (other.duration_secs - track.duration_secs).abs() <= DUPLICATE_DURATION_TOLERANCE_SECS)
+                .map(|other| (*other).clone())
+                .collect();
+            better_copies.sort_by_key(|t| std::cmp::Reverse(quality_rank(t)));
+            UpgradeCandidate { track: track.clone(), better_copies }
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.better_copies.is_empty().cmp(&b.better_copies.is_empty())
+        .then_with(|| a.track.bit_rate.cmp(&b.track.bit_rate)));
+
+    tracing::info!(
+        "Quality audit: {} of {} tracks below {} kbps, {} with a better copy",
+        candidates.len(), scanned, min_kbps, candidates.iter().filter(|c| !c.better_copies.is_empty()).count()
+    );
+    Ok(QualityAudit { min_kbps, scanned, candidates })
+}
+
+/// Tracks without any tags, newest first.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_untagged_tracks(state: State<'_, AppState>) -> Result<Vec<Track>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    db.get_untagged_tracks(None).map_err(|e| e.to_string())
+}
+
+/// Tracks added in the last `days` (default 30) that have not been tagged yet, newest first.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_recently_added_untagged(days: Option<u32>, state: State<'_, AppState>) -> Result<Vec<Track>, String> {
+    let days = days.unwrap_or(DEFAULT_RECENT_DAYS) as i64;
+    let since = chrono::Utc::now().timestamp() - days * 24 * 60 * 60;
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    db.get_untagged_tracks(Some(since)).map_err(|e| e.to_string())
+}
+
+/// Tracks lacking `field`. BPM and key come from the DB; genre and artwork are not
+/// stored there, so those read every (present) file's tags on a blocking thread.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_tracks_missing(app: AppHandle, field: MetadataField) -> Result<Vec<Track>, String> {
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || -> Result<Vec<Track>, String> {
+        let state = handle.state::<AppState>();
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        match field {
+            MetadataField::Bpm => db.get_tracks_without_bpm().map_err(|e| e.to_string()),
+            MetadataField::Key => db.get_tracks_without_key().map_err(|e| e.to_string()),
+            MetadataField::Genre | MetadataField::Artwork => {
+                let tracks = db.get_all_tracks().map_err(|e| e.to_string())?;
+                drop(db);
+                Ok(tracks.into_iter()
+                    .filter(|t| !t.missing)
+                    .filter(|t| match field {
+                        MetadataField::Genre => matches!(crate::metadata::read_genre(&t.file_path), Ok(None)),
+                        _ => matches!(crate::metadata::has_artwork(&t.file_path), Ok(false)),
+                    })
+                    .collect())
+            }
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
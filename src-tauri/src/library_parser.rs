@@ -1,10 +1,12 @@
-use crate::models::Track;
+use crate::models::{Playlist, Track};
 use anyhow::{Context, Result};
-use plist::Value;
+use plist::{Dictionary, Value};
+use std::collections::HashMap;
 use std::path::Path;
 use url::Url;
 
-pub fn parse_library<P: AsRef<Path>>(path: P) -> Result<Vec<Track>> {
+/// Parses tracks and playlists (including folders) from an iTunes/Music Library XML.
+pub fn parse_library<P: AsRef<Path>>(path: P) -> Result<(Vec<Track>, Vec<Playlist>)> {
     let value = Value::from_file(path).context("Failed to read iTunes Library XML")?;
 
     let root_dict = value.as_dictionary().context("Root is not a dictionary")?;
@@ -15,17 +17,15 @@ pub fn parse_library<P: AsRef<Path>>(path: P) -> Result<Vec<Track>> {
         .context("Tracks is not a dictionary")?;
 
     let mut tracks = Vec::new();
+    // Playlist items reference tracks by their numeric Track ID
+    let mut pid_by_track_id: HashMap<String, String> = HashMap::new();
 
-    for (_key, track_value) in tracks_dict {
+    for (track_key, track_value) in tracks_dict {
         let track_info = track_value.as_dictionary().unwrap(); // Should handle error gracefully
 
-        // Skip remote/streamed tracks
-        if track_info.contains_key("Track Type") {
-            if let Some(type_str) = track_info.get("Track Type").and_then(|v| v.as_string()) {
-                if type_str == "Remote" || type_str == "Stream" {
-                    continue;
-                }
-            }
+        // Skip streamed tracks (radio); "Remote" ones are cloud items, handled below
+        if track_info.get("Track Type").and_then(|v| v.as_string()) == Some("Stream") {
+            continue;
         }
 
         // Essential fields
@@ -34,13 +34,18 @@ pub fn parse_library<P: AsRef<Path>>(path: P) -> Result<Vec<Track>> {
             .and_then(|v| v.as_string())
             .unwrap_or_default()
             .to_string();
+        pid_by_track_id.insert(track_key.clone(), persistent_id.clone());
         let location_raw = track_info.get("Location").and_then(|v| v.as_string());
 
-        if location_raw.is_none() {
-            continue; // Skip if no file location
+        // Apple Music streaming and iCloud-only items have no location and are "Remote";
+        // anything else without a location (radio streams) is skipped
+        let is_cloud = location_raw.is_none()
+            && track_info.get("Track Type").and_then(|v| v.as_string()) == Some("Remote");
+        if location_raw.is_none() && !is_cloud {
+            continue;
         }
 
-        let location = decode_location(location_raw.unwrap());
+        let location = location_raw.map(decode_location).unwrap_or_default();
 
         let name = track_info
             .get("Name")
@@ -114,7 +119,27 @@ pub fn parse_library<P: AsRef<Path>>(path: P) -> Result<Vec<Track>> {
         let bpm = track_info
             .get("BPM")
             .and_then(|v| v.as_unsigned_integer())
+            .unwrap_or(0) as f64;
+
+        let play_count = track_info
+            .get("Play Count")
+            .and_then(|v| v.as_unsigned_integer())
             .unwrap_or(0);
+        let skip_count = track_info
+            .get("Skip Count")
+            .and_then(|v| v.as_unsigned_integer())
+            .unwrap_or(0);
+        let last_played = track_info
+            .get("Play Date UTC")
+            .and_then(|v| v.as_date())
+            .map(|d| std::time::SystemTime::from(d.clone()))
+            .and_then(|d| d.duration_since(std::time::SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        // Older iTunes XML says "Loved", newer Music.app exports say "Favorited"
+        let loved = ["Loved", "Favorited"].iter()
+            .any(|key| track_info.get(key).and_then(|v| v.as_boolean()).unwrap_or(false));
 
         // Simple format detection from extension
         let format = location
@@ -139,17 +164,76 @@ pub fn parse_library<P: AsRef<Path>>(path: P) -> Result<Vec<Track>> {
             modified_date: modified_timestamp,
             rating: rating as i64,
             date_added: date_added_timestamp,
-            bpm: bpm as i64,
+            bpm,
             missing: false,
+            play_count: play_count as i64,
+            skip_count: skip_count as i64,
+            last_played,
+            loved,
+            notes: None,
+            intro_bars: None,
+            outro_bars: None,
+            audio_health: None,
+            color_label: None,
+            offloaded: false,
+            is_cloud,
         };
 
         tracks.push(track);
     }
 
-    Ok(tracks)
+    let playlists = root_dict
+        .get("Playlists")
+        .and_then(|v| v.as_array())
+        .map(|entries| {
+            entries.iter()
+                .filter_map(|v| v.as_dictionary())
+                .filter_map(|info| parse_playlist(info, &pid_by_track_id))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok((tracks, playlists))
+}
+
+/// Converts one XML playlist entry. The library itself and built-in playlists
+/// (Music, Movies, Podcasts...) are skipped, matching the Music.app import.
+fn parse_playlist(info: &Dictionary, pid_by_track_id: &HashMap<String, String>) -> Option<Playlist> {
+    let flag = |key: &str| info.get(key).and_then(|v| v.as_boolean()).unwrap_or(false);
+    if flag("Master") || info.contains_key("Distinguished Kind") {
+        return None;
+    }
+
+    let persistent_id = info.get("Playlist Persistent ID")?.as_string()?.to_string();
+    let name = info.get("Name").and_then(|v| v.as_string()).unwrap_or_default().to_string();
+    let parent_persistent_id = info
+        .get("Parent Persistent ID")
+        .and_then(|v| v.as_string())
+        .map(|s| s.to_string());
+
+    let track_ids = info
+        .get("Playlist Items")
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items.iter()
+                .filter_map(|item| item.as_dictionary()?.get("Track ID")?.as_signed_integer())
+                .filter_map(|id| pid_by_track_id.get(&id.to_string()).cloned())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(Playlist {
+        id: 0,
+        persistent_id,
+        parent_persistent_id,
+        name,
+        is_folder: flag("Folder"),
+        track_ids: Some(track_ids),
+        is_smart: info.contains_key("Smart Info"),
+    })
 }
 
-fn decode_location(location: &str) -> String {
+pub(crate) fn decode_location(location: &str) -> String {
     // 1. Try robust parsing using url crate first
     // This handles standard file:/// paths correctly yielding system paths
     if let Ok(parsed) = Url::parse(location) {
@@ -196,3 +280,37 @@ fn finalize_path(path_str: &str) -> String {
 
     path_str.to_string()
 }
+
+/// Candidate locations for the typical "iTunes vs iTunes/Music" nesting issue:
+/// the same file may live under any of the iTunes Music / iTunes Media variations.
+/// Returns alternatives only (not `path` itself).
+pub(crate) fn itunes_path_variants(path: &str) -> Vec<String> {
+    const NESTINGS: [&str; 4] = [
+        "/iTunes/Music/",
+        "/iTunes/iTunes Music/",
+        "/iTunes/iTunes Media/Music/",
+        "/iTunes/iTunes Media/",
+    ];
+
+    let mut variants = Vec::new();
+    if !path.contains("/iTunes/") {
+        return variants;
+    }
+
+    // Collapse any known nesting back to the bare "/iTunes/" form, then expand to each candidate
+    let mut base = path.to_string();
+    for nesting in NESTINGS {
+        if path.contains(nesting) {
+            base = path.replacen(nesting, "/iTunes/", 1);
+            break;
+        }
+    }
+
+    for candidate in std::iter::once("/iTunes/").chain(NESTINGS) {
+        let variant = base.replacen("/iTunes/", candidate, 1);
+        if variant != path && !variants.contains(&variant) {
+            variants.push(variant);
+        }
+    }
+    variants
+}
@@ -1,135 +1,878 @@
+use crate::commands::AppState;
 use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::PathBuf;
-use std::sync::mpsc::{channel, RecvTimeoutError};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
 use std::thread;
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Emitter, Manager, State};
 
+/// Settings key holding the JSON array of watched paths.
+const WATCH_PATHS_KEY: &str = "watch_paths";
+/// Settings key: "true" when the audio files directory should be monitored.
+const WATCH_AUDIO_KEY: &str = "watch_audio_files";
+/// Settings key: directory holding the audio files (defaults to the Music.app media folder).
+const MEDIA_FOLDER_KEY: &str = "media_folder";
+/// Settings key: folder whose new audio files are auto-imported (empty = disabled).
+const INBOX_FOLDER_KEY: &str = "inbox_folder";
+/// Tag applied to every auto-imported inbox track.
+const INBOX_TAG: &str = "inbox";
+
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "m4a", "aac", "aif", "aiff", "wav", "flac", "alac", "ogg"];
+
+/// Holds the active watcher. Replacing it drops the previous watcher, which closes
+/// its event channel and ends that watcher's debounce thread.
+#[derive(Default)]
+pub struct WatcherState {
+    watcher: Mutex<Option<RecommendedWatcher>>,
+    audio_watcher: Mutex<Option<RecommendedWatcher>>,
+    inbox_watcher: Mutex<Option<RecommendedWatcher>>,
+    /// Bumped by every library watcher (re)start; older debounce threads and pending
+    /// restarts check it and stand down
+    generation: AtomicU64,
+    /// Restarts in a row without a successful event, for the backoff
+    failures: AtomicU32,
+    status: Mutex<WatcherStatus>,
+}
+
+#[derive(Serialize)]
+pub struct WatchPath {
+    pub path: String,
+    pub exists: bool,
+}
+
+#[derive(Serialize)]
+pub struct AudioWatchSettings {
+    pub enabled: bool,
+    pub folder: String,
+    pub exists: bool,
+}
+
+/// Payload of the `track-externally-modified` event.
+#[derive(Serialize, Clone)]
+pub struct ExternalModification {
+    pub track_id: i64,
+    pub persistent_id: String,
+    pub file_path: String,
+    pub old_comment: String,
+    pub new_comment: String,
+    pub old_grouping: String,
+    pub new_grouping: String,
+}
+
+/// Standard Music.app / iTunes library locations plus the library the configured media
+/// folder belongs to, used until the user customizes the list.
+pub fn default_watch_paths(media_folder: &Path) -> Vec<PathBuf> {
+    let home_dir = dirs::home_dir().unwrap_or(PathBuf::from("/Users/Shared"));
+    let music_dir_modern = home_dir.join("Music/Music");
+    let music_dir_legacy = home_dir.join("Music/iTunes");
+
+    let mut paths = vec![
+        // Modern: ~/Music/Music/Music Library.musiclibrary
+        music_dir_modern.join("Music Library.musiclibrary"),
+        // Modern XML: ~/Music/Music/Library.xml
+        music_dir_modern.join("Library.xml"),
+        // Legacy: ~/Music/iTunes/iTunes Library.xml
+        music_dir_legacy.join("iTunes Library.xml"),
+        // Legacy Variation: ~/Music/iTunes/iTunes Music Library.xml
+        music_dir_legacy.join("iTunes Music Library.xml"),
+    ];
+
+    // Music.app keeps the media folder inside the library folder, so a library created
+    // elsewhere (e.g. ~/Music/Music 1) is found through the media folder setting
+    if let Some(library_dir) = media_folder.parent() {
+        for path in [library_dir.join("Music Library.musiclibrary"), library_dir.join("Library.xml")] {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths
+}
+
+fn load_watch_paths(state: &AppState) -> Vec<PathBuf> {
+    let stored = state.db.read().ok()
+        .and_then(|db| db.get_setting(WATCH_PATHS_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str::<Vec<String>>(&json).ok());
+
+    match stored {
+        Some(paths) => paths.into_iter().map(PathBuf::from).collect(),
+        None => default_watch_paths(&load_audio_watch_settings(state).1),
+    }
+}
+
+fn save_watch_paths(state: &AppState, paths: &[PathBuf]) -> Result<(), String> {
+    let list: Vec<String> = paths.iter().map(|p| p.to_string_lossy().to_string()).collect();
+    let json = serde_json::to_string(&list).map_err(|e| e.to_string())?;
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    db.set_setting(WATCH_PATHS_KEY, &json).map_err(|e| e.to_string())
+}
+
+fn describe_paths(paths: &[PathBuf]) -> Vec<WatchPath> {
+    paths.iter().map(|p| WatchPath {
+        path: p.to_string_lossy().to_string(),
+        exists: p.exists(),
+    }).collect()
+}
+
+/// (Re)starts the library watcher with the configured paths.
+/// Safe to call while a watcher is already running; the old one is replaced.
 pub fn start_library_watcher(app: AppHandle) {
+    let generation = app.state::<WatcherState>().generation.fetch_add(1, Ordering::SeqCst) + 1;
     let app_handle = app.clone();
-    
-    thread::spawn(move || {
-        let (tx, rx) = channel();
-
-        // Attempt to create the watcher
-        let mut watcher: Box<dyn Watcher> = match RecommendedWatcher::new(tx, Config::default()) {
-            Ok(w) => Box::new(w),
-            Err(e) => {
-                let msg = format!("Failed to create library watcher: {}", e);
-                eprintln!("{}", msg);
-                // Try logging to app if possible, though this is a startup thread
-                return;
+    let (tx, rx) = channel();
+
+    // Attempt to create the watcher
+    let mut watcher = match RecommendedWatcher::new(tx, Config::default()) {
+        Ok(w) => w,
+        Err(e) => {
+            tracing::error!("Failed to create library watcher: {}", e);
+            watcher_failed(&app, generation, format!("Failed to create watcher: {}", e));
+            return;
+        }
+    };
+
+    let paths_to_watch = load_watch_paths(&app.state::<AppState>());
+    let mut watching = Vec::new();
+
+    for path in &paths_to_watch {
+        if path.exists() {
+           // Use Recursive to catch changes inside .musiclibrary package
+           if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
+               tracing::error!("Failed to watch path {:?}: {}", path, e);
+               record_watcher_error(&app, format!("Failed to watch {}: {}", path.display(), e));
+           } else {
+               tracing::debug!("Started watching: {:?}", path);
+               watching.push(path.to_string_lossy().to_string());
+           }
+        } else {
+            // Determine if parent exists to give a hint
+            if let Some(parent) = path.parent() {
+                if parent.exists() {
+                    tracing::debug!("Path not found, but parent exists (watching skipped): {:?}", path);
+                }
             }
-        };
+        }
+    }
 
-        // Determine paths to watch
+    let fallback = watching.is_empty();
+    if fallback {
+        tracing::warn!("No Music library files found to watch at configured locations.");
+        // Fallback: Watch ~/Music/Music folder directly
         let home_dir = dirs::home_dir().unwrap_or(PathBuf::from("/Users/Shared"));
         let music_dir_modern = home_dir.join("Music/Music");
-        let music_dir_legacy = home_dir.join("Music/iTunes");
-        
-        let mut paths_to_watch = Vec::new();
-        
-        // Modern: ~/Music/Music/Music Library.musiclibrary
-        paths_to_watch.push(music_dir_modern.join("Music Library.musiclibrary"));
-        // Modern XML: ~/Music/Music/Library.xml
-        paths_to_watch.push(music_dir_modern.join("Library.xml"));
-        
-        // Legacy: ~/Music/iTunes/iTunes Library.xml
-        paths_to_watch.push(music_dir_legacy.join("iTunes Library.xml"));
-        // Legacy Variation: ~/Music/iTunes/iTunes Music Library.xml (seen in user ls)
-        paths_to_watch.push(music_dir_legacy.join("iTunes Music Library.xml"));
-
-        // User Custom Locations (Confimed via lsof)
-        let home = dirs::home_dir().unwrap_or(PathBuf::from("/Users/Shared"));
-        paths_to_watch.push(home.join("Music/Music 1/Music Library.musiclibrary"));
-        
-        let mut watching_any = false;
-
-        for path in &paths_to_watch {
-            if path.exists() {
-               // Use Recursive to catch changes inside .musiclibrary package
-               if let Err(e) = watcher.watch(path, RecursiveMode::Recursive) {
-                   eprintln!("[WATCHER] Failed to watch path {:?}: {}", path, e);
-               } else {
-                   println!("[WATCHER] Started watching: {:?}", path);
-                   watching_any = true;
-               }
-            } else {
-                // Determine if parent exists to give a hint
-                if let Some(parent) = path.parent() {
-                    if parent.exists() {
-                        println!("[WATCHER] Path not found, but parent exists (watching skipped): {:?}", path);
+        if music_dir_modern.exists() && watcher.watch(&music_dir_modern, RecursiveMode::Recursive).is_ok() {
+             tracing::debug!("Fallback: Watching Music directory: {:?}", music_dir_modern);
+             watching.push(music_dir_modern.to_string_lossy().to_string());
+        }
+    }
+
+    // Swap in the new watcher; dropping the previous one disconnects its channel
+    if let Ok(mut slot) = app.state::<WatcherState>().watcher.lock() {
+        *slot = Some(watcher);
+    }
+    let status = update_watcher_status(&app, |status| {
+        status.running = true;
+        status.watching = watching;
+        status.fallback = fallback;
+        status.retry_at = None;
+    });
+    if fallback {
+        let _ = app.emit("watcher-degraded", status);
+    }
+
+    thread::spawn(move || {
+        let outcome = std::panic::catch_unwind(AssertUnwindSafe(|| watch_library_events(&app_handle, rx, generation)));
+        let reason = match outcome {
+            Ok(Some(reason)) => reason,
+            // Replaced by a newer watcher
+            Ok(None) => return,
+            Err(_) => "Watcher thread panicked".to_string(),
+        };
+        tracing::warn!("Library watcher stopped: {}", reason);
+        watcher_failed(&app_handle, generation, reason);
+    });
+}
+
+/// Watch errors in a row after which the watcher is rebuilt
+const MAX_CONSECUTIVE_WATCH_ERRORS: u32 = 5;
+/// First restart delay; doubles with each failure in a row
+const RESTART_BASE_DELAY: Duration = Duration::from_secs(2);
+const RESTART_MAX_DELAY: Duration = Duration::from_secs(300);
+/// Errors kept for `get_watcher_status`
+const MAX_WATCHER_ERRORS: usize = 20;
+
+/// Library watcher health, as reported by `get_watcher_status`.
+#[derive(Serialize, Clone, Default)]
+pub struct WatcherStatus {
+    pub running: bool,
+    /// Paths actually being watched
+    pub watching: Vec<String>,
+    /// No configured path existed, so only the Music folder (if any) is watched
+    pub fallback: bool,
+    /// Unix time of the last relevant file event
+    pub last_event_at: Option<i64>,
+    /// Most recent errors, oldest first
+    pub errors: Vec<String>,
+    /// Automatic restarts since launch
+    pub restarts: u32,
+    /// Unix time of the next restart attempt while the watcher is down
+    pub retry_at: Option<i64>,
+}
+
+fn update_watcher_status(app: &AppHandle, f: impl FnOnce(&mut WatcherStatus)) -> WatcherStatus {
+    let state = app.state::<WatcherState>();
+    let mut status = state.status.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut status);
+    status.clone()
+}
+
+fn record_watcher_error(app: &AppHandle, error: String) {
+    update_watcher_status(app, |status| {
+        status.errors.push(format!("{} {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), error));
+        if status.errors.len() > MAX_WATCHER_ERRORS {
+            status.errors.remove(0);
+        }
+    });
+}
+
+fn is_current_watcher(app: &AppHandle, generation: u64) -> bool {
+    app.state::<WatcherState>().generation.load(Ordering::SeqCst) == generation
+}
+
+/// Records why the watcher went down and retries with exponential backoff, unless it has
+/// been replaced in the meantime.
+fn watcher_failed(app: &AppHandle, generation: u64, reason: String) {
+    if !is_current_watcher(app, generation) {
+        return;
+    }
+    record_watcher_error(app, reason);
+    let failures = app.state::<WatcherState>().failures.fetch_add(1, Ordering::SeqCst);
+    let delay = RESTART_BASE_DELAY.saturating_mul(2u32.saturating_pow(failures)).min(RESTART_MAX_DELAY);
+    let status = update_watcher_status(app, |status| {
+        status.running = false;
+        status.retry_at = Some(chrono::Utc::now().timestamp() + delay.as_secs() as i64);
+    });
+    let _ = app.emit("watcher-degraded", status);
+    tracing::info!("Restarting library watcher in {}s", delay.as_secs());
+
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(delay);
+        if is_current_watcher(&app, generation) {
+            update_watcher_status(&app, |status| status.restarts += 1);
+            start_library_watcher(app);
+        }
+    });
+}
+
+/// Debounces file events and syncs once they settle. Returns why it stopped, or `None` when
+/// a newer watcher replaced this one.
+fn watch_library_events(
+    app: &AppHandle,
+    rx: Receiver<notify::Result<notify::Event>>,
+    generation: u64,
+) -> Option<String> {
+    // Trailing debounce per path: a file is handled once it has been quiet for
+    // `debounce_duration`, so a slow XML export doesn't hold back the database change.
+    let debounce_duration = Duration::from_secs(2);
+    let mut pending: HashMap<PathBuf, (LibraryFileKind, Instant, u32)> = HashMap::new();
+    let mut last_db_sync: Option<Instant> = None;
+    let mut consecutive_errors = 0;
+
+    loop {
+        let evt = match pending.values().map(|(_, at, _)| *at).min() {
+            Some(oldest) => rx.recv_timeout(debounce_duration.saturating_sub(oldest.elapsed())),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match evt {
+            Ok(Ok(event)) => {
+                // Events are flowing again: reset the backoff
+                consecutive_errors = 0;
+                app.state::<WatcherState>().failures.store(0, Ordering::SeqCst);
+                let mut relevant = false;
+                for path in &event.paths {
+                    let kind = classify_library_path(path);
+                    if kind == LibraryFileKind::Other {
+                        continue;
+                    }
+                    relevant = true;
+                    let entry = pending.entry(path.clone()).or_insert((kind, Instant::now(), 0));
+                    if entry.2 == 0 {
+                        tracing::debug!("Change detected in {:?} ({:?}). Waiting for activity to settle...", path, kind);
                     }
+                    entry.1 = Instant::now();
+                    entry.2 += 1;
+                }
+                if relevant {
+                    update_watcher_status(app, |status| status.last_event_at = Some(chrono::Utc::now().timestamp()));
+                }
+            }
+            Ok(Err(e)) => {
+                tracing::error!("Watch error: {:?}", e);
+                record_watcher_error(app, format!("Watch error: {}", e));
+                consecutive_errors += 1;
+                if consecutive_errors >= MAX_CONSECUTIVE_WATCH_ERRORS {
+                    return Some(format!("{} watch errors in a row", consecutive_errors));
                 }
             }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                if !is_current_watcher(app, generation) {
+                    tracing::debug!("Channel disconnected. Watcher replaced.");
+                    return None;
+                }
+                return Some("Event channel closed".to_string());
+            }
         }
 
-        if !watching_any {
-            eprintln!("[WATCHER] No Music library files found to watch at standard locations.");
-            // Fallback: Watch ~/Music/Music folder directly
-            if music_dir_modern.exists() {
-                 let _ = watcher.watch(&music_dir_modern, RecursiveMode::Recursive);
-                 println!("[WATCHER] Fallback: Watching Music directory: {:?}", music_dir_modern);
+        let settled: Vec<PathBuf> = pending.iter()
+            .filter(|(_, (_, at, _))| at.elapsed() >= debounce_duration)
+            .map(|(path, _)| path.clone())
+            .collect();
+        if settled.is_empty() {
+            continue;
+        }
+        let mut kinds = Vec::new();
+        for path in settled {
+            if let Some((kind, _, count)) = pending.remove(&path) {
+                tracing::debug!("{:?} settled after {} events", path, count);
+                kinds.push(kind);
             }
         }
 
-        // Trailing Debounce Implementation
-        // We wait for an event. Once received, we wait for silence for 'debounce_duration'.
+        let db_changed = kinds.contains(&LibraryFileKind::Database);
+        // Music.app rewrites the XML export right after its database; that export
+        // carries nothing the database sync didn't already pick up
+        let xml_is_echo = last_db_sync.is_some_and(|at| at.elapsed() < XML_ECHO_WINDOW);
+        if !db_changed && xml_is_echo {
+            tracing::debug!("Skipping sync for XML export of an already-synced change");
+            continue;
+        }
+        if db_changed {
+            last_db_sync = Some(Instant::now());
+        }
+        sync_after_change(app, db_changed);
+    }
+}
+
+/// What a changed path under a watched library location means for syncing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LibraryFileKind {
+    /// The library database (`Library.musicdb`, `iTunes Library.itl`)
+    Database,
+    /// `Library.xml` / `iTunes Library.xml`, re-exported after each database change
+    XmlExport,
+    /// Artwork cache, preferences, temp and lock files: nothing to sync
+    Other,
+}
+
+/// How long after a database sync an XML export is treated as its echo
+const XML_ECHO_WINDOW: Duration = Duration::from_secs(60);
+/// Settings key: "false" stops watcher-triggered syncs
+const REAL_TIME_SYNC_KEY: &str = "real_time_sync";
+
+fn classify_library_path(path: &Path) -> LibraryFileKind {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let s = path.to_string_lossy();
+    if s.contains(".tmp") || name.ends_with(".lock") || name.ends_with(".log") {
+        return LibraryFileKind::Other;
+    }
+    if name.ends_with(".xml") {
+        return LibraryFileKind::XmlExport;
+    }
+    if name == "library.musicdb" || name.ends_with(".itl") {
+        return LibraryFileKind::Database;
+    }
+    // Events on the package directory itself (some FSEvents coalescing) count as a database change
+    if name.ends_with(".musiclibrary") {
+        return LibraryFileKind::Database;
+    }
+    LibraryFileKind::Other
+}
+
+/// Whether a watched path covers the library database, so an XML export arriving on its
+/// own means the database did not change.
+fn database_watched(state: &AppState) -> bool {
+    load_watch_paths(state).iter()
+        .any(|path| path.exists() && classify_library_path(path) == LibraryFileKind::Database)
+}
+
+fn real_time_sync_enabled(state: &AppState) -> bool {
+    state.db.read().ok()
+        .and_then(|db| db.get_setting(REAL_TIME_SYNC_KEY).ok().flatten())
+        .as_deref() != Some("false")
+}
+
+/// Payload of `library-synced`, emitted after a watcher-triggered sync.
+#[derive(Serialize, Clone)]
+pub struct LibrarySynced {
+    pub result: Option<crate::commands::SyncResult>,
+    pub error: Option<String>,
+}
+
+/// Runs a recent-changes sync from the watcher thread and reports it to the UI. A database
+/// change gets the full sync; an XML export alone skips the rating/BPM/plays snapshot when
+/// the database is watched too, since those fields only change with the database.
+fn sync_after_change(app: &AppHandle, db_changed: bool) {
+    let state = app.state::<AppState>();
+    if !real_time_sync_enabled(&state) {
+        tracing::debug!("Library changed; real-time sync is off");
+        return;
+    }
+    let scope = if db_changed || !database_watched(&state) {
+        crate::commands::SyncScope::Full
+    } else {
+        crate::commands::SyncScope::Recent
+    };
+    tracing::info!("Library changes stabilized. Triggering {:?} sync.", scope);
+    let since = crate::commands::sync_window_start(&state);
+    let payload = match crate::commands::sync_recent(app, &state, since, scope) {
+        Ok(result) => LibrarySynced { result: Some(result), error: None },
+        Err(e) => {
+            tracing::error!("Auto-sync failed: {}", e);
+            LibrarySynced { result: None, error: Some(e) }
+        }
+    };
+    let _ = app.emit("library-synced", payload);
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn get_watcher_status(app: AppHandle) -> WatcherStatus {
+    update_watcher_status(&app, |_| {})
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_real_time_sync(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(real_time_sync_enabled(&state))
+}
+
+/// Turns watcher-triggered syncs on or off.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_real_time_sync(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    db.set_setting(REAL_TIME_SYNC_KEY, if enabled { "true" } else { "false" }).map_err(|e| e.to_string())
+}
+
+pub fn default_media_folder() -> PathBuf {
+    let home_dir = dirs::home_dir().unwrap_or(PathBuf::from("/Users/Shared"));
+    home_dir.join("Music/Music/Media")
+}
+
+fn load_audio_watch_settings(state: &AppState) -> (bool, PathBuf) {
+    let db = match state.db.read() {
+        Ok(db) => db,
+        Err(_) => return (false, default_media_folder()),
+    };
+    let enabled = db.get_setting(WATCH_AUDIO_KEY).ok().flatten().as_deref() == Some("true");
+    let folder = db.get_setting(MEDIA_FOLDER_KEY).ok().flatten()
+        .map(PathBuf::from)
+        .unwrap_or_else(default_media_folder);
+    (enabled, folder)
+}
+
+pub(crate) fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// (Re)starts monitoring of the audio files directory, or stops it when disabled in settings.
+pub fn start_audio_watcher(app: AppHandle) {
+    let (enabled, folder) = load_audio_watch_settings(&app.state::<AppState>());
+    let watcher_state = app.state::<WatcherState>();
+
+    if !enabled || !folder.exists() {
+        if enabled {
+            tracing::warn!("Audio folder not found, not watching: {:?}", folder);
+        }
+        // Dropping the watcher ends its thread
+        if let Ok(mut slot) = watcher_state.audio_watcher.lock() {
+            *slot = None;
+        }
+        return;
+    }
+
+    let (tx, rx) = channel();
+    let mut watcher = match RecommendedWatcher::new(tx, Config::default()) {
+        Ok(w) => w,
+        Err(e) => {
+            tracing::error!("Failed to create audio folder watcher: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = watcher.watch(&folder, RecursiveMode::Recursive) {
+        tracing::error!("Failed to watch audio folder {:?}: {}", folder, e);
+        return;
+    }
+    tracing::debug!("Started watching audio folder: {:?}", folder);
+
+    if let Ok(mut slot) = watcher_state.audio_watcher.lock() {
+        *slot = Some(watcher);
+    }
+
+    let app_handle = app.clone();
+    thread::spawn(move || {
+        // Same trailing debounce as the library watcher, but we keep the set of touched files
         let debounce_duration = Duration::from_secs(2);
-        let mut last_activity: Option<Instant> = None;
-        let mut coalesced_count: u32 = 0;
+        let mut changed: HashSet<PathBuf> = HashSet::new();
 
         loop {
-            // Determine behavior based on whether we have a pending change
-            let evt = if last_activity.is_some() {
-                 rx.recv_timeout(debounce_duration)
+            let evt = if changed.is_empty() {
+                rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
             } else {
-                 rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                rx.recv_timeout(debounce_duration)
             };
 
             match evt {
-                Ok(res) => {
-                    match res {
-                        Ok(event) => {
-                            // Filter out noise: Temp files, locks, etc.
-                            let is_relevant = event.paths.iter().any(|p| {
-                                let s = p.to_string_lossy();
-                                // We care about .musiclibrary (directory), .musicdb, .itdb, .xml, .plist
-                                // We strictly ignore .tmp, .lock, .log
-                                !s.ends_with(".tmp") && !s.ends_with(".lock") && !s.contains(".tmp")
-                            });
-
-                            if is_relevant {
-                                if last_activity.is_none() {
-                                    // First event in a burst — log it
-                                    println!("[WATCHER] Change detected: {:?}. Waiting for activity to settle...", event.kind);
-                                }
-                                last_activity = Some(Instant::now());
-                                coalesced_count += 1;
-                            }
-                        }
-                        Err(e) => eprintln!("[WATCHER] Watch error: {:?}", e),
+                Ok(Ok(event)) => {
+                    if event.kind.is_modify() || event.kind.is_create() {
+                        changed.extend(event.paths.into_iter().filter(|p| is_audio_file(p)));
+                    }
+                }
+                Ok(Err(e)) => tracing::error!("Audio watch error: {:?}", e),
+                Err(RecvTimeoutError::Timeout) => {
+                    for path in changed.drain() {
+                        check_external_modification(&app_handle, &path);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    tracing::debug!("Audio watcher stopped.");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// Re-reads a changed file's tags and compares them with the DB.
+/// Writes made by TagDeck itself already match the DB, so only outside edits are reported.
+fn check_external_modification(app: &AppHandle, path: &Path) {
+    let state = app.state::<AppState>();
+    let path_str = path.to_string_lossy().to_string();
+
+    let track = match state.db.read() {
+        Ok(db) => db.get_track_by_path(&path_str).ok().flatten(),
+        Err(_) => None,
+    };
+    let mut track = match track {
+        Some(t) => t,
+        None => return,
+    };
+
+    let (new_comment, new_grouping) = match crate::metadata::read_metadata(path) {
+        Ok(tags) => tags,
+        Err(e) => {
+            tracing::error!("Failed to re-read tags for {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    let old_comment = track.comment_raw.clone().unwrap_or_default();
+    let old_grouping = track.grouping_raw.clone().unwrap_or_default();
+    if new_comment == old_comment && new_grouping == old_grouping {
+        return;
+    }
+
+    track.comment_raw = Some(new_comment.clone());
+    track.grouping_raw = Some(new_grouping.clone());
+    if let Ok(mtime) = std::fs::metadata(path).and_then(|m| m.modified()) {
+        if let Ok(since_epoch) = mtime.duration_since(std::time::UNIX_EPOCH) {
+            track.modified_date = since_epoch.as_secs() as i64;
+        }
+    }
+
+    if let Ok(db) = state.db.lock() {
+        if let Err(e) = db.update_track(&track) {
+            tracing::error!("Failed to update track {}: {}", track.id, e);
+            return;
+        }
+    }
+
+    tracing::info!("Track modified outside TagDeck: {} - {}",
+        track.artist.as_deref().unwrap_or(""), track.title.as_deref().unwrap_or(""));
+
+    let _ = app.emit("track-externally-modified", ExternalModification {
+        track_id: track.id,
+        persistent_id: track.persistent_id,
+        file_path: track.file_path,
+        old_comment,
+        new_comment,
+        old_grouping,
+        new_grouping,
+    });
+}
+
+fn load_inbox_folder(state: &AppState) -> Option<PathBuf> {
+    state.db.read().ok()
+        .and_then(|db| db.get_setting(INBOX_FOLDER_KEY).ok().flatten())
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+}
+
+/// (Re)starts the inbox watcher, or stops it when no inbox folder is configured.
+pub fn start_inbox_watcher(app: AppHandle) {
+    let folder = load_inbox_folder(&app.state::<AppState>());
+    let watcher_state = app.state::<WatcherState>();
+
+    let folder = match folder {
+        Some(f) if f.exists() => f,
+        other => {
+            if let Some(f) = other {
+                tracing::warn!("Inbox folder not found, not watching: {:?}", f);
+            }
+            if let Ok(mut slot) = watcher_state.inbox_watcher.lock() {
+                *slot = None;
+            }
+            return;
+        }
+    };
+
+    let (tx, rx) = channel();
+    let mut watcher = match RecommendedWatcher::new(tx, Config::default()) {
+        Ok(w) => w,
+        Err(e) => {
+            tracing::error!("Failed to create inbox watcher: {}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = watcher.watch(&folder, RecursiveMode::Recursive) {
+        tracing::error!("Failed to watch inbox folder {:?}: {}", folder, e);
+        return;
+    }
+    tracing::debug!("Started watching inbox folder: {:?}", folder);
+
+    if let Ok(mut slot) = watcher_state.inbox_watcher.lock() {
+        *slot = Some(watcher);
+    }
+
+    let app_handle = app.clone();
+    thread::spawn(move || {
+        // Downloads arrive as a burst of create/modify events; wait for them to settle
+        let debounce_duration = Duration::from_secs(3);
+        let mut arrived: HashSet<PathBuf> = HashSet::new();
+        let mut imported: HashSet<PathBuf> = HashSet::new();
+
+        loop {
+            let evt = if arrived.is_empty() {
+                rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            } else {
+                rx.recv_timeout(debounce_duration)
+            };
+
+            match evt {
+                Ok(Ok(event)) => {
+                    if event.kind.is_create() || event.kind.is_modify() {
+                        arrived.extend(event.paths.into_iter()
+                            .filter(|p| is_audio_file(p) && !imported.contains(p)));
                     }
                 }
+                Ok(Err(e)) => tracing::error!("Inbox watch error: {:?}", e),
                 Err(RecvTimeoutError::Timeout) => {
-                    // Timeout hit! This means 'debounce_duration' passed without new events.
-                    if let Some(_) = last_activity {
-                        println!("[WATCHER] Settled after {} events. Emitting sync.", coalesced_count);
-                        let _ = app_handle.emit("music-library-changed", ());
-                        
-                        let msg = format!("Library changes stabilized ({} events coalesced). Triggering sync.", coalesced_count);
-                        app_handle.state::<crate::logging::LogState>().add_log("INFO", &msg, &app_handle);
-                        
-                        // Reset
-                        last_activity = None;
-                        coalesced_count = 0;
+                    for path in arrived.drain() {
+                        if !path.exists() {
+                            continue;
+                        }
+                        match import_inbox_file(&app_handle, &path) {
+                            Ok(()) => {
+                                imported.insert(path);
+                            }
+                            Err(e) => {
+                                tracing::warn!("Inbox import failed for {:?}: {}", path, e);
+                            }
+                        }
                     }
                 }
                 Err(RecvTimeoutError::Disconnected) => {
-                    eprintln!("[WATCHER] Channel disconnected. Stopping watcher.");
+                    tracing::debug!("Inbox watcher stopped.");
                     break;
                 }
             }
         }
     });
 }
+
+/// Adds a new inbox file to Music.app, inserts it into the DB and tags it "inbox".
+fn import_inbox_file(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let path_str = path.to_string_lossy().to_string();
+
+    // Already in the library (e.g. a re-saved file) — nothing to import
+    if let Ok(db) = state.db.read() {
+        if let Ok(Some(_)) = db.get_track_by_path(&path_str) {
+            return Ok(());
+        }
+    }
+
+    let pid = crate::apple_music::add_file_to_library(&path_str).map_err(|e| e.to_string())?;
+    let mut track = crate::apple_music::get_tracks_by_persistent_ids(&[pid.clone()])
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("Music.app returned no data for new track {}", pid))?;
+
+    if let Ok(db) = state.db.read() {
+        crate::tag_storage::restore_tag_block(&db, &mut track);
+    }
+    // Music.app may have copied the file into its media folder; tag whichever file it uses
+    let current_comment = track.comment_raw.clone().unwrap_or_default();
+    let new_comment = crate::comment::add_tags(&current_comment, &[INBOX_TAG.to_string()])
+        .unwrap_or(current_comment);
+    let new_comment = match crate::metadata::write_metadata(&track.file_path, &new_comment) {
+        Ok(()) => new_comment,
+        Err(e) => {
+            // Import it untagged rather than record a tag the file doesn't have
+            tracing::error!("Failed to write inbox tag to {}: {}", track.file_path, e);
+            track.comment_raw.clone().unwrap_or_default()
+        }
+    };
+    track.comment_raw = Some(new_comment.clone());
+
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        db.insert_track(&track).map_err(|e| e.to_string())?;
+        if let Ok(Some(id)) = db.get_track_id_by_persistent_id(&pid) {
+            track.id = id;
+        }
+        let _ = db.sync_tags();
+    }
+
+    state.apple_queue.enqueue(crate::apple_music::AppleUpdate::Comment {
+        persistent_id: pid,
+        comment: new_comment,
+    });
+
+    tracing::info!("Imported inbox file: {}", path_str);
+    let _ = app.emit("inbox-track-imported", &track);
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn list_watch_paths(state: State<'_, AppState>) -> Result<Vec<WatchPath>, String> {
+    Ok(describe_paths(&load_watch_paths(&state)))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn add_watch_path(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<Vec<WatchPath>, String> {
+    let new_path = PathBuf::from(&path);
+    if !new_path.exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+
+    let mut paths = load_watch_paths(&state);
+    if !paths.contains(&new_path) {
+        paths.push(new_path);
+        save_watch_paths(&state, &paths)?;
+
+        tracing::info!("Added watch path: {}", path);
+        start_library_watcher(app.clone());
+    }
+
+    Ok(describe_paths(&paths))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn remove_watch_path(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<Vec<WatchPath>, String> {
+    let target = PathBuf::from(&path);
+    let mut paths = load_watch_paths(&state);
+    let before = paths.len();
+    paths.retain(|p| p != &target);
+
+    if paths.len() != before {
+        save_watch_paths(&state, &paths)?;
+
+        tracing::info!("Removed watch path: {}", path);
+        start_library_watcher(app.clone());
+    }
+
+    Ok(describe_paths(&paths))
+}
+
+/// Re-initializes the watcher with the current settings without restarting the app.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn restart_library_watcher(app: AppHandle) -> Result<(), String> {
+    start_library_watcher(app.clone());
+    tracing::info!("Library watcher restarted");
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_audio_watch_settings(state: State<'_, AppState>) -> Result<AudioWatchSettings, String> {
+    let (enabled, folder) = load_audio_watch_settings(&state);
+    Ok(AudioWatchSettings {
+        enabled,
+        exists: folder.exists(),
+        folder: folder.to_string_lossy().to_string(),
+    })
+}
+
+/// Enables/disables audio folder monitoring. `folder` overrides the media folder when given.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn set_audio_watch_settings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    enabled: bool,
+    folder: Option<String>,
+) -> Result<AudioWatchSettings, String> {
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        db.set_setting(WATCH_AUDIO_KEY, if enabled { "true" } else { "false" }).map_err(|e| e.to_string())?;
+        if let Some(f) = &folder {
+            db.set_setting(MEDIA_FOLDER_KEY, f).map_err(|e| e.to_string())?;
+        }
+    }
+
+    start_audio_watcher(app.clone());
+
+    tracing::info!("Audio folder watching {}", if enabled { "enabled" } else { "disabled" });
+
+    get_audio_watch_settings(state)
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_inbox_folder(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    Ok(load_inbox_folder(&state).map(|p| p.to_string_lossy().to_string()))
+}
+
+/// Sets the inbox folder; `None` disables inbox auto-import.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn set_inbox_folder(app: AppHandle, state: State<'_, AppState>, path: Option<String>) -> Result<(), String> {
+    if let Some(p) = &path {
+        if !Path::new(p).is_dir() {
+            return Err(format!("Inbox folder does not exist: {}", p));
+        }
+    }
+
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        db.set_setting(INBOX_FOLDER_KEY, path.as_deref().unwrap_or("")).map_err(|e| e.to_string())?;
+    }
+
+    start_inbox_watcher(app.clone());
+
+    let msg = match &path {
+        Some(p) => format!("Inbox folder set to {}", p),
+        None => "Inbox auto-import disabled".to_string(),
+    };
+    tracing::info!("{}", msg);
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Queries over the listening history, which records every track change reported
+//! through `now_playing::report_now_playing` (Music.app or the automation API).
+
+use crate::commands::AppState;
+use crate::models::Track;
+use chrono::{Datelike, Local, TimeZone};
+use tauri::State;
+
+const DEFAULT_MOST_PLAYED_LIMIT: usize = 50;
+
+#[derive(serde::Serialize)]
+pub struct PlayedTrack {
+    pub plays: i64,
+    pub track: Track,
+}
+
+#[derive(serde::Serialize)]
+pub struct ListeningEntry {
+    pub played_at: i64,
+    /// "music" or "automation"
+    pub source: String,
+    pub track: Track,
+}
+
+/// Start of the current month in local time.
+fn start_of_month() -> i64 {
+    let now = Local::now();
+    Local.with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0)
+        .earliest()
+        .map(|d| d.timestamp())
+        .unwrap_or(0)
+}
+
+/// Most played tracks between `from` and `to` (Unix seconds), defaulting to this month.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_most_played(
+    from: Option<i64>,
+    to: Option<i64>,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<PlayedTrack>, String> {
+    let from = from.unwrap_or_else(start_of_month);
+    let to = to.unwrap_or(i64::MAX);
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let counts = db.get_play_counts_between(from, to, limit.unwrap_or(DEFAULT_MOST_PLAYED_LIMIT))
+        .map_err(|e| e.to_string())?;
+    Ok(counts.into_iter()
+        .filter_map(|(id, plays)| db.get_track(id).ok().flatten().map(|track| PlayedTrack { plays, track }))
+        .collect())
+}
+
+/// Tracks never played, newest additions first.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_never_played(state: State<'_, AppState>) -> Result<Vec<Track>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    db.get_never_played_tracks().map_err(|e| e.to_string())
+}
+
+/// Everything played between `from` and `to` (Unix seconds) in order, e.g. last Friday's gig.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_played_between(from: i64, to: i64, state: State<'_, AppState>) -> Result<Vec<ListeningEntry>, String> {
+    if to < from {
+        return Err("End of the range is before its start".to_string());
+    }
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let history = db.get_listening_history(from, to).map_err(|e| e.to_string())?;
+    Ok(history.into_iter()
+        .filter_map(|(id, played_at, source)| {
+            db.get_track(id).ok().flatten().map(|track| ListeningEntry { played_at, source, track })
+        })
+        .collect())
+}
@@ -1,11 +1,28 @@
+//! Logging is done with `tracing` (`tracing::info!` etc.), so every event carries its
+//! module as target. `LogBridge` forwards events into `LogState`, which keeps the
+//! in-memory buffer for the Logs window and the rotating log file. Levels are filtered
+//! per target: debug mode lowers TagDeck's own default to DEBUG, and `set_log_levels`
+//! overrides single modules.
+
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::{AppHandle, Emitter};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager};
 use serde::{Serialize, Deserialize};
 use chrono::Local;
+use tracing::field::{Field, Visit};
+use tracing::span;
+use tracing_subscriber::filter::{filter_fn, LevelFilter, Targets};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
+use crate::performance::PerformanceLayer;
 
 /// Maximum size per log file before rotation (~5 MB)
 const MAX_LOG_FILE_SIZE: u64 = 5 * 1024 * 1024;
@@ -13,11 +30,20 @@ const MAX_LOG_FILE_SIZE: u64 = 5 * 1024 * 1024;
 const MAX_LOG_FILES: usize = 5;
 /// In-memory log buffer cap (shown in the Logs window)
 const MAX_MEMORY_LOGS: usize = 2000;
+/// Settings key for the per-target level overrides
+const LOG_LEVELS_KEY: &str = "log_levels";
+/// Target prefix of everything logged from this crate
+pub(crate) const CRATE_TARGET: &str = "tagdeck_lib";
+/// Target of messages sent by the UI through `log_from_frontend`
+const FRONTEND_TARGET: &str = "frontend";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
     pub timestamp: String,
     pub level: String,
+    /// Module that logged the entry, e.g. "apple_music" or "frontend"
+    #[serde(default)]
+    pub target: String,
     pub message: String,
 }
 
@@ -25,6 +51,9 @@ pub struct LogState {
     pub logs: Mutex<Vec<LogEntry>>,
     pub log_dir: Mutex<Option<PathBuf>>,
     pub debug_mode: AtomicBool,
+    /// Per-target level overrides, e.g. "apple_music" -> "debug"
+    target_levels: Mutex<BTreeMap<String, String>>,
+    filter: Mutex<Option<reload::Handle<Targets, Registry>>>,
 }
 
 impl LogState {
@@ -33,6 +62,8 @@ impl LogState {
             logs: Mutex::new(Vec::new()),
             log_dir: Mutex::new(None),
             debug_mode: AtomicBool::new(false),
+            target_levels: Mutex::new(BTreeMap::new()),
+            filter: Mutex::new(None),
         }
     }
 
@@ -53,7 +84,7 @@ impl LogState {
         }
 
         // Write a startup marker
-        self.write_to_file("INFO", &format!(
+        self.write_to_file("INFO", "startup", &format!(
             "=== TagDeck session started at {} ===",
             Local::now().format("%Y-%m-%d %H:%M:%S %Z")
         ));
@@ -90,14 +121,15 @@ impl LogState {
     }
 
     /// Append a formatted line to the persistent log file.
-    fn write_to_file(&self, level: &str, message: &str) {
+    fn write_to_file(&self, level: &str, target: &str, message: &str) {
         self.rotate_if_needed();
         let Some(path) = self.current_log_path() else { return };
 
         let line = format!(
-            "[{}] [{}] {}\n",
+            "[{}] [{}] [{}] {}\n",
             Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
             level,
+            target,
             message
         );
 
@@ -106,16 +138,13 @@ impl LogState {
         }
     }
 
-    /// Core logging method — writes to memory, file, and emits to frontend.
-    pub fn add_log(&self, level: &str, message: &str, app: &AppHandle) {
-        // Skip DEBUG messages if debug mode is off
-        if level == "DEBUG" && !self.debug_mode.load(Ordering::Relaxed) {
-            return;
-        }
-
+    /// Sink for `LogBridge` — writes to memory, file, and emits to frontend.
+    /// Level filtering has already happened in the subscriber.
+    fn record(&self, level: &str, target: &str, message: &str, app: &AppHandle) {
         let entry = LogEntry {
             timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
             level: level.to_string(),
+            target: target.to_string(),
             message: message.to_string(),
         };
 
@@ -129,7 +158,7 @@ impl LogState {
         }
 
         // Persistent file
-        self.write_to_file(level, message);
+        self.write_to_file(level, target, message);
 
         // Emit to any open Logs window
         let _ = app.emit("log-event", entry);
@@ -141,49 +170,356 @@ impl LogState {
 
     pub fn set_debug(&self, enabled: bool) {
         self.debug_mode.store(enabled, Ordering::Relaxed);
+        self.reload_filter();
+    }
+
+    /// TagDeck's own targets log at INFO (DEBUG in debug mode), dependencies only at
+    /// WARN, and the per-target overrides win over both.
+    fn build_filter(&self) -> Targets {
+        let own = if self.is_debug() { LevelFilter::DEBUG } else { LevelFilter::INFO };
+        let mut filter = Targets::new()
+            .with_default(LevelFilter::WARN)
+            .with_target(CRATE_TARGET, own)
+            .with_target(FRONTEND_TARGET, own);
+        if let Ok(levels) = self.target_levels.lock() {
+            for (target, level) in levels.iter() {
+                if let Ok(level) = level.parse::<LevelFilter>() {
+                    filter = filter.with_target(full_target(target), level);
+                }
+            }
+        }
+        filter
+    }
+
+    fn reload_filter(&self) {
+        let filter = self.build_filter();
+        if let Some(handle) = self.filter.lock().ok().and_then(|h| h.clone()) {
+            if let Err(e) = handle.reload(filter) {
+                eprintln!("[LogState] Failed to update log filter: {}", e);
+            }
+        }
+    }
+}
+
+/// "apple_music" -> "tagdeck_lib::apple_music"; full paths and "frontend" are kept.
+fn full_target(target: &str) -> String {
+    if target == FRONTEND_TARGET || target.starts_with(CRATE_TARGET) || target.contains("::") {
+        target.to_string()
+    } else {
+        format!("{}::{}", CRATE_TARGET, target)
+    }
+}
+
+/// "tagdeck_lib::apple_music" -> "apple_music", for display.
+fn short_target(target: &str) -> &str {
+    target.strip_prefix(CRATE_TARGET)
+        .map(|t| t.trim_start_matches("::"))
+        .filter(|t| !t.is_empty())
+        .unwrap_or(target)
+}
+
+/// Collects an event's message plus any extra fields as `key=value`.
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0.insert_str(0, value);
+        } else {
+            self.0.push_str(&format!(" {}={}", field.name(), value));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{:?}", value));
+        } else {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
     }
 }
 
+fn level_name(level: &tracing::Level) -> &'static str {
+    match *level {
+        tracing::Level::ERROR => "ERROR",
+        tracing::Level::WARN => "WARN",
+        tracing::Level::INFO => "INFO",
+        tracing::Level::DEBUG => "DEBUG",
+        tracing::Level::TRACE => "TRACE",
+    }
+}
+
+/// Forwards tracing events into `LogState`, and logs how long each span (every command
+/// is instrumented) took when it closes.
+struct LogBridge {
+    app: AppHandle,
+}
+
+thread_local! {
+    /// Set while a record is being written, so anything logged by the emit to the Logs
+    /// window can't loop back into the bridge.
+    static IN_BRIDGE: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+impl LogBridge {
+    fn forward(&self, level: &str, target: &str, message: &str) {
+        if IN_BRIDGE.with(|busy| busy.replace(true)) {
+            return;
+        }
+        self.app.state::<LogState>().record(level, short_target(target), message, &self.app);
+        IN_BRIDGE.with(|busy| busy.set(false));
+    }
+}
+
+impl<S> Layer<S> for LogBridge
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let meta = event.metadata();
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        self.forward(level_name(meta.level()), meta.target(), visitor.0.trim());
+    }
+
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Instant::now());
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let Some(started) = span.extensions().get::<Instant>().copied() else { return };
+        let meta = span.metadata();
+        let message = format!("{} took {:.1} ms", meta.name(), started.elapsed().as_secs_f64() * 1000.0);
+        self.forward(level_name(meta.level()), meta.target(), &message);
+    }
+}
+
+/// Installs the global subscriber: console output plus the `LogState` bridge, behind
+/// the reloadable per-target filter. Call once, after `LogState` is managed.
+pub fn init_tracing(app: &AppHandle) {
+    let log_state = app.state::<LogState>();
+    let (filter, handle) = reload::Layer::new(log_state.build_filter());
+    // The level filter only applies to log output; performance stats see every command
+    let output = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .and_then(LogBridge { app: app.clone() })
+        .with_filter(filter);
+    let result = tracing_subscriber::registry()
+        .with(output)
+        .with(PerformanceLayer.with_filter(filter_fn(crate::performance::is_tracked)))
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("[LogState] Failed to install tracing subscriber: {}", e);
+        return;
+    }
+    if let Ok(mut slot) = log_state.filter.lock() {
+        *slot = Some(handle);
+    }
+}
+
+/// Restores the per-target overrides saved with `set_log_levels`. Called once at startup.
+pub fn load_log_levels(app: &AppHandle) {
+    let levels: BTreeMap<String, String> = app.state::<crate::commands::AppState>().db.read().ok()
+        .and_then(|db| db.get_setting(LOG_LEVELS_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let log_state = app.state::<LogState>();
+    if let Ok(mut slot) = log_state.target_levels.lock() {
+        *slot = levels;
+    }
+    log_state.reload_filter();
+}
+
 // ─── Tauri Commands ──────────────────────────────────────────────
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
 pub fn get_logs(state: tauri::State<'_, LogState>) -> Vec<LogEntry> {
     state.logs.lock().unwrap_or_else(|e| e.into_inner()).clone()
 }
 
+/// Severity rank for `query_logs`' minimum level; unknown levels rank lowest.
+fn severity(level: &str) -> u8 {
+    match level.to_uppercase().as_str() {
+        "ERROR" => 4,
+        "WARN" => 3,
+        "INFO" => 2,
+        "DEBUG" => 1,
+        _ => 0,
+    }
+}
+
+/// Parses one log file line, `[timestamp] [LEVEL] [target] message`. Lines written
+/// before targets were logged have no target part.
+fn parse_log_line(line: &str) -> Option<LogEntry> {
+    fn bracketed(s: &str) -> Option<(&str, &str)> {
+        let rest = s.strip_prefix('[')?;
+        let end = rest.find(']')?;
+        Some((&rest[..end], rest[end + 1..].strip_prefix(' ').unwrap_or(&rest[end + 1..])))
+    }
+    let (timestamp, rest) = bracketed(line)?;
+    let (level, rest) = bracketed(rest)?;
+    let (target, message) = match bracketed(rest) {
+        Some((target, message)) if !target.contains(' ') => (target, message),
+        _ => ("", rest),
+    };
+    Some(LogEntry {
+        // Same precision as the in-memory entries
+        timestamp: timestamp.get(..19).unwrap_or(timestamp).to_string(),
+        level: level.to_string(),
+        target: target.to_string(),
+        message: message.to_string(),
+    })
+}
+
+/// All entries in the log files, oldest first (rotated files, then the current one).
+fn read_log_files(dir: &std::path::Path) -> Vec<LogEntry> {
+    let mut files: Vec<PathBuf> = (1..=MAX_LOG_FILES).rev()
+        .map(|i| dir.join(format!("tagdeck.{}.log", i)))
+        .collect();
+    files.push(dir.join("tagdeck.log"));
+
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for file in files {
+        let Ok(content) = fs::read_to_string(&file) else { continue };
+        for line in content.lines() {
+            match parse_log_line(line) {
+                Some(entry) => entries.push(entry),
+                // Continuation of a multi-line message
+                None => if let Some(last) = entries.last_mut() {
+                    last.message.push('\n');
+                    last.message.push_str(line);
+                },
+            }
+        }
+    }
+    entries
+}
+
+#[derive(Serialize)]
+pub struct LogQueryResult {
+    /// Newest first
+    pub entries: Vec<LogEntry>,
+    /// Matches before `offset`/`limit` were applied
+    pub total: usize,
+}
+
+/// Searches this session's log buffer and the log files on disk. `level` is a minimum
+/// ("WARN" also returns errors), `text` matches message or target case-insensitively,
+/// and `since` is a Unix timestamp.
 #[tauri::command]
-pub fn log_error(message: String, app: AppHandle, state: tauri::State<'_, LogState>) {
-    state.add_log("ERROR", &message, &app);
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn query_logs(
+    level: Option<String>,
+    text: Option<String>,
+    since: Option<i64>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    state: tauri::State<'_, LogState>,
+) -> Result<LogQueryResult, String> {
+    let memory = state.logs.lock().map(|l| l.clone()).unwrap_or_default();
+    let dir = state.get_log_dir();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        // The files also hold this session; take from them only what the buffer no longer has
+        let oldest_in_memory = memory.first().map(|e| e.timestamp.clone());
+        let mut entries: Vec<LogEntry> = dir.map(|d| read_log_files(&d)).unwrap_or_default()
+            .into_iter()
+            .filter(|e| oldest_in_memory.as_ref().is_none_or(|oldest| &e.timestamp < oldest))
+            .collect();
+        entries.extend(memory);
+
+        let min_severity = level.as_deref().map(severity).unwrap_or(0);
+        let text = text.map(|t| t.to_lowercase()).filter(|t| !t.is_empty());
+        let since = since
+            .and_then(|s| chrono::DateTime::from_timestamp(s, 0))
+            .map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string());
+
+        let matches: Vec<LogEntry> = entries.into_iter()
+            .rev()
+            .filter(|e| severity(&e.level) >= min_severity)
+            .filter(|e| since.as_ref().is_none_or(|s| &e.timestamp >= s))
+            .filter(|e| text.as_ref().is_none_or(|t| {
+                e.message.to_lowercase().contains(t) || e.target.to_lowercase().contains(t)
+            }))
+            .collect();
+        let total = matches.len();
+        let entries = matches.into_iter()
+            .skip(offset.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+        LogQueryResult { entries, total }
+    })
+    .await
+    .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-pub fn log_from_frontend(level: String, message: String, app: AppHandle, state: tauri::State<'_, LogState>) {
-    let valid_level = match level.to_uppercase().as_str() {
-        "ERROR" | "WARN" | "INFO" | "DEBUG" => level.to_uppercase(),
-        _ => "INFO".to_string(),
-    };
-    state.add_log(&valid_level, &format!("[Frontend] {}", message), &app);
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn log_error(message: String) {
+    tracing::error!(target: "frontend", "{}", message);
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn log_from_frontend(level: String, message: String) {
+    match level.to_uppercase().as_str() {
+        "ERROR" => tracing::error!(target: "frontend", "{}", message),
+        "WARN" => tracing::warn!(target: "frontend", "{}", message),
+        "DEBUG" => tracing::debug!(target: "frontend", "{}", message),
+        _ => tracing::info!(target: "frontend", "{}", message),
+    }
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
 pub fn get_debug_mode(state: tauri::State<'_, LogState>) -> bool {
     state.is_debug()
 }
 
 #[tauri::command]
-pub fn set_debug_mode(enabled: bool, app: AppHandle, state: tauri::State<'_, LogState>) {
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn set_debug_mode(enabled: bool, state: tauri::State<'_, LogState>) {
     let was = state.is_debug();
     state.set_debug(enabled);
     if was != enabled {
-        state.add_log(
-            "INFO",
-            &format!("Debug mode {}", if enabled { "ENABLED" } else { "DISABLED" }),
-            &app,
-        );
+        tracing::info!("Debug mode {}", if enabled { "ENABLED" } else { "DISABLED" });
+    }
+}
+
+/// Per-target level overrides, e.g. `{"apple_music": "debug", "library_watcher": "warn"}`.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn get_log_levels(state: tauri::State<'_, LogState>) -> BTreeMap<String, String> {
+    state.target_levels.lock().map(|l| l.clone()).unwrap_or_default()
+}
+
+/// Replaces the per-target overrides. Targets are module names (or "frontend"); levels
+/// are "off", "error", "warn", "info", "debug" or "trace".
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn set_log_levels(
+    levels: BTreeMap<String, String>,
+    state: tauri::State<'_, LogState>,
+    app_state: tauri::State<'_, crate::commands::AppState>,
+) -> Result<(), String> {
+    if let Some((target, level)) = levels.iter().find(|(_, l)| l.parse::<LevelFilter>().is_err()) {
+        return Err(format!("Invalid level \"{}\" for {}", level, target));
     }
+    let json = serde_json::to_string(&levels).map_err(|e| e.to_string())?;
+    app_state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_setting(LOG_LEVELS_KEY, &json).map_err(|e| e.to_string())?;
+    *state.target_levels.lock().map_err(|_| "Failed to lock log levels".to_string())? = levels;
+    state.reload_filter();
+    Ok(())
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
 pub fn open_log_folder(state: tauri::State<'_, LogState>) -> Result<(), String> {
     let dir = state.get_log_dir().ok_or("Log directory not initialised")?;
     #[cfg(target_os = "macos")]
@@ -197,6 +533,7 @@ pub fn open_log_folder(state: tauri::State<'_, LogState>) -> Result<(), String>
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
 pub fn get_log_file_path(state: tauri::State<'_, LogState>) -> Option<String> {
     state.current_log_path().map(|p| p.to_string_lossy().to_string())
 }
@@ -211,6 +548,7 @@ pub struct LogStats {
 }
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
 pub fn get_log_stats(state: tauri::State<'_, LogState>) -> Option<LogStats> {
     let dir = state.get_log_dir()?;
     let current_path = state.current_log_path()?;
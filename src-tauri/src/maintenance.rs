@@ -0,0 +1,300 @@
+//! Nightly maintenance: an opt-in full reconcile run once a day in the small hours. It
+//! syncs Music.app (new and deleted persistent IDs, metadata, plays) with every playlist
+//! re-snapshotted, rebuilds the tag table from the comments and checks every file on disk,
+//! then stores a summary for `get_last_maintenance_report`. A run missed because the Mac
+//! was asleep or the app closed is caught up shortly after the next launch. The on-demand housekeeping
+//! commands (orphan cleanup, SQLite integrity check and VACUUM) live here too.
+
+use crate::commands::{AppState, MissingScanResult, SyncResult};
+use crate::db::OrphanCleanup;
+use chrono::{Local, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const NIGHTLY_KEY: &str = "nightly_maintenance";
+const REPORT_KEY: &str = "last_maintenance_report";
+/// Local hour the nightly run starts in
+const NIGHTLY_HOUR: u32 = 4;
+/// How often the scheduler looks at the clock
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+/// How long after launch a missed nightly run is caught up, so startup stays responsive
+const CATCH_UP_DELAY: Duration = Duration::from_secs(120);
+/// A last report older than this means a nightly run was missed
+const MISSED_AFTER_SECS: i64 = 24 * 60 * 60;
+
+static NIGHTLY_ENABLED: AtomicBool = AtomicBool::new(false);
+/// Set while a run is in progress, so a manual run and the nightly one don't overlap
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    pub started_at: i64,
+    pub finished_at: i64,
+    /// Music.app reconcile; `None` when skipped (file-only mode) or failed
+    pub sync: Option<SyncResult>,
+    /// Tags in the rebuilt tag table
+    pub tags: usize,
+    /// File existence check; `None` when it failed
+    pub files: Option<MissingScanResult>,
+    /// Steps that failed or were skipped, in order
+    pub errors: Vec<String>,
+}
+
+/// Runs every maintenance step, stores the report and emits `maintenance-complete`.
+/// Blocking. A step that fails is recorded and the rest still run.
+pub fn run_maintenance(app: &AppHandle) -> Result<MaintenanceReport, String> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("Maintenance is already running".to_string());
+    }
+    struct RunningGuard;
+    impl Drop for RunningGuard {
+        fn drop(&mut self) {
+            RUNNING.store(false, Ordering::SeqCst);
+        }
+    }
+    let _guard = RunningGuard;
+
+    let state = app.state::<AppState>();
+    let started_at = chrono::Utc::now().timestamp();
+    let mut errors = Vec::new();
+    tracing::info!("Maintenance started");
+
+    // 1. Music.app reconcile, with every playlist fetched again
+    let sync = if crate::file_library::is_file_only() {
+        errors.push("Music.app sync skipped: file-only mode".to_string());
+        None
+    } else if state.is_syncing.load(Ordering::SeqCst) {
+        errors.push("Music.app sync skipped: another sync is running".to_string());
+        None
+    } else {
+        let cleared = state.db.lock().map_err(|_| "Failed to lock DB".to_string())
+            .and_then(|db| db.set_playlist_sync_headers(&Default::default()).map_err(|e| e.to_string()));
+        if let Err(e) = cleared {
+            errors.push(format!("Playlist re-snapshot: {}", e));
+        }
+        match crate::commands::sync_recent(app, &state, crate::commands::sync_window_start(&state), crate::commands::SyncScope::Full) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                errors.push(format!("Music.app sync: {}", e));
+                None
+            }
+        }
+    };
+
+    // 2. Tag table rebuilt from the comments
+    let tags = match state.db.lock().map_err(|_| "Failed to lock DB".to_string())
+        .and_then(|db| {
+            db.sync_tags().map_err(|e| e.to_string())?;
+            db.get_all_tags().map(|t| t.len()).map_err(|e| e.to_string())
+        }) {
+        Ok(count) => count,
+        Err(e) => {
+            errors.push(format!("Tag rebuild: {}", e));
+            0
+        }
+    };
+
+    // 3. Every file checked on disk
+    let files = match crate::commands::run_missing_scan(app) {
+        Ok(result) => Some(result),
+        Err(e) => {
+            errors.push(format!("File check: {}", e));
+            None
+        }
+    };
+
+    let report = MaintenanceReport {
+        started_at,
+        finished_at: chrono::Utc::now().timestamp(),
+        sync,
+        tags,
+        files,
+        errors,
+    };
+    let json = serde_json::to_string(&report).map_err(|e| e.to_string())?;
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_setting(REPORT_KEY, &json)
+        .map_err(|e| e.to_string())?;
+
+    tracing::info!(
+        "Maintenance finished in {}s with {} problem(s)",
+        report.finished_at - report.started_at,
+        report.errors.len()
+    );
+    let _ = app.emit("maintenance-complete", &report);
+    Ok(report)
+}
+
+fn last_report(state: &AppState) -> Option<MaintenanceReport> {
+    state.db.read().ok()
+        .and_then(|db| db.get_setting(REPORT_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+}
+
+/// Whether the nightly run is due: enabled, in `NIGHTLY_HOUR`, and not run yet today.
+fn nightly_due(state: &AppState) -> bool {
+    if !NIGHTLY_ENABLED.load(Ordering::Relaxed) {
+        return false;
+    }
+    let now = Local::now();
+    if now.hour() != NIGHTLY_HOUR {
+        return false;
+    }
+    let last_day = last_report(state)
+        .and_then(|r| Local.timestamp_opt(r.started_at, 0).single())
+        .map(|t| t.date_naive());
+    last_day != Some(now.date_naive())
+}
+
+/// Whether a nightly run was missed: enabled, and no run in the last `MISSED_AFTER_SECS`.
+fn nightly_missed(state: &AppState) -> bool {
+    if !NIGHTLY_ENABLED.load(Ordering::Relaxed) {
+        return false;
+    }
+    let now = chrono::Utc::now().timestamp();
+    last_report(state).is_none_or(|r| now - r.started_at > MISSED_AFTER_SECS)
+}
+
+/// Starts the thread that runs the maintenance each night when it is enabled, after
+/// catching up a run missed while the app was closed.
+pub fn start_nightly_maintenance(app: AppHandle) {
+    std::thread::spawn(move || {
+        std::thread::sleep(CATCH_UP_DELAY);
+        if nightly_missed(&app.state::<AppState>()) {
+            tracing::info!("Catching up a missed nightly maintenance");
+            if let Err(e) = run_maintenance(&app) {
+                tracing::warn!("Missed nightly maintenance did not run: {}", e);
+            }
+        }
+        loop {
+            std::thread::sleep(CHECK_INTERVAL);
+            if nightly_due(&app.state::<AppState>()) {
+                if let Err(e) = run_maintenance(&app) {
+                    tracing::warn!("Nightly maintenance did not run: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Applies the stored "nightly maintenance" preference.
+pub fn load_nightly_maintenance(state: &AppState) {
+    let enabled = state.db.read().ok()
+        .and_then(|db| db.get_setting(NIGHTLY_KEY).ok().flatten())
+        .as_deref() == Some("true");
+    NIGHTLY_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn get_nightly_maintenance() -> bool {
+    NIGHTLY_ENABLED.load(Ordering::Relaxed)
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_nightly_maintenance(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_setting(NIGHTLY_KEY, if enabled { "true" } else { "false" })
+        .map_err(|e| e.to_string())?;
+    NIGHTLY_ENABLED.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Runs the maintenance now, whether or not the nightly run is enabled.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn run_maintenance_now(app: AppHandle) -> Result<MaintenanceReport, String> {
+    tauri::async_runtime::spawn_blocking(move || run_maintenance(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Removes orphaned playlist rows, unused ungrouped tags and tracks without a persistent ID.
+/// Tag usage is recounted first, so only tags no comment mentions go. There is no undo, so
+/// unless `dry_run` is `false` nothing is deleted and the result lists what would be.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn cleanup_orphans(dry_run: Option<bool>, state: State<'_, AppState>) -> Result<OrphanCleanup, String> {
+    let dry_run = dry_run.unwrap_or(true);
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    db.sync_tags().map_err(|e| e.to_string())?;
+    let result = db.cleanup_orphans(dry_run).map_err(|e| e.to_string())?;
+    if dry_run {
+        return Ok(result);
+    }
+    tracing::info!(
+        "Orphan cleanup: {} playlist row(s), {} tag(s), {} track(s) removed",
+        result.playlist_rows,
+        result.tags.len(),
+        result.tracks.len()
+    );
+    Ok(result)
+}
+
+/// Payload of `db-maintenance-progress`, emitted as each step starts.
+#[derive(Clone, Serialize)]
+pub struct DbMaintenanceProgress {
+    pub step: &'static str,
+    /// 1-based
+    pub index: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DbMaintenanceResult {
+    /// Problems `PRAGMA integrity_check` reported; empty when the database is intact
+    pub integrity_errors: Vec<String>,
+    /// Database file plus WAL, in bytes
+    pub size_before: u64,
+    pub size_after: u64,
+}
+
+const DB_STEPS: [&str; 3] = ["integrity_check", "analyze", "vacuum"];
+
+/// Checks the database for corruption, refreshes the planner statistics and vacuums it,
+/// emitting `db-maintenance-progress`. A damaged database is reported and left as is, since
+/// rewriting it could lose more. Holds the writer for the whole run.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn run_db_maintenance(app: AppHandle) -> Result<DbMaintenanceResult, String> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<DbMaintenanceResult, String> {
+        let state = app.state::<AppState>();
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        let progress = |index: usize| {
+            let _ = app.emit("db-maintenance-progress", DbMaintenanceProgress {
+                step: DB_STEPS[index],
+                index: index + 1,
+                total: DB_STEPS.len(),
+            });
+        };
+        let size_before = db.file_size();
+
+        progress(0);
+        let integrity_errors = db.integrity_check().map_err(|e| format!("Integrity check failed: {}", e))?;
+        if !integrity_errors.is_empty() {
+            tracing::error!("Database integrity check found {} problem(s)", integrity_errors.len());
+            return Ok(DbMaintenanceResult { integrity_errors, size_before, size_after: size_before });
+        }
+
+        progress(1);
+        db.analyze().map_err(|e| format!("ANALYZE failed: {}", e))?;
+        progress(2);
+        db.vacuum().map_err(|e| format!("VACUUM failed: {}", e))?;
+
+        let size_after = db.file_size();
+        tracing::info!("Database maintenance: {} -> {} bytes", size_before, size_after);
+        Ok(DbMaintenanceResult { integrity_errors, size_before, size_after })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// The report of the last maintenance run, nightly or manual; `None` before the first.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_last_maintenance_report(state: State<'_, AppState>) -> Result<Option<MaintenanceReport>, String> {
+    Ok(last_report(&state))
+}
@@ -2,18 +2,237 @@ use anyhow::{Context, Result};
 use lofty::config::WriteOptions;
 use lofty::file::FileType;
 use lofty::prelude::*;
+use lofty::picture::{Picture, PictureType};
 use lofty::read_from_path;
 use lofty::tag::ItemKey;
-use lofty::tag::{Tag, TagType};
-use std::path::Path;
+use lofty::tag::{ItemValue, Tag, TagItem, TagType};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 const DELIMITER: &str = " && ";
 
-/// Overwrites the comment field with exactly the provided string.
-/// Also mirrors to Grouping if that's the desired behavior (or we can separate them).
-/// For the UI editor, we probably want to write exactly what the user typed.
-pub fn write_metadata<P: AsRef<Path>>(path: P, comment: &str) -> Result<()> {
-    let path_ref = path.as_ref();
+/// When set, tag writes keep the file's original modification time.
+/// Off by default: Music.app and the library watcher rely on mtime to notice changes.
+static PRESERVE_MTIME: AtomicBool = AtomicBool::new(false);
+
+pub fn set_preserve_mtime(enabled: bool) {
+    PRESERVE_MTIME.store(enabled, Ordering::Relaxed);
+}
+
+pub fn preserve_mtime() -> bool {
+    PRESERVE_MTIME.load(Ordering::Relaxed)
+}
+
+/// When set, rating changes are also written into the file (ID3 POPM / MP4 `rate` / RATING)
+/// so they show up in other players. Off by default; Music.app keeps ratings in its library.
+static FILE_RATINGS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_file_ratings(enabled: bool) {
+    FILE_RATINGS.store(enabled, Ordering::Relaxed);
+}
+
+pub fn file_ratings() -> bool {
+    FILE_RATINGS.load(Ordering::Relaxed)
+}
+
+/// Where files keep the tag list: in the comment after " && " (the default), or in a
+/// dedicated `TXXX:TAGDECK` frame (MP4: `----:com.apple.iTunes:TAGDECK`, Vorbis: `TAGDECK`)
+/// so the visible comment stays readable in Music.app and on CDJs. Either way the DB and
+/// the rest of TagDeck see the combined "user && tags" comment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagStorage {
+    #[default]
+    Comment,
+    Frame,
+}
+
+static TAG_FRAME_STORAGE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_tag_storage(storage: TagStorage) {
+    TAG_FRAME_STORAGE.store(storage == TagStorage::Frame, Ordering::Relaxed);
+}
+
+pub fn tag_storage() -> TagStorage {
+    if TAG_FRAME_STORAGE.load(Ordering::Relaxed) { TagStorage::Frame } else { TagStorage::Comment }
+}
+
+/// The item holding the tag list in `TagStorage::Frame` mode. Unknown ID3v2 keys are
+/// written as TXXX frames and "----:mean:name" keys as MP4 freeform atoms.
+fn tag_frame_key(tag_type: TagType) -> ItemKey {
+    match tag_type {
+        TagType::Mp4Ilst => ItemKey::Unknown("----:com.apple.iTunes:TAGDECK".to_string()),
+        _ => ItemKey::Unknown("TAGDECK".to_string()),
+    }
+}
+
+/// The comment as Music.app should show it: without the tag block in `Frame` mode.
+pub fn music_app_comment(comment: &str) -> String {
+    match tag_storage() {
+        TagStorage::Comment => comment.to_string(),
+        TagStorage::Frame => crate::comment::split_comment(comment).0.to_string(),
+    }
+}
+
+/// A file's comment with the tags from its tag frame (if any) merged back in.
+fn combined_comment(tag: &Tag) -> String {
+    let comment = tag.get_string(&ItemKey::Comment).unwrap_or("").to_string();
+    let frame_tags: Vec<String> = tag.get_string(&tag_frame_key(tag.tag_type())).unwrap_or("")
+        .split(';')
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    crate::comment::add_tags(&comment, &frame_tags).unwrap_or(comment)
+}
+
+/// Why a comment write did not land.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TagWriteErrorKind {
+    /// The file was saved but reads back with a different comment
+    Mismatch,
+    /// The file is a cloud placeholder (see `cloud_files`) and was not downloaded
+    Offloaded,
+    /// The user's own file without write permission; `fix_permissions` can fix it
+    ReadOnly,
+    /// Locked in Finder; `fix_permissions` can unlock it
+    Locked,
+    /// Another user's file we may not write
+    NotOwner,
+    /// The file could not be read or saved
+    Failed,
+}
+
+/// A comment write that did not land, serialized as `{ kind, path, message }` so the UI
+/// can tell the cases apart. Callers leave the DB untouched when they get one.
+#[derive(Debug, Clone, serde::Serialize, thiserror::Error)]
+#[error("{message}")]
+pub struct TagWriteError {
+    pub kind: TagWriteErrorKind,
+    pub path: String,
+    pub message: String,
+}
+
+impl TagWriteError {
+    fn new(kind: TagWriteErrorKind, path: &Path, message: impl Into<String>) -> Self {
+        Self { kind, path: path.to_string_lossy().to_string(), message: message.into() }
+    }
+}
+
+/// Errors of a command around the write (DB lock, unknown track).
+impl From<String> for TagWriteError {
+    fn from(message: String) -> Self {
+        Self { kind: TagWriteErrorKind::Failed, path: String::new(), message }
+    }
+}
+
+/// Hidden sibling used as the scratch copy, so the final rename stays on one volume. It keeps
+/// the audio extension, since lofty picks the format to read back from it.
+fn temp_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    match path.extension() {
+        Some(ext) => path.with_file_name(format!(".{}.tagdeck-tmp.{}", stem, ext.to_string_lossy())),
+        None => path.with_file_name(format!(".{}.tagdeck-tmp", stem)),
+    }
+}
+
+/// Saves `tag` crash-safely: the tag is written into a temp copy, read back and checked
+/// against `expected` (key, value) pairs ("" = absent), then renamed over the original.
+/// Permissions are carried over, and the mtime too when `preserve_mtime` is on.
+/// If anything fails the original file is left untouched.
+fn save_tag_atomically(tag: &Tag, path: &Path, expected: &[(ItemKey, String)]) -> Result<()> {
+    let original_meta = std::fs::metadata(path).context(format!("Failed to stat file: {:?}", path))?;
+    let tmp = temp_path(path);
+
+    let result = (|| -> Result<()> {
+        std::fs::copy(path, &tmp).context("Failed to create temp copy")?;
+        tag.save_to_path(&tmp, WriteOptions::default())
+            .context("Failed to save tags to disk")?;
+
+        // Verification read
+        let written = read_from_path(&tmp).context("Failed to re-read written file")?;
+        let written_tag = written.tag(tag.tag_type())
+            .ok_or_else(|| anyhow::anyhow!("Written file has no {:?} tag", tag.tag_type()))?;
+        for (key, value) in expected {
+            let actual = written_tag.get_string(key).unwrap_or("");
+            if actual != value {
+                return Err(anyhow::anyhow!(
+                    "Verification failed for {:?}: expected {:?}, found {:?}", key, value, actual
+                ));
+            }
+        }
+
+        std::fs::set_permissions(&tmp, original_meta.permissions())
+            .context("Failed to copy permissions")?;
+        if preserve_mtime() {
+            if let Ok(mtime) = original_meta.modified() {
+                std::fs::File::options().write(true).open(&tmp)?.set_modified(mtime)?;
+            }
+        }
+
+        std::fs::rename(&tmp, path).context("Failed to replace original file")?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Overwrites the comment field with exactly the provided string, then reads the file back
+/// to make sure it landed. In `TagStorage::Frame` mode the tag block goes to the tag frame.
+pub fn write_metadata<P: AsRef<Path>>(path: P, comment: &str) -> std::result::Result<(), TagWriteError> {
+    let path = path.as_ref();
+    check_writable(path)?;
+    save_comment(path, comment)
+        .map_err(|e| TagWriteError::new(TagWriteErrorKind::Failed, path, e.to_string()))?;
+    verify_comment(path, comment)
+}
+
+/// `write_metadata` for a library track. Apple Music cloud tracks have no file: their
+/// comment lives in Music.app and the DB only, so there is nothing to write.
+pub fn write_track_comment(track: &crate::models::Track, comment: &str) -> std::result::Result<(), TagWriteError> {
+    if track.is_cloud {
+        return Ok(());
+    }
+    write_metadata(&track.file_path, comment)
+}
+
+/// Run before every tag write so callers get a typed error, not whatever lofty or the
+/// rename reports: cloud placeholders (see `cloud_files`) and files we may not write.
+fn check_writable(path: &Path) -> std::result::Result<(), TagWriteError> {
+    crate::cloud_files::ensure_local(path)
+        .map_err(|e| TagWriteError::new(TagWriteErrorKind::Offloaded, path, e))?;
+    match crate::file_permissions::write_block(path) {
+        Some((kind, message)) => Err(TagWriteError::new(kind, path, message)),
+        None => Ok(()),
+    }
+}
+
+/// Compares the comment the file now reads back as (the way imports read it) with the one
+/// written. Catches saves into a tag the file isn't read from, e.g. an ID3v2 block next to
+/// a primary APE tag, that the temp-copy check in `save_tag_atomically` can't see.
+fn verify_comment(path: &Path, expected: &str) -> std::result::Result<(), TagWriteError> {
+    let (found, _) = read_metadata(path).map_err(|e| {
+        TagWriteError::new(TagWriteErrorKind::Failed, path, format!("Failed to read back written file: {}", e))
+    })?;
+    let parts = |comment: &str| {
+        let (user_comment, tags) = crate::comment::split_comment(comment);
+        (user_comment.trim().to_string(), tags)
+    };
+    if parts(&found) != parts(expected) {
+        return Err(TagWriteError::new(
+            TagWriteErrorKind::Mismatch,
+            path,
+            format!("Comment did not land in {:?}: expected {:?}, file has {:?}", path, expected, found),
+        ));
+    }
+    Ok(())
+}
+
+/// The save half of `write_metadata`. For the UI editor, we write exactly what the user typed.
+fn save_comment(path_ref: &Path, comment: &str) -> Result<()> {
     let mut tagged_file = read_from_path(path_ref).context(format!("Failed to read file: {:?}", path_ref))?;
 
     // Safety: Remove ID3v1 to prevent iTunes conflicts
@@ -37,10 +256,22 @@ pub fn write_metadata<P: AsRef<Path>>(path: P, comment: &str) -> Result<()> {
         tag = Tag::new(TagType::Id3v2);
     }
 
-    // 2. Set Comment
+    // 2. Set Comment (and the tag frame, which only holds tags in `Frame` mode)
+    let (comment, frame) = match tag_storage() {
+        TagStorage::Comment => (comment.to_string(), String::new()),
+        TagStorage::Frame => {
+            let (user_comment, tags) = crate::comment::split_comment(comment);
+            (user_comment.to_string(), tags.join("; "))
+        }
+    };
+    let frame_key = tag_frame_key(tag.tag_type());
     tag.remove_key(&ItemKey::Comment);
     if !comment.is_empty() {
-        tag.insert_text(ItemKey::Comment, comment.to_string());
+        tag.insert_text(ItemKey::Comment, comment.clone());
+    }
+    tag.remove_key(&frame_key);
+    if !frame.is_empty() {
+        tag.insert_text(frame_key.clone(), frame.clone());
     }
 
     // 3. Mirror logic?
@@ -54,10 +285,32 @@ pub fn write_metadata<P: AsRef<Path>>(path: P, comment: &str) -> Result<()> {
     // }
 
     // 4. Save
-    tag.save_to_path(path, WriteOptions::default())
-        .context("Failed to save tags to disk")?;
+    save_tag_atomically(&tag, path_ref, &[(ItemKey::Comment, comment), (frame_key, frame)])
+}
 
-    Ok(())
+/// Overwrites the grouping field; used to restore a file's original grouping.
+pub fn write_grouping<P: AsRef<Path>>(path: P, grouping: &str) -> Result<()> {
+    let path_ref = path.as_ref();
+    check_writable(path_ref)?;
+    let tagged_file = read_from_path(path_ref).context(format!("Failed to read file: {:?}", path_ref))?;
+
+    let mut tag = match tagged_file.primary_tag() {
+        Some(t) => t.clone(),
+        None => Tag::new(TagType::Id3v2),
+    };
+
+    tag.remove_key(&ItemKey::ContentGroup);
+    if !grouping.is_empty() {
+        tag.insert_text(ItemKey::ContentGroup, grouping.to_string());
+    }
+
+    save_tag_atomically(&tag, path_ref, &[(ItemKey::ContentGroup, grouping.to_string())])
+}
+
+/// Formats a BPM with up to two decimals and no trailing zeros ("174.5", "128").
+pub fn format_bpm(bpm: f64) -> String {
+    let s = format!("{:.2}", bpm);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
 }
 
 /// Reads tags from a file path
@@ -67,10 +320,7 @@ pub fn read_metadata<P: AsRef<Path>>(path: P) -> Result<(String, String)> {
         .primary_tag()
         .or_else(|| tagged_file.first_tag());
 
-    let comment = tag
-        .and_then(|t| t.get_string(&ItemKey::Comment))
-        .unwrap_or("")
-        .to_string();
+    let comment = tag.map(combined_comment).unwrap_or_default();
     let grouping = tag
         .and_then(|t| t.get_string(&ItemKey::ContentGroup))
         .unwrap_or("")
@@ -82,6 +332,7 @@ pub fn read_metadata<P: AsRef<Path>>(path: P) -> Result<(String, String)> {
 /// Writes tags to a file path using the "Left-Side" preservation strategy
 pub fn write_tags<P: AsRef<Path>>(path: P, new_tags_string: &str) -> Result<()> {
     let path = path.as_ref();
+    check_writable(path)?;
     let mut tagged_file = read_from_path(path).context("Failed to read file for writing")?;
 
     // 1. Clean ID3v1 to avoid iTunes conflicts (as proven in verify_tags.rs)
@@ -134,7 +385,7 @@ pub fn write_tags<P: AsRef<Path>>(path: P, new_tags_string: &str) -> Result<()>
     // Update Comment
     tag.remove_key(&ItemKey::Comment);
     if !final_comment.is_empty() {
-        tag.insert_text(ItemKey::Comment, final_comment);
+        tag.insert_text(ItemKey::Comment, final_comment.clone());
     }
 
     // Update Grouping Mirror (Secondary)
@@ -144,10 +395,10 @@ pub fn write_tags<P: AsRef<Path>>(path: P, new_tags_string: &str) -> Result<()>
     // Note: We use save_to_path on the *tag* to overwrite just that chunk ideally,
     // or we can use tagged_file.save_to_path if we put the tag back in.
     // In verify_tags we used tag.save_to_path.
-    tag.save_to_path(path, WriteOptions::default())
-        .context("Failed to save tags to disk")?;
-
-    Ok(())
+    save_tag_atomically(&tag, path, &[
+        (ItemKey::Comment, final_comment),
+        (ItemKey::ContentGroup, new_tags_string.to_string()),
+    ])
 }
 
 pub fn get_artwork<P: AsRef<Path>>(path: P) -> Result<Option<Vec<u8>>> {
@@ -165,16 +416,125 @@ pub fn get_artwork<P: AsRef<Path>>(path: P) -> Result<Option<Vec<u8>>> {
     Ok(None)
 }
 
-/// Writes track info fields (title, artist, album, BPM) to the audio file's metadata tags.
+/// Embeds `image` (JPEG or PNG bytes) as the front cover, replacing any existing front cover.
+pub fn write_artwork<P: AsRef<Path>>(path: P, image: &[u8]) -> Result<()> {
+    let path_ref = path.as_ref();
+    check_writable(path_ref)?;
+    let mut tagged_file = read_from_path(path_ref)
+        .context(format!("Failed to read file: {:?}", path_ref))?;
+
+    // Safety: Remove ID3v1 to prevent iTunes conflicts
+    if tagged_file.tag(TagType::Id3v1).is_some() {
+        tagged_file.remove(TagType::Id3v1);
+    }
+
+    let mut tag = match tagged_file.primary_tag_mut() {
+        Some(t) => t.clone(),
+        None => Tag::new(TagType::Id3v2),
+    };
+
+    // Force ID3v2 for MP3/AIFF
+    if (tagged_file.file_type() == FileType::Mpeg || tagged_file.file_type() == FileType::Aiff)
+        && tag.tag_type() != TagType::Id3v2
+    {
+        tag = Tag::new(TagType::Id3v2);
+    }
+
+    let mut picture = Picture::from_reader(&mut std::io::Cursor::new(image))
+        .context("Unsupported image data")?;
+    picture.set_pic_type(PictureType::CoverFront);
+    tag.remove_picture_type(PictureType::CoverFront);
+    tag.push_picture(picture);
+
+    save_tag_atomically(&tag, path_ref, &[])
+        .context("Failed to save artwork to disk")
+}
+
+/// Genre from the file's tags; the library DB does not keep it.
+pub fn read_genre<P: AsRef<Path>>(path: P) -> Result<Option<String>> {
+    let tagged_file = read_from_path(path.as_ref()).context("Failed to read file")?;
+    let genre = tagged_file
+        .primary_tag()
+        .or_else(|| tagged_file.first_tag())
+        .and_then(|t| t.genre().map(|g| g.trim().to_string()))
+        .filter(|g| !g.is_empty());
+    Ok(genre)
+}
+
+/// Builds a library track from a file's tags and audio properties, for libraries indexed
+/// straight from disk. The persistent ID is `file:` plus the path.
+pub fn read_file_track<P: AsRef<Path>>(path: P) -> Result<crate::models::Track> {
+    let path = path.as_ref();
+    let tagged_file = read_from_path(path).context("Failed to read file")?;
+    let file_meta = std::fs::metadata(path).context("Failed to stat file")?;
+    let tag = tagged_file.primary_tag().or_else(|| tagged_file.first_tag());
+    let text = |key: ItemKey| {
+        tag.and_then(|t| t.get_string(&key))
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let properties = tagged_file.properties();
+    let modified = file_meta.modified().ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let path_str = path.to_string_lossy().to_string();
+
+    Ok(crate::models::Track {
+        id: 0,
+        persistent_id: format!("file:{}", path_str),
+        file_path: path_str,
+        artist: text(ItemKey::TrackArtist),
+        title: text(ItemKey::TrackTitle)
+            .or_else(|| path.file_stem().map(|s| s.to_string_lossy().to_string())),
+        album: text(ItemKey::AlbumTitle),
+        comment_raw: tag.map(combined_comment).map(|c| c.trim().to_string()).filter(|c| !c.is_empty()),
+        grouping_raw: text(ItemKey::ContentGroup),
+        duration_secs: properties.duration().as_secs_f64(),
+        format: path.extension().map(|e| e.to_string_lossy().to_uppercase()).unwrap_or_default(),
+        size_bytes: file_meta.len() as i64,
+        bit_rate: properties.audio_bitrate().unwrap_or(0) as i64,
+        modified_date: modified,
+        rating: read_rating(path).ok().flatten().unwrap_or(0) as i64,
+        date_added: chrono::Utc::now().timestamp(),
+        bpm: text(ItemKey::Bpm).or_else(|| text(ItemKey::IntegerBpm))
+            .and_then(|b| b.parse::<f64>().ok())
+            .unwrap_or(0.0),
+        missing: false,
+        play_count: 0,
+        skip_count: 0,
+        last_played: 0,
+        loved: false,
+        notes: None,
+        intro_bars: None,
+        outro_bars: None,
+        audio_health: None,
+        color_label: None,
+        offloaded: false,
+        is_cloud: false,
+    })
+}
+
+/// Whether any tag in the file carries embedded artwork.
+pub fn has_artwork<P: AsRef<Path>>(path: P) -> Result<bool> {
+    let tagged_file = read_from_path(path.as_ref()).context("Failed to read file")?;
+    Ok(tagged_file.tags().iter().any(|t| !t.pictures().is_empty()))
+}
+
+/// Writes track info fields (title, artist, album, BPM, musical key) to the audio file's metadata tags.
 /// Only updates fields that are Some; leaves existing values for None fields.
+/// BPM lands in TBPM / `tmpo` / BPM and the key in TKEY / `initialkey` / INITIALKEY,
+/// which is where DJ software reading the file looks.
 pub fn write_track_info<P: AsRef<Path>>(
     path: P,
     title: Option<&str>,
     artist: Option<&str>,
     album: Option<&str>,
-    bpm: Option<i64>,
+    bpm: Option<f64>,
+    key: Option<&str>,
 ) -> Result<()> {
     let path_ref = path.as_ref();
+    check_writable(path_ref)?;
     let mut tagged_file = read_from_path(path_ref)
         .context(format!("Failed to read file: {:?}", path_ref))?;
 
@@ -195,11 +555,14 @@ pub fn write_track_info<P: AsRef<Path>>(
         tag = Tag::new(TagType::Id3v2);
     }
 
+    let mut expected = Vec::new();
+
     if let Some(t) = title {
         tag.remove_key(&ItemKey::TrackTitle);
         if !t.is_empty() {
             tag.insert_text(ItemKey::TrackTitle, t.to_string());
         }
+        expected.push((ItemKey::TrackTitle, t.to_string()));
     }
 
     if let Some(a) = artist {
@@ -207,6 +570,7 @@ pub fn write_track_info<P: AsRef<Path>>(
         if !a.is_empty() {
             tag.insert_text(ItemKey::TrackArtist, a.to_string());
         }
+        expected.push((ItemKey::TrackArtist, a.to_string()));
     }
 
     if let Some(al) = album {
@@ -214,17 +578,203 @@ pub fn write_track_info<P: AsRef<Path>>(
         if !al.is_empty() {
             tag.insert_text(ItemKey::AlbumTitle, al.to_string());
         }
+        expected.push((ItemKey::AlbumTitle, al.to_string()));
     }
 
     if let Some(b) = bpm {
-        tag.remove_key(&ItemKey::Bpm);
-        if b > 0 {
-            tag.insert_text(ItemKey::Bpm, b.to_string());
+        // IntegerBpm is TBPM (ID3v2) / tmpo (MP4) and must be whole; Bpm covers
+        // Vorbis/APE and TXXX:BPM and keeps full precision.
+        // A tag type only accepts the keys it can store, so only verify what was inserted.
+        let values = if b > 0.0 {
+            [(ItemKey::IntegerBpm, (b.round() as i64).to_string()), (ItemKey::Bpm, format_bpm(b))]
+        } else {
+            [(ItemKey::IntegerBpm, String::new()), (ItemKey::Bpm, String::new())]
+        };
+        for (item_key, value) in values {
+            tag.remove_key(&item_key);
+            if value.is_empty() || tag.insert_text(item_key.clone(), value.clone()) {
+                expected.push((item_key, value));
+            }
         }
     }
 
-    tag.save_to_path(path, WriteOptions::default())
-        .context("Failed to save track info to disk")?;
+    if let Some(k) = key {
+        tag.remove_key(&ItemKey::InitialKey);
+        if k.is_empty() || tag.insert_text(ItemKey::InitialKey, k.to_string()) {
+            expected.push((ItemKey::InitialKey, k.to_string()));
+        }
+    }
 
-    Ok(())
+    save_tag_atomically(&tag, path_ref, &expected)
+        .context("Failed to save track info to disk")
+}
+
+/// Writes release details (label, genre, year, catalog number) to the file's tags.
+/// Only updates fields that are Some; keys a tag format cannot hold are skipped.
+pub fn write_release_info<P: AsRef<Path>>(
+    path: P,
+    label: Option<&str>,
+    genre: Option<&str>,
+    year: Option<i32>,
+    catalog_number: Option<&str>,
+) -> Result<()> {
+    let path_ref = path.as_ref();
+    check_writable(path_ref)?;
+    let mut tagged_file = read_from_path(path_ref)
+        .context(format!("Failed to read file: {:?}", path_ref))?;
+
+    // Safety: Remove ID3v1 to prevent iTunes conflicts
+    if tagged_file.tag(TagType::Id3v1).is_some() {
+        tagged_file.remove(TagType::Id3v1);
+    }
+
+    let mut tag = match tagged_file.primary_tag_mut() {
+        Some(t) => t.clone(),
+        None => Tag::new(TagType::Id3v2),
+    };
+
+    // Force ID3v2 for MP3/AIFF
+    if (tagged_file.file_type() == FileType::Mpeg || tagged_file.file_type() == FileType::Aiff)
+        && tag.tag_type() != TagType::Id3v2
+    {
+        tag = Tag::new(TagType::Id3v2);
+    }
+
+    let fields = [
+        (ItemKey::Label, label.map(str::to_string)),
+        (ItemKey::Genre, genre.map(str::to_string)),
+        (ItemKey::Year, year.map(|y| y.to_string())),
+        (ItemKey::CatalogNumber, catalog_number.map(str::to_string)),
+    ];
+    let mut expected = Vec::new();
+    for (item_key, value) in fields {
+        let Some(value) = value else { continue };
+        tag.remove_key(&item_key);
+        if value.is_empty() || tag.insert_text(item_key.clone(), value.clone()) {
+            expected.push((item_key, value));
+        }
+    }
+
+    save_tag_atomically(&tag, path_ref, &expected)
+        .context("Failed to save release info to disk")
+}
+
+/// Email field of the POPM frames TagDeck writes. Readers accept a POPM from any player.
+const POPM_EMAIL: &str = "TagDeck";
+
+/// 0–100 to the POPM byte, using the Windows Media Player star steps most players read.
+/// POPM has no half stars; they round to the nearest whole star.
+fn rating_to_popm(rating: u32) -> u8 {
+    match rating {
+        0 => 0,
+        1..=29 => 1,
+        30..=49 => 64,
+        50..=69 => 128,
+        70..=89 => 196,
+        _ => 255,
+    }
+}
+
+/// POPM byte to 0–100, accepting the ranges other players write for each star.
+fn popm_to_rating(popm: u8) -> u32 {
+    match popm {
+        0 => 0,
+        1..=31 => 20,
+        32..=95 => 40,
+        96..=159 => 60,
+        160..=223 => 80,
+        _ => 100,
+    }
+}
+
+/// Text key holding a 0–100 rating for tag types without POPM.
+fn rating_text_key(tag_type: TagType) -> Option<ItemKey> {
+    match tag_type {
+        TagType::Mp4Ilst => Some(ItemKey::Unknown("rate".to_string())),
+        TagType::VorbisComments | TagType::Ape => Some(ItemKey::Unknown("RATING".to_string())),
+        _ => None,
+    }
+}
+
+/// Writes a 0–100 rating into the file: POPM for ID3v2, a 0–100 text value otherwise.
+/// A rating of 0 removes it.
+pub fn write_rating<P: AsRef<Path>>(path: P, rating: u32) -> Result<()> {
+    let path_ref = path.as_ref();
+    check_writable(path_ref)?;
+    let mut tagged_file = read_from_path(path_ref)
+        .context(format!("Failed to read file: {:?}", path_ref))?;
+
+    // Safety: Remove ID3v1 to prevent iTunes conflicts
+    if tagged_file.tag(TagType::Id3v1).is_some() {
+        tagged_file.remove(TagType::Id3v1);
+    }
+
+    let mut tag = match tagged_file.primary_tag_mut() {
+        Some(t) => t.clone(),
+        None => Tag::new(TagType::Id3v2),
+    };
+
+    // Force ID3v2 for MP3/AIFF
+    if (tagged_file.file_type() == FileType::Mpeg || tagged_file.file_type() == FileType::Aiff)
+        && tag.tag_type() != TagType::Id3v2
+    {
+        tag = Tag::new(TagType::Id3v2);
+    }
+
+    let mut expected = Vec::new();
+    if tag.tag_type() == TagType::Id3v2 {
+        // POPM is binary (email, NUL, rating byte), so it is not part of the string verification
+        tag.remove_key(&ItemKey::Popularimeter);
+        if rating > 0 {
+            let mut popm = POPM_EMAIL.as_bytes().to_vec();
+            popm.push(0);
+            popm.push(rating_to_popm(rating));
+            tag.insert(TagItem::new(ItemKey::Popularimeter, ItemValue::Binary(popm)));
+        }
+    } else {
+        let key = rating_text_key(tag.tag_type())
+            .ok_or_else(|| anyhow::anyhow!("{:?} tags cannot store a rating", tag.tag_type()))?;
+        let value = if rating > 0 { rating.min(100).to_string() } else { String::new() };
+        tag.remove_key(&key);
+        if value.is_empty() || tag.insert_text(key.clone(), value.clone()) {
+            expected.push((key, value));
+        }
+    }
+
+    save_tag_atomically(&tag, path_ref, &expected)
+        .context("Failed to save rating to disk")
+}
+
+/// Writes the rating into the file when file ratings are enabled; otherwise does nothing.
+pub fn mirror_rating<P: AsRef<Path>>(path: P, rating: u32) -> Result<()> {
+    if !file_ratings() {
+        return Ok(());
+    }
+    write_rating(path, rating)
+}
+
+/// Reads a 0–100 rating stored in the file, if any.
+pub fn read_rating<P: AsRef<Path>>(path: P) -> Result<Option<u32>> {
+    let tagged_file = read_from_path(path.as_ref()).context("Failed to read file")?;
+    let tag = match tagged_file.primary_tag().or_else(|| tagged_file.first_tag()) {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+
+    if tag.tag_type() == TagType::Id3v2 {
+        let rating = tag.get_items(&ItemKey::Popularimeter)
+            .find_map(|item| match item.value() {
+                ItemValue::Binary(bytes) => {
+                    let nul = bytes.iter().position(|b| *b == 0)?;
+                    bytes.get(nul + 1).map(|b| popm_to_rating(*b))
+                }
+                _ => None,
+            });
+        return Ok(rating);
+    }
+
+    Ok(rating_text_key(tag.tag_type())
+        .and_then(|key| tag.get_string(&key).map(str::to_string))
+        .and_then(|s| s.trim().parse::<u32>().ok())
+        .map(|r| r.min(100)))
 }
@@ -0,0 +1,253 @@
+//! Hands-on tagging from a MIDI controller (or a Stream Deck sending MIDI): each mapped
+//! note or CC applies a tag to the tracks selected in the UI, or to the track reported
+//! through the `now-playing` event.
+
+use crate::commands::AppState;
+use midir::{MidiInput, MidiInputConnection};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Listener, Manager, State};
+
+const MIDI_SETTINGS_KEY: &str = "midi_tagging";
+
+const CLIENT_NAME: &str = "TagDeck";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MidiMessageKind {
+    Note,
+    /// Control change; fires when the value goes above zero (button pressed)
+    Cc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MappingAction {
+    #[default]
+    Add,
+    Remove,
+    /// Removes the tag if the first target track has it, otherwise adds it
+    Toggle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagTarget {
+    #[default]
+    Selected,
+    Playing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MidiMapping {
+    pub kind: MidiMessageKind,
+    /// 0–15; `None` matches any channel
+    #[serde(default)]
+    pub channel: Option<u8>,
+    /// Note or controller number
+    pub number: u8,
+    pub tag: String,
+    #[serde(default)]
+    pub action: MappingAction,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MidiSettings {
+    pub enabled: bool,
+    /// Input port to listen on; `None` uses the first available one
+    pub port_name: Option<String>,
+    pub target: TagTarget,
+    pub mappings: Vec<MidiMapping>,
+}
+
+/// Payload of the `midi-message` event, sent for every note/CC press so the UI can
+/// offer "press a pad to map it".
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct MidiMessage {
+    pub kind: MidiMessageKind,
+    pub channel: u8,
+    pub number: u8,
+}
+
+/// Payload of the `midi-tag-applied` event.
+#[derive(Clone, Serialize)]
+pub struct MidiTagApplied {
+    pub tag: String,
+    pub added: bool,
+    pub track_ids: Vec<i64>,
+}
+
+/// The open MIDI connection plus the tracks a mapping currently applies to.
+#[derive(Default)]
+pub struct MidiState {
+    connection: Mutex<Option<MidiInputConnection<()>>>,
+    selected: Arc<Mutex<Vec<i64>>>,
+    playing: Arc<Mutex<Option<i64>>>,
+}
+
+impl MidiMessage {
+    /// Note on (velocity > 0) or CC with a non-zero value; everything else is ignored.
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let (&status, rest) = bytes.split_first()?;
+        let (&number, &value) = (rest.first()?, rest.get(1)?);
+        let kind = match status & 0xF0 {
+            0x90 => MidiMessageKind::Note,
+            0xB0 => MidiMessageKind::Cc,
+            _ => return None,
+        };
+        if value == 0 {
+            return None;
+        }
+        Some(Self { kind, channel: status & 0x0F, number })
+    }
+}
+
+impl MidiMapping {
+    fn matches(&self, message: &MidiMessage) -> bool {
+        self.kind == message.kind
+            && self.number == message.number
+            && self.channel.is_none_or(|c| c == message.channel)
+    }
+}
+
+fn load_settings(state: &AppState) -> MidiSettings {
+    state.db.read().ok()
+        .and_then(|db| db.get_setting(MIDI_SETTINGS_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Keeps `MidiState.playing` in step with the `now-playing` event. Called once at startup.
+pub fn track_now_playing(app: &AppHandle) {
+    let playing = app.state::<MidiState>().playing.clone();
+    app.listen("now-playing", move |event| {
+        let id = serde_json::from_str::<serde_json::Value>(event.payload()).ok()
+            .and_then(|v| v["track"]["id"].as_i64());
+        if let (Some(id), Ok(mut slot)) = (id, playing.lock()) {
+            *slot = Some(id);
+        }
+    });
+}
+
+/// (Re)opens the configured MIDI input, or closes it when disabled in settings.
+pub fn start_midi_listener(app: AppHandle) {
+    let settings = load_settings(&app.state::<AppState>());
+    let midi = app.state::<MidiState>();
+
+    // Dropping the connection closes the port
+    if let Ok(mut slot) = midi.connection.lock() {
+        *slot = None;
+    }
+    if !settings.enabled {
+        return;
+    }
+
+    let input = match MidiInput::new(CLIENT_NAME) {
+        Ok(input) => input,
+        Err(e) => {
+            tracing::error!("MIDI unavailable: {}", e);
+            return;
+        }
+    };
+    let port = input.ports().into_iter().find(|p| match &settings.port_name {
+        Some(name) => input.port_name(p).is_ok_and(|n| &n == name),
+        None => true,
+    });
+    let Some(port) = port else {
+        let wanted = settings.port_name.as_deref().unwrap_or("any");
+        tracing::warn!("MIDI input not found ({}); MIDI tagging is off", wanted);
+        return;
+    };
+    let port_name = input.port_name(&port).unwrap_or_default();
+
+    let handle = app.clone();
+    let selected = midi.selected.clone();
+    let playing = midi.playing.clone();
+    let connection = input.connect(&port, "tagdeck-tagging", move |_stamp, bytes, _| {
+        let Some(message) = MidiMessage::parse(bytes) else { return };
+        let _ = handle.emit("midi-message", message);
+
+        let Some(mapping) = settings.mappings.iter().find(|m| m.matches(&message)) else { return };
+        let ids: Vec<i64> = match settings.target {
+            TagTarget::Selected => selected.lock().map(|s| s.clone()).unwrap_or_default(),
+            TagTarget::Playing => playing.lock().ok().and_then(|p| *p).into_iter().collect(),
+        };
+        if ids.is_empty() {
+            return;
+        }
+        let app = handle.clone();
+        let mapping = mapping.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = apply_mapping(&app, &mapping, ids).await {
+                tracing::error!("MIDI tagging failed: {}", e);
+            }
+        });
+    }, ());
+
+    match connection {
+        Ok(connection) => {
+            if let Ok(mut slot) = midi.connection.lock() {
+                *slot = Some(connection);
+            }
+            tracing::info!("Listening for MIDI tagging on {}", port_name);
+        }
+        Err(e) => tracing::error!("Could not open MIDI input {}: {}", port_name, e),
+    }
+}
+
+/// Applies a mapping right away as one undoable batch, like the batch tag commands do.
+async fn apply_mapping(app: &AppHandle, mapping: &MidiMapping, ids: Vec<i64>) -> Result<(), String> {
+    let add = match mapping.action {
+        MappingAction::Add => true,
+        MappingAction::Remove => false,
+        MappingAction::Toggle => {
+            let state = app.state::<AppState>();
+            let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+            let first = db.get_track(ids[0]).map_err(|e| e.to_string())?;
+            let comment = first.and_then(|t| t.comment_raw).unwrap_or_default();
+            let (_, tags) = crate::comment::split_comment(&comment);
+            !tags.iter().any(|t| t.eq_ignore_ascii_case(&mapping.tag))
+        }
+    };
+
+    let state = app.state::<AppState>();
+    if add {
+        crate::commands::add_tag_to_tracks(app, &state, &ids, &mapping.tag).await?;
+    } else {
+        crate::commands::remove_tag_from_tracks(app, &state, &ids, &mapping.tag).await?;
+    }
+    let _ = app.emit("midi-tag-applied", MidiTagApplied { tag: mapping.tag.clone(), added: add, track_ids: ids });
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn list_midi_inputs() -> Result<Vec<String>, String> {
+    let input = MidiInput::new(CLIENT_NAME).map_err(|e| e.to_string())?;
+    Ok(input.ports().iter().filter_map(|p| input.port_name(p).ok()).collect())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_midi_settings(state: State<'_, AppState>) -> Result<MidiSettings, String> {
+    Ok(load_settings(&state))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn set_midi_settings(app: AppHandle, state: State<'_, AppState>, settings: MidiSettings) -> Result<(), String> {
+    let json = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_setting(MIDI_SETTINGS_KEY, &json).map_err(|e| e.to_string())?;
+    start_midi_listener(app);
+    Ok(())
+}
+
+/// The UI reports its track selection here so `Selected` mappings know their targets.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn set_selected_tracks(ids: Vec<i64>, midi: State<'_, MidiState>) -> Result<(), String> {
+    *midi.selected.lock().map_err(|_| "Failed to lock selection".to_string())? = ids;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! Mixed In Key 8 as an `ExternalAnalyzer`: files are opened in MIK, which writes the key
+//! and energy into the comment and the BPM tag itself; the analysis worker picks them up
+//! once the files have been rewritten.
+
+use crate::commands::AppState;
+use crate::external_analyzer::{AnalysisSubmission, ExternalAnalyzer, Submission};
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+
+pub const MIK_NAME: &str = "Mixed In Key 8";
+const MIK_APP_PATH: &str = "/Applications/Mixed In Key 8.app";
+
+pub struct MixedInKey;
+
+impl ExternalAnalyzer for MixedInKey {
+    fn name(&self) -> &str {
+        MIK_NAME
+    }
+
+    fn check_available(&self) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        {
+            if !std::path::Path::new(MIK_APP_PATH).exists() {
+                return Err("Mixed In Key 8 not found. Please install from https://mixedinkey.com/".to_string());
+            }
+            Ok(())
+        }
+
+        #[cfg(not(target_os = "macos"))]
+        {
+            let _ = MIK_APP_PATH;
+            Err("Mixed In Key integration is only supported on macOS".to_string())
+        }
+    }
+
+    /// Opens the files in MIK; those it refuses to open are reported as refused.
+    fn submit(&self, paths: &[&str]) -> Result<Vec<Submission>, String> {
+        #[cfg(target_os = "macos")]
+        {
+            let script = r#"
+                on run argv
+                    set accepted to {}
+                    tell application "Mixed In Key 8"
+                        activate
+                        repeat with i from 1 to count of argv
+                            try
+                                open (POSIX file (item i of argv))
+                                set end of accepted to i
+                            end try
+                        end repeat
+                    end tell
+                    set AppleScript's text item delimiters to ","
+                    return accepted as text
+                end run
+            "#;
+            let output = std::process::Command::new("osascript")
+                .arg("-e")
+                .arg(script)
+                .args(paths)
+                .output()
+                .map_err(|e| format!("Failed to launch Mixed In Key with AppleScript: {}", e))?;
+            if !output.status.success() {
+                return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+            }
+            let accepted: Vec<usize> = String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .split(',')
+                .filter_map(|i| i.trim().parse::<usize>().ok())
+                .collect();
+            Ok((1..=paths.len())
+                .map(|i| if accepted.contains(&i) {
+                    Submission::Pending
+                } else {
+                    Submission::Refused("Mixed In Key did not accept the file".to_string())
+                })
+                .collect())
+        }
+
+        #[cfg(not(target_os = "macos"))]
+        {
+            let _ = paths;
+            Err("Mixed In Key integration is only supported on macOS".to_string())
+        }
+    }
+
+    fn finish(&self) {
+        #[cfg(target_os = "macos")]
+        {
+            let _ = std::process::Command::new("osascript")
+                .arg("-e")
+                .arg(r#"tell application "Mixed In Key 8" to quit"#)
+                .output();
+        }
+    }
+}
+
+/// Queues tracks for Mixed In Key; see `external_analyzer` for how the run is reported.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn analyze_with_mixed_in_key(
+    track_ids: Vec<i64>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<AnalysisSubmission, String> {
+    crate::external_analyzer::queue_analysis(app, &state, Arc::new(MixedInKey), track_ids)
+}
@@ -1,6 +1,15 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Version of the shapes that cross the IPC boundary; bump it when a field is added, here
+/// and in `SCHEMA_VERSION` of `src/types.ts`. Every struct the frontend receives or sends
+/// defaults missing fields: the ones below and the settings structs default as a whole,
+/// and structs with required fields (`MidiMapping`, `PathRule`, ...) mark each field added
+/// after version 1 with `#[serde(default)]`. Payloads from a frontend built against an
+/// older version still deserialize, and older frontends ignore fields they don't know.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct Track {
     pub id: i64,               // Database ID
     pub persistent_id: String, // From iTunes XML (Persistent ID)
@@ -17,12 +26,23 @@ pub struct Track {
     pub modified_date: i64, // Unix timestamp
     pub rating: i64, // 0-100
     pub date_added: i64, // Unix timestamp
-    pub bpm: i64,
-    #[serde(default)]
+    pub bpm: f64, // May be fractional (e.g. 174.5); Music.app only stores whole numbers
     pub missing: bool,
+    pub play_count: i64,
+    pub skip_count: i64,
+    pub last_played: i64, // Unix timestamp, 0 = never played
+    pub loved: bool, // "Favorite" in newer Music.app versions
+    pub notes: Option<String>, // TagDeck-only free text; never written to the file or Music.app
+    pub intro_bars: Option<i64>, // Mixable intro in bars found by `analyze_track_structure`; None = not analyzed
+    pub outro_bars: Option<i64>,
+    pub audio_health: Option<String>, // Last `verify_audio_files` verdict ("ok", "corrupt", ...); None = never checked
+    pub color_label: Option<TrackColor>,
+    pub offloaded: bool, // File is a cloud placeholder (iCloud Drive, Dropbox online-only), not on disk
+    pub is_cloud: bool, // Apple Music streaming/cloud item: no local file, tagged through Music.app only
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct Playlist {
     pub id: i64,               // Database ID
     pub persistent_id: String, // From iTunes XML
@@ -30,9 +50,11 @@ pub struct Playlist {
     pub name: String,
     pub is_folder: bool,
     pub track_ids: Option<Vec<String>>, // Persistent IDs of tracks
+    pub is_smart: bool, // Smart or built-in playlist; membership is managed by Music.app
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct Tag {
     pub id: i64,
     pub name: String,
@@ -40,9 +62,161 @@ pub struct Tag {
     pub group_id: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct TagGroup {
     pub id: i64,
     pub name: String,
     pub position: i64,
 }
+
+/// A named bundle of tags applied together, e.g. "Warmup kit" = chill; groovy; warmup.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TagSet {
+    pub id: i64,
+    pub name: String,
+    pub tags: Vec<String>,
+}
+
+/// A keyboard key bound to a tag.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TagShortcut {
+    /// "1"-"9" or "a"-"z"
+    pub key: String,
+    pub tag_id: i64,
+    pub tag_name: String,
+}
+
+/// Color label on a track: Rekordbox's eight track colors, which most DJ software and
+/// Finder's tag colors map onto.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackColor {
+    Pink,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Aqua,
+    Blue,
+    Purple,
+}
+
+impl TrackColor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TrackColor::Pink => "pink",
+            TrackColor::Red => "red",
+            TrackColor::Orange => "orange",
+            TrackColor::Yellow => "yellow",
+            TrackColor::Green => "green",
+            TrackColor::Aqua => "aqua",
+            TrackColor::Blue => "blue",
+            TrackColor::Purple => "purple",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pink" => Some(TrackColor::Pink),
+            "red" => Some(TrackColor::Red),
+            "orange" => Some(TrackColor::Orange),
+            "yellow" => Some(TrackColor::Yellow),
+            "green" => Some(TrackColor::Green),
+            "aqua" => Some(TrackColor::Aqua),
+            "blue" => Some(TrackColor::Blue),
+            "purple" => Some(TrackColor::Purple),
+            _ => None,
+        }
+    }
+
+    /// The value of Rekordbox's `Colour` track attribute.
+    pub fn rekordbox_colour(&self) -> &'static str {
+        match self {
+            TrackColor::Pink => "0xFF007F",
+            TrackColor::Red => "0xFF0000",
+            TrackColor::Orange => "0xFFA500",
+            TrackColor::Yellow => "0xFFFF00",
+            TrackColor::Green => "0x00FF00",
+            TrackColor::Aqua => "0x25FDE9",
+            TrackColor::Blue => "0x0000FF",
+            TrackColor::Purple => "0x660099",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CueType {
+    /// Memory cue
+    #[default]
+    Cue,
+    /// Numbered hot cue (A, B, C... in Rekordbox)
+    Hot,
+    Loop,
+}
+
+impl CueType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CueType::Cue => "cue",
+            CueType::Hot => "hot",
+            CueType::Loop => "loop",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "hot" => CueType::Hot,
+            "loop" => CueType::Loop,
+            _ => CueType::Cue,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CuePoint {
+    pub id: i64,
+    pub track_id: i64,
+    pub position_secs: f64,
+    #[serde(rename = "type")]
+    pub cue_type: CueType,
+    /// Loops only: where the loop ends
+    pub end_secs: Option<f64>,
+    pub label: String,
+    /// "#RRGGBB"
+    pub color: Option<String>,
+}
+
+/// A recorded DJ set: the tracks marked as played, in order. `ended_at` is `None` while running.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Session {
+    pub id: i64,
+    pub name: String,
+    pub started_at: i64, // Unix timestamp
+    pub ended_at: Option<i64>,
+}
+
+/// A saved point-in-time copy of the library's tags, ratings and playlists.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct LibrarySnapshot {
+    pub id: i64,
+    pub label: String,
+    pub created_at: i64, // Unix timestamp
+}
+
+/// A tag with its usage count and group name, as returned by `get_global_tags`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct TagSummary {
+    pub id: i64,
+    pub name: String,
+    pub usage_count: i64,
+    pub group_id: Option<i64>,
+    pub group_name: Option<String>,
+}
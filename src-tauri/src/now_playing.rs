@@ -0,0 +1,121 @@
+//! What is playing right now, for "tag what's playing" workflows. Music.app is polled
+//! in the background once the user turns the watcher on; other players can report
+//! through the automation API. Either way the UI gets a `now-playing` event when the
+//! track changes.
+
+use crate::commands::AppState;
+use crate::models::Track;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const WATCHER_KEY: &str = "now_playing_watcher";
+
+/// Whether Music.app's player is polled; off until the user enables it
+static WATCHER_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// How often Music.app's player is checked.
+#[cfg(target_os = "macos")]
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Payload of the `now-playing` event.
+#[derive(Clone, Serialize)]
+pub struct NowPlaying {
+    pub track: Track,
+    /// Where the report came from: "music" or "automation"
+    pub source: String,
+}
+
+/// Tells the UI which track is playing and records the play in the listening history.
+/// Sessions only get the tracks the user marks with `append_to_session`.
+pub fn report_now_playing(app: &AppHandle, track: Track, source: &str) {
+    let state = app.state::<AppState>();
+    let now = chrono::Utc::now().timestamp();
+    if let Ok(db) = state.db.lock() {
+        if let Err(e) = db.add_listening_event(track.id, now, source) {
+            tracing::error!("Failed to record listening history: {}", e);
+        }
+    }
+    let _ = app.emit("now-playing", NowPlaying { track, source: source.to_string() });
+}
+
+fn track_for_pid(app: &AppHandle, pid: &str) -> Result<Option<Track>, String> {
+    let state = app.state::<AppState>();
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    match db.get_track_id_by_persistent_id(pid).map_err(|e| e.to_string())? {
+        Some(id) => db.get_track(id).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Watches Music.app's player while the watcher is enabled and reports each new track
+/// once. Tracks TagDeck doesn't know yet (not synced) are skipped.
+pub fn start_now_playing_watcher(app: AppHandle) {
+    #[cfg(target_os = "macos")]
+    std::thread::spawn(move || {
+        let mut last_pid: Option<String> = None;
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            if !WATCHER_ENABLED.load(Ordering::Relaxed) {
+                last_pid = None;
+                continue;
+            }
+            let pid = match crate::apple_music::get_playing_track_pid() {
+                Ok(pid) => pid,
+                Err(_) => continue,
+            };
+            // Pausing and resuming the same track is not a change
+            if pid.is_none() || pid == last_pid {
+                continue;
+            }
+            last_pid = pid.clone();
+            if let Some(pid) = pid {
+                if let Ok(Some(track)) = track_for_pid(&app, &pid) {
+                    report_now_playing(&app, track, "music");
+                }
+            }
+        }
+    });
+
+    #[cfg(not(target_os = "macos"))]
+    let _ = app;
+}
+
+/// The track Music.app is playing, if TagDeck knows it.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_now_playing(app: AppHandle) -> Result<Option<Track>, String> {
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || -> Result<Option<Track>, String> {
+        match crate::apple_music::get_playing_track_pid().map_err(|e| e.to_string())? {
+            Some(pid) => track_for_pid(&handle, &pid),
+            None => Ok(None),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Applies the stored "watch Music.app's player" preference.
+pub fn load_now_playing_watcher(state: &AppState) {
+    let enabled = state.db.read().ok()
+        .and_then(|db| db.get_setting(WATCHER_KEY).ok().flatten())
+        .as_deref() == Some("true");
+    WATCHER_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn get_now_playing_watcher() -> bool {
+    WATCHER_ENABLED.load(Ordering::Relaxed)
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_now_playing_watcher(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+        .set_setting(WATCHER_KEY, if enabled { "true" } else { "false" })
+        .map_err(|e| e.to_string())?;
+    WATCHER_ENABLED.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
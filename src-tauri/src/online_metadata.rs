@@ -0,0 +1,432 @@
+//! Release metadata lookup (label, genre, year, catalog number) from online databases.
+//! Each service is a `MetadataProvider`; lookups return candidates and nothing is
+//! written until the caller applies the fields it wants with `apply_track_metadata`.
+
+use crate::commands::AppState;
+use crate::db::Database;
+use crate::models::Track;
+use reqwest::Client;
+use std::time::Duration;
+use tauri::State;
+
+const API_KEYS_KEY: &str = "metadata_api_keys";
+
+const MAX_CANDIDATES: usize = 5;
+
+/// MusicBrainz asks clients to stay at one request per second.
+const MUSICBRAINZ_INTERVAL: Duration = Duration::from_secs(1);
+
+/// API credentials, stored in the settings table.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct MetadataApiKeys {
+    /// Personal access token from discogs.com/settings/developers
+    pub discogs_token: Option<String>,
+    /// OAuth bearer token for the Beatport v4 API
+    pub beatport_token: Option<String>,
+    /// Application key from acoustid.org, used by `identify_track`
+    pub acoustid_key: Option<String>,
+}
+
+impl MetadataApiKeys {
+    pub fn load(db: &Database) -> Self {
+        db.get_setting(API_KEYS_KEY).ok().flatten()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    MusicBrainz,
+    Discogs,
+    Beatport,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MetadataCandidate {
+    pub provider: ProviderKind,
+    pub artist: String,
+    pub title: String,
+    pub release: Option<String>,
+    pub label: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<i32>,
+    pub catalog_number: Option<String>,
+    /// Page on the provider's site, for the user to check the match
+    pub url: Option<String>,
+}
+
+pub struct TrackQuery {
+    pub artist: String,
+    pub title: String,
+}
+
+pub(crate) trait MetadataProvider {
+    async fn search(&self, client: &Client, query: &TrackQuery) -> Result<Vec<MetadataCandidate>, String>;
+}
+
+/// First four digits of a date like "2019-05-03" or "2019".
+fn parse_year(date: &str) -> Option<i32> {
+    date.get(..4)?.parse().ok()
+}
+
+pub(crate) fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+pub(crate) async fn get_json<T: serde::de::DeserializeOwned>(request: reqwest::RequestBuilder, service: &str) -> Result<T, String> {
+    request.send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("{} request failed: {}", service, e))?
+        .json()
+        .await
+        .map_err(|e| format!("Unexpected {} response: {}", service, e))
+}
+
+// --- MusicBrainz ---
+
+pub struct MusicBrainz;
+
+#[derive(serde::Deserialize)]
+struct MbSearch {
+    #[serde(default)]
+    recordings: Vec<MbRecording>,
+}
+
+#[derive(serde::Deserialize)]
+struct MbRecording {
+    id: String,
+    title: String,
+    #[serde(default, rename = "artist-credit")]
+    artist_credit: Vec<MbArtistCredit>,
+    #[serde(default)]
+    releases: Vec<MbRelease>,
+    #[serde(default)]
+    tags: Vec<MbTag>,
+}
+
+#[derive(serde::Deserialize)]
+struct MbArtistCredit {
+    name: String,
+    #[serde(default)]
+    joinphrase: String,
+}
+
+#[derive(serde::Deserialize)]
+struct MbRelease {
+    id: String,
+    title: String,
+    date: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct MbTag {
+    name: String,
+    #[serde(default)]
+    count: i64,
+}
+
+#[derive(serde::Deserialize)]
+struct MbReleaseDetail {
+    #[serde(default, rename = "label-info")]
+    label_info: Vec<MbLabelInfo>,
+}
+
+#[derive(serde::Deserialize)]
+struct MbLabelInfo {
+    #[serde(rename = "catalog-number")]
+    catalog_number: Option<String>,
+    label: Option<MbLabel>,
+}
+
+#[derive(serde::Deserialize)]
+struct MbLabel {
+    name: String,
+}
+
+/// Lucene special characters would break the fielded query.
+fn lucene_escape(value: &str) -> String {
+    value.chars()
+        .map(|c| if "+-&|!(){}[]^\"~*?:\\/".contains(c) { ' ' } else { c })
+        .collect()
+}
+
+impl MetadataProvider for MusicBrainz {
+    async fn search(&self, client: &Client, query: &TrackQuery) -> Result<Vec<MetadataCandidate>, String> {
+        let lucene = format!(
+            "recording:\"{}\" AND artist:\"{}\"",
+            lucene_escape(&query.title),
+            lucene_escape(&query.artist)
+        );
+        let search: MbSearch = get_json(
+            client.get("https://musicbrainz.org/ws/2/recording")
+                .query(&[("query", lucene.as_str()), ("fmt", "json"), ("limit", &MAX_CANDIDATES.to_string())]),
+            "MusicBrainz",
+        ).await?;
+
+        let mut candidates = Vec::new();
+        for recording in search.recordings {
+            let artist: String = recording.artist_credit.iter()
+                .map(|c| format!("{}{}", c.name, c.joinphrase))
+                .collect();
+            let genre = recording.tags.iter().max_by_key(|t| t.count).map(|t| t.name.clone());
+            let release = recording.releases.first();
+
+            // Label and catalog number are only on the release itself
+            let (label, catalog_number) = match release {
+                Some(r) => {
+                    tokio::time::sleep(MUSICBRAINZ_INTERVAL).await;
+                    let detail: Option<MbReleaseDetail> = get_json(
+                        client.get(format!("https://musicbrainz.org/ws/2/release/{}", r.id))
+                            .query(&[("inc", "labels"), ("fmt", "json")]),
+                        "MusicBrainz",
+                    ).await.ok();
+                    let info = detail.and_then(|d| d.label_info.into_iter().next());
+                    (
+                        info.as_ref().and_then(|i| i.label.as_ref().map(|l| l.name.clone())),
+                        info.and_then(|i| i.catalog_number),
+                    )
+                }
+                None => (None, None),
+            };
+
+            candidates.push(MetadataCandidate {
+                provider: ProviderKind::MusicBrainz,
+                artist,
+                title: recording.title,
+                release: release.map(|r| r.title.clone()),
+                label: non_empty(label),
+                genre: non_empty(genre),
+                year: release.and_then(|r| r.date.as_deref()).and_then(parse_year),
+                catalog_number: non_empty(catalog_number),
+                url: Some(format!("https://musicbrainz.org/recording/{}", recording.id)),
+            });
+        }
+        Ok(candidates)
+    }
+}
+
+// --- Discogs ---
+
+pub struct Discogs {
+    token: String,
+}
+
+#[derive(serde::Deserialize)]
+struct DiscogsSearch {
+    #[serde(default)]
+    results: Vec<DiscogsResult>,
+}
+
+#[derive(serde::Deserialize)]
+struct DiscogsResult {
+    /// "Artist - Release"
+    title: String,
+    year: Option<String>,
+    #[serde(default)]
+    label: Vec<String>,
+    #[serde(default)]
+    genre: Vec<String>,
+    #[serde(default)]
+    style: Vec<String>,
+    catno: Option<String>,
+    uri: Option<String>,
+}
+
+impl MetadataProvider for Discogs {
+    async fn search(&self, client: &Client, query: &TrackQuery) -> Result<Vec<MetadataCandidate>, String> {
+        let search: DiscogsSearch = get_json(
+            client.get("https://api.discogs.com/database/search")
+                .header("Authorization", format!("Discogs token={}", self.token))
+                .query(&[
+                    ("artist", query.artist.as_str()),
+                    ("track", query.title.as_str()),
+                    ("type", "release"),
+                    ("per_page", &MAX_CANDIDATES.to_string()),
+                ]),
+            "Discogs",
+        ).await?;
+
+        Ok(search.results.into_iter().map(|r| {
+            let (artist, release) = match r.title.split_once(" - ") {
+                Some((a, t)) => (a.to_string(), t.to_string()),
+                None => (query.artist.clone(), r.title.clone()),
+            };
+            MetadataCandidate {
+                provider: ProviderKind::Discogs,
+                artist,
+                title: query.title.clone(),
+                release: Some(release),
+                label: non_empty(r.label.into_iter().next()),
+                // Styles ("Deep House") are what DJs call genres; Discogs genres are broad ("Electronic")
+                genre: non_empty(r.style.into_iter().next().or_else(|| r.genre.into_iter().next())),
+                year: r.year.as_deref().and_then(parse_year),
+                catalog_number: non_empty(r.catno).filter(|c| c != "none"),
+                url: r.uri.map(|u| format!("https://www.discogs.com{}", u)),
+            }
+        }).collect())
+    }
+}
+
+// --- Beatport ---
+
+pub struct Beatport {
+    token: String,
+}
+
+#[derive(serde::Deserialize)]
+struct BeatportSearch {
+    #[serde(default)]
+    tracks: Vec<BeatportTrack>,
+}
+
+#[derive(serde::Deserialize)]
+struct BeatportTrack {
+    id: i64,
+    name: String,
+    mix_name: Option<String>,
+    #[serde(default)]
+    artists: Vec<BeatportNamed>,
+    genre: Option<BeatportNamed>,
+    publish_date: Option<String>,
+    release: Option<BeatportRelease>,
+}
+
+#[derive(serde::Deserialize)]
+struct BeatportNamed {
+    name: String,
+}
+
+#[derive(serde::Deserialize)]
+struct BeatportRelease {
+    name: String,
+    label: Option<BeatportNamed>,
+    catalog_number: Option<String>,
+}
+
+impl MetadataProvider for Beatport {
+    async fn search(&self, client: &Client, query: &TrackQuery) -> Result<Vec<MetadataCandidate>, String> {
+        let search: BeatportSearch = get_json(
+            client.get("https://api.beatport.com/v4/catalog/search/")
+                .bearer_auth(&self.token)
+                .query(&[
+                    ("q", format!("{} {}", query.artist, query.title).as_str()),
+                    ("type", "tracks"),
+                    ("per_page", &MAX_CANDIDATES.to_string()),
+                ]),
+            "Beatport",
+        ).await?;
+
+        Ok(search.tracks.into_iter().map(|t| {
+            let title = match t.mix_name.as_deref().filter(|m| !m.is_empty()) {
+                Some(mix) => format!("{} ({})", t.name, mix),
+                None => t.name,
+            };
+            MetadataCandidate {
+                provider: ProviderKind::Beatport,
+                artist: t.artists.iter().map(|a| a.name.as_str()).collect::<Vec<_>>().join(", "),
+                title,
+                release: t.release.as_ref().map(|r| r.name.clone()),
+                label: non_empty(t.release.as_ref().and_then(|r| r.label.as_ref().map(|l| l.name.clone()))),
+                genre: non_empty(t.genre.map(|g| g.name)),
+                year: t.publish_date.as_deref().and_then(parse_year),
+                catalog_number: non_empty(t.release.and_then(|r| r.catalog_number)),
+                url: Some(format!("https://www.beatport.com/track/-/{}", t.id)),
+            }
+        }).collect())
+    }
+}
+
+/// Searches the provider of `kind`, with the API key it needs from `keys`.
+async fn search_provider(
+    kind: ProviderKind,
+    keys: &MetadataApiKeys,
+    client: &Client,
+    query: &TrackQuery,
+) -> Result<Vec<MetadataCandidate>, String> {
+    let require = |token: &Option<String>, name: &str| {
+        non_empty(token.clone()).ok_or_else(|| format!("No {} API key configured", name))
+    };
+    match kind {
+        ProviderKind::MusicBrainz => MusicBrainz.search(client, query).await,
+        ProviderKind::Discogs => Discogs { token: require(&keys.discogs_token, "Discogs")? }.search(client, query).await,
+        ProviderKind::Beatport => Beatport { token: require(&keys.beatport_token, "Beatport")? }.search(client, query).await,
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_metadata_api_keys(state: State<'_, AppState>) -> Result<MetadataApiKeys, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    Ok(MetadataApiKeys::load(&db))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn set_metadata_api_keys(keys: MetadataApiKeys, state: State<'_, AppState>) -> Result<(), String> {
+    let json = serde_json::to_string(&keys).map_err(|e| e.to_string())?;
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    db.set_setting(API_KEYS_KEY, &json).map_err(|e| e.to_string())
+}
+
+/// Searches `provider` by the track's artist and title and returns candidate releases.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn lookup_track_metadata(
+    track_id: i64,
+    provider: ProviderKind,
+    state: State<'_, AppState>,
+) -> Result<Vec<MetadataCandidate>, String> {
+    let (track, keys): (Track, MetadataApiKeys) = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let track = db.get_track(track_id).map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Track {} not found", track_id))?;
+        (track, MetadataApiKeys::load(&db))
+    };
+    let query = TrackQuery {
+        artist: track.artist.unwrap_or_default(),
+        title: track.title.ok_or_else(|| "Track has no title to search for".to_string())?,
+    };
+    search_provider(provider, &keys, &crate::artwork::http_client()?, &query).await
+}
+
+/// Writes the chosen `fields` ("label", "genre", "year", "catalog_number") of a
+/// candidate to the track's file; other fields are left as they are.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn apply_track_metadata(
+    track_id: i64,
+    candidate: MetadataCandidate,
+    fields: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    if let Some(unknown) = fields.iter().find(|f| !["label", "genre", "year", "catalog_number"].contains(&f.as_str())) {
+        return Err(format!("Unknown metadata field: {}", unknown));
+    }
+    let wants = |field: &str| fields.iter().any(|f| f == field);
+
+    let file_path = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        db.get_track(track_id).map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Track {} not found", track_id))?
+            .file_path
+    };
+
+    let label = candidate.label.filter(|_| wants("label"));
+    let genre = candidate.genre.filter(|_| wants("genre"));
+    let year = candidate.year.filter(|_| wants("year"));
+    let catalog_number = candidate.catalog_number.filter(|_| wants("catalog_number"));
+    let path = file_path.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::metadata::write_release_info(&path, label.as_deref(), genre.as_deref(), year, catalog_number.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+
+    tracing::info!("Applied {} from {:?} to {}", fields.join(", "), candidate.provider, file_path);
+    Ok(())
+}
@@ -0,0 +1,166 @@
+//! User-defined path rewrite rules (e.g. `/Volumes/OldSSD/Music → /Volumes/NewSSD/Music`)
+//! applied when a track's stored path no longer exists.
+
+use crate::commands::AppState;
+use crate::db::Database;
+use crate::library_parser::itunes_path_variants;
+use crate::models::Track;
+use std::path::Path;
+use tauri::State;
+
+const PATH_RULES_KEY: &str = "path_rules";
+const ITUNES_HEURISTICS_KEY: &str = "itunes_path_heuristics";
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PathRule {
+    pub from: String,
+    pub to: String,
+}
+
+impl PathRule {
+    /// Rewrites `path` if it lies under `from`, matching whole path components.
+    fn rewrite(&self, path: &str) -> Option<String> {
+        let rest = path.strip_prefix(&self.from)?;
+        if !rest.is_empty() && !rest.starts_with('/') {
+            return None;
+        }
+        Some(format!("{}{}", self.to, rest))
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct PathRuleSettings {
+    pub rules: Vec<PathRule>,
+    /// Whether the built-in iTunes folder-nesting variants are tried after the user rules
+    pub itunes_heuristics: bool,
+}
+
+/// The configured rules, loaded once and reused for a batch of lookups.
+pub struct PathResolver {
+    rules: Vec<PathRule>,
+    itunes_heuristics: bool,
+}
+
+impl PathResolver {
+    pub fn load(db: &Database) -> Self {
+        let rules = db.get_setting(PATH_RULES_KEY).ok().flatten()
+            .and_then(|json| serde_json::from_str::<Vec<PathRule>>(&json).ok())
+            .unwrap_or_default();
+        let itunes_heuristics = db.get_setting(ITUNES_HEURISTICS_KEY).ok().flatten().as_deref() != Some("false");
+        Self { rules, itunes_heuristics }
+    }
+
+    /// Alternative locations for `path`, user rules first (in order), then the iTunes variants.
+    pub fn candidates(&self, path: &str) -> Vec<String> {
+        let mut out: Vec<String> = self.rules.iter().filter_map(|r| r.rewrite(path)).collect();
+        if self.itunes_heuristics {
+            out.extend(itunes_path_variants(path));
+        }
+        out.retain(|c| c != path);
+        out.dedup();
+        out
+    }
+
+    /// First alternative location that exists on disk.
+    pub fn resolve(&self, path: &str) -> Option<String> {
+        self.candidates(path).into_iter().find(|c| Path::new(c).exists())
+    }
+
+    /// Rewrites the paths of imported tracks whose file is missing but a user rule finds it.
+    /// Returns the number of tracks changed. No-op without rules, so imports stay stat-free.
+    pub fn apply_to_tracks(&self, tracks: &mut [Track]) -> usize {
+        if self.rules.is_empty() {
+            return 0;
+        }
+        let mut changed = 0;
+        for track in tracks.iter_mut() {
+            let rewrites: Vec<String> = self.rules.iter().filter_map(|r| r.rewrite(&track.file_path)).collect();
+            // Only stat files a rule applies to
+            if rewrites.is_empty() || Path::new(&track.file_path).exists() {
+                continue;
+            }
+            if let Some(found) = rewrites.into_iter().find(|c| Path::new(c).exists()) {
+                track.file_path = found;
+                changed += 1;
+            }
+        }
+        changed
+    }
+}
+
+/// Loads the resolver through a reader connection, for callers that have not locked the DB yet.
+pub fn load_resolver(state: &AppState) -> PathResolver {
+    match state.db.read() {
+        Ok(db) => PathResolver::load(&db),
+        Err(_) => PathResolver { rules: Vec::new(), itunes_heuristics: true },
+    }
+}
+
+fn save_rules(state: &AppState, rules: &[PathRule]) -> Result<(), String> {
+    let json = serde_json::to_string(rules).map_err(|e| e.to_string())?;
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    db.set_setting(PATH_RULES_KEY, &json).map_err(|e| e.to_string())
+}
+
+fn current_settings(state: &AppState) -> PathRuleSettings {
+    let resolver = load_resolver(state);
+    PathRuleSettings {
+        rules: resolver.rules,
+        itunes_heuristics: resolver.itunes_heuristics,
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn list_path_rules(state: State<'_, AppState>) -> Result<PathRuleSettings, String> {
+    Ok(current_settings(&state))
+}
+
+/// Adds a rule; rules are tried in the order they were added.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn add_path_rule(state: State<'_, AppState>, from: String, to: String) -> Result<PathRuleSettings, String> {
+    let rule = PathRule {
+        from: from.trim().trim_end_matches('/').to_string(),
+        to: to.trim().trim_end_matches('/').to_string(),
+    };
+    if rule.from.is_empty() || rule.to.is_empty() {
+        return Err("Both path prefixes are required".to_string());
+    }
+    if rule.from == rule.to {
+        return Err("A rule must change the path".to_string());
+    }
+
+    let mut rules = load_resolver(&state).rules;
+    if !rules.contains(&rule) {
+        let msg = format!("Added path rule: {} → {}", rule.from, rule.to);
+        rules.push(rule);
+        save_rules(&state, &rules)?;
+        tracing::info!("{}", msg);
+    }
+    Ok(current_settings(&state))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn remove_path_rule(state: State<'_, AppState>, from: String, to: String) -> Result<PathRuleSettings, String> {
+    let mut rules = load_resolver(&state).rules;
+    let before = rules.len();
+    rules.retain(|r| !(r.from == from && r.to == to));
+    if rules.len() != before {
+        save_rules(&state, &rules)?;
+        tracing::info!("Removed path rule: {} → {}", from, to);
+    }
+    Ok(current_settings(&state))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn set_itunes_path_heuristics(state: State<'_, AppState>, enabled: bool) -> Result<PathRuleSettings, String> {
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        db.set_setting(ITUNES_HEURISTICS_KEY, if enabled { "true" } else { "false" })
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(current_settings(&state))
+}
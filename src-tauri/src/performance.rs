@@ -0,0 +1,151 @@
+//! Per-command performance numbers (calls, failures, durations), collected from the
+//! `tracing` spans every command is instrumented with. Kept in memory for the session
+//! so a "syncs got slow" report can come with concrete figures.
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Durations kept per command for percentiles
+const RECENT_SAMPLES: usize = 200;
+
+#[derive(Default)]
+struct CommandStats {
+    calls: u64,
+    failures: u64,
+    total_ms: f64,
+    max_ms: f64,
+    recent_ms: VecDeque<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandPerformance {
+    pub command: String,
+    pub calls: u64,
+    pub failures: u64,
+    /// failures / calls, 0–1
+    pub failure_rate: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    /// Over the last 200 calls
+    pub p95_ms: f64,
+}
+
+fn stats() -> &'static Mutex<HashMap<String, CommandStats>> {
+    static STATS: OnceLock<Mutex<HashMap<String, CommandStats>>> = OnceLock::new();
+    STATS.get_or_init(Default::default)
+}
+
+/// Span extension: when the command started and whether it returned an error.
+struct CommandTiming {
+    started: Instant,
+    failed: bool,
+}
+
+/// Sees the `error` field `#[instrument(err)]` records when a command returns `Err`.
+struct ErrorField(bool);
+
+impl Visit for ErrorField {
+    fn record_debug(&mut self, field: &Field, _value: &dyn std::fmt::Debug) {
+        if field.name() == "error" {
+            self.0 = true;
+        }
+    }
+}
+
+/// Records every span of this crate regardless of the log filter, which only governs
+/// what gets written to the log.
+pub struct PerformanceLayer;
+
+/// Per-layer filter for [`PerformanceLayer`]: only TagDeck's spans and error events, so it
+/// doesn't switch on every debug event in the dependencies.
+pub fn is_tracked(meta: &tracing::Metadata<'_>) -> bool {
+    meta.target().starts_with(crate::logging::CRATE_TARGET)
+        && (meta.is_span() || meta.fields().field("error").is_some())
+}
+
+impl<S> Layer<S> for PerformanceLayer
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(CommandTiming { started: Instant::now(), failed: false });
+        }
+    }
+
+    fn on_event(&self, event: &tracing::Event<'_>, ctx: Context<'_, S>) {
+        let mut error = ErrorField(false);
+        event.record(&mut error);
+        if !error.0 {
+            return;
+        }
+        if let Some(span) = ctx.event_span(event) {
+            if let Some(timing) = span.extensions_mut().get_mut::<CommandTiming>() {
+                timing.failed = true;
+            }
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let Some((elapsed_ms, failed)) = span.extensions().get::<CommandTiming>()
+            .map(|t| (t.started.elapsed().as_secs_f64() * 1000.0, t.failed))
+        else {
+            return;
+        };
+        let Ok(mut stats) = stats().lock() else { return };
+        let entry = stats.entry(span.name().to_string()).or_default();
+        entry.calls += 1;
+        if failed {
+            entry.failures += 1;
+        }
+        entry.total_ms += elapsed_ms;
+        entry.max_ms = entry.max_ms.max(elapsed_ms);
+        entry.recent_ms.push_back(elapsed_ms);
+        if entry.recent_ms.len() > RECENT_SAMPLES {
+            entry.recent_ms.pop_front();
+        }
+    }
+}
+
+fn percentile(samples: &VecDeque<f64>, p: f64) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let mut sorted: Vec<f64> = samples.iter().copied().collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let index = ((sorted.len() as f64 * p).ceil() as usize).clamp(1, sorted.len()) - 1;
+    sorted[index]
+}
+
+/// Per-command numbers since startup, slowest (by total time) first.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn get_performance_stats() -> Vec<CommandPerformance> {
+    let stats = stats().lock().unwrap_or_else(|e| e.into_inner());
+    let mut result: Vec<CommandPerformance> = stats.iter()
+        .map(|(command, s)| CommandPerformance {
+            command: command.clone(),
+            calls: s.calls,
+            failures: s.failures,
+            failure_rate: if s.calls > 0 { s.failures as f64 / s.calls as f64 } else { 0.0 },
+            avg_ms: if s.calls > 0 { s.total_ms / s.calls as f64 } else { 0.0 },
+            max_ms: s.max_ms,
+            p95_ms: percentile(&s.recent_ms, 0.95),
+        })
+        .collect();
+    result.sort_by(|a, b| (b.avg_ms * b.calls as f64).total_cmp(&(a.avg_ms * a.calls as f64)));
+    result
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn reset_performance_stats() {
+    stats().lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
@@ -0,0 +1,324 @@
+//! Playlist organization: folders, the nested playlist tree, duplication, merging and sorting.
+
+use crate::apple_music::{create_playlist, create_playlist_folder as create_music_folder, AppleUpdate};
+use crate::commands::{ensure_editable_playlist, playlist_track_refs, AppState};
+use crate::db::{Database, KEY_LETTER_SQL, KEY_NUMBER_SQL};
+use crate::models::Playlist;
+use crate::undo::{Action, TrackRef};
+use std::collections::HashMap;
+use tauri::{AppHandle, State};
+
+/// A playlist or folder with its children, folders first, then by name.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PlaylistNode {
+    #[serde(flatten)]
+    pub playlist: Playlist,
+    pub children: Vec<PlaylistNode>,
+}
+
+/// Nests playlists under their parent folders. Playlists whose parent is unknown
+/// (e.g. a folder that was not imported) are placed at the top level.
+fn build_tree(playlists: Vec<Playlist>) -> Vec<PlaylistNode> {
+    let known: std::collections::HashSet<String> = playlists.iter()
+        .filter(|p| p.is_folder)
+        .map(|p| p.persistent_id.clone())
+        .collect();
+
+    let mut by_parent: HashMap<Option<String>, Vec<Playlist>> = HashMap::new();
+    for playlist in playlists {
+        let parent = playlist.parent_persistent_id.clone().filter(|p| known.contains(p));
+        by_parent.entry(parent).or_default().push(playlist);
+    }
+
+    fn attach(parent: Option<String>, by_parent: &mut HashMap<Option<String>, Vec<Playlist>>) -> Vec<PlaylistNode> {
+        let children = by_parent.remove(&parent).unwrap_or_default();
+        children.into_iter().map(|playlist| {
+            let children = if playlist.is_folder {
+                attach(Some(playlist.persistent_id.clone()), by_parent)
+            } else {
+                Vec::new()
+            };
+            PlaylistNode { playlist, children }
+        }).collect()
+    }
+
+    // get_playlists already orders folders first, then by name
+    attach(None, &mut by_parent)
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_playlist_tree(state: State<'_, AppState>) -> Result<Vec<PlaylistNode>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let playlists = db.get_playlists().map_err(|e| e.to_string())?;
+    Ok(build_tree(playlists))
+}
+
+fn find_playlist(db: &Database, playlist_id: i64) -> Result<Playlist, String> {
+    db.get_playlists().map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|p| p.id == playlist_id)
+        .ok_or_else(|| format!("Playlist {} not found", playlist_id))
+}
+
+/// Looks up a folder's persistent ID, rejecting playlists that are not folders.
+fn folder_pid(state: &AppState, folder_id: i64) -> Result<String, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let folder = find_playlist(&db, folder_id)?;
+    if !folder.is_folder {
+        return Err(format!("\"{}\" is not a folder", folder.name));
+    }
+    Ok(folder.persistent_id)
+}
+
+/// Creates a folder in Music.app and locally, inside `parent_id` or at the top level.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn create_playlist_folder(
+    name: String,
+    parent_id: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Playlist, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Folder name cannot be empty".to_string());
+    }
+    let parent_pid = parent_id.map(|id| folder_pid(&state, id)).transpose()?;
+
+    let persistent_id = create_music_folder(&name, parent_pid.as_deref()).map_err(|e| e.to_string())?;
+
+    let mut folder = Playlist {
+        id: 0,
+        persistent_id: persistent_id.clone(),
+        parent_persistent_id: parent_pid,
+        name: name.clone(),
+        is_folder: true,
+        track_ids: None,
+        is_smart: false,
+    };
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        db.insert_playlist(&folder).map_err(|e| e.to_string())?;
+        folder.id = db.get_playlist_id_by_persistent_id(&persistent_id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Created folder missing from DB".to_string())?;
+    }
+
+    tracing::info!("Created playlist folder \"{}\"", name);
+    Ok(folder)
+}
+
+/// Moves a playlist or folder into `folder_id`, or to the top level when `None`.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn move_playlist(
+    playlist_id: i64,
+    folder_id: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let parent_pid = folder_id.map(|id| folder_pid(&state, id)).transpose()?;
+
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    let playlists = db.get_playlists().map_err(|e| e.to_string())?;
+    let playlist = playlists.iter().find(|p| p.id == playlist_id)
+        .ok_or_else(|| format!("Playlist {} not found", playlist_id))?;
+
+    // A folder cannot move into itself or one of its own subfolders
+    let mut ancestor = parent_pid.clone();
+    while let Some(pid) = ancestor {
+        if pid == playlist.persistent_id {
+            return Err("A folder cannot be moved into itself".to_string());
+        }
+        ancestor = playlists.iter().find(|p| p.persistent_id == pid)
+            .and_then(|p| p.parent_persistent_id.clone());
+    }
+
+    if playlist.parent_persistent_id == parent_pid {
+        return Ok(());
+    }
+
+    db.set_playlist_parent(playlist_id, parent_pid.as_deref()).map_err(|e| e.to_string())?;
+    state.apple_queue.enqueue(AppleUpdate::MovePlaylist {
+        playlist_pid: playlist.persistent_id.clone(),
+        parent_pid,
+    });
+    Ok(())
+}
+
+/// Copies a playlist (track order included) into a new regular playlist named `new_name`,
+/// placed in the same folder. Smart playlists are copied as a static snapshot of their tracks.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn duplicate_playlist(
+    app: AppHandle,
+    id: i64,
+    new_name: String,
+    state: State<'_, AppState>,
+) -> Result<Playlist, String> {
+    let new_name = new_name.trim().to_string();
+    if new_name.is_empty() {
+        return Err("Playlist name cannot be empty".to_string());
+    }
+
+    let (source, tracks) = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let source = find_playlist(&db, id)?;
+        if source.is_folder {
+            return Err(format!("\"{}\" is a folder, not a playlist", source.name));
+        }
+        let tracks = playlist_track_refs(&db, id)?;
+        (source, tracks)
+    };
+
+    // 1. Create the playlist in Music.app (we need its persistent ID)
+    let playlist_pid = create_playlist(&new_name).map_err(|e| e.to_string())?;
+
+    // 2. Local DB
+    let mut playlist = Playlist {
+        id: 0,
+        persistent_id: playlist_pid.clone(),
+        parent_persistent_id: source.parent_persistent_id.clone(),
+        name: new_name.clone(),
+        is_folder: false,
+        is_smart: false,
+        track_ids: Some(tracks.iter().map(|t| t.persistent_id.clone()).collect()),
+    };
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        db.insert_playlist(&playlist).map_err(|e| e.to_string())?;
+        playlist.id = db.get_playlist_id_by_persistent_id(&playlist_pid)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Duplicated playlist missing from DB".to_string())?;
+    }
+
+    // 3. Music.app: move next to the original, then fill in order
+    if let Some(parent_pid) = &source.parent_persistent_id {
+        state.apple_queue.enqueue(AppleUpdate::MovePlaylist {
+            playlist_pid: playlist_pid.clone(),
+            parent_pid: Some(parent_pid.clone()),
+        });
+    }
+    state.apple_queue.enqueue(AppleUpdate::playlist_adds(
+        tracks.iter().map(|t| t.persistent_id.as_str()),
+        &playlist_pid,
+    ));
+
+    tracing::info!("Duplicated playlist \"{}\" as \"{}\" ({} tracks)", source.name, new_name, tracks.len());
+    Ok(playlist)
+}
+
+/// Appends the tracks of the playlists in `ids` to `target`, in the order given, skipping
+/// tracks the target already holds. The source playlists are left untouched. Undoable.
+/// Returns the number of tracks added.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn merge_playlists(
+    ids: Vec<i64>,
+    target: i64,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let (target_playlist, added) = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let target_playlist = find_playlist(&db, target)?;
+        if target_playlist.is_folder {
+            return Err(format!("\"{}\" is a folder, not a playlist", target_playlist.name));
+        }
+        ensure_editable_playlist(&db, target)?;
+
+        let mut seen: std::collections::HashSet<i64> = db.get_playlist_track_ids(target)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .collect();
+        let mut added: Vec<TrackRef> = Vec::new();
+        for source in ids.iter().filter(|id| **id != target) {
+            for track in playlist_track_refs(&db, *source)? {
+                if seen.insert(track.id) {
+                    added.push(track);
+                }
+            }
+        }
+        (target_playlist, added)
+    };
+
+    if added.is_empty() {
+        return Ok(0);
+    }
+
+    {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        for track in &added {
+            db.add_track_to_playlist_db(target, track.id).map_err(|e| e.to_string())?;
+        }
+    }
+    state.apple_queue.enqueue(AppleUpdate::playlist_adds(
+        added.iter().map(|t| t.persistent_id.as_str()),
+        &target_playlist.persistent_id,
+    ));
+
+    let count = added.len();
+    if let Ok(mut stack) = state.undo_stack.lock() {
+        stack.push(Action::AddToPlaylist {
+            playlist_id: target,
+            playlist_persistent_id: target_playlist.persistent_id.clone(),
+            tracks: added,
+        });
+    }
+
+    let sources = ids.iter().filter(|id| **id != target).count();
+    tracing::info!("Merged {} playlists into \"{}\" ({} tracks added)", sources, target_playlist.name, count);
+    Ok(count)
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaylistSortField {
+    Bpm,
+    /// Camelot key from the start of the comment ("8A - ..."), by wheel number then A/B
+    Key,
+    Rating,
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl PlaylistSortField {
+    /// ORDER BY list for `Database::get_playlist_track_ids_ordered`. Tracks without
+    /// a BPM or key always sort last, whatever the direction.
+    fn order_by(self, direction: SortDirection) -> String {
+        let dir = match direction {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        };
+        match self {
+            PlaylistSortField::Bpm => format!("(COALESCE(t.bpm, 0) <= 0), t.bpm {}", dir),
+            PlaylistSortField::Key => format!(
+                "NOT (COALESCE({number}, 0) BETWEEN 1 AND 12 AND COALESCE({letter}, '') IN ('A', 'B')), {number} {dir}, {letter} {dir}",
+                number = KEY_NUMBER_SQL, letter = KEY_LETTER_SQL, dir = dir
+            ),
+            PlaylistSortField::Rating => format!("COALESCE(t.rating, 0) {}", dir),
+        }
+    }
+}
+
+/// Sorts a playlist by BPM, key or rating and pushes the new order to Music.app.
+/// Goes through `reorder_playlist_tracks`, so the previous order can be restored with undo.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn sort_playlist(
+    playlist_id: i64,
+    by: PlaylistSortField,
+    direction: SortDirection,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let ordered = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        ensure_editable_playlist(&db, playlist_id)?;
+        db.get_playlist_track_ids_ordered(playlist_id, &by.order_by(direction))
+            .map_err(|e| e.to_string())?
+    };
+    crate::commands::reorder_playlist_tracks(playlist_id, ordered, state).await
+}
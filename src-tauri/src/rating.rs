@@ -0,0 +1,140 @@
+//! Conversion between stored ratings (0–100, as Music.app keeps them) and the scale
+//! the user picked for display: 5 stars (optionally in half steps) or 100 points.
+
+use crate::apple_music::AppleUpdate;
+use crate::commands::AppState;
+use crate::db::Database;
+use crate::undo::{Action, RatingState};
+use tauri::State;
+
+const RATING_SCALE_KEY: &str = "rating_scale";
+
+/// Stored points per star.
+const POINTS_PER_STAR: f64 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RatingScale {
+    Stars,
+    Points,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct RatingSettings {
+    pub scale: RatingScale,
+    /// Stars only: allow 0.5 steps (stored as multiples of 10)
+    pub half_stars: bool,
+}
+
+impl RatingSettings {
+    pub const DEFAULT: RatingSettings = RatingSettings {
+        scale: RatingScale::Stars,
+        half_stars: false,
+    };
+
+    pub fn load(db: &Database) -> Self {
+        db.get_setting(RATING_SCALE_KEY).ok().flatten()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or(Self::DEFAULT)
+    }
+
+    fn star_step(&self) -> f64 {
+        if self.half_stars { 0.5 } else { 1.0 }
+    }
+
+    /// Display value to the stored 0–100 value, snapping stars to the allowed step.
+    pub fn to_stored(&self, display: f64) -> Result<u32, String> {
+        let max = match self.scale {
+            RatingScale::Stars => 5.0,
+            RatingScale::Points => 100.0,
+        };
+        if !(0.0..=max).contains(&display) {
+            return Err(format!("Rating must be between 0 and {}", max));
+        }
+        let stored = match self.scale {
+            RatingScale::Stars => {
+                let step = self.star_step();
+                (display / step).round() * step * POINTS_PER_STAR
+            }
+            RatingScale::Points => display.round(),
+        };
+        Ok(stored as u32)
+    }
+}
+
+impl Default for RatingSettings {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_rating_settings(state: State<'_, AppState>) -> Result<RatingSettings, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    Ok(RatingSettings::load(&db))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn set_rating_settings(settings: RatingSettings, state: State<'_, AppState>) -> Result<(), String> {
+    let json = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    db.set_setting(RATING_SCALE_KEY, &json).map_err(|e| e.to_string())
+}
+
+/// Sets the same rating on every track in `ids`. `rating` is in the configured display
+/// scale (stars or points). All changes form one undo step. Returns the number of tracks changed.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn batch_update_rating(
+    ids: Vec<i64>,
+    rating: f64,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    let stored = RatingSettings::load(&db).to_stored(rating)?;
+
+    let mut changed = Vec::new();
+    for id in ids {
+        let track = match db.get_track(id).map_err(|e| e.to_string())? {
+            Some(t) => t,
+            None => continue,
+        };
+        let old_rating = track.rating.clamp(0, 100) as u32;
+        if old_rating == stored {
+            continue;
+        }
+
+        db.update_track_rating(id, stored).map_err(|e| e.to_string())?;
+        if !track.is_cloud {
+            if let Err(e) = crate::metadata::mirror_rating(&track.file_path, stored) {
+                tracing::warn!("Failed to write rating to file: {}", e);
+            }
+        }
+        if !track.persistent_id.is_empty() {
+            state.apple_queue.enqueue(AppleUpdate::Rating {
+                persistent_id: track.persistent_id.clone(),
+                rating: stored,
+            });
+        }
+        changed.push(RatingState {
+            id,
+            persistent_id: track.persistent_id,
+            old_rating,
+            new_rating: stored,
+        });
+    }
+    drop(db);
+
+    let count = changed.len();
+    if !changed.is_empty() {
+        if let Ok(mut stack) = state.undo_stack.lock() {
+            stack.push(Action::UpdateRatings { tracks: changed });
+        }
+    }
+
+    tracing::info!("Set rating {} on {} tracks", stored, count);
+    Ok(count)
+}
@@ -0,0 +1,136 @@
+//! Rekordbox collection XML (`DJ_PLAYLISTS`), the format Rekordbox imports through
+//! "Imported Library" and which most other DJ software can read as well.
+
+use crate::models::{CuePoint, CueType, Track};
+use std::collections::HashMap;
+
+/// Rekordbox players offer eight hot cue pads (A–H).
+const MAX_HOT_CUES: usize = 8;
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Rekordbox wants `file://localhost/` URLs with each path segment percent-encoded.
+fn location_url(path: &str) -> String {
+    let encoded: Vec<String> = path.split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect();
+    format!("file://localhost{}", encoded.join("/"))
+}
+
+/// Stored 0–100 rating to Rekordbox's 0/51/102/153/204/255 star steps.
+fn rekordbox_rating(rating: i64) -> i64 {
+    (rating.clamp(0, 100) / 20) * 51
+}
+
+/// "#RRGGBB" to ` Red=".." Green=".." Blue=".."` attributes.
+fn color_attributes(color: Option<&str>) -> String {
+    let rgb = color
+        .and_then(|c| c.strip_prefix('#'))
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            Some((channel(0)?, channel(2)?, channel(4)?))
+        });
+    match rgb {
+        Some((r, g, b)) => format!(" Red=\"{}\" Green=\"{}\" Blue=\"{}\"", r, g, b),
+        None => String::new(),
+    }
+}
+
+/// `POSITION_MARK` elements for a track. Hot cues are numbered by position; memory
+/// cues, loops and hot cues beyond the pads get `Num="-1"`.
+fn position_marks(cues: &[CuePoint]) -> String {
+    let mut out = String::new();
+    let mut hot = 0;
+    for cue in cues {
+        let num = if cue.cue_type == CueType::Hot && hot < MAX_HOT_CUES {
+            hot += 1;
+            (hot - 1) as i64
+        } else {
+            -1
+        };
+        let (kind, end) = match (cue.cue_type, cue.end_secs) {
+            (CueType::Loop, Some(end)) => (4, format!(" End=\"{:.3}\"", end)),
+            _ => (0, String::new()),
+        };
+        out.push_str(&format!(
+            "      <POSITION_MARK Name=\"{}\" Type=\"{}\" Start=\"{:.3}\"{} Num=\"{}\"{}/>\n",
+            xml_escape(&cue.label),
+            kind,
+            cue.position_secs,
+            end,
+            num,
+            color_attributes(cue.color.as_deref()),
+        ));
+    }
+    out
+}
+
+/// Renders a collection plus playlists. `tracks` pairs each track with the path to
+/// reference (e.g. the copy on a USB drive); `cues` holds each track's cue points by
+/// track id; `playlists` are (name, track ids in order).
+pub fn render_xml(tracks: &[(Track, String)], cues: &HashMap<i64, Vec<CuePoint>>, playlists: &[(String, Vec<i64>)]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<DJ_PLAYLISTS Version=\"1.0.0\">\n");
+    out.push_str(&format!(
+        "  <PRODUCT Name=\"TagDeck\" Version=\"{}\" Company=\"\"/>\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+
+    out.push_str(&format!("  <COLLECTION Entries=\"{}\">\n", tracks.len()));
+    for (track, path) in tracks {
+        let (user_comment, _) = crate::comment::split_comment(track.comment_raw.as_deref().unwrap_or(""));
+        out.push_str(&format!(
+            "    <TRACK TrackID=\"{}\" Name=\"{}\" Artist=\"{}\" Album=\"{}\" Kind=\"{}\" Size=\"{}\" TotalTime=\"{}\" BitRate=\"{}\" AverageBpm=\"{:.2}\" Tonality=\"{}\" Rating=\"{}\" Comments=\"{}\" Location=\"{}\"",
+            track.id,
+            xml_escape(track.title.as_deref().unwrap_or("")),
+            xml_escape(track.artist.as_deref().unwrap_or("")),
+            xml_escape(track.album.as_deref().unwrap_or("")),
+            xml_escape(&track.format),
+            track.size_bytes,
+            track.duration_secs.round() as i64,
+            track.bit_rate,
+            track.bpm.max(0.0),
+            xml_escape(&crate::import_export::camelot_key(user_comment)),
+            rekordbox_rating(track.rating),
+            xml_escape(user_comment),
+            xml_escape(&location_url(path)),
+        ));
+        if let Some(color) = track.color_label {
+            out.push_str(&format!(" Colour=\"{}\"", color.rekordbox_colour()));
+        }
+        match cues.get(&track.id).filter(|c| !c.is_empty()) {
+            Some(track_cues) => {
+                out.push_str(">\n");
+                out.push_str(&position_marks(track_cues));
+                out.push_str("    </TRACK>\n");
+            }
+            None => out.push_str("/>\n"),
+        }
+    }
+    out.push_str("  </COLLECTION>\n");
+
+    out.push_str(&format!(
+        "  <PLAYLISTS>\n    <NODE Type=\"0\" Name=\"ROOT\" Count=\"{}\">\n",
+        playlists.len()
+    ));
+    for (name, ids) in playlists {
+        out.push_str(&format!(
+            "      <NODE Name=\"{}\" Type=\"1\" KeyType=\"0\" Entries=\"{}\">\n",
+            xml_escape(name),
+            ids.len()
+        ));
+        for id in ids {
+            out.push_str(&format!("        <TRACK Key=\"{}\"/>\n", id));
+        }
+        out.push_str("      </NODE>\n");
+    }
+    out.push_str("    </NODE>\n  </PLAYLISTS>\n</DJ_PLAYLISTS>\n");
+    out
+}
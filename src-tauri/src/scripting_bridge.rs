@@ -0,0 +1,135 @@
+//! Music.app backend built on ScriptingBridge (through objc2) instead of spawning `osascript`.
+//!
+//! Every `osascript` call pays 100–300 ms for process spawn and script compilation.
+//! ScriptingBridge sends Apple Events in-process, so a batch of thousands of comment
+//! updates costs one event round-trip per track. Enabled with the `scripting-bridge`
+//! cargo feature; anything this backend does not cover falls back to AppleScript.
+
+use crate::apple_music::AppleUpdate;
+use anyhow::{anyhow, Result};
+use objc2::rc::{autoreleasepool, Retained};
+use objc2::runtime::AnyObject;
+use objc2::{class, msg_send};
+use objc2_foundation::NSString;
+
+#[link(name = "ScriptingBridge", kind = "framework")]
+extern "C" {}
+
+const MUSIC_BUNDLE_ID: &str = "com.apple.Music";
+
+/// Handle to the Music.app scripting object plus its main library playlist.
+struct MusicApp {
+    library_tracks: Retained<AnyObject>,
+}
+
+impl MusicApp {
+    /// Connects to a running Music.app. Never launches it.
+    fn connect() -> Result<Self> {
+        unsafe {
+            let bundle_id = NSString::from_str(MUSIC_BUNDLE_ID);
+            let app: Option<Retained<AnyObject>> =
+                msg_send![class!(SBApplication), applicationWithBundleIdentifier: &*bundle_id];
+            let app = app.ok_or_else(|| anyhow!("Music.app is not available via ScriptingBridge"))?;
+
+            let running: bool = msg_send![&*app, isRunning];
+            if !running {
+                return Err(anyhow!("Music.app is not running"));
+            }
+
+            let sources: Retained<AnyObject> = msg_send![&*app, sources];
+            let source: Option<Retained<AnyObject>> = msg_send![&*sources, firstObject];
+            let source = source.ok_or_else(|| anyhow!("Music.app has no library source"))?;
+
+            let playlists: Retained<AnyObject> = msg_send![&*source, libraryPlaylists];
+            let library: Option<Retained<AnyObject>> = msg_send![&*playlists, firstObject];
+            let library = library.ok_or_else(|| anyhow!("Music.app has no library playlist"))?;
+
+            let library_tracks: Retained<AnyObject> = msg_send![&*library, tracks];
+            Ok(Self { library_tracks })
+        }
+    }
+
+    /// Finds a library track by persistent ID.
+    fn track(&self, persistent_id: &str) -> Result<Retained<AnyObject>> {
+        // Persistent IDs are 16 hex digits; anything else would be interpreted by the predicate parser.
+        if persistent_id.is_empty() || !persistent_id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!("Invalid persistent ID: {}", persistent_id));
+        }
+
+        unsafe {
+            let format = NSString::from_str(&format!("persistentID == '{}'", persistent_id));
+            let predicate: Retained<AnyObject> = msg_send![class!(NSPredicate), predicateWithFormat: &*format];
+            let matches: Retained<AnyObject> = msg_send![&*self.library_tracks, filteredArrayUsingPredicate: &*predicate];
+            let first: Option<Retained<AnyObject>> = msg_send![&*matches, firstObject];
+            first.ok_or_else(|| anyhow!("Track {} not found in Music.app", persistent_id))
+        }
+    }
+
+    fn set_string(&self, persistent_id: &str, key: &str, value: &str) -> Result<()> {
+        let track = self.track(persistent_id)?;
+        unsafe {
+            let key = NSString::from_str(key);
+            let value = NSString::from_str(value);
+            let _: () = msg_send![&*track, setValue: &*value, forKey: &*key];
+        }
+        Ok(())
+    }
+
+    fn set_integer(&self, persistent_id: &str, key: &str, value: i64) -> Result<()> {
+        let track = self.track(persistent_id)?;
+        unsafe {
+            let key = NSString::from_str(key);
+            let number: Retained<AnyObject> = msg_send![class!(NSNumber), numberWithLongLong: value];
+            let _: () = msg_send![&*track, setValue: &*number, forKey: &*key];
+        }
+        Ok(())
+    }
+}
+
+/// Applies an update through ScriptingBridge.
+/// Returns `None` for update kinds this backend does not implement, so the caller can fall back
+/// to AppleScript; Music.app is only connected to for the kinds it does implement.
+pub fn apply(update: &AppleUpdate) -> Option<Result<()>> {
+    autoreleasepool(|_| -> Option<Result<()>> {
+        match update {
+            AppleUpdate::Comment { persistent_id, comment } => {
+                Some(MusicApp::connect().and_then(|music| music.set_string(persistent_id, "comment", comment)))
+            }
+            AppleUpdate::Comments { updates } => Some(MusicApp::connect().and_then(|music| {
+                // One failure doesn't stop the rest
+                let failed = updates.iter()
+                    .filter(|(pid, comment)| music.set_string(pid, "comment", comment).is_err())
+                    .count();
+                if failed > 0 {
+                    // Comment writes are idempotent, so the queue can retry the whole batch
+                    return Err(anyhow!("{} of {} comment updates failed", failed, updates.len()));
+                }
+                Ok(())
+            })),
+            AppleUpdate::Rating { persistent_id, rating } => {
+                Some(MusicApp::connect().and_then(|music| music.set_integer(persistent_id, "rating", *rating as i64)))
+            }
+            AppleUpdate::TrackInfo { persistent_id, name, artist, album, bpm } => Some(MusicApp::connect().and_then(|music| {
+                if let Some(n) = name {
+                    music.set_string(persistent_id, "name", n)?;
+                }
+                if let Some(a) = artist {
+                    music.set_string(persistent_id, "artist", a)?;
+                }
+                if let Some(al) = album {
+                    music.set_string(persistent_id, "album", al)?;
+                }
+                if let Some(b) = bpm {
+                    music.set_integer(persistent_id, "bpm", *b)?;
+                }
+                Ok(())
+            })),
+            AppleUpdate::PlayCount { persistent_id, count } => {
+                Some(MusicApp::connect().and_then(|music| music.set_integer(persistent_id, "playedCount", *count)))
+            }
+            // Playlist membership edits use `duplicate`/`delete` commands that ScriptingBridge
+            // exposes differently per OS release; keep them on the AppleScript path.
+            _ => None,
+        }
+    })
+}
@@ -0,0 +1,214 @@
+//! Set-list sessions: a running log of the tracks marked as played during a gig,
+//! exportable as a track list for publishing afterwards.
+
+use crate::commands::AppState;
+use crate::import_export::{camelot_key, csv_escape};
+use crate::models::{Session, Track};
+use tauri::State;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SessionTrack {
+    /// 0-based play order
+    pub position: usize,
+    pub played_at: i64,
+    pub track: Track,
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionExportFormat {
+    /// Numbered "Artist - Title" lines with the time into the set
+    Text,
+    Csv,
+    M3u,
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn load_session_tracks(state: &AppState, session_id: i64) -> Result<(Session, Vec<SessionTrack>), String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let session = db.get_session(session_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Session {} not found", session_id))?;
+    let tracks = db.get_session_tracks(session_id).map_err(|e| e.to_string())?
+        .into_iter()
+        .enumerate()
+        // Tracks deleted from the library since the gig drop out of the list
+        .filter_map(|(position, (track_id, played_at))| {
+            db.get_track(track_id).ok().flatten().map(|track| SessionTrack { position, played_at, track })
+        })
+        .collect();
+    Ok((session, tracks))
+}
+
+/// Starts recording a new session, stopping any session still running.
+/// `name` defaults to the start date and time.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn start_session(
+    name: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Session, String> {
+    let started_at = now();
+    let name = name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| format!("Set {}", chrono::Local::now().format("%Y-%m-%d %H:%M")));
+
+    let session = {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        if let Some(active) = db.get_active_session().map_err(|e| e.to_string())? {
+            db.end_session(active.id, started_at).map_err(|e| e.to_string())?;
+        }
+        let id = db.create_session(&name, started_at).map_err(|e| e.to_string())?;
+        Session { id, name, started_at, ended_at: None }
+    };
+
+    tracing::info!("Started session \"{}\"", session.name);
+    Ok(session)
+}
+
+/// Stops the running session and returns it, or `None` if no session was running.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn stop_session(state: State<'_, AppState>) -> Result<Option<Session>, String> {
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    let mut session = match db.get_active_session().map_err(|e| e.to_string())? {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    let ended_at = now();
+    db.end_session(session.id, ended_at).map_err(|e| e.to_string())?;
+    let count = db.get_session_tracks(session.id).map_err(|e| e.to_string())?.len();
+    drop(db);
+    session.ended_at = Some(ended_at);
+
+    tracing::info!("Stopped session \"{}\" ({} tracks played)", session.name, count);
+    Ok(Some(session))
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_active_session(state: State<'_, AppState>) -> Result<Option<Session>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    db.get_active_session().map_err(|e| e.to_string())
+}
+
+/// All recorded sessions, newest first.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub fn get_sessions(state: State<'_, AppState>) -> Result<Vec<Session>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    db.get_sessions().map_err(|e| e.to_string())
+}
+
+/// Marks a track as played now in the running session.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn append_to_session(track_id: i64, state: State<'_, AppState>) -> Result<SessionTrack, String> {
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    let session = db.get_active_session().map_err(|e| e.to_string())?
+        .ok_or_else(|| "No session is running".to_string())?;
+    let track = db.get_track(track_id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Track {} not found", track_id))?;
+
+    let played_at = now();
+    db.add_session_track(session.id, track_id, played_at).map_err(|e| e.to_string())?;
+    let position = db.get_session_tracks(session.id).map_err(|e| e.to_string())?.len() - 1;
+    Ok(SessionTrack { position, played_at, track })
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_session_tracks(session_id: i64, state: State<'_, AppState>) -> Result<Vec<SessionTrack>, String> {
+    load_session_tracks(&state, session_id).map(|(_, tracks)| tracks)
+}
+
+fn artist_title(track: &Track) -> String {
+    format!(
+        "{} - {}",
+        track.artist.as_deref().unwrap_or("Unknown Artist"),
+        track.title.as_deref().unwrap_or("Untitled"),
+    )
+}
+
+/// h:mm:ss (or m:ss under an hour) since the session started.
+fn elapsed(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if h > 0 { format!("{}:{:02}:{:02}", h, m, s) } else { format!("{}:{:02}", m, s) }
+}
+
+fn render_text(session: &Session, tracks: &[SessionTrack]) -> String {
+    let mut out = format!("{}\n\n", session.name);
+    for entry in tracks {
+        out.push_str(&format!(
+            "{:02}. [{}] {}\n",
+            entry.position + 1,
+            elapsed(entry.played_at - session.started_at),
+            artist_title(&entry.track),
+        ));
+    }
+    out
+}
+
+fn render_csv(tracks: &[SessionTrack]) -> String {
+    let mut out = String::from("position,played_at,artist,title,album,bpm,key,file_path\n");
+    for entry in tracks {
+        let track = &entry.track;
+        let (user_comment, _) = crate::comment::split_comment(track.comment_raw.as_deref().unwrap_or(""));
+        let played_at = chrono::DateTime::from_timestamp(entry.played_at, 0)
+            .map(|d| d.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        let fields = [
+            (entry.position + 1).to_string(),
+            played_at,
+            track.artist.clone().unwrap_or_default(),
+            track.title.clone().unwrap_or_default(),
+            track.album.clone().unwrap_or_default(),
+            if track.bpm > 0.0 { crate::metadata::format_bpm(track.bpm) } else { String::new() },
+            camelot_key(user_comment),
+            track.file_path.clone(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_escape(f)).collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+fn render_m3u(session: &Session, tracks: &[SessionTrack]) -> String {
+    let mut out = format!("#EXTM3U\n#PLAYLIST:{}\n", session.name);
+    for entry in tracks {
+        out.push_str(&format!(
+            "#EXTINF:{},{}\n{}\n",
+            entry.track.duration_secs.round() as i64,
+            artist_title(&entry.track),
+            entry.track.file_path,
+        ));
+    }
+    out
+}
+
+/// Writes a session's track list to `path` as plain text, CSV or M3U.
+/// Returns the number of tracks written.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn export_session(
+    session_id: i64,
+    format: SessionExportFormat,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let (session, tracks) = load_session_tracks(&state, session_id)?;
+    let content = match format {
+        SessionExportFormat::Text => render_text(&session, &tracks),
+        SessionExportFormat::Csv => render_csv(&tracks),
+        SessionExportFormat::M3u => render_m3u(&session, &tracks),
+    };
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write session export: {}", e))?;
+
+    tracing::info!("Exported session \"{}\" ({} tracks) to {}", session.name, tracks.len(), path);
+    Ok(tracks.len())
+}
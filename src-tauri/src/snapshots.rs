@@ -0,0 +1,302 @@
+//! Point-in-time library snapshots: each track's tags and rating plus every user
+//! playlist's contents, stored compactly as JSON so two dates can be compared long
+//! after the undo history is gone. Tags and ratings can be restored from a snapshot;
+//! playlists are only compared.
+
+use crate::commands::AppState;
+use crate::db::Database;
+use crate::models::LibrarySnapshot;
+use crate::undo::{Action, RatingState};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use tauri::State;
+
+/// What a snapshot stores, keyed by persistent ID.
+#[derive(Default, Serialize, Deserialize)]
+struct SnapshotData {
+    /// persistent ID -> (tags, rating)
+    tracks: BTreeMap<String, (Vec<String>, i64)>,
+    /// persistent ID -> (name, track persistent IDs in order)
+    playlists: BTreeMap<String, (String, Vec<String>)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackChange {
+    pub persistent_id: String,
+    /// "Artist - Title" from the current library, or the persistent ID if the track is gone
+    pub name: String,
+    pub tags_added: Vec<String>,
+    pub tags_removed: Vec<String>,
+    /// `None` when the track is not in that snapshot
+    pub rating_before: Option<i64>,
+    pub rating_after: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaylistChange {
+    pub persistent_id: String,
+    pub name: String,
+    /// Previous name, if the playlist was renamed
+    pub renamed_from: Option<String>,
+    pub created: bool,
+    pub deleted: bool,
+    pub tracks_added: Vec<String>,
+    pub tracks_removed: Vec<String>,
+    /// Same tracks, different order
+    pub reordered: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotDiff {
+    pub from: LibrarySnapshot,
+    /// `id` 0 stands for the library as it is now
+    pub to: LibrarySnapshot,
+    pub tracks: Vec<TrackChange>,
+    pub playlists: Vec<PlaylistChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotRestore {
+    pub tags_restored: usize,
+    pub ratings_restored: usize,
+}
+
+fn capture(db: &Database) -> Result<SnapshotData, String> {
+    let mut data = SnapshotData::default();
+    for track in db.get_all_tracks().map_err(|e| e.to_string())? {
+        let comment = track.comment_raw.unwrap_or_default();
+        let (_, tags) = crate::comment::split_comment(&comment);
+        data.tracks.insert(track.persistent_id, (tags, track.rating));
+    }
+    // Folders have no tracks and smart playlists are Music.app's business
+    db.for_each_playlist_snapshot(|pid, row| {
+        if !row.is_folder && !row.is_smart {
+            data.playlists.insert(pid, (row.name, row.track_ids));
+        }
+        Ok(())
+    }).map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+fn load(db: &Database, id: i64) -> Result<(LibrarySnapshot, SnapshotData), String> {
+    let (info, json) = db.get_library_snapshot(id).map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Snapshot {} not found", id))?;
+    let data = serde_json::from_str(&json).map_err(|e| format!("Snapshot {} is corrupt: {}", id, e))?;
+    Ok((info, data))
+}
+
+/// Items of `b` not in `a`, case-insensitively for tags, keeping `b`'s order.
+fn added(a: &[String], b: &[String]) -> Vec<String> {
+    let existing: HashSet<String> = a.iter().map(|s| s.to_lowercase()).collect();
+    b.iter().filter(|s| !existing.contains(&s.to_lowercase())).cloned().collect()
+}
+
+fn describe_track(db: &Database, pid: &str) -> String {
+    db.get_track_id_by_persistent_id(pid).ok().flatten()
+        .and_then(|id| db.get_track(id).ok().flatten())
+        .map(|t| format!(
+            "{} - {}",
+            t.artist.as_deref().unwrap_or("Unknown Artist"),
+            t.title.as_deref().unwrap_or("Untitled")
+        ))
+        .unwrap_or_else(|| pid.to_string())
+}
+
+fn diff(db: &Database, a: &SnapshotData, b: &SnapshotData) -> (Vec<TrackChange>, Vec<PlaylistChange>) {
+    let empty = (Vec::new(), 0);
+    let track_pids: BTreeSet<&String> = a.tracks.keys().chain(b.tracks.keys()).collect();
+    let mut tracks = Vec::new();
+    for pid in track_pids {
+        let before = a.tracks.get(pid);
+        let after = b.tracks.get(pid);
+        let (old_tags, old_rating) = before.unwrap_or(&empty);
+        let (new_tags, new_rating) = after.unwrap_or(&empty);
+        let tags_added = added(old_tags, new_tags);
+        let tags_removed = added(new_tags, old_tags);
+        // A track entering or leaving the library only counts if it carried tags or a rating
+        let rating_changed = match (before, after) {
+            (Some(_), Some(_)) => old_rating != new_rating,
+            _ => *old_rating != 0 || *new_rating != 0,
+        };
+        if tags_added.is_empty() && tags_removed.is_empty() && !rating_changed {
+            continue;
+        }
+        tracks.push(TrackChange {
+            persistent_id: pid.clone(),
+            name: describe_track(db, pid),
+            tags_added,
+            tags_removed,
+            rating_before: before.map(|(_, r)| *r),
+            rating_after: after.map(|(_, r)| *r),
+        });
+    }
+
+    let playlist_pids: BTreeSet<&String> = a.playlists.keys().chain(b.playlists.keys()).collect();
+    let mut playlists = Vec::new();
+    for pid in playlist_pids {
+        let before = a.playlists.get(pid);
+        let after = b.playlists.get(pid);
+        let (name, renamed_from) = match (before, after) {
+            (Some((old, _)), Some((new, _))) => (new.clone(), (old != new).then(|| old.clone())),
+            (_, Some((new, _))) => (new.clone(), None),
+            (Some((old, _)), None) => (old.clone(), None),
+            (None, None) => continue,
+        };
+        let old_ids = before.map(|(_, ids)| ids.as_slice()).unwrap_or_default();
+        let new_ids = after.map(|(_, ids)| ids.as_slice()).unwrap_or_default();
+        let old_set: HashSet<&String> = old_ids.iter().collect();
+        let new_set: HashSet<&String> = new_ids.iter().collect();
+        let tracks_added: Vec<String> = new_ids.iter().filter(|p| !old_set.contains(p)).map(|p| describe_track(db, p)).collect();
+        let tracks_removed: Vec<String> = old_ids.iter().filter(|p| !new_set.contains(p)).map(|p| describe_track(db, p)).collect();
+        let reordered = old_set == new_set && old_ids != new_ids;
+        let change = PlaylistChange {
+            persistent_id: pid.clone(),
+            name,
+            renamed_from,
+            created: before.is_none(),
+            deleted: after.is_none(),
+            tracks_added,
+            tracks_removed,
+            reordered,
+        };
+        if change.created || change.deleted || change.renamed_from.is_some() || change.reordered
+            || !change.tracks_added.is_empty() || !change.tracks_removed.is_empty()
+        {
+            playlists.push(change);
+        }
+    }
+
+    (tracks, playlists)
+}
+
+/// Saves the current tags, ratings and playlists under `label` (defaults to the date).
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn create_snapshot(
+    label: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<LibrarySnapshot, String> {
+    let created_at = chrono::Utc::now().timestamp();
+    let label = label
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| format!("Snapshot {}", chrono::Local::now().format("%Y-%m-%d %H:%M")));
+
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    let data = capture(&db)?;
+    let json = serde_json::to_string(&data).map_err(|e| e.to_string())?;
+    let id = db.create_library_snapshot(&label, created_at, &json).map_err(|e| e.to_string())?;
+
+    tracing::info!(
+        "Created snapshot \"{}\" ({} tracks, {} playlists)",
+        label, data.tracks.len(), data.playlists.len()
+    );
+    Ok(LibrarySnapshot { id, label, created_at })
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn list_snapshots(state: State<'_, AppState>) -> Result<Vec<LibrarySnapshot>, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    db.get_library_snapshots().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn delete_snapshot(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    db.delete_library_snapshot(id).map_err(|e| e.to_string())
+}
+
+/// What changed from snapshot `a` to snapshot `b`, or to the current library when `b` is `None`.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn diff_snapshots(a: i64, b: Option<i64>, state: State<'_, AppState>) -> Result<SnapshotDiff, String> {
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let (from, before) = load(&db, a)?;
+    let (to, after) = match b {
+        Some(b) => load(&db, b)?,
+        None => (
+            LibrarySnapshot { id: 0, label: "Now".to_string(), created_at: chrono::Utc::now().timestamp() },
+            capture(&db)?,
+        ),
+    };
+    let (tracks, playlists) = diff(&db, &before, &after);
+    Ok(SnapshotDiff { from, to, tracks, playlists })
+}
+
+/// Puts back the tags and ratings a snapshot recorded on every track still in the
+/// library, keeping each track's current user comment. Tracks added since are left
+/// alone and playlists are not touched. The whole restore is one undo step.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn restore_snapshot(
+    id: i64,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SnapshotRestore, String> {
+    let mut comment_changes = Vec::new();
+    let mut ratings = Vec::new();
+    {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        let (_, data) = load(&db, id)?;
+        for track in db.get_all_tracks().map_err(|e| e.to_string())? {
+            let Some((tags, rating)) = data.tracks.get(&track.persistent_id) else {
+                continue;
+            };
+            let comment = track.comment_raw.clone().unwrap_or_default();
+            let (user_comment, current) = crate::comment::split_comment(&comment);
+            let new_rating = (*rating).clamp(0, 100) as u32;
+            let old_rating = track.rating.clamp(0, 100) as u32;
+            if old_rating != new_rating {
+                ratings.push(RatingState {
+                    id: track.id,
+                    persistent_id: track.persistent_id.clone(),
+                    old_rating,
+                    new_rating,
+                });
+            }
+            if !added(&current, tags).is_empty() || !added(tags, &current).is_empty() {
+                let new_comment = crate::comment::build_comment(user_comment, tags);
+                comment_changes.push((track, new_comment));
+            }
+        }
+    }
+
+    let handle = app.clone();
+    let comment_states = tauri::async_runtime::spawn_blocking(move || {
+        crate::commands::write_comment_batch(&handle, comment_changes)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let tags_restored = comment_states.len();
+
+    let ratings_restored = ratings.len();
+    let ratings_result = if ratings.is_empty() {
+        Ok(())
+    } else {
+        state.db.lock().map_err(|_| "Failed to lock DB".to_string())
+            .and_then(|db| crate::undo::apply_ratings(&db, &state.apple_queue, &ratings, false).map_err(|e| e.to_string()))
+    };
+
+    // One step for the whole restore; written tags stay undoable even if the ratings failed
+    let mut actions = Vec::new();
+    if !comment_states.is_empty() {
+        actions.push(Action::UpdateTrackComments { tracks: comment_states });
+    }
+    if !ratings.is_empty() {
+        actions.push(Action::UpdateRatings { tracks: ratings });
+    }
+    if !actions.is_empty() {
+        if let Ok(mut stack) = state.undo_stack.lock() {
+            stack.push(Action::Composite { description: "Restore Snapshot".to_string(), actions });
+        }
+    }
+    ratings_result.map_err(|e| format!("Tags restored on {} tracks, but ratings failed: {}", tags_restored, e))?;
+
+    tracing::info!(
+        "Restored snapshot {}: tags on {} tracks, ratings on {}",
+        id, tags_restored, ratings_restored
+    );
+    Ok(SnapshotRestore { tags_restored, ratings_restored })
+}
@@ -1,6 +1,8 @@
 use anyhow::{Context, Result};
 use serde::Deserialize;
+use tauri::async_runtime::Receiver;
 use tauri::AppHandle;
+use tauri_plugin_shell::process::CommandEvent;
 use tauri_plugin_shell::ShellExt;
 
 use crate::models::{Track, Playlist};
@@ -12,13 +14,18 @@ struct ExternalPlaylist {
     pub parent_persistent_id: Option<String>,
     pub name: String,
     pub is_folder: bool,
+    #[serde(default)]
+    pub is_smart: bool,
     pub track_ids: Vec<String>,
 }
 
+/// One line of the sidecar's newline-delimited JSON output.
 #[derive(Debug, Deserialize)]
-struct LibraryExport {
-    tracks: Vec<ExternalTrack>,
-    playlists: Vec<ExternalPlaylist>,
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum ExportLine {
+    Header { track_count: usize },
+    Track(ExternalTrack),
+    Playlist(ExternalPlaylist),
 }
 
 #[derive(Debug, Deserialize)]
@@ -38,6 +45,16 @@ struct ExternalTrack {
     pub rating: i64,
     pub date_added: i64,
     pub bpm: i64,
+    // Absent in sidecar builds that predate play statistics
+    #[serde(default)]
+    pub play_count: i64,
+    #[serde(default)]
+    pub skip_count: i64,
+    #[serde(default)]
+    pub last_played: i64,
+    // Apple Music streaming/cloud item; file_path is empty
+    #[serde(default)]
+    pub is_cloud: bool,
 }
 
 impl ExternalTrack {
@@ -58,47 +75,125 @@ impl ExternalTrack {
             modified_date: self.modified_date,
             rating: self.rating,
             date_added: self.date_added,
-            bpm: self.bpm,
+            bpm: self.bpm as f64,
             missing: false,
+            play_count: self.play_count,
+            skip_count: self.skip_count,
+            last_played: self.last_played,
+            loved: false,
+            notes: None,
+            intro_bars: None,
+            outro_bars: None,
+            audio_health: None,
+            color_label: None,
+            offloaded: false,
+            is_cloud: self.is_cloud,
         }
     }
 }
 
-pub async fn fetch_system_library(app: &AppHandle) -> Result<(Vec<Track>, Vec<Playlist>)> {
-    let output = app.shell()
+/// Tracks handed to the caller per batch while the sidecar is still reading the library.
+pub const FETCH_BATCH_SIZE: usize = 1000;
+
+/// Progress of a running library fetch, emitted as `library-fetch-progress`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FetchProgress {
+    pub fetched: usize,
+    /// Upper bound reported by the sidecar up front; 0 until its header arrives
+    pub total: usize,
+}
+
+impl ExternalPlaylist {
+    fn into_playlist(self) -> Playlist {
+        Playlist {
+            id: 0,
+            persistent_id: self.persistent_id,
+            parent_persistent_id: self.parent_persistent_id,
+            name: self.name,
+            is_folder: self.is_folder,
+            track_ids: Some(self.track_ids),
+            is_smart: self.is_smart,
+        }
+    }
+}
+
+/// Streams the library from the bundled `fetch-library` sidecar (resolved by Tauri from
+/// `externalBin`). The sidecar writes one JSON object per line; tracks are parsed as they
+/// arrive and passed to `on_batch` in chunks of `FETCH_BATCH_SIZE` together with the
+/// progress so far. Playlists come last and are returned.
+pub async fn stream_system_library(
+    app: &AppHandle,
+    on_batch: impl FnMut(Vec<Track>, &FetchProgress) -> Result<()>,
+) -> Result<Vec<Playlist>> {
+    let (mut rx, child) = app.shell()
         .sidecar("fetch-library")
         .context("Failed to create sidecar command")?
-        .output()
-        .await
+        .spawn()
         .context("Failed to execute fetch-library sidecar")?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow::anyhow!("fetch-library binary failed: {}", stderr));
+    let mut terminated = false;
+    let result = read_sidecar_output(&mut rx, &mut terminated, on_batch).await;
+    if result.is_err() && !terminated {
+        // Bailed out while the sidecar was still running: stop it and wait for it to exit
+        if let Err(e) = child.kill() {
+            tracing::warn!("Failed to kill fetch-library: {}", e);
+        }
+        while let Some(event) = rx.recv().await {
+            if matches!(event, CommandEvent::Terminated(_)) {
+                break;
+            }
+        }
     }
+    result
+}
 
-    let json_str = String::from_utf8(output.stdout)
-        .context("Failed to parse binary output as UTF-8")?;
-
-    let library_export: LibraryExport = serde_json::from_str(&json_str)
-        .context("Failed to parse JSON output from fetch-library")?;
-
-    let tracks = library_export.tracks
-        .into_iter()
-        .map(|t| t.into_track())
-        .collect();
+/// Reads the sidecar's events until it exits; `terminated` is set once it has.
+async fn read_sidecar_output(
+    rx: &mut Receiver<CommandEvent>,
+    terminated: &mut bool,
+    mut on_batch: impl FnMut(Vec<Track>, &FetchProgress) -> Result<()>,
+) -> Result<Vec<Playlist>> {
+    let mut progress = FetchProgress { fetched: 0, total: 0 };
+    let mut batch = Vec::with_capacity(FETCH_BATCH_SIZE);
+    let mut playlists = Vec::new();
+    let mut stderr = String::new();
 
-    let playlists = library_export.playlists
-        .into_iter()
-        .map(|p| Playlist {
-            id: 0,
-            persistent_id: p.persistent_id,
-            parent_persistent_id: p.parent_persistent_id,
-            name: p.name,
-            is_folder: p.is_folder,
-            track_ids: Some(p.track_ids),
-        })
-        .collect();
+    while let Some(event) = rx.recv().await {
+        match event {
+            CommandEvent::Stdout(line) => {
+                let line = String::from_utf8(line).context("Failed to parse binary output as UTF-8")?;
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+                let parsed: ExportLine = serde_json::from_str(line)
+                    .context("Failed to parse JSON output from fetch-library")?;
+                match parsed {
+                    ExportLine::Header { track_count, .. } => progress.total = track_count,
+                    ExportLine::Track(t) => {
+                        batch.push(t.into_track());
+                        progress.fetched += 1;
+                        if batch.len() >= FETCH_BATCH_SIZE {
+                            on_batch(std::mem::take(&mut batch), &progress)?;
+                        }
+                    }
+                    ExportLine::Playlist(p) => playlists.push(p.into_playlist()),
+                }
+            }
+            CommandEvent::Stderr(line) => stderr.push_str(&String::from_utf8_lossy(&line)),
+            CommandEvent::Error(e) => return Err(anyhow::anyhow!("fetch-library binary failed: {}", e)),
+            CommandEvent::Terminated(status) => {
+                *terminated = true;
+                if status.code != Some(0) {
+                    return Err(anyhow::anyhow!("fetch-library binary failed: {}", stderr.trim()));
+                }
+            }
+            _ => {}
+        }
+    }
 
-    Ok((tracks, playlists))
+    if !batch.is_empty() {
+        on_batch(batch, &progress)?;
+    }
+    Ok(playlists)
 }
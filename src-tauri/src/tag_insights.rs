@@ -0,0 +1,285 @@
+//! Read-only analytics over the tag taxonomy: usage stats and tag suggestions.
+
+use crate::comment::split_comment;
+use crate::commands::AppState;
+use std::collections::HashMap;
+use tauri::State;
+
+/// Number of co-occurring tags reported per tag.
+const TOP_CO_OCCURRING: usize = 10;
+
+#[derive(serde::Serialize)]
+pub struct TagCoOccurrence {
+    pub name: String,
+    /// Tracks carrying both tags
+    pub count: i64,
+}
+
+#[derive(serde::Serialize)]
+pub struct TagTrendPoint {
+    /// Calendar month, "YYYY-MM"
+    pub month: String,
+    pub added: i64,
+    pub removed: i64,
+}
+
+#[derive(serde::Serialize)]
+pub struct TagStats {
+    pub name: String,
+    pub usage_count: i64,
+    /// Unix timestamps; edits from the audit log, falling back to the date added
+    /// of tracks tagged before history was recorded
+    pub first_used: Option<i64>,
+    pub last_used: Option<i64>,
+    pub co_occurring: Vec<TagCoOccurrence>,
+    /// Monthly additions and removals from the audit log, oldest first
+    pub trend: Vec<TagTrendPoint>,
+}
+
+#[derive(Default)]
+struct TagAccumulator {
+    name: String,
+    usage_count: i64,
+    first_used: Option<i64>,
+    last_used: Option<i64>,
+    co_occurring: HashMap<String, (String, i64)>,
+    trend: HashMap<String, (i64, i64)>,
+}
+
+impl TagAccumulator {
+    fn mark_used(&mut self, timestamp: i64) {
+        if timestamp <= 0 {
+            return;
+        }
+        self.first_used = Some(self.first_used.map_or(timestamp, |t| t.min(timestamp)));
+        self.last_used = Some(self.last_used.map_or(timestamp, |t| t.max(timestamp)));
+    }
+}
+
+fn month_of(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|d| d.format("%Y-%m").to_string())
+        .unwrap_or_default()
+}
+
+/// Returns usage, first/last use, co-occurring tags and monthly trend for every tag in the library.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn get_tag_stats(state: State<'_, AppState>) -> Result<Vec<TagStats>, String> {
+    let (tracks, history) = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        (
+            db.get_all_tracks().map_err(|e| e.to_string())?,
+            db.get_comment_history().map_err(|e| e.to_string())?,
+        )
+    };
+
+    // Keyed by lowercase name; tags are case-insensitive everywhere else
+    let mut stats: HashMap<String, TagAccumulator> = HashMap::new();
+    let entry = |stats: &mut HashMap<String, TagAccumulator>, tag: &str| -> String {
+        let key = tag.to_lowercase();
+        stats.entry(key.clone()).or_insert_with(|| TagAccumulator { name: tag.to_string(), ..Default::default() });
+        key
+    };
+
+    for track in &tracks {
+        let (_, tags) = split_comment(track.comment_raw.as_deref().unwrap_or(""));
+        let mut keys: Vec<(String, &String)> = Vec::new();
+        for tag in &tags {
+            let key = entry(&mut stats, tag);
+            if !keys.iter().any(|(k, _)| *k == key) {
+                keys.push((key, tag));
+            }
+        }
+
+        for (key, _) in &keys {
+            let acc = stats.get_mut(key).unwrap();
+            acc.usage_count += 1;
+            acc.mark_used(track.date_added);
+            for (other, other_name) in &keys {
+                if other != key {
+                    acc.co_occurring.entry(other.clone()).or_insert_with(|| ((*other_name).clone(), 0)).1 += 1;
+                }
+            }
+        }
+    }
+
+    for (old, new, changed_at) in &history {
+        let (_, old_tags) = split_comment(old.as_deref().unwrap_or(""));
+        let (_, new_tags) = split_comment(new.as_deref().unwrap_or(""));
+        let contains = |tags: &[String], tag: &str| tags.iter().any(|t| t.eq_ignore_ascii_case(tag));
+        let month = month_of(*changed_at);
+
+        for tag in new_tags.iter().filter(|t| !contains(&old_tags, t)) {
+            let key = entry(&mut stats, tag);
+            let acc = stats.get_mut(&key).unwrap();
+            acc.mark_used(*changed_at);
+            acc.trend.entry(month.clone()).or_default().0 += 1;
+        }
+        for tag in old_tags.iter().filter(|t| !contains(&new_tags, t)) {
+            let key = entry(&mut stats, tag);
+            stats.get_mut(&key).unwrap().trend.entry(month.clone()).or_default().1 += 1;
+        }
+    }
+
+    let mut result: Vec<TagStats> = stats.into_values().map(|acc| {
+        let mut co_occurring: Vec<TagCoOccurrence> = acc.co_occurring.into_values()
+            .map(|(name, count)| TagCoOccurrence { name, count })
+            .collect();
+        co_occurring.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+        co_occurring.truncate(TOP_CO_OCCURRING);
+
+        let mut trend: Vec<TagTrendPoint> = acc.trend.into_iter()
+            .map(|(month, (added, removed))| TagTrendPoint { month, added, removed })
+            .collect();
+        trend.sort_by(|a, b| a.month.cmp(&b.month));
+
+        TagStats {
+            name: acc.name,
+            usage_count: acc.usage_count,
+            first_used: acc.first_used,
+            last_used: acc.last_used,
+            co_occurring,
+            trend,
+        }
+    }).collect();
+
+    result.sort_by(|a, b| b.usage_count.cmp(&a.usage_count).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+    Ok(result)
+}
+
+/// Tracks within this many BPM of the target count as neighbours (no double/half-time matching).
+const BPM_NEIGHBOURHOOD: f64 = 3.0;
+
+// Relative weight of each signal in `suggest_tags`
+const WEIGHT_CO_OCCURRENCE: f64 = 1.0;
+const WEIGHT_SAME_ALBUM: f64 = 0.8;
+const WEIGHT_SAME_ARTIST: f64 = 0.6;
+const WEIGHT_BPM_KEY: f64 = 0.4;
+
+#[derive(serde::Serialize)]
+pub struct TagSuggestion {
+    pub name: String,
+    pub score: f64,
+    /// Which signals contributed, e.g. "same artist"
+    pub reasons: Vec<String>,
+}
+
+/// Parses a Camelot key like "8A" into (number, is_major).
+fn parse_camelot(key: &str) -> Option<(u8, bool)> {
+    let (number, letter) = key.split_at(key.len().checked_sub(1)?);
+    Some((number.parse().ok()?, letter == "B"))
+}
+
+/// Same key, relative major/minor, or one step around the wheel.
+fn keys_compatible(a: &str, b: &str) -> bool {
+    match (parse_camelot(a), parse_camelot(b)) {
+        (Some((na, ma)), Some((nb, mb))) => {
+            let distance = (na as i16 - nb as i16).rem_euclid(12);
+            (ma == mb && (distance <= 1 || distance == 11)) || (na == nb)
+        }
+        _ => false,
+    }
+}
+
+/// Accumulates, for one signal, the share of a group of tracks carrying each tag.
+fn add_signal(
+    scores: &mut HashMap<String, TagSuggestion>,
+    group: &[&Vec<String>],
+    weight: f64,
+    reason: &str,
+) {
+    if group.is_empty() {
+        return;
+    }
+    let mut counts: HashMap<String, (String, usize)> = HashMap::new();
+    for tags in group {
+        for tag in tags.iter() {
+            counts.entry(tag.to_lowercase()).or_insert_with(|| (tag.clone(), 0)).1 += 1;
+        }
+    }
+    for (key, (name, count)) in counts {
+        let suggestion = scores.entry(key).or_insert_with(|| TagSuggestion { name, score: 0.0, reasons: Vec::new() });
+        suggestion.score += weight * count as f64 / group.len() as f64;
+        if !suggestion.reasons.iter().any(|r| r == reason) {
+            suggestion.reasons.push(reason.to_string());
+        }
+    }
+}
+
+/// Ranks tags the track does not have yet by how often they appear alongside its current
+/// tags, on the same artist/album, and on tracks with a nearby BPM and compatible key.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn suggest_tags(track_id: i64, limit: Option<usize>, state: State<'_, AppState>) -> Result<Vec<TagSuggestion>, String> {
+    let tracks = {
+        let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+        db.get_all_tracks().map_err(|e| e.to_string())?
+    };
+    let target = tracks.iter().find(|t| t.id == track_id)
+        .ok_or_else(|| format!("Track {} not found", track_id))?;
+
+    let (target_comment, target_tags) = split_comment(target.comment_raw.as_deref().unwrap_or(""));
+    let target_key = crate::import_export::camelot_key(target_comment);
+    let lower = |s: &Option<String>| s.as_deref().map(|v| v.trim().to_lowercase()).filter(|v| !v.is_empty());
+    let target_artist = lower(&target.artist);
+    let target_album = lower(&target.album);
+
+    let others: Vec<(&crate::models::Track, String, Vec<String>)> = tracks.iter()
+        .filter(|t| t.id != track_id)
+        .filter_map(|t| {
+            let (user_comment, tags) = split_comment(t.comment_raw.as_deref().unwrap_or(""));
+            if tags.is_empty() {
+                return None;
+            }
+            Some((t, crate::import_export::camelot_key(user_comment), tags))
+        })
+        .collect();
+
+    let mut scores: HashMap<String, TagSuggestion> = HashMap::new();
+
+    // Co-occurrence: for each existing tag, the tracks sharing it
+    for tag in &target_tags {
+        let group: Vec<&Vec<String>> = others.iter()
+            .filter(|(_, _, tags)| tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            .map(|(_, _, tags)| tags)
+            .collect();
+        add_signal(&mut scores, &group, WEIGHT_CO_OCCURRENCE / target_tags.len() as f64, "co-occurrence");
+    }
+
+    if target_artist.is_some() {
+        let group: Vec<&Vec<String>> = others.iter()
+            .filter(|(t, _, _)| lower(&t.artist) == target_artist)
+            .map(|(_, _, tags)| tags)
+            .collect();
+        add_signal(&mut scores, &group, WEIGHT_SAME_ARTIST, "same artist");
+    }
+
+    if target_album.is_some() {
+        let group: Vec<&Vec<String>> = others.iter()
+            .filter(|(t, _, _)| lower(&t.album) == target_album)
+            .map(|(_, _, tags)| tags)
+            .collect();
+        add_signal(&mut scores, &group, WEIGHT_SAME_ALBUM, "same album");
+    }
+
+    if target.bpm > 0.0 {
+        let group: Vec<&Vec<String>> = others.iter()
+            .filter(|(t, key, _)| {
+                t.bpm > 0.0
+                    && (t.bpm - target.bpm).abs() <= BPM_NEIGHBOURHOOD
+                    && (target_key.is_empty() || keys_compatible(&target_key, key))
+            })
+            .map(|(_, _, tags)| tags)
+            .collect();
+        add_signal(&mut scores, &group, WEIGHT_BPM_KEY, "similar BPM/key");
+    }
+
+    let mut suggestions: Vec<TagSuggestion> = scores.into_values()
+        .filter(|s| !target_tags.iter().any(|t| t.eq_ignore_ascii_case(&s.name)))
+        .collect();
+    suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal)
+        .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+    suggestions.truncate(limit.unwrap_or(10));
+    Ok(suggestions)
+}
@@ -0,0 +1,136 @@
+//! Switching where files keep the tag list (see `metadata::TagStorage`), and keeping the
+//! tags when Music.app hands back comments without them.
+
+use crate::commands::AppState;
+use crate::db::Database;
+use crate::metadata::TagStorage;
+use crate::models::Track;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+const TAG_STORAGE_KEY: &str = "tag_storage";
+/// `tag-storage-progress` is emitted every this many files
+const PROGRESS_INTERVAL: usize = 50;
+
+/// Payload of `tag-storage-progress`, sent while files are rewritten.
+#[derive(Clone, Serialize)]
+pub struct TagStorageProgress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagStorageMigration {
+    pub storage: TagStorage,
+    pub rewritten: usize,
+    /// Files that could not be rewritten, with the error
+    pub failed: Vec<(String, String)>,
+}
+
+/// Applies the stored tag storage preference.
+pub fn load_tag_storage(state: &AppState) {
+    let storage = state.db.read().ok()
+        .and_then(|db| db.get_setting(TAG_STORAGE_KEY).ok().flatten())
+        .and_then(|value| serde_json::from_value(serde_json::Value::String(value)).ok())
+        .unwrap_or_default();
+    crate::metadata::set_tag_storage(storage);
+}
+
+/// In `Frame` mode Music.app only holds the user comment, so a comment coming from it has
+/// no tag block. This puts back the tags TagDeck already has for the track, or, for a
+/// track it hasn't seen yet, the ones in the file's tag frame.
+pub(crate) fn restore_tag_block(db: &Database, track: &mut Track) {
+    if crate::metadata::tag_storage() == TagStorage::Comment {
+        return;
+    }
+    let incoming = track.comment_raw.clone().unwrap_or_default();
+    let (user_comment, incoming_tags) = crate::comment::split_comment(&incoming);
+    if !incoming_tags.is_empty() {
+        return;
+    }
+
+    let known = db.get_track_id_by_persistent_id(&track.persistent_id).ok().flatten()
+        .and_then(|id| db.get_track(id).ok().flatten());
+    let comment = match known {
+        Some(known) => known.comment_raw.unwrap_or_default(),
+        None => match crate::metadata::read_metadata(&track.file_path) {
+            Ok((comment, _)) => comment,
+            Err(_) => return,
+        },
+    };
+    let (_, tags) = crate::comment::split_comment(&comment);
+    if !tags.is_empty() {
+        track.comment_raw = Some(crate::comment::build_comment(user_comment, &tags));
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
+pub fn get_tag_storage() -> TagStorage {
+    crate::metadata::tag_storage()
+}
+
+/// Switches the tag storage and migrates the library to it: every tagged file is rewritten
+/// (tags move between the comment and the tag frame) and Music.app gets the comments in the
+/// new form. The DB is unchanged; it always holds the combined comment.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn set_tag_storage(
+    storage: TagStorage,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<TagStorageMigration, String> {
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let value = serde_json::to_value(storage).map_err(|e| e.to_string())?;
+    let tracks: Vec<Track> = {
+        let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+        db.set_setting(TAG_STORAGE_KEY, value.as_str().unwrap_or_default())
+            .map_err(|e| e.to_string())?;
+        db.get_all_tracks().map_err(|e| e.to_string())?
+    };
+    crate::metadata::set_tag_storage(storage);
+
+    let tagged: Vec<Track> = tracks.into_iter()
+        .filter(|t| !t.is_cloud)
+        .filter(|t| !crate::comment::split_comment(t.comment_raw.as_deref().unwrap_or_default()).1.is_empty())
+        .collect();
+    let total = tagged.len();
+    tracing::info!("Moving the tags of {} files to {:?} storage", total, storage);
+
+    let handle = app.clone();
+    let (written, failed): (Vec<_>, Vec<_>) = tauri::async_runtime::spawn_blocking(move || {
+        let processed = AtomicUsize::new(0);
+        tagged.into_par_iter()
+            .map(|track| {
+                let comment = track.comment_raw.clone().unwrap_or_default();
+                let result = crate::metadata::write_metadata(&track.file_path, &comment)
+                    .map_err(|e| (track.file_path.clone(), e.to_string()));
+                let done = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                if done % PROGRESS_INTERVAL == 0 || done == total {
+                    let _ = handle.emit("tag-storage-progress", TagStorageProgress { processed: done, total });
+                }
+                result.map(|()| (track.persistent_id, comment))
+            })
+            .partition(|r| r.is_ok())
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let updates: Vec<(String, String)> = written.into_iter()
+        .filter_map(Result::ok)
+        .filter(|(pid, _)| !pid.is_empty())
+        .collect();
+    let failed: Vec<(String, String)> = failed.into_iter().filter_map(Result::err).collect();
+    let rewritten = total - failed.len();
+    if !updates.is_empty() {
+        state.apple_queue.enqueue(crate::apple_music::AppleUpdate::Comments { updates });
+    }
+    for (path, e) in &failed {
+        tracing::warn!("Failed to move tags of {}: {}", path, e);
+    }
+
+    tracing::info!("Tag storage is now {:?}: {} files rewritten, {} failed", storage, rewritten, failed.len());
+    Ok(TagStorageMigration { storage, rewritten, failed })
+}
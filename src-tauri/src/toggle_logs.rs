@@ -2,6 +2,7 @@
 use tauri::Manager;
 
 #[tauri::command]
+#[tracing::instrument(level = "debug", skip_all)]
 pub async fn toggle_logs(app: tauri::AppHandle) {
     if let Some(window) = app.get_webview_window("logs") {
         let _ = window.close();
@@ -0,0 +1,14 @@
+//! Secrets handed to clients: the automation API token and deletion confirmations.
+
+/// 128 random bits from the OS CSPRNG, as hex.
+pub fn random_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Failed to generate token: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Compares two tokens without stopping at the first differing byte, so response times
+/// don't reveal how much of a guess was right. Only the length can leak.
+pub fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
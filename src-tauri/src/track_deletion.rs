@@ -0,0 +1,230 @@
+//! Deleting tracks from TagDeck, optionally along with their Music.app entries and audio
+//! files. `preview_delete_tracks` describes what would go and hands out a short-lived
+//! confirmation token; only `delete_tracks` with that token deletes anything. Files are
+//! moved to the Trash through Finder, never removed outright.
+
+use crate::commands::AppState;
+use crate::undo::Action;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::State;
+
+/// How long a confirmation token stays valid
+const CONFIRMATION_TTL: Duration = Duration::from_secs(120);
+/// Tracks listed by name in the preview
+const PREVIEW_SAMPLE: usize = 10;
+
+/// What an issued token confirms: exactly these tracks with exactly these options.
+struct PendingDeletion {
+    token: String,
+    ids: Vec<i64>,
+    remove_from_music: bool,
+    move_file_to_trash: bool,
+    issued: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeletionPreview {
+    pub confirmation_token: String,
+    pub track_count: usize,
+    /// "Artist - Title" of the first few tracks, for the confirmation dialog
+    pub sample: Vec<String>,
+    /// Playlist entries that disappear with the tracks
+    pub playlist_entries: usize,
+    pub remove_from_music: bool,
+    /// Existing files that would be moved to the Trash
+    pub files_to_trash: usize,
+    pub expires_in_secs: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeletionResult {
+    pub deleted: usize,
+    pub removed_from_music: usize,
+    pub trashed: usize,
+    /// Music.app and Trash failures; the tracks are gone from TagDeck regardless
+    pub errors: Vec<String>,
+}
+
+fn pending() -> &'static Mutex<Option<PendingDeletion>> {
+    static PENDING: OnceLock<Mutex<Option<PendingDeletion>>> = OnceLock::new();
+    PENDING.get_or_init(Default::default)
+}
+
+fn normalize(mut ids: Vec<i64>) -> Vec<i64> {
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+/// Moves a file to the Trash via Finder, so it can be put back from there.
+fn move_to_trash(path: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            on run argv
+                tell application "Finder"
+                    delete (POSIX file (item 1 of argv) as alias)
+                end tell
+            end run
+        "#;
+        let output = std::process::Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .arg(path)
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = path;
+        Err("Moving files to the Trash is only supported on macOS".to_string())
+    }
+}
+
+/// Describes a deletion and issues the token `delete_tracks` needs to carry it out.
+/// A new preview replaces any earlier, unconfirmed one.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn preview_delete_tracks(
+    ids: Vec<i64>,
+    remove_from_music: bool,
+    move_file_to_trash: bool,
+    state: State<'_, AppState>,
+) -> Result<DeletionPreview, String> {
+    let ids = normalize(ids);
+    if ids.is_empty() {
+        return Err("No tracks to delete".to_string());
+    }
+
+    let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+    let mut track_count = 0;
+    let mut sample = Vec::new();
+    let mut playlist_entries = 0;
+    let mut files_to_trash = 0;
+    for &id in &ids {
+        let Some(track) = db.get_track(id).map_err(|e| e.to_string())? else { continue };
+        track_count += 1;
+        if sample.len() < PREVIEW_SAMPLE {
+            sample.push(format!(
+                "{} - {}",
+                track.artist.as_deref().unwrap_or("Unknown Artist"),
+                track.title.as_deref().unwrap_or("Unknown Title"),
+            ));
+        }
+        playlist_entries += db.get_playlists_for_track(id).map_err(|e| e.to_string())?.len();
+        if move_file_to_trash && !track.file_path.is_empty() && Path::new(&track.file_path).exists() {
+            files_to_trash += 1;
+        }
+    }
+    drop(db);
+    if track_count == 0 {
+        return Err("None of the tracks exist".to_string());
+    }
+
+    let token = crate::tokens::random_token()?;
+    *pending().lock().map_err(|_| "Failed to lock pending deletion".to_string())? = Some(PendingDeletion {
+        token: token.clone(),
+        ids,
+        remove_from_music,
+        move_file_to_trash,
+        issued: Instant::now(),
+    });
+
+    Ok(DeletionPreview {
+        confirmation_token: token,
+        track_count,
+        sample,
+        playlist_entries,
+        remove_from_music,
+        files_to_trash,
+        expires_in_secs: CONFIRMATION_TTL.as_secs(),
+    })
+}
+
+/// Deletes tracks and their playlist memberships from TagDeck, then optionally deletes
+/// the Music.app entries and moves the files to the Trash. Needs the token from a
+/// `preview_delete_tracks` call with the same tracks and options; tokens are single-use.
+/// Undo restores the TagDeck side only.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn delete_tracks(
+    ids: Vec<i64>,
+    remove_from_music: bool,
+    move_file_to_trash: bool,
+    confirmation_token: String,
+    state: State<'_, AppState>,
+) -> Result<DeletionResult, String> {
+    let ids = normalize(ids);
+    let confirmed = pending().lock().map_err(|_| "Failed to lock pending deletion".to_string())?
+        .take()
+        .is_some_and(|p| crate::tokens::tokens_match(&p.token, &confirmation_token)
+            && p.ids == ids
+            && p.remove_from_music == remove_from_music
+            && p.move_file_to_trash == move_file_to_trash
+            && p.issued.elapsed() < CONFIRMATION_TTL);
+    if !confirmed {
+        return Err("Deletion not confirmed or confirmation expired; preview it again".to_string());
+    }
+
+    let db = state.db.lock().map_err(|_| "Failed to lock DB".to_string())?;
+    let deleted = db.delete_tracks(&ids).map_err(|e| e.to_string())?;
+    drop(db);
+    tracing::info!("Deleted {} track(s) from TagDeck", deleted.len());
+
+    let mut result = DeletionResult { deleted: deleted.len(), ..Default::default() };
+    let pids: Vec<String> = deleted.iter()
+        .map(|d| d.track.persistent_id.clone())
+        .filter(|pid| !pid.is_empty())
+        .collect();
+    let paths: Vec<String> = deleted.iter()
+        .map(|d| d.track.file_path.clone())
+        .filter(|path| !path.is_empty() && Path::new(path).exists())
+        .collect();
+
+    if let Ok(mut stack) = state.undo_stack.lock() {
+        stack.push(Action::DeleteTracks { tracks: deleted });
+    }
+
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        if remove_from_music && !pids.is_empty() {
+            match crate::apple_music::delete_tracks_from_library(&pids) {
+                Ok(removed) => {
+                    result.removed_from_music = removed.len();
+                    if removed.len() < pids.len() {
+                        result.errors.push(format!("{} track(s) were not found in Music.app", pids.len() - removed.len()));
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to delete tracks from Music.app: {}", e);
+                    result.errors.push(format!("Music.app: {}", e));
+                }
+            }
+        }
+        if move_file_to_trash {
+            for path in &paths {
+                match move_to_trash(path) {
+                    Ok(()) => result.trashed += 1,
+                    Err(e) => {
+                        tracing::error!("Failed to move {} to the Trash: {}", path, e);
+                        result.errors.push(format!("{}: {}", path, e));
+                    }
+                }
+            }
+        }
+        result
+    }).await.map_err(|e| e.to_string())?;
+
+    tracing::info!(
+        "Track deletion done: {} removed from Music.app, {} moved to the Trash, {} error(s)",
+        result.removed_from_music, result.trashed, result.errors.len()
+    );
+    Ok(result)
+}
@@ -0,0 +1,245 @@
+//! Intro/outro detection: how long a track runs on a steady beat before the main part comes
+//! in, and after it leaves. The beat grid comes from the track's BPM (or an estimate from
+//! the kick onsets), and a bar counts as intro/outro while its kick lines up with the grid
+//! and its energy stays below the body of the track. Lengths are stored in bars, so the
+//! search filters ("intro:>=16") work on tracks without a BPM too. The audio is reduced to
+//! per-frame features as it decodes; no track is held in memory whole.
+
+use crate::commands::AppState;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+const ANALYSIS_RATE: u32 = 11_025;
+/// Samples per analysis frame (~23 ms)
+const HOP: usize = 256;
+/// Cut-off of the low-pass that isolates the kick
+const KICK_CUTOFF_HZ: f32 = 150.0;
+/// BPM range searched when the track has no BPM
+const MIN_BPM: f64 = 80.0;
+const MAX_BPM: f64 = 180.0;
+const BEATS_PER_BAR: usize = 4;
+/// A bar is on the grid when its beats carry this much more kick onset than the bar on average
+const PULSE_RATIO: f32 = 1.5;
+/// A bar belongs to the intro/outro while its energy is below this share of the body's
+const INTRO_ENERGY_RATIO: f32 = 0.8;
+/// Bars of beatless lead-in (or fade) skipped before the intro (or after the outro)
+const MAX_LEAD_BARS: usize = 8;
+/// `structure-progress` is emitted every this many tracks
+const PROGRESS_INTERVAL: usize = 10;
+
+const NOT_BUILT: &str = "Intro/outro detection is not included in this build (enable the audio-analysis feature)";
+
+/// Payload of `structure-progress`.
+#[derive(Clone, Serialize)]
+pub struct StructureProgress {
+    pub processed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StructureAnalysisResult {
+    pub analyzed: usize,
+    /// Tracks that could not be analyzed, with the error
+    pub failed: Vec<(i64, String)>,
+}
+
+/// Intro and outro length in bars.
+#[derive(Debug, Clone, Copy)]
+struct TrackStructure {
+    intro_bars: usize,
+    outro_bars: usize,
+}
+
+#[cfg(feature = "audio-analysis")]
+fn stream_samples(path: &str, f: impl FnMut(&[f32])) -> Result<(), String> {
+    crate::audio::stream_mono(path, ANALYSIS_RATE, f).map_err(|e| format!("Failed to decode audio: {}", e))
+}
+
+#[cfg(not(feature = "audio-analysis"))]
+fn stream_samples(_path: &str, _f: impl FnMut(&[f32])) -> Result<(), String> {
+    Err(NOT_BUILT.to_string())
+}
+
+/// Per-frame RMS of the full signal and of the kick band, built up as samples arrive.
+#[derive(Default)]
+struct FrameFeatures {
+    /// Low-pass state carried across frames
+    low: f32,
+    /// Samples short of a full frame, waiting for the next chunk
+    pending: Vec<f32>,
+    energy: Vec<f32>,
+    kick: Vec<f32>,
+}
+
+impl FrameFeatures {
+    fn push(&mut self, samples: &[f32]) {
+        let alpha = 1.0 - (-2.0 * std::f32::consts::PI * KICK_CUTOFF_HZ / ANALYSIS_RATE as f32).exp();
+        self.pending.extend_from_slice(samples);
+        let whole = self.pending.len() / HOP * HOP;
+        for frame in self.pending[..whole].chunks_exact(HOP) {
+            let mut full_sum = 0.0f32;
+            let mut low_sum = 0.0f32;
+            for &s in frame {
+                self.low += alpha * (s - self.low);
+                full_sum += s * s;
+                low_sum += self.low * self.low;
+            }
+            self.energy.push((full_sum / HOP as f32).sqrt());
+            self.kick.push((low_sum / HOP as f32).sqrt());
+        }
+        self.pending.drain(..whole);
+    }
+
+    /// Per-frame energy and kick onset strength (rise in low-band RMS).
+    fn finish(self) -> (Vec<f32>, Vec<f32>) {
+        let onset: Vec<f32> = std::iter::once(0.0)
+            .chain(self.kick.windows(2).map(|w| (w[1] - w[0]).max(0.0)))
+            .collect();
+        (self.energy, onset)
+    }
+}
+
+fn frame_features(path: &str) -> Result<(Vec<f32>, Vec<f32>), String> {
+    let mut features = FrameFeatures::default();
+    stream_samples(path, |chunk| features.push(chunk))?;
+    Ok(features.finish())
+}
+
+fn frames_per_beat(bpm: f64) -> f64 {
+    60.0 / bpm * ANALYSIS_RATE as f64 / HOP as f64
+}
+
+/// Beat period (in frames) with the strongest onset autocorrelation within the BPM range.
+fn estimate_period(onset: &[f32]) -> Option<f64> {
+    let min_lag = frames_per_beat(MAX_BPM).floor() as usize;
+    let max_lag = frames_per_beat(MIN_BPM).ceil() as usize;
+    if onset.len() <= max_lag * 8 {
+        return None;
+    }
+    (min_lag..=max_lag)
+        .map(|lag| (lag, onset.iter().zip(&onset[lag..]).map(|(a, b)| a * b).sum::<f32>()))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .filter(|(_, score)| *score > 0.0)
+        .map(|(lag, _)| lag as f64)
+}
+
+/// Onset at a frame, allowing one frame of slack either side of the grid.
+fn onset_near(onset: &[f32], frame: f64) -> f32 {
+    let center = frame.round() as usize;
+    (center.saturating_sub(1)..=center + 1)
+        .filter_map(|i| onset.get(i))
+        .fold(0.0, |a, &b| a.max(b))
+}
+
+fn analyze(path: &str, bpm: f64) -> Result<TrackStructure, String> {
+    let (energy, onset) = frame_features(path)?;
+    let period = (if bpm > 0.0 { Some(frames_per_beat(bpm)) } else { estimate_period(&onset) })
+        .ok_or("No steady beat found")?;
+
+    // Grid phase: the offset whose beats carry the most onset
+    let phase = (0..period.ceil() as usize)
+        .max_by(|&a, &b| {
+            let score = |offset: usize| {
+                let mut total = 0.0f32;
+                let mut frame = offset as f64;
+                while (frame as usize) < onset.len() {
+                    total += onset_near(&onset, frame);
+                    frame += period;
+                }
+                total
+            };
+            score(a).total_cmp(&score(b))
+        })
+        .unwrap_or(0) as f64;
+
+    let bar_frames = period * BEATS_PER_BAR as f64;
+    let bar_count = ((onset.len() as f64 - phase) / bar_frames).floor() as usize;
+    if bar_count < MAX_LEAD_BARS * 2 {
+        return Err("Track too short to analyze".to_string());
+    }
+
+    // Per bar: whether the kick sits on the grid, and its mean energy
+    let bars: Vec<(bool, f32)> = (0..bar_count)
+        .map(|bar| {
+            let start = phase + bar as f64 * bar_frames;
+            let range = start.round() as usize..((start + bar_frames).round() as usize).min(onset.len());
+            let frames = range.len().max(1) as f32;
+            let mean_onset = onset[range.clone()].iter().sum::<f32>() / frames;
+            let beat_onset = (0..BEATS_PER_BAR)
+                .map(|beat| onset_near(&onset, start + beat as f64 * period))
+                .sum::<f32>() / BEATS_PER_BAR as f32;
+            let on_grid = mean_onset > 0.0 && beat_onset >= mean_onset * PULSE_RATIO;
+            let mean_energy = energy[range].iter().sum::<f32>() / frames;
+            (on_grid, mean_energy)
+        })
+        .collect();
+
+    // The body: upper quartile of bar energy
+    let mut sorted: Vec<f32> = bars.iter().map(|(_, e)| *e).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let body = sorted[sorted.len() * 3 / 4];
+    let in_intro = |&&(on_grid, energy): &&(bool, f32)| on_grid && energy < body * INTRO_ENERGY_RATIO;
+
+    // Count from the first on-grid bar within the lead-in, from either end
+    let lead_in = bars.iter().take(MAX_LEAD_BARS).position(|(on_grid, _)| *on_grid);
+    let lead_out = bars.iter().rev().take(MAX_LEAD_BARS).position(|(on_grid, _)| *on_grid);
+    let intro_bars = lead_in.map_or(0, |lead| bars.iter().skip(lead).take_while(in_intro).count());
+    let outro_bars = lead_out.map_or(0, |lead| bars.iter().rev().skip(lead).take_while(in_intro).count());
+
+    Ok(TrackStructure { intro_bars, outro_bars })
+}
+
+/// Estimates the intro and outro length of `ids`, or of every track not analyzed yet, and
+/// stores them. Tracks that fail are marked so the next run skips them. Emits
+/// `structure-progress`.
+#[tauri::command]
+#[tracing::instrument(level = "debug", skip_all, err(level = "debug"))]
+pub async fn analyze_track_structure(
+    ids: Option<Vec<i64>>,
+    app: AppHandle,
+) -> Result<StructureAnalysisResult, String> {
+    use rayon::prelude::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    if !cfg!(feature = "audio-analysis") {
+        return Err(NOT_BUILT.to_string());
+    }
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || -> Result<StructureAnalysisResult, String> {
+        let state = handle.state::<AppState>();
+        let tracks: Vec<(i64, String, f64)> = {
+            let db = state.db.read().map_err(|_| "Failed to lock DB".to_string())?;
+            let tracks = match ids {
+                Some(ids) => ids.iter().filter_map(|id| db.get_track(*id).ok().flatten()).collect(),
+                None => db.get_tracks_without_structure().map_err(|e| e.to_string())?,
+            };
+            tracks.into_iter().filter(|t| !t.is_cloud).map(|t| (t.id, t.file_path, t.bpm)).collect()
+        };
+
+        let total = tracks.len();
+        let processed = AtomicUsize::new(0);
+        let (done, failed): (Vec<_>, Vec<_>) = tracks.into_par_iter()
+            .map(|(id, path, bpm)| {
+                let result = analyze(&path, bpm);
+                let n = processed.fetch_add(1, Ordering::Relaxed) + 1;
+                if n % PROGRESS_INTERVAL == 0 || n == total {
+                    let _ = handle.emit("structure-progress", StructureProgress { processed: n, total });
+                }
+                result.map(|s| (id, s.intro_bars as i64, s.outro_bars as i64)).map_err(|e| (id, e))
+            })
+            .partition(|r| r.is_ok());
+
+        let results: Vec<(i64, i64, i64)> = done.into_iter().filter_map(Result::ok).collect();
+        let failed: Vec<(i64, String)> = failed.into_iter().filter_map(Result::err).collect();
+        let failed_ids: Vec<i64> = failed.iter().map(|(id, _)| *id).collect();
+        state.db.lock().map_err(|_| "Failed to lock DB".to_string())?
+            .update_track_structure(&results, &failed_ids, chrono::Utc::now().timestamp())
+            .map_err(|e| format!("DB Error (update structure): {}", e))?;
+
+        tracing::info!("Intro/outro detection: analyzed {} of {} tracks", results.len(), total);
+        Ok(StructureAnalysisResult { analyzed: results.len(), failed })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -1,8 +1,9 @@
-use crate::db::Database;
-use crate::apple_music::{batch_update_track_comments, update_track_info as apple_update_track_info, update_track_comment, touch_file};
+use crate::db::{Database, DeletedTrack};
+use crate::models::TrackColor;
+use crate::apple_music::{touch_file, AppleUpdate, SyncQueue};
 use crate::metadata::{write_metadata as write_tags_to_file, write_track_info};
 use anyhow::Result;
-use std::process::Command;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub enum Action {
@@ -19,6 +20,47 @@ pub enum Action {
     UpdateTrackInfo {
         track: TrackInfoState,
     },
+    RemoveFromPlaylist {
+        playlist_id: i64,
+        playlist_persistent_id: String,
+        // Tracks that were removed
+        tracks: Vec<TrackRef>,
+        // Full playlist order before the removal, so undo restores positions
+        previous_order: Vec<TrackRef>,
+    },
+    ReorderPlaylist {
+        playlist_id: i64,
+        playlist_persistent_id: String,
+        old_order: Vec<TrackRef>,
+        new_order: Vec<TrackRef>,
+    },
+    UpdateRatings {
+        tracks: Vec<RatingState>,
+    },
+    UpdateLoved {
+        tracks: Vec<LovedState>,
+    },
+    UpdateGroupings {
+        tracks: Vec<GroupingState>,
+    },
+    UpdateColors {
+        tracks: Vec<ColorState>,
+    },
+    /// Play counts moved from one track to another; holds both tracks' original counts.
+    TransferPlayCount {
+        source: PlayCountState,
+        target: PlayCountState,
+    },
+    /// Tracks deleted from TagDeck. Undo restores the DB side only: a Music.app entry or a
+    /// file moved to the Trash stays gone.
+    DeleteTracks {
+        tracks: Vec<DeletedTrack>,
+    },
+    /// Several actions undone/redone as one step (undo runs them in reverse order)
+    Composite {
+        description: String,
+        actions: Vec<Action>,
+    },
 }
 
 /// Stores old and new values for a track info edit (title, artist, album, bpm, comment).
@@ -34,13 +76,13 @@ pub struct TrackInfoState {
     pub new_artist: Option<String>,
     pub old_album: Option<String>,
     pub new_album: Option<String>,
-    pub old_bpm: Option<i64>,
-    pub new_bpm: Option<i64>,
+    pub old_bpm: Option<f64>,
+    pub new_bpm: Option<f64>,
     pub old_comment_raw: Option<String>,
     pub new_comment_raw: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackState {
     pub id: i64,
     pub persistent_id: String,
@@ -49,15 +91,116 @@ pub struct TrackState {
     pub new_comment: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct GroupingState {
+    pub id: i64,
+    pub persistent_id: String,
+    pub file_path: String,
+    pub old_grouping: String,
+    pub new_grouping: String,
+}
+
+/// Color labels are TagDeck-only, so no persistent ID or file is needed.
+#[derive(Debug, Clone)]
+pub struct ColorState {
+    pub id: i64,
+    pub old_color: Option<TrackColor>,
+    pub new_color: Option<TrackColor>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayCountState {
+    pub id: i64,
+    pub persistent_id: String,
+    pub old_count: i64,
+    pub new_count: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RatingState {
+    pub id: i64,
+    pub persistent_id: String,
+    pub old_rating: u32,
+    pub new_rating: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct LovedState {
+    pub id: i64,
+    pub persistent_id: String,
+    pub old_loved: bool,
+    pub new_loved: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct TrackRef {
     pub id: i64,
     pub persistent_id: String,
 }
 
+impl Action {
+    /// Human-readable description for the undo history.
+    pub fn describe(&self) -> String {
+        match self {
+            Action::UpdateTrackComments { tracks } => {
+                if tracks.len() == 1 { "Tag Change".to_string() } else { format!("Tag Change ({} tracks)", tracks.len()) }
+            }
+            Action::AddToPlaylist { tracks, .. } => format!("Add {} track(s) to Playlist", tracks.len()),
+            Action::UpdateTrackInfo { .. } => "Edit Track Info".to_string(),
+            Action::RemoveFromPlaylist { tracks, .. } => format!("Remove {} track(s) from Playlist", tracks.len()),
+            Action::ReorderPlaylist { .. } => "Reorder Playlist".to_string(),
+            Action::UpdateRatings { tracks } => {
+                if tracks.len() == 1 { "Rating Change".to_string() } else { format!("Rating Change ({} tracks)", tracks.len()) }
+            }
+            Action::UpdateLoved { tracks } => {
+                if tracks.len() == 1 { "Loved Change".to_string() } else { format!("Loved Change ({} tracks)", tracks.len()) }
+            }
+            Action::UpdateGroupings { tracks } => format!("Grouping Change ({} tracks)", tracks.len()),
+            Action::UpdateColors { tracks } => {
+                if tracks.len() == 1 { "Color Change".to_string() } else { format!("Color Change ({} tracks)", tracks.len()) }
+            }
+            Action::TransferPlayCount { .. } => "Transfer Play Count".to_string(),
+            Action::DeleteTracks { tracks } => format!("Delete {} track(s)", tracks.len()),
+            Action::Composite { description, .. } => description.clone(),
+        }
+    }
+
+    /// Number of tracks the action touched.
+    pub fn track_count(&self) -> usize {
+        match self {
+            Action::UpdateTrackComments { tracks } => tracks.len(),
+            Action::AddToPlaylist { tracks, .. } => tracks.len(),
+            Action::UpdateTrackInfo { .. } => 1,
+            Action::RemoveFromPlaylist { tracks, .. } => tracks.len(),
+            Action::ReorderPlaylist { new_order, .. } => new_order.len(),
+            Action::UpdateRatings { tracks } => tracks.len(),
+            Action::UpdateLoved { tracks } => tracks.len(),
+            Action::UpdateGroupings { tracks } => tracks.len(),
+            Action::UpdateColors { tracks } => tracks.len(),
+            Action::TransferPlayCount { .. } => 2,
+            Action::DeleteTracks { tracks } => tracks.len(),
+            Action::Composite { actions, .. } => actions.iter().map(|a| a.track_count()).max().unwrap_or(0),
+        }
+    }
+}
+
+/// An undo history entry as shown to the frontend. `index` 0 is the most recent action.
+#[derive(Debug, Clone, Serialize)]
+pub struct UndoActionInfo {
+    pub index: usize,
+    pub description: String,
+    pub timestamp: i64,
+    pub track_count: usize,
+}
+
+struct UndoEntry {
+    action: Action,
+    timestamp: i64,
+}
+
 pub struct UndoStack {
-    undo_stack: Vec<Action>,
-    redo_stack: Vec<Action>,
+    undo_stack: Vec<UndoEntry>,
+    redo_stack: Vec<UndoEntry>,
 }
 
 impl UndoStack {
@@ -69,156 +212,445 @@ impl UndoStack {
     }
 
     pub fn push(&mut self, action: Action) {
-        self.undo_stack.push(action);
+        self.undo_stack.push(UndoEntry { action, timestamp: chrono::Utc::now().timestamp() });
         self.redo_stack.clear(); // Clear redo stack on new action
     }
 
-    pub fn undo(&mut self, db: &Database) -> Result<Option<String>> {
-        if let Some(action) = self.undo_stack.pop() {
-            let message = match &action {
-                Action::UpdateTrackComments { tracks } => {
-                    let mut updates = Vec::new();
-                    for track in tracks {
-                        // Revert to old comment
-                        
-                        // 1. File
-                        if let Err(e) = write_tags_to_file(&track.file_path, &track.old_comment) {
-                            eprintln!("Undo Write File Error: {}", e);
-                            continue;
-                        }
-                        
-                        // 2. DB
-                        if let Err(e) = db.update_track_metadata(track.id, &track.old_comment) {
-                            eprintln!("Undo DB Error: {}", e);
-                        }
-
-                        // 3. Queue AM Update
-                        if !track.persistent_id.is_empty() {
-                            updates.push((track.persistent_id.clone(), track.old_comment.clone()));
-                        }
-                    }
+    /// Lists undoable actions, most recent first.
+    pub fn list(&self) -> Vec<UndoActionInfo> {
+        self.undo_stack.iter().rev().enumerate().map(|(index, entry)| UndoActionInfo {
+            index,
+            description: entry.action.describe(),
+            timestamp: entry.timestamp,
+            track_count: entry.action.track_count(),
+        }).collect()
+    }
 
-                    // Flush AM
-                    if !updates.is_empty() {
-                         let _ = batch_update_track_comments(updates);
-                    }
-                    
-                    if tracks.len() == 1 {
-                        "Undo Tag Change".to_string()
-                    } else {
-                        format!("Undo Tag Change ({} tracks)", tracks.len())
-                    }
-                },
-                Action::AddToPlaylist { playlist_id, playlist_persistent_id, tracks } => {
-                     // Reverse: Remove tracks from playlist
-                     
-                     // 1. Apple Music
-                     #[cfg(target_os = "macos")]
-                     {
-                        // Generate AppleScript to remove these tracks from this playlist
-                         for track in tracks {
-                             let script = format!(
-                                r#"
-                                tell application "Music"
-                                    try
-                                        set thePlaylist to (first playlist whose persistent ID is "{}")
-                                        delete (every track of thePlaylist whose persistent ID is "{}")
-                                    end try
-                                end tell
-                                "#,
-                                playlist_persistent_id, track.persistent_id
-                             );
-                             let _ = Command::new("osascript").arg("-e").arg(&script).output();
-                         }
-                     }
-
-                     // 2. DB
-                     for track in tracks {
-                         // This is a naive delete: removes all instances of this track in this playlist
-                         // A more robust undo would track the specific 'position' or 'id' in the join table
-                         let _ = db.remove_track_from_playlist(*playlist_id, track.id);
-                     }
-                     
-                     "Undo Add to Playlist".to_string()
-                },
-                Action::UpdateTrackInfo { track } => {
-                    // Revert track info to old values
-                    apply_track_info(db, track, true);
-                    "Undo Edit Track Info".to_string()
+    /// Undoes every action up to and including `index` (as returned by `list`).
+    /// The caller holds the stack and DB locks for the whole run, so no other edit can interleave.
+    /// Stops at the first action that fails; the error says how many steps were undone.
+    pub fn undo_to(&mut self, index: usize, db: &Database, queue: &SyncQueue) -> Result<Vec<String>> {
+        if index >= self.undo_stack.len() {
+            return Err(anyhow::anyhow!("No undo action at index {}", index));
+        }
+
+        let mut messages = Vec::new();
+        for _ in 0..=index {
+            match self.undo(db, queue) {
+                Ok(Some(message)) => messages.push(message),
+                Ok(None) => break,
+                Err(e) => {
+                    return Err(anyhow::anyhow!("Undid {} of {} step(s), then: {}", messages.len(), index + 1, e));
                 }
-            };
-            
-            self.redo_stack.push(action);
-            Ok(Some(message))
-        } else {
-            Ok(None)
+            }
         }
+        Ok(messages)
     }
 
-    pub fn redo(&mut self, db: &Database) -> Result<Option<String>> {
-        if let Some(action) = self.redo_stack.pop() {
-             let message = match &action {
-                Action::UpdateTrackComments { tracks } => {
-                    let mut updates = Vec::new();
-                    for track in tracks {
-                        // Re-apply new comment
-                        
-                        // 1. File
-                        let _ = write_tags_to_file(&track.file_path, &track.new_comment);
-                        
-                        // 2. DB
-                        let _ = db.update_track_metadata(track.id, &track.new_comment);
-
-                        // 3. Queue AM Update
-                        if !track.persistent_id.is_empty() {
-                            updates.push((track.persistent_id.clone(), track.new_comment.clone()));
-                        }
-                    }
-                    if !updates.is_empty() {
-                         let _ = batch_update_track_comments(updates);
+    /// Reverts the latest action. One that fails stays on the undo stack, so it can be
+    /// tried again; parts that did apply are safe to apply twice.
+    pub fn undo(&mut self, db: &Database, queue: &SyncQueue) -> Result<Option<String>> {
+        let Some(entry) = self.undo_stack.pop() else { return Ok(None) };
+        match undo_action(&entry.action, db, queue) {
+            Ok(message) => {
+                self.redo_stack.push(entry);
+                Ok(Some(message))
+            }
+            Err(e) => {
+                self.undo_stack.push(entry);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn redo(&mut self, db: &Database, queue: &SyncQueue) -> Result<Option<String>> {
+        let Some(entry) = self.redo_stack.pop() else { return Ok(None) };
+        match redo_action(&entry.action, db, queue) {
+            Ok(message) => {
+                self.undo_stack.push(entry);
+                Ok(Some(message))
+            }
+            Err(e) => {
+                self.redo_stack.push(entry);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Reverts a single action and returns its status message.
+fn undo_action(action: &Action, db: &Database, queue: &SyncQueue) -> Result<String> {
+    Ok(match action {
+        Action::UpdateTrackComments { tracks } => {
+            let mut updates = Vec::new();
+            let mut file_errors = FileErrors::default();
+            for track in tracks {
+                // Revert to old comment
+
+                // 1. File (Apple Music cloud tracks have none; their path is empty)
+                if !track.file_path.is_empty() {
+                    if let Err(e) = write_tags_to_file(&track.file_path, &track.old_comment) {
+                        file_errors.push(&track.file_path, e);
+                        continue;
                     }
-                    if tracks.len() == 1 {
-                        "Redo Tag Change".to_string()
-                    } else {
-                         format!("Redo Tag Change ({} tracks)", tracks.len())
+                }
+
+                // 2. DB
+                db.update_track_metadata(track.id, &track.old_comment)?;
+
+                // 3. Queue AM Update
+                if !track.persistent_id.is_empty() {
+                    updates.push((track.persistent_id.clone(), track.old_comment.clone()));
+                }
+            }
+
+            // Flush AM
+            if !updates.is_empty() {
+                 queue.enqueue(AppleUpdate::Comments { updates });
+            }
+            file_errors.into_result()?;
+
+            if tracks.len() == 1 {
+                "Undo Tag Change".to_string()
+            } else {
+                format!("Undo Tag Change ({} tracks)", tracks.len())
+            }
+        },
+        Action::AddToPlaylist { playlist_id, playlist_persistent_id, tracks } => {
+             // Reverse: Remove tracks from playlist
+
+             // 1. Apple Music
+             for track in tracks {
+                 queue.enqueue(AppleUpdate::RemoveFromPlaylist {
+                     track_pid: track.persistent_id.clone(),
+                     playlist_pid: playlist_persistent_id.clone(),
+                 });
+             }
+
+             // 2. DB
+             for track in tracks {
+                 // This is a naive delete: removes all instances of this track in this playlist
+                 // A more robust undo would track the specific 'position' or 'id' in the join table
+                 db.remove_track_from_playlist(*playlist_id, track.id)?;
+             }
+
+             "Undo Add to Playlist".to_string()
+        },
+        Action::UpdateTrackInfo { track } => {
+            // Revert track info to old values
+            apply_track_info(db, queue, track, true)?;
+            "Undo Edit Track Info".to_string()
+        }
+        Action::RemoveFromPlaylist { playlist_id, playlist_persistent_id, tracks, previous_order } => {
+            // Reverse: Re-add removed tracks, then restore the original order
+
+            // 1. Apple Music
+            queue.enqueue(AppleUpdate::playlist_adds(
+                tracks.iter().map(|t| t.persistent_id.as_str()),
+                playlist_persistent_id,
+            ));
+
+            // 2. DB
+            for track in tracks {
+                db.add_track_to_playlist_db(*playlist_id, track.id)?;
+            }
+
+            restore_playlist_order(db, queue, *playlist_id, playlist_persistent_id, previous_order)?;
+
+            "Undo Remove from Playlist".to_string()
+        }
+        Action::ReorderPlaylist { playlist_id, playlist_persistent_id, old_order, .. } => {
+            restore_playlist_order(db, queue, *playlist_id, playlist_persistent_id, old_order)?;
+            "Undo Reorder Playlist".to_string()
+        }
+        Action::UpdateRatings { tracks } => {
+            apply_ratings(db, queue, tracks, true)?;
+            if tracks.len() == 1 {
+                "Undo Rating Change".to_string()
+            } else {
+                format!("Undo Rating Change ({} tracks)", tracks.len())
+            }
+        }
+        Action::UpdateLoved { tracks } => {
+            apply_loved(db, queue, tracks, true)?;
+            if tracks.len() == 1 {
+                "Undo Loved Change".to_string()
+            } else {
+                format!("Undo Loved Change ({} tracks)", tracks.len())
+            }
+        }
+        Action::UpdateGroupings { tracks } => {
+            apply_groupings(db, queue, tracks, true)?;
+            format!("Undo Grouping Change ({} tracks)", tracks.len())
+        }
+        Action::UpdateColors { tracks } => {
+            apply_colors(db, tracks, true)?;
+            if tracks.len() == 1 {
+                "Undo Color Change".to_string()
+            } else {
+                format!("Undo Color Change ({} tracks)", tracks.len())
+            }
+        }
+        Action::TransferPlayCount { source, target } => {
+            apply_play_counts(db, queue, &[source, target], true)?;
+            "Undo Transfer Play Count".to_string()
+        }
+        Action::DeleteTracks { tracks } => {
+            db.restore_deleted_tracks(tracks)?;
+            format!("Undo Delete {} track(s)", tracks.len())
+        }
+        Action::Composite { description, actions } => {
+            for inner in actions.iter().rev() {
+                undo_action(inner, db, queue)?;
+            }
+            format!("Undo {}", description)
+        }
+    })
+}
+
+/// Re-applies a single action and returns its status message.
+fn redo_action(action: &Action, db: &Database, queue: &SyncQueue) -> Result<String> {
+    Ok(match action {
+        Action::UpdateTrackComments { tracks } => {
+            let mut updates = Vec::new();
+            let mut file_errors = FileErrors::default();
+            for track in tracks {
+                // Re-apply new comment
+
+                // 1. File (Apple Music cloud tracks have none; their path is empty)
+                if !track.file_path.is_empty() {
+                    if let Err(e) = write_tags_to_file(&track.file_path, &track.new_comment) {
+                        file_errors.push(&track.file_path, e);
+                        continue;
                     }
-                },
-                Action::AddToPlaylist { playlist_id, playlist_persistent_id, tracks } => {
-                     // Re-apply Add
-
-                     // 1. Apple Music
-                     #[cfg(target_os = "macos")]
-                     {
-                         for track in tracks {
-                            let _ = crate::apple_music::add_track_to_playlist(&track.persistent_id, playlist_persistent_id);
-                         }
-                     }
-                     
-                     // 2. DB
-                     for track in tracks {
-                         let _ = db.add_track_to_playlist_db(*playlist_id, track.id);
-                     }
-
-                     "Redo Add to Playlist".to_string()
-                },
-                Action::UpdateTrackInfo { track } => {
-                    // Re-apply new track info values
-                    apply_track_info(db, track, false);
-                    "Redo Edit Track Info".to_string()
                 }
-             };
-             
-             self.undo_stack.push(action);
-             Ok(Some(message))
-        } else {
-            Ok(None)
+
+                // 2. DB
+                db.update_track_metadata(track.id, &track.new_comment)?;
+
+                // 3. Queue AM Update
+                if !track.persistent_id.is_empty() {
+                    updates.push((track.persistent_id.clone(), track.new_comment.clone()));
+                }
+            }
+            if !updates.is_empty() {
+                 queue.enqueue(AppleUpdate::Comments { updates });
+            }
+            file_errors.into_result()?;
+            if tracks.len() == 1 {
+                "Redo Tag Change".to_string()
+            } else {
+                 format!("Redo Tag Change ({} tracks)", tracks.len())
+            }
+        },
+        Action::AddToPlaylist { playlist_id, playlist_persistent_id, tracks } => {
+             // Re-apply Add
+
+             // 1. Apple Music
+             queue.enqueue(AppleUpdate::playlist_adds(
+                 tracks.iter().map(|t| t.persistent_id.as_str()),
+                 playlist_persistent_id,
+             ));
+
+             // 2. DB
+             for track in tracks {
+                 db.add_track_to_playlist_db(*playlist_id, track.id)?;
+             }
+
+             "Redo Add to Playlist".to_string()
+        },
+        Action::UpdateTrackInfo { track } => {
+            // Re-apply new track info values
+            apply_track_info(db, queue, track, false)?;
+            "Redo Edit Track Info".to_string()
+        }
+        Action::RemoveFromPlaylist { playlist_id, playlist_persistent_id, tracks, .. } => {
+            // Re-apply Remove
+
+            // 1. Apple Music
+            for track in tracks {
+                queue.enqueue(AppleUpdate::RemoveFromPlaylist {
+                    track_pid: track.persistent_id.clone(),
+                    playlist_pid: playlist_persistent_id.clone(),
+                });
+            }
+
+            // 2. DB
+            let ids: Vec<i64> = tracks.iter().map(|t| t.id).collect();
+            db.remove_tracks_from_playlist(*playlist_id, &ids)?;
+
+            "Redo Remove from Playlist".to_string()
+        }
+        Action::ReorderPlaylist { playlist_id, playlist_persistent_id, new_order, .. } => {
+            restore_playlist_order(db, queue, *playlist_id, playlist_persistent_id, new_order)?;
+            "Redo Reorder Playlist".to_string()
+        }
+        Action::UpdateRatings { tracks } => {
+            apply_ratings(db, queue, tracks, false)?;
+            if tracks.len() == 1 {
+                "Redo Rating Change".to_string()
+            } else {
+                format!("Redo Rating Change ({} tracks)", tracks.len())
+            }
+        }
+        Action::UpdateLoved { tracks } => {
+            apply_loved(db, queue, tracks, false)?;
+            if tracks.len() == 1 {
+                "Redo Loved Change".to_string()
+            } else {
+                format!("Redo Loved Change ({} tracks)", tracks.len())
+            }
+        }
+        Action::UpdateGroupings { tracks } => {
+            apply_groupings(db, queue, tracks, false)?;
+            format!("Redo Grouping Change ({} tracks)", tracks.len())
+        }
+        Action::UpdateColors { tracks } => {
+            apply_colors(db, tracks, false)?;
+            if tracks.len() == 1 {
+                "Redo Color Change".to_string()
+            } else {
+                format!("Redo Color Change ({} tracks)", tracks.len())
+            }
+        }
+        Action::TransferPlayCount { source, target } => {
+            apply_play_counts(db, queue, &[source, target], false)?;
+            "Redo Transfer Play Count".to_string()
+        }
+        Action::DeleteTracks { tracks } => {
+            let ids: Vec<i64> = tracks.iter().map(|t| t.track.id).collect();
+            db.delete_tracks(&ids)?;
+            format!("Redo Delete {} track(s)", tracks.len())
+        }
+        Action::Composite { description, actions } => {
+            for inner in actions {
+                redo_action(inner, db, queue)?;
+            }
+            format!("Redo {}", description)
+        }
+    })
+}
+
+/// File writes that failed during an undo/redo. The other tracks are still written, then
+/// the action fails as a whole so it stays where it was on the stack.
+#[derive(Default)]
+struct FileErrors {
+    count: usize,
+    first: Option<String>,
+}
+
+impl FileErrors {
+    fn push(&mut self, path: &str, error: impl std::fmt::Display) {
+        tracing::error!("Undo/Redo File Error ({}): {}", path, error);
+        self.count += 1;
+        self.first.get_or_insert_with(|| format!("{}: {}", path, error));
+    }
+
+    fn into_result(self) -> Result<()> {
+        match self.first {
+            Some(first) => Err(anyhow::anyhow!("{} file write(s) failed, first {}", self.count, first)),
+            None => Ok(()),
         }
     }
 }
 
+/// Writes old (`revert`) or new groupings to the files, the DB and Music.app.
+fn apply_groupings(db: &Database, queue: &SyncQueue, tracks: &[GroupingState], revert: bool) -> Result<()> {
+    let mut db_updates = Vec::new();
+    let mut updates = Vec::new();
+    let mut file_errors = FileErrors::default();
+    for track in tracks {
+        let grouping = if revert { &track.old_grouping } else { &track.new_grouping };
+        if let Err(e) = crate::metadata::write_grouping(&track.file_path, grouping) {
+            file_errors.push(&track.file_path, e);
+            continue;
+        }
+        db_updates.push((track.id, grouping.clone()));
+        if !track.persistent_id.is_empty() {
+            updates.push((track.persistent_id.clone(), grouping.clone()));
+        }
+    }
+    db.update_track_groupings(&db_updates)?;
+    if !updates.is_empty() {
+        queue.enqueue(AppleUpdate::Groupings { updates });
+    }
+    file_errors.into_result()
+}
+
+/// Writes old (`revert`) or new ratings to the DB and Music.app.
+pub(crate) fn apply_ratings(db: &Database, queue: &SyncQueue, tracks: &[RatingState], revert: bool) -> Result<()> {
+    let mut file_errors = FileErrors::default();
+    for track in tracks {
+        let rating = if revert { track.old_rating } else { track.new_rating };
+        db.update_track_rating(track.id, rating)?;
+        if let Some(path) = db.get_track(track.id)?.map(|t| t.file_path) {
+            if let Err(e) = crate::metadata::mirror_rating(&path, rating) {
+                file_errors.push(&path, e);
+            }
+        }
+        if !track.persistent_id.is_empty() {
+            queue.enqueue(AppleUpdate::Rating {
+                persistent_id: track.persistent_id.clone(),
+                rating,
+            });
+        }
+    }
+    file_errors.into_result()
+}
+
+/// Writes old (`revert`) or new color labels to the DB.
+fn apply_colors(db: &Database, tracks: &[ColorState], revert: bool) -> Result<()> {
+    let colors: Vec<(i64, Option<TrackColor>)> = tracks.iter()
+        .map(|t| (t.id, if revert { t.old_color } else { t.new_color }))
+        .collect();
+    db.update_track_colors(&colors)
+}
+
+/// Writes old (`revert`) or new play counts to the DB and Music.app.
+fn apply_play_counts(db: &Database, queue: &SyncQueue, tracks: &[&PlayCountState], revert: bool) -> Result<()> {
+    for track in tracks {
+        let count = if revert { track.old_count } else { track.new_count };
+        db.update_track_play_count(track.id, count)?;
+        if !track.persistent_id.is_empty() {
+            queue.enqueue(AppleUpdate::PlayCount {
+                persistent_id: track.persistent_id.clone(),
+                count,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Writes old (`revert`) or new loved flags to the DB and Music.app.
+fn apply_loved(db: &Database, queue: &SyncQueue, tracks: &[LovedState], revert: bool) -> Result<()> {
+    for track in tracks {
+        let loved = if revert { track.old_loved } else { track.new_loved };
+        db.update_track_loved(track.id, loved)?;
+        if !track.persistent_id.is_empty() {
+            queue.enqueue(AppleUpdate::Loved {
+                persistent_id: track.persistent_id.clone(),
+                loved,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Puts a playlist back into the given order, locally and in Music.app.
+fn restore_playlist_order(db: &Database, queue: &SyncQueue, playlist_id: i64, playlist_persistent_id: &str, order: &[TrackRef]) -> Result<()> {
+    let ids: Vec<i64> = order.iter().map(|t| t.id).collect();
+    db.reorder_playlist_tracks(playlist_id, &ids)?;
+
+    queue.enqueue(AppleUpdate::ReorderPlaylist {
+        playlist_pid: playlist_persistent_id.to_string(),
+        track_pids: order.iter().map(|t| t.persistent_id.clone()).collect(),
+    });
+    Ok(())
+}
+
 /// Applies track info changes for undo/redo.
 /// If `revert` is true, applies old values (undo); otherwise applies new values (redo).
-fn apply_track_info(db: &Database, track: &TrackInfoState, revert: bool) {
+fn apply_track_info(db: &Database, queue: &SyncQueue, track: &TrackInfoState, revert: bool) -> Result<()> {
     let (title, artist, album, bpm, comment_raw) = if revert {
         (
             track.old_title.as_deref(),
@@ -238,20 +670,21 @@ fn apply_track_info(db: &Database, track: &TrackInfoState, revert: bool) {
     };
 
     // 1. DB
-    if let Err(e) = db.update_track_info(track.id, title, artist, album, bpm, comment_raw) {
-        eprintln!("Undo/Redo DB Error: {}", e);
-    }
+    db.update_track_info(track.id, title, artist, album, bpm, comment_raw)?;
 
     // 2. File metadata (title/artist/album/bpm)
+    let mut file_errors = FileErrors::default();
     if title.is_some() || artist.is_some() || album.is_some() || bpm.is_some() {
-        if let Err(e) = write_track_info(&track.file_path, title, artist, album, bpm) {
-            eprintln!("Undo/Redo File Write Error: {}", e);
+        if let Err(e) = write_track_info(&track.file_path, title, artist, album, bpm, None) {
+            file_errors.push(&track.file_path, e);
         }
     }
 
     // 3. Comment in file (uses write_metadata which writes the full comment_raw)
     if let Some(c) = comment_raw {
-        let _ = write_tags_to_file(&track.file_path, c);
+        if let Err(e) = write_tags_to_file(&track.file_path, c) {
+            file_errors.push(&track.file_path, e);
+        }
     }
 
     // 4. Touch file
@@ -261,11 +694,21 @@ fn apply_track_info(db: &Database, track: &TrackInfoState, revert: bool) {
     if !track.persistent_id.is_empty() {
         // Sync title/artist/album/bpm
         if title.is_some() || artist.is_some() || album.is_some() || bpm.is_some() {
-            let _ = apple_update_track_info(&track.persistent_id, title, artist, album, bpm);
+            queue.enqueue(AppleUpdate::TrackInfo {
+                persistent_id: track.persistent_id.clone(),
+                name: title.map(str::to_string),
+                artist: artist.map(str::to_string),
+                album: album.map(str::to_string),
+                bpm: bpm.map(|b| b.round() as i64),
+            });
         }
         // Sync comment
         if let Some(c) = comment_raw {
-            let _ = update_track_comment(&track.persistent_id, c);
+            queue.enqueue(AppleUpdate::Comment {
+                persistent_id: track.persistent_id.clone(),
+                comment: c.to_string(),
+            });
         }
     }
+    file_errors.into_result()
 }
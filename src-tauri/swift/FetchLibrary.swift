@@ -1,21 +1,27 @@
 import Foundation
 import iTunesLibrary
 
-// Standard output wrapper for JSON
-struct LibraryExport: Encodable {
-    let tracks: [ExportTrack]
-    let playlists: [ExportPlaylist]
+// Output is newline-delimited JSON so the app can parse and report progress while
+// the library is still being read: one header line, then one line per track and
+// per playlist, each tagged with `kind`.
+struct ExportHeader: Encodable {
+    let kind = "header"
+    let track_count: Int
+    let playlist_count: Int
 }
 
 struct ExportPlaylist: Encodable {
+    let kind = "playlist"
     let persistent_id: String
     let parent_persistent_id: String?
     let name: String
     let is_folder: Bool
+    let is_smart: Bool
     let track_ids: [String]
 }
 
 struct ExportTrack: Encodable {
+    let kind = "track"
     let persistent_id: String
     let file_path: String
     let artist: String?
@@ -31,6 +37,21 @@ struct ExportTrack: Encodable {
     let rating: Int
     let date_added: Int64
     let bpm: Int
+    let play_count: Int
+    let skip_count: Int
+    let last_played: Int64
+    let is_cloud: Bool
+}
+
+let encoder = JSONEncoder()
+
+/// Writes one NDJSON line and flushes, so the reader sees it immediately.
+func emit<T: Encodable>(_ value: T) throws {
+    let data = try encoder.encode(value)
+    if let line = String(data: data, encoding: .utf8) {
+        print(line)
+        fflush(stdout)
+    }
 }
 
 func main() {
@@ -40,14 +61,18 @@ func main() {
         // or if running in an environment without TCC permissions.
         let library = try ITLibrary(apiVersion: "1.0")
         
-        var exportTracks: [ExportTrack] = []
-        
         let allItems = library.allMediaItems
+        let allPlaylists = library.allPlaylists
+
+        // Counts are upper bounds: non-cloud items without a location and the master playlist are skipped
+        try emit(ExportHeader(track_count: allItems.count, playlist_count: allPlaylists.count))
         
         for item in allItems {
-            // Filter for only music/audio files with valid locations
-            guard let location = item.location else { continue }
-            // Skip "File not found" or remote files if possible (location usually implies local)
+            // Apple Music streaming and iCloud-only items have no local file; they are
+            // exported with an empty path. Anything else without a location is skipped.
+            let isCloud = item.location == nil && (item.isCloud || item.locationType == .remote)
+            if item.location == nil && !isCloud { continue }
+            let location = item.location
             
             // Basic metadata
             let artist = item.artist?.name
@@ -72,11 +97,13 @@ func main() {
             
             let dateAdded = item.addedDate?.timeIntervalSince1970 ?? 0
             let bpm = item.beatsPerMinute
+            let lastPlayed = item.lastPlayedDate?.timeIntervalSince1970 ?? 0
             
             // Date Modified
             // ITLibMediaItem does not expose modificationDate, so we read it from the file system
             var modDate: Double = 0
-            if let attributes = try? FileManager.default.attributesOfItem(atPath: location.path),
+            if let location = location,
+               let attributes = try? FileManager.default.attributesOfItem(atPath: location.path),
                let date = attributes[.modificationDate] as? Date {
                 modDate = date.timeIntervalSince1970
             }
@@ -89,11 +116,11 @@ func main() {
             let pidString = String(format: "%016llX", pidNumber)
 
             // Format extension
-            let ext = location.pathExtension.lowercased()
+            let ext = location?.pathExtension.lowercased() ?? ""
             
             let track = ExportTrack(
                 persistent_id: pidString,
-                file_path: location.path, // Absolute path
+                file_path: location?.path ?? "", // Absolute path
                 artist: artist,
                 title: title,
                 album: album,
@@ -106,16 +133,17 @@ func main() {
                 modified_date: Int64(modDate),
                 rating: rating,
                 date_added: Int64(dateAdded),
-                bpm: bpm
+                bpm: bpm,
+                play_count: item.playCount,
+                skip_count: item.skipCount,
+                last_played: Int64(lastPlayed),
+                is_cloud: isCloud
             )
             
-            exportTracks.append(track)
+            try emit(track)
         }
         
-        // Collect Playlists
-        var exportPlaylists: [ExportPlaylist] = []
-        let allPlaylists = library.allPlaylists
-        
+        // Playlists
         for playlist in allPlaylists {
             // Skip master library to avoid duplication
             if playlist.isMaster { continue }
@@ -136,25 +164,19 @@ func main() {
             }
             
             let isFolder = (playlist.kind == .folder)
+            // Smart and built-in (Purchased, Genius...) playlists are managed by Music.app
+            let isSmart = playlist.kind == .smart || playlist.kind == .genius
+                || (!isFolder && playlist.distinguishedKind != .kindNone)
             
-            exportPlaylists.append(ExportPlaylist(
+            try emit(ExportPlaylist(
                 persistent_id: pidString,
                 parent_persistent_id: parentPidString,
                 name: playlist.name,
                 is_folder: isFolder,
+                is_smart: isSmart,
                 track_ids: trackIds
             ))
         }
-
-        // Output JSON
-        let exportData = LibraryExport(tracks: exportTracks, playlists: exportPlaylists)
-        let encoder = JSONEncoder()
-        // encoder.outputFormatting = .prettyPrinted // Compact matches Rust better
-        
-        let data = try encoder.encode(exportData)
-        if let jsonString = String(data: data, encoding: .utf8) {
-            print(jsonString)
-        }
         
     } catch {
         // Print error to stderr so Rust can capture it distinct from stdout JSON
@@ -16,14 +16,15 @@ import { TagEditor } from './components/TagEditor';
 import { TagDeck } from './components/TagDeck';
 import { BpmCounter } from './components/BpmCounter';
 import { CopyPlaylistsModal } from './components/CopyPlaylistsModal';
-import { Track, Playlist } from './types';
+import { Track, Playlist, SchemaVersion, SCHEMA_VERSION } from './types';
 import { useToast } from './components/Toast';
 import { useDebug } from './components/DebugContext';
 
 function App() {
   const { showSuccess, showError } = useToast();
-  const { debugMode, log } = useDebug();
+  const { debugMode } = useDebug();
   const [refreshTrigger, setRefreshTrigger] = useState(0);
+  const [playlistsVersion, setPlaylistsVersion] = useState(0);
   const [selectedTrack, setSelectedTrack] = useState<Track | null>(null);
   const [playingTrack, setPlayingTrack] = useState<Track | null>(() => {
     const saved = localStorage.getItem('app_playing_track');
@@ -34,6 +35,7 @@ function App() {
     return saved ? Number(saved) : null;
   });
   const [playlistNames, setPlaylistNames] = useState<Map<number, string>>(new Map());
+  const [smartPlaylistIds, setSmartPlaylistIds] = useState<Set<number>>(new Set());
   const [isPlaying, setIsPlaying] = useState(false);
   const [selectedTrackIds, setSelectedTrackIds] = useState<Set<number>>(new Set());
   const [lastSelectedTrackId, setLastSelectedTrackId] = useState<number | null>(null);
@@ -54,7 +56,6 @@ function App() {
     const saved = localStorage.getItem('app_show_sidebar_artwork');
     return saved ? saved === 'true' : false;
   });
-  const [syncEnabledTrigger, setSyncEnabledTrigger] = useState(0);
   const [copyPlaylistsTarget, setCopyPlaylistsTarget] = useState<Track | null>(null);
   const [scrollToTrackId, setScrollToTrackId] = useState<number | null>(null);
 
@@ -76,12 +77,15 @@ function App() {
   }, []);
 
   useEffect(() => {
-    const handleToggle = () => {
-        console.log("[App] Sync toggle detected, reloading listener...");
-        setSyncEnabledTrigger(p => p + 1);
-    };
-    window.addEventListener('real-time-sync-toggled', handleToggle);
-    return () => window.removeEventListener('real-time-sync-toggled', handleToggle);
+    // The real-time sync switch used to live in localStorage; the backend setting owns it now
+    const legacySetting = localStorage.getItem('app_real_time_sync_enabled');
+    if (legacySetting === null) return;
+    const migrate = legacySetting === 'false'
+      ? invoke('set_real_time_sync', { enabled: false })
+      : Promise.resolve();
+    migrate
+      .then(() => localStorage.removeItem('app_real_time_sync_enabled'))
+      .catch(console.error);
   }, []);
 
   useEffect(() => {
@@ -92,6 +96,11 @@ function App() {
     }
   }, [playingTrack]);
 
+  useEffect(() => {
+    // MIDI and global-shortcut tagging target the selection by default
+    invoke('set_selected_tracks', { ids: Array.from(selectedTrackIds) }).catch(console.error);
+  }, [selectedTrackIds]);
+
   useEffect(() => {
     if (playingPlaylistId !== null) {
       localStorage.setItem('app_playing_playlist_id', String(playingPlaylistId));
@@ -100,105 +109,83 @@ function App() {
     }
   }, [playingPlaylistId]);
 
+  useEffect(() => {
+    invoke<SchemaVersion>('get_schema_version')
+      .then(schema => {
+        if (schema.version !== SCHEMA_VERSION) {
+          console.warn(`Backend schema v${schema.version}, frontend built for v${SCHEMA_VERSION}`);
+        }
+      })
+      .catch(console.error);
+  }, []);
+
   useEffect(() => {
     invoke<Playlist[]>('get_playlists')
       .then(playlists => {
         const map = new Map<number, string>();
         playlists.forEach(p => map.set(p.id, p.name));
         setPlaylistNames(map);
+        setSmartPlaylistIds(new Set(playlists.filter(p => p.is_smart).map(p => p.id)));
       })
       .catch(console.error);
-  }, [refreshTrigger]);
+  }, [refreshTrigger, playlistsVersion]);
+
+  useEffect(() => {
+    // Renames and smart-playlist flags arrive as `playlist-updated`; one reload per burst
+    let timer: ReturnType<typeof setTimeout> | undefined;
+    const unlisten = listen('playlist-updated', () => {
+      clearTimeout(timer);
+      timer = setTimeout(() => setPlaylistsVersion(v => v + 1), 50);
+    });
+    return () => {
+      clearTimeout(timer);
+      unlisten.then(f => f());
+    };
+  }, []);
 
   useEffect(() => {
     let unlistenFn: (() => void) | undefined;
     let isMounted = true;
 
     const setupListener = async () => {
-      // Check setting first
-      const savedSetting = localStorage.getItem('app_real_time_sync_enabled');
-      const isEnabled = savedSetting !== 'false'; // Default to true
-
-      if (!isEnabled) {
-          console.log("[App] Real-Time Sync is disabled by user setting.");
-          return;
+      // The backend skips watcher syncs (and this event) while real-time sync is off
+      console.log("[App] Setting up library-synced listener");
+      interface SyncResult {
+          tracks_updated: number;
+          tracks_added: number;
+          tracks_deleted: number;
+          playlists_updated: number;
       }
-
-      console.log("[App] Setting up music-library-changed listener");
-      const unlisten = await listen('music-library-changed', async () => {
+      // The backend watcher runs the sync itself and reports the outcome
+      const unlisten = await listen<{ result: SyncResult | null; error: string | null }>('library-synced', (event) => {
         if (!isMounted) return;
-        
-        console.log("[App] music-library-changed event received!");
-        showSuccess('Library change detected. Syncing...');
-        log('INFO', 'Library change detected, starting auto-sync');
-        
-        try {
-          const lastSync = localStorage.getItem('app_last_sync_time');
-          // Default to 24 hours ago if no record to be safe
-          const defaultTime = Math.floor(Date.now() / 1000) - 86400;
-          let timestamp = (lastSync && !isNaN(parseInt(lastSync))) ? parseInt(lastSync) : defaultTime;
-          
-          // Safety Buffer: Go back 3600 seconds (1 hour) to ensure we catch everything, including
-          // tracks that might have been missed if previous syncs were buggy or incomplete.
-          // Since our DB operation is an upsert, reprocessing unchanged tracks is cheap and safe.
-          const bufferSeconds = 3600; 
-          const bufferTimestamp = Math.max(0, timestamp - bufferSeconds);
-
-          console.log(`[App] Requesting sync since timestamp: ${bufferTimestamp} (Original: ${timestamp})`);
-
-          interface SyncResult {
-              tracks_updated: number;
-              tracks_added: number;
-              tracks_deleted: number;
-              playlists_updated: number;
-          }
-
-          const result = await invoke<SyncResult | number>('sync_recent_changes', { 
-            sinceTimestamp: bufferTimestamp 
-          });
 
-          console.log('[App] Raw sync result:', result);
-
-          // Handle potential legacy return (number) if backend didn't update/recompile yet
-          let tracksVal = 0;
-          let addedVal = 0;
-          let deletedVal = 0;
-          let playlistsVal = 0;
-
-          if (typeof result === 'number') {
-              tracksVal = result;
-          } else if (result && typeof result === 'object') {
-              tracksVal = result.tracks_updated || 0;
-              addedVal = result.tracks_added || 0;
-              deletedVal = result.tracks_deleted || 0;
-              playlistsVal = result.playlists_updated || 0;
-          }
+        const { result, error } = event.payload;
+        if (error || !result) {
+          console.error("Auto-sync failed:", error);
+          showError(`Auto-sync failed: ${error}`);
+          return;
+        }
 
-          const totalUpdated = tracksVal + playlistsVal;
-          
-          console.log(`[App] Sync parsed: Tracks=${tracksVal}, Added=${addedVal}, Deleted=${deletedVal}, Playlists=${playlistsVal}, Total=${totalUpdated}`);
-
-          if (totalUpdated > 0) {
-            const parts: string[] = [];
-            // Show added/deleted separately for clarity, group the rest as "updated"
-            const pureUpdated = tracksVal - addedVal - deletedVal;
-            if (addedVal > 0) parts.push(`${addedVal} track${addedVal > 1 ? 's' : ''} imported`);
-            if (deletedVal > 0) parts.push(`${deletedVal} track${deletedVal > 1 ? 's' : ''} removed`);
-            if (pureUpdated > 0) parts.push(`${pureUpdated} track${pureUpdated > 1 ? 's' : ''} updated`);
-            if (playlistsVal > 0) parts.push(`${playlistsVal} playlist${playlistsVal > 1 ? 's' : ''}`);
-            
-            showSuccess(`Synced: ${parts.join(', ')}`);
-            setRefreshTrigger(p => p + 1);
-            localStorage.setItem('app_last_sync_time', Math.floor(Date.now() / 1000).toString());
-          } else {
-             // If nothing found, show feedback so user knows it finished
-             showSuccess("Sync complete. No changes detected.");
-             localStorage.setItem('app_last_sync_time', Math.floor(Date.now() / 1000).toString());
-          }
-        } catch (e) {
-          console.error("Auto-sync failed:", e);
-          showError(`Auto-sync failed: ${e}`);
-          log('ERROR', `Auto-sync failed: ${e}`);
+        const tracksVal = result.tracks_updated || 0;
+        const addedVal = result.tracks_added || 0;
+        const deletedVal = result.tracks_deleted || 0;
+        const playlistsVal = result.playlists_updated || 0;
+        const totalUpdated = tracksVal + playlistsVal;
+
+        console.log(`[App] Sync parsed: Tracks=${tracksVal}, Added=${addedVal}, Deleted=${deletedVal}, Playlists=${playlistsVal}, Total=${totalUpdated}`);
+
+        if (totalUpdated > 0) {
+          const parts: string[] = [];
+          // Show added/deleted separately for clarity, group the rest as "updated"
+          const pureUpdated = tracksVal - addedVal - deletedVal;
+          if (addedVal > 0) parts.push(`${addedVal} track${addedVal > 1 ? 's' : ''} imported`);
+          if (deletedVal > 0) parts.push(`${deletedVal} track${deletedVal > 1 ? 's' : ''} removed`);
+          if (pureUpdated > 0) parts.push(`${pureUpdated} track${pureUpdated > 1 ? 's' : ''} updated`);
+          if (playlistsVal > 0) parts.push(`${playlistsVal} playlist${playlistsVal > 1 ? 's' : ''}`);
+
+          // The change events have already patched the track list, sidebar and tag deck
+          showSuccess(`Synced: ${parts.join(', ')}`);
         }
       });
 
@@ -215,7 +202,7 @@ function App() {
       isMounted = false;
       if (unlistenFn) unlistenFn();
     };
-  }, [syncEnabledTrigger]);
+  }, []);
 
   const sensors = useSensors(
       useSensor(PointerSensor, {
@@ -241,6 +228,7 @@ function App() {
       if (activeId.startsWith('track-') && overId.startsWith('playlist-')) {
           const trackId = Number(activeId.replace('track-', ''));
           const playlistId = Number(overId.replace('playlist-', ''));
+          if (smartPlaylistIds.has(playlistId)) return;
           
           let idsToAdd: number[] = [trackId];
           if (selectedTrackIds.has(trackId)) {
@@ -700,6 +688,7 @@ function App() {
             <TrackList 
               ref={trackListRef}
               playlistId={selectedPlaylistId}
+              playlistReadOnly={selectedPlaylistId !== null && smartPlaylistIds.has(selectedPlaylistId)}
               refreshTrigger={refreshTrigger}
               onSelectionChange={handleSelectionChange}
               onTrackDoubleClick={handleTrackDoubleClick}
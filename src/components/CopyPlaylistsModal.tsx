@@ -7,6 +7,7 @@ interface PlaylistInfo {
     id: number;
     persistent_id: string;
     name: string;
+    is_smart: boolean;
 }
 
 interface Props {
@@ -66,7 +67,9 @@ export function CopyPlaylistsModal({ targetTrack, onClose, onComplete, onError,
         setSourceTrack(track);
         setLoading(true);
         try {
-            const result = await invoke<PlaylistInfo[]>('get_playlists_for_track', { trackId: track.id });
+            // Smart playlists pick up tracks by their own rules and can't be added to
+            const result = (await invoke<PlaylistInfo[]>('get_playlists_for_track', { trackId: track.id }))
+                .filter(p => !p.is_smart);
             setPlaylists(result);
             // Pre-select all playlists
             setSelectedPlaylistIds(new Set(result.map(p => p.id)));
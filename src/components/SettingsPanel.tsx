@@ -65,9 +65,7 @@ export function SettingsPanel({
     const [status, setStatus] = useState('');
     const [logStats, setLogStats] = useState<LogStats | null>(null);
     const { debugMode, setDebugMode } = useDebug();
-    const [realTimeSyncEnabled, setRealTimeSyncEnabled] = useState(() => {
-        return localStorage.getItem('app_real_time_sync_enabled') !== 'false';
-    });
+    const [realTimeSyncEnabled, setRealTimeSyncEnabled] = useState(true);
     const [playerMode, setPlayerMode] = useState<'standard' | 'waveform'>(() => {
         return (localStorage.getItem('app_player_mode') as 'standard' | 'waveform') || 'standard';
     });
@@ -75,8 +73,7 @@ export function SettingsPanel({
     const handleRealTimeSyncToggle = () => {
         const newValue = !realTimeSyncEnabled;
         setRealTimeSyncEnabled(newValue);
-        localStorage.setItem('app_real_time_sync_enabled', String(newValue));
-        window.dispatchEvent(new Event('real-time-sync-toggled'));
+        invoke('set_real_time_sync', { enabled: newValue }).catch(console.error);
     };
 
     const loadSyncInfo = () => {
@@ -94,6 +91,7 @@ export function SettingsPanel({
         if (isOpen) {
              loadSyncInfo();
              invoke<LogStats | null>('get_log_stats').then(setLogStats).catch(console.error);
+             invoke<boolean>('get_real_time_sync').then(setRealTimeSyncEnabled).catch(console.error);
         }
     }, [isOpen]);
 
@@ -1,8 +1,9 @@
 import { useState, useEffect, useMemo } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useDroppable } from '@dnd-kit/core';
 import { Playlist, Track } from '../types';
-import { ChevronRight, ChevronDown, Folder, ListMusic } from 'lucide-react';
+import { ChevronRight, ChevronDown, Folder, ListMusic, ListFilter } from 'lucide-react';
 
 interface SidebarProps {
   onSelectPlaylist: (id: number | null) => void;
@@ -44,7 +45,8 @@ const PlaylistRow = ({
             type: 'Playlist',
             playlist: node
         },
-        disabled: node.is_folder
+        // Smart playlists are filled by Music.app's rules; tracks can't be dropped on them
+        disabled: node.is_folder || !!node.is_smart
     });
 
     const isExpanded = expandedFolders.has(node.persistent_id);
@@ -67,6 +69,7 @@ const PlaylistRow = ({
                       }
                   }}
                   className={isHighlighted ? 'flash-highlight' : ''}
+                  title={node.is_smart ? 'Smart playlist (managed by Music.app, read-only)' : undefined}
                   style={{
                       padding: `6px 16px 6px ${paddingLeft}px`,
                       fontSize: '13px',
@@ -107,6 +110,8 @@ const PlaylistRow = ({
                         fill={isSelected ? "currentColor" : "var(--text-secondary)"} 
                         color={isSelected ? "currentColor" : "var(--text-secondary)"} 
                      />
+                  ) : node.is_smart ? (
+                     <ListFilter size={16} style={{ minWidth: 16, flexShrink: 0 }} />
                   ) : (
                      <ListMusic size={16} style={{ minWidth: 16, flexShrink: 0 }} />
                   )}
@@ -163,6 +168,19 @@ export default function Sidebar({ onSelectPlaylist, selectedPlaylistId, refreshT
     loadPlaylists();
   }, [refreshTrigger]);
 
+  // Sync and edits report changed playlists; reload the tree once per burst
+  useEffect(() => {
+    let timer: ReturnType<typeof setTimeout> | undefined;
+    const unlisten = listen('playlist-updated', () => {
+      clearTimeout(timer);
+      timer = setTimeout(loadPlaylists, 50);
+    });
+    return () => {
+      clearTimeout(timer);
+      unlisten.then(f => f());
+    };
+  }, []);
+
   useEffect(() => {
     setHasScrolledToSelection(false);
   }, [selectedPlaylistId]);
@@ -1,5 +1,6 @@
 import { useState, useEffect, useMemo } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { DndContext, useDraggable, useDroppable, DragEndEvent, DragStartEvent, DragOverlay, PointerSensor, useSensor, useSensors } from '@dnd-kit/core';
 import { SortableContext, verticalListSortingStrategy, useSortable, arrayMove } from '@dnd-kit/sortable';
 import { CSS } from '@dnd-kit/utilities';
@@ -37,6 +38,19 @@ export function TagDeck({ onTagClick, currentTrackTags, refreshTrigger, keyboard
         loadData();
     }, [refreshTrigger]);
 
+    // Tags created by a sync or another window show up without a full refresh
+    useEffect(() => {
+        let timer: ReturnType<typeof setTimeout> | undefined;
+        const unlisten = listen<{ name: string }>('tag-created', () => {
+            clearTimeout(timer);
+            timer = setTimeout(loadData, 50);
+        });
+        return () => {
+            clearTimeout(timer);
+            unlisten.then(f => f());
+        };
+    }, []);
+
     const loadData = async () => {
         try {
             setError(null);
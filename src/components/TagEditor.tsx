@@ -2,7 +2,8 @@ import { useState, useEffect } from 'react';
 import React from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { Info } from 'lucide-react';
-import { Track } from '../types';
+import { Track, TagWriteError } from '../types';
+import { waitForJob } from '../utils/jobs';
 import { useToast } from './Toast';
 import { MetadataViewer } from './MetadataViewer';
 
@@ -168,7 +169,7 @@ export function TagEditor({ track, onUpdate, selectedTrackIds, commonTags }: Pro
             onUpdate(); 
         } catch (e) {
             console.error(e);
-            const msg = 'Failed to save tags: ' + e;
+            const msg = 'Failed to save tags: ' + ((e as TagWriteError)?.message ?? e);
             showError(msg);
             invoke('log_error', { message: msg }).catch(console.error);
         }
@@ -198,7 +199,8 @@ export function TagEditor({ track, onUpdate, selectedTrackIds, commonTags }: Pro
                 
                 console.log(`Executing ${command} on ${idsToUpdate.length} tracks for tag: ${val}`);
 
-                invoke(command, { ids: idsToUpdate, tag: val })
+                invoke<number | null>(command, { ids: idsToUpdate, tag: val })
+                    .then(jobId => jobId != null ? waitForJob(jobId) : undefined)
                     .then(() => {
                          // Optimistic update for Primary Track (UI feedback)
                          if (track) {
@@ -243,7 +245,8 @@ export function TagEditor({ track, onUpdate, selectedTrackIds, commonTags }: Pro
         if (isMultiSelect) {
             const ids = Array.from(selectedTrackIds || []);
             try {
-                await invoke('batch_remove_tag', { ids, tag: tagToRemove });
+                const jobId = await invoke<number | null>('batch_remove_tag', { ids, tag: tagToRemove });
+                if (jobId != null) await waitForJob(jobId);
                 setTags(prev => prev.filter((_, idx) => idx !== index));
                 onUpdate();
             } catch (e) {
@@ -267,7 +270,8 @@ export function TagEditor({ track, onUpdate, selectedTrackIds, commonTags }: Pro
             if (isMultiSelect) {
                 const ids = Array.from(selectedTrackIds || []);
                 try {
-                    await invoke('batch_add_tag', { ids, tag: val });
+                    const jobId = await invoke<number | null>('batch_add_tag', { ids, tag: val });
+                    if (jobId != null) await waitForJob(jobId);
                     setTags(prev => {
                         const exists = prev.some(t => t.toLowerCase() === val.toLowerCase());
                         if (exists) return prev;
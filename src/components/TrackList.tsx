@@ -2,6 +2,7 @@ import { useEffect, useState, useMemo, useRef, forwardRef, useImperativeHandle,
 import { StarRating } from './StarRating';
 import { parseSearchQuery } from '../utils/searchParser';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useVirtualizer } from '@tanstack/react-virtual';
 import { 
     useReactTable, 
@@ -37,7 +38,7 @@ import {
 import type { AnimateLayoutChanges } from '@dnd-kit/sortable';
 import { CSS } from '@dnd-kit/utilities';
 import { Folder, ArrowUp, ArrowDown, Settings, Volume2, Volume, ListMusic, ChevronRight, Trash2, Activity } from 'lucide-react';
-import { Track } from '../types';
+import { Track, TrackUpdated } from '../types';
 import { useDebug } from './DebugContext';
 
 interface Props {
@@ -50,6 +51,8 @@ interface Props {
     onTrackDoubleClick?: (track: Track) => void;
     searchTerm: string;
     playlistId: number | null;
+    /** The playlist is a smart playlist: no reordering or removing */
+    playlistReadOnly?: boolean;
     onRefresh?: () => void;
     onCopyPlaylistMemberships?: (track: Track) => void;
     onNavigateToPlaylist?: (playlistId: number, track: Track) => void;
@@ -597,11 +600,12 @@ const SortableMenuItem = ({ column, label }: { column: any, label: string }) =>
     );
 };
 
-export const TrackList = forwardRef<TrackListHandle, Props>(({ refreshTrigger, onSelectionChange, onTrackDoubleClick, selectedTrackIds, lastSelectedTrackId, playingTrackId, isPlaying, searchTerm, playlistId, onRefresh, onCopyPlaylistMemberships, onNavigateToPlaylist, scrollToTrackId, onScrollToTrackComplete }, ref) => {
+export const TrackList = forwardRef<TrackListHandle, Props>(({ refreshTrigger, onSelectionChange, onTrackDoubleClick, selectedTrackIds, lastSelectedTrackId, playingTrackId, isPlaying, searchTerm, playlistId, playlistReadOnly, onRefresh, onCopyPlaylistMemberships, onNavigateToPlaylist, scrollToTrackId, onScrollToTrackComplete }, ref) => {
     const { debugMode } = useDebug();
     const [tracks, setTracks] = useState<Track[]>([]);
     const [allowedTrackIds, setAllowedTrackIds] = useState<Set<number> | null>(null);
     const [playlistTrackOrder, setPlaylistTrackOrder] = useState<number[] | null>(null);
+    const [playlistVersion, setPlaylistVersion] = useState(0);
     const [loading, setLoading] = useState(false);
     const [contextMenu, setContextMenu] = useState<ContextMenuState | null>(null);
     const [contextMenuPlaylists, setContextMenuPlaylists] = useState<{id: number; name: string}[] | null>(null);
@@ -734,7 +738,18 @@ export const TrackList = forwardRef<TrackListHandle, Props>(({ refreshTrigger, o
             }
         }
         loadPlaylistFilter();
-    }, [playlistId, refreshTrigger]);
+    }, [playlistId, refreshTrigger, playlistVersion]);
+
+    // Reload the membership when the shown playlist changes
+    useEffect(() => {
+        if (playlistId === null) return;
+        const unlisten = listen<{ id: number }>('playlist-updated', (event) => {
+            if (event.payload.id === playlistId) setPlaylistVersion(v => v + 1);
+        });
+        return () => {
+            unlisten.then(f => f());
+        };
+    }, [playlistId]);
 
 
 
@@ -769,9 +784,10 @@ export const TrackList = forwardRef<TrackListHandle, Props>(({ refreshTrigger, o
         return result.filter(track => {
             // 1. Check Numeric Filters
             for (const filter of query.numericFilters) {
-                // Only BPM is supported in Track for now
                 let val: number | undefined;
                 if (filter.field === 'bpm') val = track.bpm;
+                if (filter.field === 'intro') val = track.intro_bars ?? undefined;
+                if (filter.field === 'outro') val = track.outro_bars ?? undefined;
                 // Add year support if available in Track interface
                 // if (filter.field === 'year') val = ...; 
                 
@@ -841,6 +857,7 @@ export const TrackList = forwardRef<TrackListHandle, Props>(({ refreshTrigger, o
                             }
                             break;
                         case 'label': fieldValue = track.grouping_raw; break;
+                        case 'color': fieldValue = track.color_label ?? undefined; break;
                         case 'key': 
                             // Try parsing key from comment or look for future key field
                             // For now, if we match in comment or grouping?
@@ -874,7 +891,7 @@ export const TrackList = forwardRef<TrackListHandle, Props>(({ refreshTrigger, o
 
     // Remove selected tracks from current playlist
     const handleRemoveFromPlaylist = useCallback(async () => {
-        if (playlistId === null || selectedTrackIds.size === 0) return;
+        if (playlistId === null || playlistReadOnly || selectedTrackIds.size === 0) return;
         const ids = Array.from(selectedTrackIds);
         try {
             await invoke('remove_from_playlist', { trackIds: ids, playlistId });
@@ -884,7 +901,7 @@ export const TrackList = forwardRef<TrackListHandle, Props>(({ refreshTrigger, o
         } catch (e) {
             console.error('Failed to remove from playlist:', e);
         }
-    }, [playlistId, selectedTrackIds, onSelectionChange, onRefresh]);
+    }, [playlistId, playlistReadOnly, selectedTrackIds, onSelectionChange, onRefresh]);
 
     // Keyboard Shortcuts (Select All, Enter to Play)
     useEffect(() => {
@@ -955,12 +972,12 @@ export const TrackList = forwardRef<TrackListHandle, Props>(({ refreshTrigger, o
     // Determine if we're in a reorderable state:
     // Must be viewing a playlist AND sorted by position (# column) with no other sort or ascending position sort
     const isReorderable = useMemo(() => {
-        if (playlistId === null) return false;
+        if (playlistId === null || playlistReadOnly) return false;
         // Reorderable when: no sort applied (default position order), or explicitly sorted by position asc
         if (sorting.length === 0) return true;
         if (sorting.length === 1 && sorting[0].id === 'position' && !sorting[0].desc) return true;
         return false;
-    }, [playlistId, sorting]);
+    }, [playlistId, playlistReadOnly, sorting]);
 
     // Handle reorder drag end
     const handleReorderDragEnd = useCallback(async (event: DragEndEvent) => {
@@ -1081,6 +1098,43 @@ export const TrackList = forwardRef<TrackListHandle, Props>(({ refreshTrigger, o
         loadTracks();
     }, [refreshTrigger, loadTracks]);
 
+    // Patch rows in place from the backend's change events instead of refetching the library
+    useEffect(() => {
+        let pending = new Set<number>();
+        let timer: ReturnType<typeof setTimeout> | undefined;
+        const flush = async () => {
+            const ids = [...pending];
+            pending = new Set();
+            try {
+                const fresh = await invoke<Track[]>('get_tracks_by_ids', { ids });
+                const byId = new Map(fresh.map(t => [t.id, t]));
+                setTracks(prev => {
+                    const known = new Set(prev.map(t => t.id));
+                    return [...prev.map(t => byId.get(t.id) ?? t), ...fresh.filter(t => !known.has(t.id))];
+                });
+            } catch (err) {
+                console.error(err);
+            }
+        };
+
+        const unlisteners = Promise.all([
+            listen<TrackUpdated>('track-updated', (event) => {
+                pending.add(event.payload.id);
+                clearTimeout(timer);
+                timer = setTimeout(flush, 50);
+            }),
+            listen<{ id: number }>('track-removed', (event) => {
+                setTracks(prev => prev.filter(t => t.id !== event.payload.id));
+            }),
+            listen('library-changed', () => loadTracks()),
+        ]);
+
+        return () => {
+            clearTimeout(timer);
+            unlisteners.then(fns => fns.forEach(unlisten => unlisten()));
+        };
+    }, [loadTracks]);
+
     const handleRatingChange = async (trackId: number, newRating: number) => {
         setTracks(prev => prev.map(t => 
             t.id === trackId ? { ...t, rating: newRating } : t
@@ -1864,16 +1918,18 @@ export const TrackList = forwardRef<TrackListHandle, Props>(({ refreshTrigger, o
                                     : [contextMenu.track];
                                 
                                 const trackIds = tracksToAnalyze.map(t => t.id);
-                                const filePaths = tracksToAnalyze.map(t => t.file_path);
-                                const count = filePaths.length;
                                 
                                 setContextMenu(null);
                                 
-                                try {
-                                    await invoke('analyze_with_mixed_in_key', { trackIds, filePaths });
-                                    console.log(`Mixed In Key analysis complete for ${count} track${count > 1 ? 's' : ''}. Refreshing...`);
+                                const unlisten = await listen('analysis-complete', () => {
+                                    unlisten();
                                     onRefresh?.();
+                                });
+                                try {
+                                    const { queued, rejected } = await invoke<{ queued: number[]; rejected: { track_id: number; reason: string }[] }>('analyze_with_mixed_in_key', { trackIds });
+                                    console.log(`Queued ${queued.length} track(s) for Mixed In Key, ${rejected.length} rejected`);
                                 } catch (err) {
+                                    unlisten();
                                     console.error('Failed to launch Mixed In Key:', err);
                                     alert(`Error: ${err}`);
                                 }
@@ -1962,8 +2018,8 @@ export const TrackList = forwardRef<TrackListHandle, Props>(({ refreshTrigger, o
                             )}
                         </div>
                         <div className="context-menu-separator" />
-                        {/* Remove from Playlist — only shown when viewing a playlist */}
-                        {playlistId !== null && (
+                        {/* Remove from Playlist — only shown when viewing a playlist that isn't smart */}
+                        {playlistId !== null && !playlistReadOnly && (
                             <>
                                 <div
                                     className="context-menu-item"
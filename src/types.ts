@@ -1,3 +1,12 @@
+/** IPC schema version this bundle was built against; see `models::SCHEMA_VERSION`. */
+export const SCHEMA_VERSION = 1;
+
+export interface SchemaVersion {
+    version: number;
+}
+
+export type TrackColor = 'pink' | 'red' | 'orange' | 'yellow' | 'green' | 'aqua' | 'blue' | 'purple';
+
 export interface Track {
     id: number;
     persistent_id: string;
@@ -16,6 +25,23 @@ export interface Track {
     date_added: number;
     bpm: number;
     missing?: boolean;
+    play_count?: number;
+    skip_count?: number;
+    last_played?: number;
+    loved?: boolean;
+    notes?: string | null;
+    intro_bars?: number | null;
+    outro_bars?: number | null;
+    audio_health?: string | null;
+    color_label?: TrackColor | null;
+    offloaded?: boolean;
+    is_cloud?: boolean;
+}
+
+/** Payload of `track-updated`; empty `fields` means the whole row changed. */
+export interface TrackUpdated {
+    id: number;
+    fields: string[];
 }
 
 export interface Playlist {
@@ -24,6 +50,11 @@ export interface Playlist {
     parent_persistent_id?: string;
     name: string;
     is_folder: boolean;
+    is_smart?: boolean;
+}
+
+export interface PlaylistNode extends Playlist {
+    children: PlaylistNode[];
 }
 
 export interface Tag {
@@ -38,3 +69,48 @@ export interface TagGroup {
     name: string;
     position: number;
 }
+
+export interface Session {
+    id: number;
+    name: string;
+    started_at: number;
+    ended_at?: number | null;
+}
+
+export interface SessionTrack {
+    position: number;
+    played_at: number;
+    track: Track;
+}
+
+export type CueType = 'cue' | 'hot' | 'loop';
+
+export interface CuePoint {
+    id: number;
+    track_id: number;
+    position_secs: number;
+    type: CueType;
+    end_secs?: number | null;
+    label: string;
+    color?: string | null;
+}
+
+/** Error of `write_tags` when the comment did not land in the file. */
+export interface TagWriteError {
+    kind: 'mismatch' | 'offloaded' | 'read_only' | 'locked' | 'not_owner' | 'failed';
+    path: string;
+    message: string;
+}
+
+export type JobStatus = 'queued' | 'running' | 'paused' | 'cancelled' | 'completed' | 'failed';
+
+/** Background job from the job queue; also the payload of `job-progress`. */
+export interface Job {
+    id: number;
+    description: string;
+    status: JobStatus;
+    progress: number;
+    total: number;
+    error?: string | null;
+    created_at: number;
+}
@@ -0,0 +1,31 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { Job } from '../types';
+
+/** Resolves once a background job has finished; rejects with its error if it failed. */
+export function waitForJob(id: number): Promise<void> {
+    return new Promise((resolve, reject) => {
+        let settled = false;
+        let unlisten: (() => void) | undefined;
+
+        const check = (job: Job) => {
+            if (settled || job.id !== id) return;
+            if (job.status === 'failed') {
+                reject(job.error ?? 'Job failed');
+            } else if (job.status === 'completed' || job.status === 'cancelled') {
+                resolve();
+            } else {
+                return;
+            }
+            settled = true;
+            unlisten?.();
+        };
+
+        listen<Job>('job-progress', e => check(e.payload)).then(fn => {
+            unlisten = fn;
+            if (settled) fn();
+            // The job may have finished before the listener was registered
+            invoke<Job[]>('list_jobs').then(jobs => jobs.forEach(check)).catch(console.error);
+        });
+    });
+}
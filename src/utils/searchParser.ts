@@ -1,6 +1,6 @@
 
-export type NumericField = 'bpm' | 'year';
-export type StringField = 'artist' | 'title' | 'album' | 'genre' | 'label' | 'key' | 'tag' | 'any';
+export type NumericField = 'bpm' | 'year' | 'intro' | 'outro';
+export type StringField = 'artist' | 'title' | 'album' | 'genre' | 'label' | 'key' | 'tag' | 'color' | 'any';
 
 export type FilterOperator = '=' | '>' | '<' | '>=' | '<=' | 'range';
 
@@ -31,6 +31,8 @@ export interface SearchQuery {
  * - Fields: artist:Prince
  * - Negation: -minimal
  * - Numeric ranges: bpm:120-130, bpm:>120
+ * - Intro/outro length in bars: intro:>=16, outro:8-32
+ * - Color label: color:red
  */
 export function parseSearchQuery(query: string): SearchQuery {
   const result: SearchQuery = {
@@ -150,11 +152,11 @@ function parseToken(token: string, query: SearchQuery) {
 }
 
 function isNumericField(f: string): boolean {
-    return ['bpm', 'year'].includes(f);
+    return ['bpm', 'year', 'intro', 'outro'].includes(f);
 }
 
 function isStringField(f: string): boolean {
-    return ['artist', 'title', 'album', 'genre', 'label', 'key', 'tag'].includes(f);
+    return ['artist', 'title', 'album', 'genre', 'label', 'key', 'tag', 'color'].includes(f);
 }
 
 function parseNumericToken(field: NumericField, valueStr: string, query: SearchQuery) {